@@ -0,0 +1,210 @@
+//! Counts the allocations made rendering a typical view: a list of cards,
+//! each with a heading, some text and a couple of buttons. Then those made
+//! gathering the attributes of a single element with dozens of them, and
+//! how long that takes.
+//!
+//! Run with `cargo bench --bench allocations --features experimental`, for
+//! the stress trees. Each count is compared with the baseline saved by
+//! running it with `-- --save-baseline`, e.g. before making a change.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use bevy_declarative_ui_experiment::{
+    background, border,
+    element::{
+        align_right, center_y, column, el, fill, height, layout, padding, px,
+        row, spacing, width,
+    },
+    events::on_click,
+    experimental::stress::{random_tree, StressConfig},
+    font,
+    model::{Attribute, Color, Element},
+    theme::default_light,
+};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// The index of the card a button was clicked on.
+type Msg = usize;
+
+fn card(i: usize) -> Element<Msg> {
+    let theme = default_light();
+    let mut attrs = theme.card();
+    attrs.extend(vec![width(fill()), spacing(theme.space(0))]);
+    let button = |label: &str, msg| {
+        let mut attrs = theme.button();
+        attrs.push(on_click(msg));
+        el(attrs, Element::Text(label.to_string()))
+    };
+    column(
+        attrs,
+        vec![
+            el(vec![], Element::Text(format!("Item {}", i))),
+            el(
+                vec![padding(4)],
+                Element::Text("Some text describing the item.".to_string()),
+            ),
+            row(
+                vec![spacing(8)],
+                vec![button("Open", i), button("Delete", i)],
+            ),
+        ],
+    )
+}
+
+fn view(items: usize) -> Element<Msg> {
+    column(
+        vec![width(fill()), spacing(16)],
+        (0..items).map(card).collect(),
+    )
+}
+
+// `count` attributes of the usual kinds, most repeating one of those
+// before them, as when a theme's attributes are overridden.
+fn many_attributes(count: usize) -> Vec<Attribute<Msg>> {
+    (0..count)
+        .map(|i| {
+            let n = i as u32;
+            let shade = Color::new(0.1, 0.2, i as f32 / count as f32, 1.0);
+            match i % 10 {
+                0 => padding(n),
+                1 => spacing(n),
+                2 => width(px(100 + n as u64)),
+                3 => height(fill()),
+                4 => background::color(shade),
+                5 => border::rounded(n),
+                6 => font::size(12 + n),
+                7 => font::color(shade),
+                8 => align_right(),
+                _ => center_y(),
+            }
+        })
+        .collect()
+}
+
+const BASELINE: &str =
+    concat!(env!("CARGO_TARGET_TMPDIR"), "/allocations-baseline");
+
+// The counts saved by the last run with `--save-baseline`, by label.
+fn baseline() -> HashMap<String, usize> {
+    fs::read_to_string(BASELINE)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (label, count) = line.rsplit_once(' ')?;
+            Some((label.to_string(), count.parse().ok()?))
+        })
+        .collect()
+}
+
+// How `count` compares with the baseline's count for `label`.
+fn change(
+    baseline: &HashMap<String, usize>,
+    label: &str,
+    count: usize,
+) -> String {
+    match baseline.get(label) {
+        Some(&before) if before > 0 => format!(
+            " ({:+.1}% on {})",
+            (count as f64 - before as f64) / before as f64 * 100.0,
+            before
+        ),
+        _ => String::new(),
+    }
+}
+
+fn main() {
+    let save = std::env::args().any(|arg| arg == "--save-baseline");
+    let baseline = baseline();
+    let mut counts = vec![];
+    for items in [10, 100] {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let node = layout(vec![], view(items));
+        let after = ALLOCATIONS.load(Ordering::Relaxed);
+        drop(node);
+        let label = format!("{} cards", items);
+        println!(
+            "{:>9}: {:>9} allocations, {:>6.1} per card{}",
+            label,
+            after - before,
+            (after - before) as f64 / items as f64,
+            change(&baseline, &label, after - before)
+        );
+        counts.push((label, after - before));
+    }
+    // The same random trees every run, so the counts can be compared.
+    for seed in 0..3 {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let node =
+            layout(vec![], random_tree::<Msg>(seed, &StressConfig::default()));
+        let after = ALLOCATIONS.load(Ordering::Relaxed);
+        drop(node);
+        let label = format!("random tree {}", seed);
+        println!(
+            "{}: {:>9} allocations{}",
+            label,
+            after - before,
+            change(&baseline, &label, after - before)
+        );
+        counts.push((label, after - before));
+    }
+    const RUNS: u32 = 1000;
+    for count in [10, 50, 100] {
+        let attrs = many_attributes(count);
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let element = el(attrs.clone(), Element::Empty);
+        let after = ALLOCATIONS.load(Ordering::Relaxed);
+        drop(element);
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            drop(el(attrs.clone(), Element::Empty));
+        }
+        let label = format!("{} attributes", count);
+        println!(
+            "{:>14}: {:>6} allocations, {:>7.1?} per element{}",
+            label,
+            after - before,
+            start.elapsed() / RUNS,
+            change(&baseline, &label, after - before)
+        );
+        counts.push((label, after - before));
+    }
+    if save {
+        let lines: Vec<String> = counts
+            .iter()
+            .map(|(label, count)| format!("{} {}", label, count))
+            .collect();
+        fs::write(BASELINE, lines.join("\n")).unwrap();
+        println!("Saved as the baseline.");
+    }
+}
@@ -0,0 +1,148 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+
+use crate::{
+    element::{el, in_front, move_down, move_right},
+    model::{Attribute, Element},
+    vdom,
+};
+
+/// Something in the world an element can follow on screen, like a unit
+/// with a health bar over it. With the `bevy` feature, any `Entity` is
+/// one.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Anchor(pub u64);
+
+#[cfg(feature = "bevy")]
+impl From<bevy::prelude::Entity> for Anchor {
+    fn from(entity: bevy::prelude::Entity) -> Self {
+        Anchor(entity.to_bits())
+    }
+}
+
+/// Finds where a point in the world is on screen, usually with the game's
+/// camera's `world_to_viewport`. Anchored elements are only shown once
+/// it's inserted.
+#[cfg(feature = "bevy")]
+#[derive(bevy::prelude::Resource, Clone, Copy)]
+pub struct WorldToScreen(
+    pub  fn(
+        &bevy::prelude::World,
+        bevy::prelude::Vec3,
+    ) -> Option<bevy::prelude::Vec2>,
+);
+
+// Where a program's anchors are on screen, and which its last view used.
+#[derive(Default)]
+pub(crate) struct Anchors {
+    positions: BTreeMap<Anchor, (f32, f32)>,
+    used: BTreeSet<Anchor>,
+}
+
+thread_local! {
+    // The anchors of the program whose view is being built.
+    static VIEWING: RefCell<Option<Anchors>> = const { RefCell::new(None) };
+}
+
+impl Anchors {
+    // Build a view with `view`, lending it these anchors for
+    // `world_anchored`, and keeping only the ones it uses.
+    pub(crate) fn view<T>(&mut self, view: impl FnOnce() -> T) -> T {
+        self.used.clear();
+        let lent = Some(mem::take(self));
+        // A program built inside another's view has its own anchors.
+        let outer = VIEWING.with(|viewing| viewing.replace(lent));
+        let built = view();
+        *self = VIEWING
+            .with(|viewing| viewing.replace(outer))
+            .unwrap_or_default();
+        built
+    }
+
+    pub(crate) fn used(&self) -> Vec<Anchor> {
+        self.used.iter().copied().collect()
+    }
+
+    pub(crate) fn position(&self, anchor: Anchor) -> Option<(f32, f32)> {
+        self.positions.get(&anchor).copied()
+    }
+
+    // Set where each anchor is on screen, `None` for those out of view,
+    // forgetting the rest. Returns whether any moved.
+    pub(crate) fn set_positions(
+        &mut self,
+        positions: impl IntoIterator<Item = (Anchor, Option<(f32, f32)>)>,
+    ) -> bool {
+        let positions: BTreeMap<_, _> = positions
+            .into_iter()
+            .filter_map(|(anchor, position)| Some((anchor, position?)))
+            .collect();
+        let moved = self.positions != positions;
+        self.positions = positions;
+        moved
+    }
+}
+
+/// Show `element` over the UI where `anchor` is on screen, moved by
+/// `offset`, e.g. a name tag over a character. It's hidden while the
+/// anchor is out of view.
+///
+/// The backend finds where the anchors of a `Program`'s view are each
+/// frame, with `Program::set_anchor_positions`, rendering it again as they
+/// move. Outside a program's view, it's never shown.
+pub fn world_anchored<Msg>(
+    anchor: impl Into<Anchor>,
+    offset: (f32, f32),
+    element: Element<Msg>,
+) -> Attribute<Msg> {
+    let anchor = anchor.into();
+    let position = VIEWING.with(|viewing| {
+        let mut viewing = viewing.borrow_mut();
+        let anchors = viewing.as_mut()?;
+        anchors.used.insert(anchor);
+        anchors.position(anchor)
+    });
+    match position {
+        Some((x, y)) => in_front(el(
+            vec![Attribute::Attr(vdom::Attribute::Class("modal".to_string()))],
+            el(
+                vec![move_right(x + offset.0), move_down(y + offset.1)],
+                element,
+            ),
+        )),
+        None => Attribute::None,
+    }
+}
+
+#[test]
+fn test_world_anchored() {
+    use crate::{app::Program, cmd::Cmd};
+
+    let anchor = Anchor(7);
+    let tag = |_: &()| {
+        el(
+            vec![world_anchored(Anchor(7), (0.0, -20.0), Element::Empty)],
+            Element::Empty,
+        )
+    };
+    let shown = |program: &mut Program<(), ()>| {
+        let html = crate::render::to_html(program.render());
+        html.contains(" modal\"")
+    };
+    let mut program = Program::new(|| (), |_, _| Cmd::none(), tag);
+    assert_eq!(program.anchors(), vec![anchor]);
+    assert!(!shown(&mut program));
+    assert!(program.set_anchor_positions(vec![(anchor, Some((100.0, 50.0)))]));
+    assert!(!program.set_anchor_positions(vec![(anchor, Some((100.0, 50.0)))]));
+    assert_eq!(program.anchor_position(anchor), Some((100.0, 50.0)));
+    assert!(shown(&mut program));
+
+    // Each program has its own positions, and outside of one there are none.
+    let mut other = Program::new(|| (), |_, _| Cmd::none(), tag);
+    assert!(!shown(&mut other));
+    assert!(matches!(
+        world_anchored::<()>(anchor, (0.0, -20.0), Element::Empty),
+        Attribute::None
+    ));
+}
@@ -0,0 +1,113 @@
+use crate::{
+    element::{column, el, visually_hidden},
+    model::{Attribute, Description, Element},
+    vdom,
+};
+
+/// How urgently a screen reader should read out an announcement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Politeness {
+    /// Wait until the user is idle before reading the message.
+    Polite,
+    /// Interrupt whatever is currently being read.
+    Assertive,
+}
+
+/// Holds the live regions used to announce transient status messages,
+/// e.g. "Saved" or "3 results found", to screen readers.
+///
+/// Screen readers only pick up changes to a live region that was already
+/// in the document, so `view` should be rendered on every frame, usually
+/// as the last child of the root, whether or not there is anything to say.
+#[derive(Debug, Clone, Default)]
+pub struct Announcer {
+    polite: String,
+    assertive: String,
+    pad: bool,
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `text` to be read out the next time the announcer is rendered.
+    pub fn announce(&mut self, text: String, politeness: Politeness) {
+        // Repeating the exact same text is not a change, so it would be
+        // ignored. Alternating a trailing non-breaking space makes sure
+        // the same message can be announced twice in a row.
+        self.pad = !self.pad;
+        let text = if self.pad {
+            format!("{}\u{a0}", text)
+        } else {
+            text
+        };
+
+        match politeness {
+            Politeness::Polite => {
+                self.polite = text;
+                self.assertive.clear();
+            }
+            Politeness::Assertive => {
+                self.assertive = text;
+                self.polite.clear();
+            }
+        }
+    }
+
+    /// Empty both regions so stale messages aren't found when browsing.
+    pub fn clear(&mut self) {
+        self.polite.clear();
+        self.assertive.clear();
+    }
+
+    /// The hidden live regions. They take up no space in the layout.
+    pub fn view<Msg>(&self) -> Element<Msg> {
+        column(
+            vec![visually_hidden()],
+            vec![
+                region(Description::LivePolite, self.polite.clone()),
+                region(Description::LiveAssertive, self.assertive.clone()),
+            ],
+        )
+    }
+}
+
+fn region<Msg>(description: Description, content: String) -> Element<Msg> {
+    el(
+        vec![
+            Attribute::Describe(description),
+            Attribute::Attr(vdom::Attribute::property("aria-atomic", "true")),
+        ],
+        Element::Text(content),
+    )
+}
+
+#[test]
+fn test_announcer() {
+    use crate::element::layout;
+    use crate::render::to_html;
+
+    let render = |announcer: &Announcer| {
+        to_html(&layout::<()>(vec![], announcer.view()))
+    };
+    let mut announcer = Announcer::new();
+    let quiet = render(&announcer);
+    assert!(quiet.contains("aria-live=\"polite\""), "{}", quiet);
+    assert!(quiet.contains("aria-live=\"assertive\""), "{}", quiet);
+
+    announcer.announce("Saved".to_string(), Politeness::Assertive);
+    let first = render(&announcer);
+    let polite = first.find("aria-live=\"polite\"").unwrap();
+    let assertive = first.find("aria-live=\"assertive\"").unwrap();
+    let saved = first.find("Saved").unwrap();
+    assert!(polite < assertive && assertive < saved, "{}", first);
+
+    // The same message again still changes the region, so it's read out.
+    announcer.announce("Saved".to_string(), Politeness::Assertive);
+    let second = render(&announcer);
+    assert!(second.contains("Saved") && second != first);
+
+    announcer.clear();
+    assert!(!render(&announcer).contains("Saved"));
+}
@@ -0,0 +1,1355 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    anchor::Anchors,
+    budget::FrameBudget,
+    cmd::Cmd,
+    element::layout_cached_with,
+    events::Gestures,
+    focus::{FocusState, NavInput},
+    haptic,
+    i18n::locale_dir,
+    keyboard::{self, KeyboardKind, VirtualKey, VirtualKeyboard},
+    lazy,
+    model::{Element, Opt, RenderCache},
+    sound::{self, SoundMap},
+    style::Classes,
+    sub::{Sub, Visibility},
+    vdom::{self, EventData, Node, Rate, Rects},
+};
+
+// Taken or returned by `Program`, from subsystems otherwise only public
+// with the `experimental` feature.
+pub use crate::{
+    anchor::Anchor,
+    budget::Degradation,
+    haptic::HapticPattern,
+    sound::{Interaction, UiSound},
+};
+
+// A debounced or throttled listener's message waiting to be sent, with
+// when it's due, or for a throttled one, when it last sent one.
+struct Held<Msg> {
+    rate: Rate,
+    msg: Option<Msg>,
+    at: Duration,
+}
+
+/// The Elm Architecture: a `Model` holding all of the UI's state, a `view`
+/// rendering it, and an `update` applying the messages sent by the
+/// view's event attributes. `update` can also ask for async work to be
+/// done by returning a `Cmd`.
+pub struct Program<Model, Msg> {
+    model: Model,
+    update: fn(&mut Model, Msg) -> Cmd<Msg>,
+    view: fn(&Model) -> Element<Msg>,
+    subscriptions: fn(&Model) -> Vec<Sub<Msg>>,
+    node: Node<Msg>,
+    dirty: bool,
+    // Time elapsed towards the next tick, for each `every` interval.
+    timers: HashMap<Duration, Duration>,
+    // The last path seen on each navigator subscribed to.
+    paths: HashMap<usize, String>,
+    focus: FocusState,
+    cache: RenderCache<Msg>,
+    anchors: Anchors,
+    opts: Vec<Opt>,
+    // The time passed to `tick` so far.
+    clock: Duration,
+    // Keyed by the listener's path, and its index among the node's.
+    held: HashMap<(Vec<usize>, usize), Held<Msg>>,
+    gestures: Gestures,
+    pause_when_idle: bool,
+    hidden: bool,
+    unfocused: bool,
+    budget: Option<FrameBudget>,
+    // Renders skipped at `Degradation::Minimal` since the last one.
+    skipped: u32,
+    keyboard: Option<Box<dyn VirtualKeyboard>>,
+    // What the focused text input wants from an on-screen keyboard.
+    keyboard_kind: Option<KeyboardKind>,
+    // The text input the on-screen keyboard types into, kept while focus
+    // is on the built-in keyboard's keys.
+    keyboard_input: Option<Vec<usize>>,
+    // Played as elements are interacted with, until taken by the app.
+    sounds: Vec<UiSound>,
+    haptics: Vec<HapticPattern>,
+}
+
+impl<Model, Msg> Program<Model, Msg> {
+    pub fn new(
+        init: fn() -> Model,
+        update: fn(&mut Model, Msg) -> Cmd<Msg>,
+        view: fn(&Model) -> Element<Msg>,
+    ) -> Self {
+        let model = init();
+        let mut focus = FocusState::default();
+        let mut cache = RenderCache::default();
+        let mut anchors = Anchors::default();
+        let element = anchors.view(|| view(&model));
+        let mut node =
+            layout_cached_with(&mut cache, vec![], vec![locale_dir()], element);
+        focus.sync(&mut node);
+        Self {
+            model,
+            update,
+            view,
+            subscriptions: |_| vec![],
+            node,
+            dirty: false,
+            timers: HashMap::new(),
+            paths: HashMap::new(),
+            focus,
+            cache,
+            anchors,
+            opts: vec![],
+            clock: Duration::ZERO,
+            held: HashMap::new(),
+            gestures: Gestures::default(),
+            pause_when_idle: false,
+            hidden: false,
+            unfocused: false,
+            budget: None,
+            skipped: 0,
+            keyboard: None,
+            keyboard_kind: None,
+            keyboard_input: None,
+            sounds: vec![],
+            haptics: vec![],
+        }
+    }
+
+    /// Render the view with `opts`, like `layout_with`.
+    pub fn with_options(mut self, opts: Vec<Opt>) -> Self {
+        self.opts = opts;
+        self.dirty = true;
+        self
+    }
+
+    pub fn with_subscriptions(
+        mut self,
+        subscriptions: fn(&Model) -> Vec<Sub<Msg>>,
+    ) -> Self {
+        self.subscriptions = subscriptions;
+        self
+    }
+
+    /// Stop rendering while the window is hidden or another window has
+    /// focus, saving the battery for tool-style apps left open in the
+    /// background. Messages still update the model, and the view catches
+    /// up as soon as the window's visible and focused again.
+    pub fn pause_when_idle(mut self, pause: bool) -> Self {
+        self.pause_when_idle = pause;
+        self
+    }
+
+    /// Cut back on transitions and rendering while the UI takes longer
+    /// than `budget` each frame, as reported by the backend through
+    /// `frame_time`.
+    pub fn with_frame_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(FrameBudget::new(budget));
+        self
+    }
+
+    /// Show the platform's on-screen `keyboard` while a text input has
+    /// focus.
+    pub fn with_virtual_keyboard(
+        mut self,
+        keyboard: impl VirtualKeyboard + 'static,
+    ) -> Self {
+        self.keyboard = Some(Box::new(keyboard));
+        self
+    }
+
+    /// How much the UI is currently cutting back.
+    pub fn degradation(&self) -> Degradation {
+        self.budget
+            .as_ref()
+            .map_or(Degradation::Full, FrameBudget::level)
+    }
+
+    /// Record how long the UI's work took in the last frame, returning the
+    /// messages of the `on_degradation_change` subscriptions if that
+    /// changes how much it cuts back.
+    pub fn frame_time(&mut self, elapsed: Duration) -> Vec<Msg> {
+        let level = match self.budget.as_mut().and_then(|b| b.report(elapsed)) {
+            Some(level) => level,
+            None => return vec![],
+        };
+        // The root's class changes.
+        self.dirty = true;
+        self.subscriptions()
+            .iter()
+            .filter_map(|sub| match sub {
+                Sub::DegradationChange(f) => Some(f(level)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the model has changed since the view was last rendered.
+    pub fn needs_render(&self) -> bool {
+        self.dirty
+    }
+
+    /// Whether rendering is paused, so the backend can skip its own work.
+    pub fn is_idle(&self) -> bool {
+        self.pause_when_idle && (self.hidden || self.unfocused)
+    }
+
+    /// Tell the program whether its window has keyboard focus.
+    pub fn window_focused(&mut self, focused: bool) {
+        self.unfocused = !focused;
+    }
+
+    pub fn subscriptions(&self) -> Vec<Sub<Msg>> {
+        (self.subscriptions)(&self.model)
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// The most recently rendered view.
+    pub fn node(&self) -> &Node<Msg> {
+        &self.node
+    }
+
+    /// The messages produced by the `event` listeners on the node at
+    /// `path`, a list of child indices starting from the root node.
+    ///
+    /// The messages of `debounced` and `throttled` listeners may be held
+    /// back, to be returned by a later `tick`. A `pointerup` reported with
+    /// `EventData::Pointer` is given to listeners as `EventData::Release`,
+    /// with where this program last saw the pointer go down, and a `click`
+    /// as `EventData::Click`, with how far apart the clicks before it were
+    /// by the program's clock.
+    pub fn dispatch(
+        &mut self,
+        path: &[usize],
+        event: &str,
+        data: &EventData,
+    ) -> Vec<Msg> {
+        let data = self.gestures.see(path, event, data, self.clock);
+        let mut msgs = vec![];
+        for (i, rate, msg) in self.node.dispatch_rated(path, event, &data) {
+            let key = (path.to_vec(), i);
+            match rate {
+                Rate::Every => msgs.push(msg),
+                Rate::Debounce(wait) => {
+                    let held = Held {
+                        rate,
+                        msg: Some(msg),
+                        at: self.clock + wait,
+                    };
+                    self.held.insert(key, held);
+                }
+                Rate::Throttle(gap) => match self.held.get_mut(&key) {
+                    Some(held) if self.clock < held.at + gap => {
+                        held.msg = Some(msg);
+                    }
+                    _ => {
+                        let held = Held {
+                            rate,
+                            msg: None,
+                            at: self.clock,
+                        };
+                        self.held.insert(key, held);
+                        msgs.push(msg);
+                    }
+                },
+            }
+        }
+        msgs
+    }
+
+    // The sounds set with `interaction_sounds`.
+    fn sound_map(&self) -> Option<&SoundMap> {
+        self.opts.iter().find_map(|opt| match opt {
+            Opt::InteractionSounds(sounds) => Some(sounds),
+            _ => None,
+        })
+    }
+
+    /// Whether the view is rendered with `shadow_root`, so the web backend
+    /// mounts it in a shadow root.
+    pub fn in_shadow_root(&self) -> bool {
+        self.opts.contains(&Opt::ShadowRoot)
+    }
+
+    /// Whether the view plays any sounds, so the backend knows to report
+    /// hovers and clicks with `interacted`.
+    pub fn plays_sounds(&self) -> bool {
+        self.sound_map().is_some() || sound::has_sounds(&self.node)
+    }
+
+    /// Whether the view has any `haptic_on_press` elements, so the backend
+    /// knows to report clicks with `interacted`.
+    pub fn plays_haptics(&self) -> bool {
+        haptic::has_haptics(&self.node)
+    }
+
+    /// Play the sound and haptic pattern, if any, for `interaction` with
+    /// the node at `path`, where a hover or click started. Focus plays its
+    /// sound through `focus`.
+    pub fn interacted(&mut self, path: &[usize], interaction: Interaction) {
+        let sound =
+            sound::sound_for(&self.node, path, interaction, self.sound_map());
+        if let Some(sound) = sound {
+            self.sounds.push(UiSound { sound, interaction });
+        }
+        if interaction == Interaction::Click {
+            self.haptics.extend(haptic::haptic_for(&self.node, path));
+        }
+    }
+
+    /// The sounds to play since this was last called.
+    pub fn take_sounds(&mut self) -> Vec<UiSound> {
+        std::mem::take(&mut self.sounds)
+    }
+
+    /// The haptic patterns to play since this was last called.
+    pub fn take_haptics(&mut self) -> Vec<HapticPattern> {
+        std::mem::take(&mut self.haptics)
+    }
+
+    /// The path of the node with keyboard focus.
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focus.focused()
+    }
+
+    /// Move keyboard focus to the node at `path`, returning the messages
+    /// of the `blur` listeners on the node losing it and the `focus`
+    /// listeners on the one gaining it.
+    pub fn focus(&mut self, path: &[usize]) -> Vec<Msg> {
+        let mut msgs = match self.focus.focused() {
+            Some(old) if old == path => return vec![],
+            Some(old) => self.node.dispatch(old, "blur", &EventData::None),
+            None => vec![],
+        };
+        self.focus.focus(&self.node, path);
+        self.focus.sync(&mut self.node);
+        self.interacted(path, Interaction::Focus);
+        msgs.extend(self.node.dispatch(path, "focus", &EventData::None));
+        msgs.extend(self.keyboard_changed());
+        msgs
+    }
+
+    // Show or hide the on-screen keyboard if focus has moved to or from a
+    // text input, returning the messages of the `on_virtual_keyboard`
+    // subscriptions. Focusing the built-in keyboard's keys leaves it be.
+    fn keyboard_changed(&mut self) -> Vec<Msg> {
+        let focused = self.focus.focused();
+        if focused.is_some_and(|path| keyboard::in_keyboard(&self.node, path)) {
+            return vec![];
+        }
+        let kind =
+            focused.and_then(|path| keyboard::keyboard_kind(&self.node, path));
+        self.keyboard_input = kind.and(focused.map(<[usize]>::to_vec));
+        if kind == self.keyboard_kind {
+            return vec![];
+        }
+        self.keyboard_kind = kind;
+        if let Some(keyboard) = &mut self.keyboard {
+            match kind {
+                Some(kind) => keyboard.show(kind),
+                None => keyboard.hide(),
+            }
+        }
+        self.subscriptions()
+            .iter()
+            .filter_map(|sub| match sub {
+                Sub::VirtualKeyboard(f) => Some(f(kind)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Type a key from an on-screen keyboard into the text input it was
+    /// shown for, even while one of the built-in keyboard's keys has focus,
+    /// returning the messages of its `input` listeners with its new text,
+    /// or for `Enter`, of its `keydown` listeners.
+    pub fn virtual_key(&mut self, key: VirtualKey) -> Vec<Msg> {
+        let path = match &self.keyboard_input {
+            Some(path) => path.clone(),
+            None => return vec![],
+        };
+        if key == VirtualKey::Enter {
+            let data = EventData::Key {
+                key: "Enter".to_string(),
+                shift: false,
+            };
+            return self.dispatch(&path, "keydown", &data);
+        }
+        let mut text = self
+            .node
+            .at(&path)
+            .and_then(|node| node.attr("value"))
+            .unwrap_or_default()
+            .to_string();
+        key.apply(&mut text);
+        let data = EventData::Text(text);
+        (0..=path.len())
+            .rev()
+            .flat_map(|len| self.dispatch(&path[..len], "input", &data))
+            .collect()
+    }
+
+    /// Handle a key press: moving focus for `Tab` and within roving groups,
+    /// and otherwise sending it to the `keydown` listeners of the focused
+    /// node.
+    pub fn key_down(&mut self, key: &str, shift: bool) -> Vec<Msg> {
+        // Worked out on a copy, so `focus` sees where focus moved from.
+        let mut focus = self.focus.clone();
+        if focus.key_down(&self.node, key, shift) {
+            match focus.focused() {
+                Some(path) => {
+                    let path = path.to_vec();
+                    self.focus(&path)
+                }
+                None => vec![],
+            }
+        } else {
+            let data = EventData::Key {
+                key: key.to_string(),
+                shift,
+            };
+            match self.focus.focused() {
+                Some(path) => {
+                    let path = path.to_vec();
+                    self.dispatch(&path, "keydown", &data)
+                }
+                None => vec![],
+            }
+        }
+    }
+
+    /// Handle input from a gamepad or other controller: moving focus to the
+    /// nearest element by where the view was laid out in `rects`, or
+    /// clicking or pressing `Escape` on the focused element. The messages
+    /// of the listeners are returned, as for `dispatch`.
+    pub fn navigate(&mut self, rects: &Rects, input: NavInput) -> Vec<Msg> {
+        match input {
+            NavInput::Move(direction) => {
+                let mut focus = self.focus.clone();
+                if !focus.move_spatially(&self.node, rects, direction) {
+                    return vec![];
+                }
+                match focus.focused() {
+                    Some(path) => {
+                        let path = path.to_vec();
+                        self.focus(&path)
+                    }
+                    None => vec![],
+                }
+            }
+            NavInput::Activate => {
+                let path = match self.focus.focused() {
+                    Some(path) => path.to_vec(),
+                    None => return vec![],
+                };
+                self.interacted(&path, Interaction::Click);
+                // Bubbling up from the focused node, like a real click.
+                (0..=path.len())
+                    .rev()
+                    .flat_map(|len| {
+                        self.dispatch(&path[..len], "click", &EventData::None)
+                    })
+                    .collect()
+            }
+            NavInput::Back => self.key_down("Escape", false),
+        }
+    }
+
+    /// Apply a message to the model. The view is only rendered again on
+    /// the next call to `render`, so a batch of messages renders once.
+    ///
+    /// The returned command is left for the caller to run on an executor.
+    pub fn update(&mut self, msg: Msg) -> Cmd<Msg> {
+        self.dirty = true;
+        (self.update)(&mut self.model, msg)
+    }
+
+    /// The anchors the last view used with `world_anchored`, for the
+    /// backend to find on screen.
+    pub fn anchors(&self) -> Vec<Anchor> {
+        self.anchors.used()
+    }
+
+    /// Where `anchor` was last set to be on screen, in logical pixels from
+    /// the top left of the window.
+    pub fn anchor_position(&self, anchor: Anchor) -> Option<(f32, f32)> {
+        self.anchors.position(anchor)
+    }
+
+    /// Set where each anchor is on screen, `None` for those out of view,
+    /// forgetting the rest. If any moved, the view is rendered again on
+    /// the next call to `render`, and this returns `true`.
+    pub fn set_anchor_positions(
+        &mut self,
+        positions: impl IntoIterator<Item = (Anchor, Option<(f32, f32)>)>,
+    ) -> bool {
+        let moved = self.anchors.set_positions(positions);
+        self.dirty |= moved;
+        moved
+    }
+
+    /// Render the view again on the next call to `render`, even if the
+    /// model hasn't changed, e.g. because the translator has.
+    pub fn refresh(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Advance the `every` subscriptions by `delta`, returning the messages
+    /// of those that are due, then those of `debounced` and `throttled`
+    /// listeners that are. An interval fires at most once per call.
+    pub fn tick(&mut self, delta: Duration) -> Vec<Msg> {
+        let subs = self.subscriptions();
+        let mut timers = HashMap::new();
+        let mut msgs = vec![];
+        for sub in subs.iter() {
+            if let Sub::Every(interval, _) = sub {
+                if timers.contains_key(interval) {
+                    continue;
+                }
+                // Unsubscribed intervals are dropped, so they start over
+                // if they're subscribed to again.
+                let elapsed =
+                    self.timers.get(interval).copied().unwrap_or_default()
+                        + delta;
+                let elapsed = if elapsed >= *interval {
+                    msgs.extend(subs.iter().filter_map(|s| match s {
+                        Sub::Every(i, f) if i == interval => Some(f()),
+                        _ => None,
+                    }));
+                    (elapsed - *interval).min(*interval)
+                } else {
+                    elapsed
+                };
+                timers.insert(*interval, elapsed);
+            }
+        }
+        self.timers = timers;
+        msgs.extend(self.release(delta));
+        msgs
+    }
+
+    // The held messages that are due after `delta` more time, oldest first.
+    fn release(&mut self, delta: Duration) -> Vec<Msg> {
+        self.clock += delta;
+        let clock = self.clock;
+        let mut due = vec![];
+        self.held.retain(|_, held| match held.rate {
+            Rate::Throttle(gap) if clock >= held.at + gap => {
+                match held.msg.take() {
+                    Some(msg) => {
+                        due.push((held.at + gap, msg));
+                        held.at = clock;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Rate::Debounce(_) if clock >= held.at => {
+                due.extend(held.msg.take().map(|msg| (held.at, msg)));
+                false
+            }
+            _ => true,
+        });
+        due.sort_by_key(|(at, _)| *at);
+        due.into_iter().map(|(_, msg)| msg).collect()
+    }
+
+    /// The messages of the `on_resize` subscriptions.
+    pub fn resized(&self, width: f32, height: f32) -> Vec<Msg> {
+        self.subscriptions()
+            .iter()
+            .filter_map(|sub| match sub {
+                Sub::Resize(f) => Some(f(width, height)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The messages of the `on_visibility_change` subscriptions.
+    pub fn visibility_changed(&mut self, visibility: Visibility) -> Vec<Msg> {
+        self.hidden = visibility == Visibility::Hidden;
+        self.subscriptions()
+            .iter()
+            .filter_map(|sub| match sub {
+                Sub::VisibilityChange(f) => Some(f(visibility)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The messages of the `on_route_change` subscriptions whose navigator
+    /// has moved to a new path since the last call.
+    pub fn route_changes(&mut self) -> Vec<Msg> {
+        let subs = self.subscriptions();
+        let mut paths = HashMap::new();
+        let mut msgs = vec![];
+        for sub in subs.iter() {
+            if let Sub::RouteChange(navigator, f) = sub {
+                let path = paths
+                    .entry(navigator.id())
+                    .or_insert_with(|| navigator.path());
+                // A navigator's first path is the one `init` started with.
+                match self.paths.get(&navigator.id()) {
+                    Some(last) if last != path => msgs.push(f(path)),
+                    _ => {}
+                }
+            }
+        }
+        self.paths = paths;
+        msgs
+    }
+
+    /// Render the view again if the model has changed since the last time,
+    /// unless it's paused by `pause_when_idle`, or it was rendered by the
+    /// last call at `Degradation::Minimal`.
+    pub fn render(&mut self) -> &Node<Msg> {
+        let degradation = self.degradation();
+        if degradation == Degradation::Minimal && self.skipped == 0 {
+            self.skipped += 1;
+            return &self.node;
+        }
+        if self.dirty && !self.is_idle() {
+            self.skipped = 0;
+            self.cache.next_frame();
+            lazy::next_frame();
+            let (view, model) = (self.view, &self.model);
+            let element = self.anchors.view(|| view(model));
+            self.node = layout_cached_with(
+                &mut self.cache,
+                self.opts.clone(),
+                vec![locale_dir()],
+                element,
+            );
+            self.focus.sync(&mut self.node);
+            if degradation > Degradation::Full {
+                let reduced = Classes::Reduced.to_string().to_string();
+                self.node.attrs.push(vdom::Attribute::Class(reduced));
+            }
+            self.dirty = false;
+        }
+        &self.node
+    }
+}
+
+#[cfg(feature = "bevy")]
+pub use self::bevy_app::{
+    run_ui, UiEvent, UiMessage, UiPlugin, UiProgram, UiRects,
+};
+
+#[cfg(feature = "bevy")]
+mod bevy_app {
+    use std::any::{Any, TypeId};
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use bevy::input::gamepad::{
+        Gamepad, GamepadButton, GamepadRumbleIntensity, GamepadRumbleRequest,
+    };
+    use bevy::prelude::*;
+    use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+    use bevy::window::{WindowFocused, WindowOccluded, WindowResized};
+
+    use super::Program;
+    use crate::{
+        anchor::WorldToScreen,
+        cmd::Cmd,
+        focus::{NavDirection, NavInput},
+        i18n::{set_translator, Translations},
+        model::{Element, Opt},
+        sound::{Interaction, UiSound},
+        sub::{Sub, Visibility},
+        vdom::{EventData, Rects},
+    };
+
+    /// An interaction reported by a backend, e.g. a click on the node
+    /// found by following `path` from the root.
+    #[derive(Event, Debug, Clone)]
+    pub struct UiEvent {
+        pub path: Vec<usize>,
+        pub event: String,
+        pub data: EventData,
+    }
+
+    /// A message produced by one of the view's event attributes, queued
+    /// until it is fed to `update`.
+    #[derive(Event, Debug, Clone)]
+    pub struct UiMessage<Msg: Send + Sync + 'static>(pub Msg);
+
+    #[derive(Resource)]
+    pub struct UiProgram<Model, Msg>(pub Program<Model, Msg>);
+
+    // Commands that are still running.
+    #[derive(Resource)]
+    struct UiTasks<Msg>(Vec<Task<Option<Msg>>>);
+
+    // Where each subscribed Bevy event type was last read up to.
+    #[derive(Resource, Default)]
+    struct BridgeCursors(HashMap<TypeId, Box<dyn Any + Send + Sync>>);
+
+    /// Where the UI was last laid out, kept up to date by the renderer, for
+    /// moving focus between elements with a gamepad.
+    #[derive(Resource, Default)]
+    pub struct UiRects(pub Rects);
+
+    // How long `update_ui` took last frame, for the frame budget.
+    #[derive(Resource, Default)]
+    struct UiFrameTime(Option<Duration>);
+
+    pub struct UiPlugin<Model, Msg> {
+        init: fn() -> Model,
+        update: fn(&mut Model, Msg) -> Cmd<Msg>,
+        view: fn(&Model) -> Element<Msg>,
+        subscriptions: fn(&Model) -> Vec<Sub<Msg>>,
+        opts: Vec<Opt>,
+        pause_when_idle: bool,
+        frame_budget: Option<Duration>,
+    }
+
+    impl<Model, Msg> UiPlugin<Model, Msg> {
+        pub fn new(
+            init: fn() -> Model,
+            update: fn(&mut Model, Msg) -> Cmd<Msg>,
+            view: fn(&Model) -> Element<Msg>,
+        ) -> Self {
+            Self {
+                init,
+                update,
+                view,
+                subscriptions: |_| vec![],
+                opts: vec![],
+                pause_when_idle: false,
+                frame_budget: None,
+            }
+        }
+
+        pub fn with_subscriptions(
+            mut self,
+            subscriptions: fn(&Model) -> Vec<Sub<Msg>>,
+        ) -> Self {
+            self.subscriptions = subscriptions;
+            self
+        }
+
+        /// Render the view with `opts`, like `Program::with_options`.
+        pub fn with_options(mut self, opts: Vec<Opt>) -> Self {
+            self.opts = opts;
+            self
+        }
+
+        /// Stop rendering while the window is hidden or unfocused, like
+        /// `Program::pause_when_idle`.
+        pub fn pause_when_idle(mut self) -> Self {
+            self.pause_when_idle = true;
+            self
+        }
+
+        /// Cut back while the UI takes longer than `budget` each frame,
+        /// like `Program::with_frame_budget`.
+        pub fn with_frame_budget(mut self, budget: Duration) -> Self {
+            self.frame_budget = Some(budget);
+            self
+        }
+    }
+
+    impl<Model, Msg> Plugin for UiPlugin<Model, Msg>
+    where
+        Model: Send + Sync + 'static,
+        Msg: Clone + Send + Sync + 'static,
+    {
+        fn build(&self, app: &mut App) {
+            let mut program = Program::new(self.init, self.update, self.view)
+                .with_subscriptions(self.subscriptions)
+                .with_options(self.opts.clone())
+                .pause_when_idle(self.pause_when_idle);
+            if let Some(budget) = self.frame_budget {
+                program = program.with_frame_budget(budget);
+            }
+            app.add_event::<UiEvent>()
+                .add_event::<UiMessage<Msg>>()
+                .add_event::<UiSound>()
+                .add_event::<GamepadRumbleRequest>()
+                .init_resource::<BridgeCursors>()
+                .init_resource::<UiFrameTime>()
+                .init_resource::<UiRects>()
+                .insert_resource(UiTasks::<Msg>(vec![]))
+                .insert_resource(UiProgram(program))
+                .add_systems(
+                    Update,
+                    (
+                        dispatch_ui_events::<Model, Msg>,
+                        gamepad_navigation::<Model, Msg>,
+                        interaction_sounds::<Model, Msg>,
+                        haptic_feedback::<Model, Msg>,
+                        tick_subscriptions::<Model, Msg>,
+                        window_subscriptions::<Model, Msg>,
+                        frame_budget::<Model, Msg>,
+                        route_subscriptions::<Model, Msg>,
+                        bridge_subscriptions::<Model, Msg>,
+                        poll_ui_tasks::<Msg>,
+                        sync_translations::<Model, Msg>,
+                        anchor_positions::<Model, Msg>,
+                        update_ui::<Model, Msg>,
+                    )
+                        .chain(),
+                );
+        }
+    }
+
+    fn dispatch_ui_events<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut events: EventReader<UiEvent>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        for event in events.read() {
+            if let Some(interaction) = Interaction::from_event(&event.event) {
+                program.0.interacted(&event.path, interaction);
+            }
+            // Focus is tracked here, so key presses go wherever it is
+            // rather than to `path`.
+            let msgs = match (event.event.as_str(), &event.data) {
+                ("keydown", EventData::Key { key, shift }) => {
+                    program.0.key_down(key, *shift)
+                }
+                ("focus", _) => program.0.focus(&event.path),
+                _ => program.0.dispatch(&event.path, &event.event, &event.data),
+            };
+            for msg in msgs {
+                messages.send(UiMessage(msg));
+            }
+        }
+    }
+
+    // How far a stick has to be pushed to move focus. It has to come back
+    // under this before it moves focus again.
+    const STICK_THRESHOLD: f32 = 0.5;
+
+    fn stick_direction(stick: Vec2) -> Option<NavDirection> {
+        if stick.length() < STICK_THRESHOLD {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x > 0.0 {
+                NavDirection::Right
+            } else {
+                NavDirection::Left
+            })
+        } else {
+            // Up is positive on a stick.
+            Some(if stick.y > 0.0 {
+                NavDirection::Up
+            } else {
+                NavDirection::Down
+            })
+        }
+    }
+
+    // Move focus with the D-pad and left stick of any gamepad, clicking
+    // with the south button (A on Xbox) and going back with the east one.
+    fn gamepad_navigation<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        rects: Res<UiRects>,
+        gamepads: Query<(Entity, &Gamepad)>,
+        mut sticks: Local<HashMap<Entity, Option<NavDirection>>>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        let buttons = [
+            (GamepadButton::DPadUp, NavInput::Move(NavDirection::Up)),
+            (GamepadButton::DPadDown, NavInput::Move(NavDirection::Down)),
+            (GamepadButton::DPadLeft, NavInput::Move(NavDirection::Left)),
+            (
+                GamepadButton::DPadRight,
+                NavInput::Move(NavDirection::Right),
+            ),
+            (GamepadButton::South, NavInput::Activate),
+            (GamepadButton::East, NavInput::Back),
+        ];
+        for (entity, gamepad) in &gamepads {
+            let mut inputs: Vec<NavInput> = buttons
+                .iter()
+                .filter(|(button, _)| gamepad.just_pressed(*button))
+                .map(|(_, input)| *input)
+                .collect();
+            let stick = stick_direction(gamepad.left_stick());
+            if stick.is_some() && sticks.get(&entity) != Some(&stick) {
+                inputs.extend(stick.map(NavInput::Move));
+            }
+            sticks.insert(entity, stick);
+            for input in inputs {
+                for msg in program.0.navigate(&rects.0, input) {
+                    messages.send(UiMessage(msg));
+                }
+            }
+        }
+    }
+
+    // Sent on as events for the app to play, e.g. with an `AudioPlayer`.
+    fn interaction_sounds<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut sounds: EventWriter<UiSound>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        sounds.send_batch(program.0.take_sounds());
+    }
+
+    fn haptic_feedback<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        gamepads: Query<Entity, With<Gamepad>>,
+        mut rumble: EventWriter<GamepadRumbleRequest>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        for pattern in program.0.take_haptics() {
+            for gamepad in &gamepads {
+                rumble.send(GamepadRumbleRequest::Add {
+                    duration: pattern.duration,
+                    intensity: GamepadRumbleIntensity {
+                        strong_motor: pattern.strong,
+                        weak_motor: pattern.weak,
+                    },
+                    gamepad,
+                });
+            }
+        }
+    }
+
+    fn tick_subscriptions<Model, Msg>(
+        time: Res<Time>,
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        for msg in program.0.tick(time.delta()) {
+            messages.send(UiMessage(msg));
+        }
+    }
+
+    fn window_subscriptions<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut resized: EventReader<WindowResized>,
+        mut occluded: EventReader<WindowOccluded>,
+        mut focused: EventReader<WindowFocused>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        // Only the latest size matters when several arrive in one frame.
+        if let Some(size) = resized.read().last() {
+            for msg in program.0.resized(size.width, size.height) {
+                messages.send(UiMessage(msg));
+            }
+        }
+        for event in occluded.read() {
+            let visibility = if event.occluded {
+                Visibility::Hidden
+            } else {
+                Visibility::Visible
+            };
+            for msg in program.0.visibility_changed(visibility) {
+                messages.send(UiMessage(msg));
+            }
+        }
+        if let Some(event) = focused.read().last() {
+            program.0.window_focused(event.focused);
+        }
+    }
+
+    fn frame_budget<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut frame_time: ResMut<UiFrameTime>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        if let Some(elapsed) = frame_time.0.take() {
+            for msg in program.0.frame_time(elapsed) {
+                messages.send(UiMessage(msg));
+            }
+        }
+    }
+
+    fn route_subscriptions<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        for msg in program.0.route_changes() {
+            messages.send(UiMessage(msg));
+        }
+    }
+
+    fn bridge_subscriptions<Model, Msg>(world: &mut World)
+    where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        let subs = world.resource::<UiProgram<Model, Msg>>().0.subscriptions();
+        let cursors = &world.resource::<BridgeCursors>().0;
+        // Every bridge of the same event type starts from the same cursor
+        // and reads up to the same point, so they all see every event.
+        let mut next = HashMap::new();
+        let mut msgs = vec![];
+        for sub in subs {
+            if let Sub::Event(bridge) = sub {
+                let event_type = bridge.event_type();
+                let (read, cursor) = bridge
+                    .read(world, cursors.get(&event_type).map(|c| c.as_ref()));
+                msgs.extend(read);
+                next.insert(event_type, cursor);
+            }
+        }
+        world.resource_mut::<BridgeCursors>().0 = next;
+        for msg in msgs {
+            world.send_event(UiMessage(msg));
+        }
+    }
+
+    fn poll_ui_tasks<Msg>(
+        mut tasks: ResMut<UiTasks<Msg>>,
+        mut messages: EventWriter<UiMessage<Msg>>,
+    ) where
+        Msg: Send + Sync + 'static,
+    {
+        tasks.0.retain_mut(|task| match block_on(poll_once(task)) {
+            Some(Some(msg)) => {
+                messages.send(UiMessage(msg));
+                false
+            }
+            Some(None) => false,
+            None => true,
+        });
+    }
+
+    fn sync_translations<Model, Msg>(
+        translations: Option<Res<Translations>>,
+        mut program: ResMut<UiProgram<Model, Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        if let Some(translations) = translations {
+            if translations.is_changed() {
+                set_translator(translations.0.clone());
+                program.0.refresh();
+            }
+        }
+    }
+
+    // Find where the entities the view anchors elements to are on screen,
+    // rendering it again if they've moved.
+    fn anchor_positions<Model, Msg>(world: &mut World)
+    where
+        Model: Send + Sync + 'static,
+        Msg: Send + Sync + 'static,
+    {
+        let project = match world.get_resource::<WorldToScreen>() {
+            Some(project) => project.0,
+            None => return,
+        };
+        let anchors = world.resource::<UiProgram<Model, Msg>>().0.anchors();
+        let positions: Vec<_> = anchors
+            .into_iter()
+            .map(|anchor| {
+                let position = Entity::try_from_bits(anchor.0)
+                    .ok()
+                    .and_then(|entity| world.get::<GlobalTransform>(entity))
+                    .and_then(|transform| {
+                        project(world, transform.translation())
+                    });
+                (anchor, position.map(|p| (p.x, p.y)))
+            })
+            .collect();
+        world
+            .resource_mut::<UiProgram<Model, Msg>>()
+            .0
+            .set_anchor_positions(positions);
+    }
+
+    fn update_ui<Model, Msg>(
+        mut program: ResMut<UiProgram<Model, Msg>>,
+        mut tasks: ResMut<UiTasks<Msg>>,
+        mut frame_time: ResMut<UiFrameTime>,
+        mut messages: EventReader<UiMessage<Msg>>,
+    ) where
+        Model: Send + Sync + 'static,
+        Msg: Clone + Send + Sync + 'static,
+    {
+        let start = Instant::now();
+        let pool = AsyncComputeTaskPool::get();
+        for UiMessage(msg) in messages.read() {
+            let cmd = program.0.update(msg.clone());
+            for future in cmd.into_futures() {
+                tasks.0.push(pool.spawn(future));
+            }
+        }
+        program.0.render();
+        frame_time.0 = Some(start.elapsed());
+    }
+
+    /// Run a UI program in a new Bevy app.
+    ///
+    /// To add the program to an existing app, use `UiPlugin` instead.
+    pub fn run_ui<Model, Msg>(
+        init: fn() -> Model,
+        update: fn(&mut Model, Msg) -> Cmd<Msg>,
+        view: fn(&Model) -> Element<Msg>,
+    ) -> AppExit
+    where
+        Model: Send + Sync + 'static,
+        Msg: Clone + Send + Sync + 'static,
+    {
+        App::new()
+            .add_plugins((MinimalPlugins, UiPlugin::new(init, update, view)))
+            .run()
+    }
+}
+
+#[test]
+fn test_click_updates_model() {
+    use crate::{element::el, events::on_click};
+
+    let mut program = Program::new(
+        || 0,
+        |count: &mut u32, msg: u32| {
+            *count += msg;
+            Cmd::none()
+        },
+        |count| el(vec![on_click(1)], Element::Text(count.to_string())),
+    );
+
+    // The root's only child holds the two stylesheets followed by the
+    // wrapper around the `el`.
+    let msgs = program.dispatch(&[0, 2, 0], "click", &EventData::None);
+    assert_eq!(msgs, vec![1]);
+
+    for msg in msgs {
+        program.update(msg);
+    }
+    program.render();
+    assert_eq!(*program.model(), 1);
+}
+
+#[test]
+fn test_every_fires_once_per_interval() {
+    use crate::sub::every;
+
+    let mut program = Program::new(
+        || 0,
+        |count: &mut u32, msg: u32| {
+            *count += msg;
+            Cmd::none()
+        },
+        |_| Element::Empty,
+    )
+    .with_subscriptions(|_| vec![every(Duration::from_millis(100), 1)]);
+
+    assert!(program.tick(Duration::from_millis(60)).is_empty());
+    assert_eq!(program.tick(Duration::from_millis(60)), vec![1]);
+    assert!(program.tick(Duration::from_millis(60)).is_empty());
+    assert_eq!(program.tick(Duration::from_millis(60)), vec![1]);
+}
+
+#[test]
+fn test_update_returns_commands() {
+    use std::future::{ready, Future};
+    use std::task::{Context, Poll, Waker};
+
+    let mut program = Program::new(
+        || 0,
+        |count: &mut u32, msg: u32| {
+            *count += msg;
+            Cmd::perform(ready(msg * 2), |n| n)
+        },
+        |_| Element::Empty,
+    );
+
+    let cmd = Cmd::batch(vec![program.update(1), program.update(2)]);
+    let mut cx = Context::from_waker(Waker::noop());
+    let msgs: Vec<u32> = cmd
+        .into_futures()
+        .into_iter()
+        .filter_map(|mut future| match future.as_mut().poll(&mut cx) {
+            Poll::Ready(msg) => msg,
+            Poll::Pending => panic!("`ready` should resolve immediately"),
+        })
+        .collect();
+    assert_eq!(msgs, vec![2, 4]);
+}
+
+#[test]
+fn test_debounced_and_throttled() {
+    use crate::{
+        element::el,
+        events::{debounced, on, throttled},
+    };
+
+    let mut program = Program::new(
+        || (),
+        |_, _: f32| Cmd::none(),
+        |_| {
+            let ms = Duration::from_millis;
+            let y = |data: &EventData| match data {
+                EventData::Pointer { y, .. } => *y,
+                _ => 0.0,
+            };
+            el(
+                vec![
+                    debounced(ms(100), on("input".to_string(), y)),
+                    // Held apart from the first, though on the same event.
+                    debounced(
+                        ms(100),
+                        on("input".to_string(), move |data| y(data) + 10.0),
+                    ),
+                    throttled(ms(100), on("scroll".to_string(), y)),
+                ],
+                Element::Empty,
+            )
+        },
+    );
+    let fire = |program: &mut Program<(), f32>, event: &str, y: f32| {
+        let data = EventData::Pointer { x: 0.0, y };
+        program.dispatch(&[0, 2, 0], event, &data)
+    };
+    assert!(fire(&mut program, "input", 1.0).is_empty());
+    assert!(fire(&mut program, "input", 2.0).is_empty());
+    assert_eq!(fire(&mut program, "scroll", 1.0), vec![1.0]);
+    assert!(fire(&mut program, "scroll", 2.0).is_empty());
+    assert!(fire(&mut program, "scroll", 3.0).is_empty());
+
+    let ms = Duration::from_millis;
+    assert!(program.tick(ms(60)).is_empty());
+    // Typing again puts the input off for another 100ms.
+    assert!(fire(&mut program, "input", 4.0).is_empty());
+    // The last scroll held back since the first.
+    assert_eq!(program.tick(ms(60)), vec![3.0]);
+    assert!(program.tick(ms(30)).is_empty());
+    let mut typed = program.tick(ms(30));
+    typed.sort_by(f32::total_cmp);
+    assert_eq!(typed, vec![4.0, 14.0]);
+    assert!(program.tick(ms(100)).is_empty());
+}
+
+#[test]
+fn test_pause_when_idle() {
+    use crate::render::to_html;
+
+    let mut program = Program::new(
+        || 0,
+        |count: &mut u32, msg: u32| {
+            *count += msg;
+            Cmd::none()
+        },
+        |count| Element::Text(count.to_string()),
+    )
+    .pause_when_idle(true);
+    let rendered = |program: &mut Program<u32, u32>, text: &str| {
+        to_html(program.render()).contains(&format!(">{}<", text))
+    };
+
+    program.window_focused(false);
+    assert!(program.is_idle());
+    program.update(1);
+    assert!(rendered(&mut program, "0"));
+    program.window_focused(true);
+    assert!(rendered(&mut program, "1"));
+
+    assert!(program.visibility_changed(Visibility::Hidden).is_empty());
+    program.update(1);
+    assert!(rendered(&mut program, "1"));
+    program.visibility_changed(Visibility::Visible);
+    assert!(rendered(&mut program, "2"));
+}
+
+#[test]
+fn test_virtual_keyboard() {
+    use std::sync::Arc;
+
+    use crate::{
+        element::{column, el, id},
+        events::{on_input, on_key_down},
+        focus::focusable,
+        keyboard::{keyboard, KeyboardConfig},
+        model::Attribute,
+        sub,
+        test::{find_by_id, find_by_text},
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Msg {
+        Keyboard(Option<KeyboardKind>),
+        Name(String),
+        Key(VirtualKey),
+        Submit,
+    }
+    let mut program = Program::new(
+        String::new,
+        |_, _: Msg| Cmd::none(),
+        |name| {
+            let input = el(
+                vec![
+                    id("name".to_string()),
+                    focusable(),
+                    Attribute::Attr(vdom::Attribute::property(
+                        "inputmode",
+                        "email",
+                    )),
+                    Attribute::Attr(vdom::Attribute::property("value", name)),
+                    on_input(Msg::Name),
+                    on_key_down(|key, _| {
+                        Some(Msg::Submit).filter(|_| key == "Enter")
+                    }),
+                ],
+                Element::Empty,
+            );
+            let config = KeyboardConfig {
+                on_key: Arc::new(Msg::Key),
+                kind: KeyboardKind::Email,
+                shifted: false,
+                key: vec![],
+            };
+            column(vec![], vec![input, keyboard(vec![], config)])
+        },
+    )
+    .with_subscriptions(|_| vec![sub::on_virtual_keyboard(Msg::Keyboard)]);
+    let input = find_by_id(program.node(), "name").unwrap().0;
+    let key = find_by_text(program.node(), "q").unwrap().0;
+
+    assert!(program.virtual_key(VirtualKey::Char('a')).is_empty());
+    assert_eq!(
+        program.focus(&input),
+        vec![Msg::Keyboard(Some(KeyboardKind::Email))]
+    );
+    assert_eq!(
+        program.virtual_key(VirtualKey::Char('a')),
+        vec![Msg::Name("a".to_string())]
+    );
+    // Focusing the built-in keyboard's keys still types into the input.
+    assert!(program.focus(&key).is_empty());
+    assert_eq!(
+        program.virtual_key(VirtualKey::Char('a')),
+        vec![Msg::Name("a".to_string())]
+    );
+    assert_eq!(program.virtual_key(VirtualKey::Enter), vec![Msg::Submit]);
+    assert_eq!(program.focus(&[0]), vec![Msg::Keyboard(None)]);
+    assert!(program.virtual_key(VirtualKey::Char('a')).is_empty());
+}
@@ -0,0 +1,1277 @@
+use core::cell::RefCell;
+
+use crate::vdom::{Attrs, Node, NodeType};
+
+/// Something that shows rendered nodes, e.g. the browser's DOM.
+pub trait RenderBackend<Msg> {
+    type Error;
+
+    /// Show `node` in place of the one rendered last. After an error, what's
+    /// shown is left for the next render to build again from scratch.
+    fn render(&mut self, node: &Node<Msg>) -> Result<(), Self::Error>;
+}
+
+/// A change that turns an old tree into a new one. Paths are lists of
+/// child indices starting from the root node.
+#[derive(Debug, PartialEq)]
+pub enum Patch<'a, Msg> {
+    /// Put `node` where the node or text at the path is.
+    Replace(Vec<usize>, &'a Node<Msg>),
+    /// Set the text at the path, or put the text where a node is.
+    Text(Vec<usize>, &'a str),
+    /// Change the attributes of the node at the path from the first set to
+    /// the second.
+    Attrs(Vec<usize>, &'a Attrs, &'a Attrs),
+    /// Add a child to the end of the node at the path.
+    Append(Vec<usize>, &'a NodeType<Msg>),
+    /// Remove the children of the node at the path from this index on.
+    Truncate(Vec<usize>, usize),
+}
+
+/// The patches that turn `old` into `new`. Children are matched up by
+/// index, keyed ones being replaced when their key has changed, and event
+/// listeners are left to the backend.
+pub fn diff<'a, Msg>(
+    old: &'a Node<Msg>,
+    new: &'a Node<Msg>,
+) -> Vec<Patch<'a, Msg>> {
+    let mut patches = vec![];
+    diff_node(old, new, &mut vec![], &mut patches);
+    patches
+}
+
+fn diff_node<'a, Msg>(
+    old: &'a Node<Msg>,
+    new: &'a Node<Msg>,
+    path: &mut Vec<usize>,
+    patches: &mut Vec<Patch<'a, Msg>>,
+) {
+    if old.tag != new.tag {
+        patches.push(Patch::Replace(path.clone(), new));
+        return;
+    }
+    if old.attrs != new.attrs {
+        patches.push(Patch::Attrs(path.clone(), &old.attrs, &new.attrs));
+    }
+    for (i, (old, new)) in old.children.iter().zip(&new.children).enumerate() {
+        path.push(i);
+        match (old, new) {
+            (NodeType::Text(old), NodeType::Text(new)) if old == new => {}
+            // A different keyed child, so nothing of the old one is kept.
+            (NodeType::KeyedNode(old, _), NodeType::KeyedNode(key, new))
+                if old != key =>
+            {
+                patches.push(Patch::Replace(path.clone(), new))
+            }
+            (_, NodeType::Text(new)) => {
+                patches.push(Patch::Text(path.clone(), new))
+            }
+            (
+                NodeType::Node(old) | NodeType::KeyedNode(_, old),
+                NodeType::Node(new) | NodeType::KeyedNode(_, new),
+            ) => diff_node(old, new, path, patches),
+            (
+                NodeType::Text(_),
+                NodeType::Node(new) | NodeType::KeyedNode(_, new),
+            ) => patches.push(Patch::Replace(path.clone(), new)),
+        }
+        path.pop();
+    }
+    let (old_len, new_len) = (old.children.len(), new.children.len());
+    if new_len > old_len {
+        for child in &new.children[old_len..] {
+            patches.push(Patch::Append(path.clone(), child));
+        }
+    } else if new_len < old_len {
+        patches.push(Patch::Truncate(path.clone(), new_len));
+    }
+}
+
+// The instance of a custom element running on `host`, started with `start`
+// if there isn't one, as when it's added to the page again after being
+// removed. One that fails to start is reported, and started again the next
+// time it's asked for.
+fn instance_on<Host, State, E>(
+    instances: &RefCell<Vec<(Host, State)>>,
+    host: &Host,
+    start: impl FnOnce() -> Result<State, E>,
+    report: impl FnOnce(E),
+) -> Option<State>
+where
+    Host: PartialEq + Clone,
+    State: Clone,
+{
+    let found = instances
+        .borrow()
+        .iter()
+        .find(|(h, _)| h == host)
+        .map(|(_, state)| state.clone());
+    if found.is_some() {
+        return found;
+    }
+    // Not borrowed while starting, as rendering can start other instances.
+    match start() {
+        Ok(state) => {
+            instances.borrow_mut().push((host.clone(), state.clone()));
+            Some(state)
+        }
+        Err(error) => {
+            report(error);
+            None
+        }
+    }
+}
+
+// Whether the backend's own action for `event` on the node at `path` is
+// skipped: when a listener on it or an ancestor asks, or for
+// `contextmenu`, when it sent a message, as the crate's menu opens
+// instead. `plain` is whether it's a click of the main button with no
+// modifier keys held.
+fn prevents_default<Msg>(
+    root: &Node<Msg>,
+    path: &[usize],
+    event: &str,
+    plain: bool,
+    sent: bool,
+) -> bool {
+    let asked = (0..=path.len())
+        .filter_map(|len| root.at(&path[..len]))
+        .flat_map(|node| &node.events)
+        .any(|l| l.event == event && l.default.prevents(plain));
+    asked || (event == "contextmenu" && sent)
+}
+
+#[cfg(feature = "web")]
+pub use self::web::{define_custom_element, mount, CustomElement, WebBackend};
+
+#[cfg(feature = "web")]
+mod web {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashSet;
+    use std::mem;
+    use std::rc::{Rc, Weak};
+    use std::time::Duration;
+
+    use js_sys::{Array, Function, Reflect};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use web_sys::{
+        CustomEvent, CustomEventInit, Document, Element, Event, EventTarget,
+        HtmlAudioElement, HtmlElement, HtmlInputElement, HtmlTextAreaElement,
+        IntersectionObserver, IntersectionObserverEntry,
+        IntersectionObserverInit, KeyboardEvent, MouseEvent, ShadowRootInit,
+        ShadowRootMode, WheelEvent,
+    };
+
+    use super::{diff, instance_on, prevents_default, Patch, RenderBackend};
+    use crate::app::Program;
+    use crate::haptic::HapticPattern;
+    use crate::sound::{Interaction, UiSound};
+    use crate::sub::Visibility;
+    use crate::vdom::{
+        html_attributes, Attrs, Caret, DeltaUnit, EventData, Node, NodeType,
+        Wheel,
+    };
+
+    /// Renders into the DOM, under a container element or shadow root. The
+    /// first render builds the tree, and later ones apply the patches from
+    /// `diff`.
+    pub struct WebBackend<Msg> {
+        document: Document,
+        container: web_sys::Node,
+        current: Option<Node<Msg>>,
+    }
+
+    impl<Msg> WebBackend<Msg> {
+        /// A backend rendering into `container`, replacing what's there.
+        pub fn new(container: Element) -> Self {
+            container.set_inner_html("");
+            Self {
+                document: container.owner_document().unwrap(),
+                container: container.into(),
+                current: None,
+            }
+        }
+
+        /// A backend rendering into a new shadow root on `host`, so the
+        /// page's styles and the view's don't apply to each other. A host
+        /// can only have one, so if it's been rendered into before, as a
+        /// custom element added to the page again, its root is emptied and
+        /// used again.
+        pub fn in_shadow_root(host: Element) -> Result<Self, JsValue> {
+            let root = match host.shadow_root() {
+                Some(root) => {
+                    root.set_inner_html("");
+                    root
+                }
+                None => host.attach_shadow(&ShadowRootInit::new(
+                    ShadowRootMode::Open,
+                ))?,
+            };
+            Ok(Self {
+                document: host.owner_document().unwrap(),
+                container: root.into(),
+                current: None,
+            })
+        }
+
+        fn find(&self, path: &[usize]) -> Option<web_sys::Node> {
+            path.iter()
+                .try_fold(self.container.first_child()?, |node, i| {
+                    node.child_nodes().item(*i as u32)
+                })
+        }
+
+        fn create(
+            &self,
+            node: &NodeType<Msg>,
+        ) -> Result<web_sys::Node, JsValue> {
+            Ok(match node {
+                NodeType::Text(text) => {
+                    self.document.create_text_node(text).into()
+                }
+                NodeType::Node(node) | NodeType::KeyedNode(_, node) => {
+                    self.create_element(node)?.into()
+                }
+            })
+        }
+
+        fn create_element(&self, node: &Node<Msg>) -> Result<Element, JsValue> {
+            let element = self.document.create_element(&node.tag)?;
+            set_attributes(&element, &Attrs::new(), &node.attrs)?;
+            for child in &node.children {
+                element.append_child(&self.create(child)?)?;
+            }
+            Ok(element)
+        }
+
+        fn apply(&self, patch: Patch<Msg>) -> Result<(), JsValue> {
+            let missing = || JsValue::from_str("patched node is missing");
+            match patch {
+                Patch::Replace(path, node) => {
+                    let new = self.create_element(node)?;
+                    let old = self.find(&path).ok_or_else(missing)?;
+                    old.parent_node()
+                        .ok_or_else(missing)?
+                        .replace_child(&new, &old)?;
+                }
+                Patch::Text(path, text) => {
+                    let old = self.find(&path).ok_or_else(missing)?;
+                    if old.node_type() == web_sys::Node::TEXT_NODE {
+                        old.set_text_content(Some(text));
+                    } else {
+                        let new = self.document.create_text_node(text);
+                        old.parent_node()
+                            .ok_or_else(missing)?
+                            .replace_child(&new, &old)?;
+                    }
+                }
+                Patch::Attrs(path, old, new) => {
+                    let element = self.find(&path).ok_or_else(missing)?;
+                    set_attributes(element.unchecked_ref(), old, new)?;
+                }
+                Patch::Append(path, child) => {
+                    let parent = self.find(&path).ok_or_else(missing)?;
+                    parent.append_child(&self.create(child)?)?;
+                }
+                Patch::Truncate(path, len) => {
+                    let parent = self.find(&path).ok_or_else(missing)?;
+                    let children = parent.child_nodes();
+                    while children.length() > len as u32 {
+                        let last = parent.last_child().ok_or_else(missing)?;
+                        parent.remove_child(&last)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<Msg> RenderBackend<Msg> for WebBackend<Msg> {
+        type Error = JsValue;
+
+        fn render(&mut self, node: &Node<Msg>) -> Result<(), JsValue> {
+            let rendered = match self.current.take() {
+                Some(current) => diff(&current, node)
+                    .into_iter()
+                    .try_for_each(|patch| self.apply(patch)),
+                None => self
+                    .create_element(node)
+                    .and_then(|root| self.container.append_child(&root))
+                    .map(drop),
+            };
+            match rendered {
+                Ok(()) => self.current = Some(node.clone()),
+                // Half patched, so it's emptied to be built again.
+                Err(_) => self.container.set_text_content(None),
+            }
+            rendered
+        }
+    }
+
+    fn set_attributes(
+        element: &Element,
+        old: &Attrs,
+        new: &Attrs,
+    ) -> Result<(), JsValue> {
+        let old = html_attributes(old);
+        let new = html_attributes(new);
+        for (key, _) in
+            old.iter().filter(|(k, _)| !new.iter().any(|(n, _)| n == k))
+        {
+            element.remove_attribute(key)?;
+        }
+        for (key, value) in &new {
+            if !old.iter().any(|(k, v)| k == key && v == value) {
+                element.set_attribute(key, value)?;
+            }
+            if *key == "value" {
+                set_value(element, value);
+            }
+        }
+        Ok(())
+    }
+
+    // The `value` attribute is only where an input starts, so the text
+    // that's showing is set too, keeping it what the model says. It's left
+    // alone when it's already that, so the caret doesn't jump.
+    fn set_value(element: &Element, value: &str) {
+        if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+            if input.value() != value {
+                input.set_value(value);
+            }
+        } else if let Some(area) = element.dyn_ref::<HtmlTextAreaElement>() {
+            if area.value() != value {
+                area.set_value(value);
+            }
+        }
+    }
+
+    fn event_data(event: &Event) -> EventData {
+        if let Some(key) = event.dyn_ref::<KeyboardEvent>() {
+            EventData::Key {
+                key: key.key(),
+                shift: key.shift_key(),
+            }
+        } else if let Some(wheel) = event.dyn_ref::<WheelEvent>() {
+            EventData::Wheel(Wheel {
+                dx: wheel.delta_x() as f32,
+                dy: wheel.delta_y() as f32,
+                unit: match wheel.delta_mode() {
+                    WheelEvent::DOM_DELTA_LINE => DeltaUnit::Line,
+                    WheelEvent::DOM_DELTA_PAGE => DeltaUnit::Page,
+                    _ => DeltaUnit::Pixel,
+                },
+                ctrl: wheel.ctrl_key(),
+                shift: wheel.shift_key(),
+                alt: wheel.alt_key(),
+            })
+        } else if let Some(mouse) = event.dyn_ref::<MouseEvent>() {
+            EventData::Pointer {
+                x: mouse.client_x() as f32,
+                y: mouse.client_y() as f32,
+            }
+        } else if let Some(input) = event
+            .target()
+            .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        {
+            EventData::Text(input.value())
+        } else if let Some(area) = event
+            .target()
+            .and_then(|t| t.dyn_into::<HtmlTextAreaElement>().ok())
+        {
+            EventData::Text(area.value())
+        } else {
+            EventData::None
+        }
+    }
+
+    // The properties that decide where text wraps in an input, copied to
+    // the mirror measuring it.
+    const TEXT_PROPERTIES: [&str; 16] = [
+        "box-sizing",
+        "width",
+        "font-family",
+        "font-size",
+        "font-style",
+        "font-weight",
+        "letter-spacing",
+        "line-height",
+        "padding-top",
+        "padding-right",
+        "padding-bottom",
+        "padding-left",
+        "border-top-width",
+        "border-left-width",
+        "text-transform",
+        "word-spacing",
+    ];
+
+    // Where the caret is in a text input or area, found by laying out its
+    // text up to the caret in a hidden copy of it, followed by a marker.
+    fn caret_data(target: &Element) -> Option<EventData> {
+        let (value, start, wrap) =
+            if let Some(area) = target.dyn_ref::<HtmlTextAreaElement>() {
+                (area.value(), area.selection_start().ok()??, "pre-wrap")
+            } else if let Some(input) = target.dyn_ref::<HtmlInputElement>() {
+                (input.value(), input.selection_start().ok()??, "pre")
+            } else {
+                return None;
+            };
+        // The selection is counted in UTF-16 code units.
+        let units: Vec<u16> =
+            value.encode_utf16().take(start as usize).collect();
+        let before = String::from_utf16_lossy(&units);
+
+        let window = web_sys::window()?;
+        let document = window.document()?;
+        let computed = window.get_computed_style(target).ok()??;
+        let mirror: HtmlElement =
+            document.create_element("div").ok()?.dyn_into().ok()?;
+        let style = mirror.style();
+        for property in TEXT_PROPERTIES {
+            let value = computed.get_property_value(property).ok()?;
+            style.set_property(property, &value).ok()?;
+        }
+        for (property, value) in [
+            ("position", "absolute"),
+            ("visibility", "hidden"),
+            ("top", "0"),
+            ("left", "-9999px"),
+            ("white-space", wrap),
+            ("overflow-wrap", "break-word"),
+        ] {
+            style.set_property(property, value).ok()?;
+        }
+        mirror.set_text_content(Some(&before));
+        let marker: HtmlElement =
+            document.create_element("span").ok()?.dyn_into().ok()?;
+        marker.set_text_content(Some("\u{200b}"));
+        mirror.append_child(&marker).ok()?;
+        document.body()?.append_child(&mirror).ok()?;
+        let caret = Caret {
+            offset: before.chars().count(),
+            x: (marker.offset_left() - target.scroll_left()) as f32,
+            y: (marker.offset_top() - target.scroll_top()) as f32,
+            line_height: marker.offset_height() as f32,
+        };
+        mirror.remove();
+        Some(EventData::Caret(caret))
+    }
+
+    struct Mounted<Model, Msg> {
+        program: Program<Model, Msg>,
+        backend: WebBackend<Msg>,
+        // The events listened for on the container so far.
+        listening: HashSet<String>,
+        // Those listeners, and the window's, removed along with the program.
+        listeners: Vec<Listener>,
+        // Runs the subscriptions, until the program's removed.
+        frames: Option<Frames>,
+        // Watches the elements listening for `intersect`, once any are.
+        intersections: Option<Intersections>,
+        dirty: bool,
+        last_frame: Option<f64>,
+        // The element messages are sent out from as DOM events, and the
+        // events they're sent as.
+        emit: Option<(Element, ToEvent<Msg>)>,
+    }
+
+    type State<Model, Msg> = Rc<RefCell<Mounted<Model, Msg>>>;
+
+    // The name and detail of the DOM event a message is sent out as.
+    type ToEvent<Msg> = fn(&Msg) -> Option<(String, JsValue)>;
+
+    // The message an attribute's value is sent as.
+    type FromAttribute<Msg> = fn(Option<String>) -> Msg;
+
+    type Instances<Model, Msg> = Rc<RefCell<Vec<(Element, State<Model, Msg>)>>>;
+
+    fn window() -> Result<web_sys::Window, JsValue> {
+        web_sys::window().ok_or_else(|| JsValue::from_str("no window"))
+    }
+
+    // Errors in listeners and callbacks have nowhere to go but the log.
+    fn report(error: JsValue) {
+        log::error!("{:?}", error);
+    }
+
+    // An event listener on some targets, removed from them when dropped.
+    struct Listener {
+        targets: Vec<(EventTarget, String)>,
+        capture: bool,
+        closure: Closure<dyn Fn(Event)>,
+    }
+
+    impl Listener {
+        fn new(
+            targets: Vec<(EventTarget, String)>,
+            capture: bool,
+            closure: Closure<dyn Fn(Event)>,
+        ) -> Result<Self, JsValue> {
+            let mut listener = Listener {
+                targets: vec![],
+                capture,
+                closure,
+            };
+            for (target, event) in targets {
+                target.add_event_listener_with_callback_and_bool(
+                    &event,
+                    listener.closure.as_ref().unchecked_ref(),
+                    capture,
+                )?;
+                listener.targets.push((target, event));
+            }
+            Ok(listener)
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            for (target, event) in &self.targets {
+                let _ = target.remove_event_listener_with_callback_and_bool(
+                    event,
+                    self.closure.as_ref().unchecked_ref(),
+                    self.capture,
+                );
+            }
+        }
+    }
+
+    // A callback run each animation frame, asking for the next one itself,
+    // and cancelled when dropped.
+    struct Frames {
+        callback: Closure<dyn Fn(f64)>,
+        request: Cell<i32>,
+    }
+
+    impl Frames {
+        fn request(&self) -> Result<(), JsValue> {
+            let callback = self.callback.as_ref().unchecked_ref();
+            self.request
+                .set(window()?.request_animation_frame(callback)?);
+            Ok(())
+        }
+    }
+
+    impl Drop for Frames {
+        fn drop(&mut self) {
+            if let Ok(window) = window() {
+                let _ = window.cancel_animation_frame(self.request.get());
+            }
+        }
+    }
+
+    // The path from the root node to `node`, if it's under the root.
+    fn path_to(
+        container: &web_sys::Node,
+        node: web_sys::Node,
+    ) -> Option<Vec<usize>> {
+        let root = container.first_child()?;
+        let mut path = vec![];
+        let mut node = node;
+        while node != root {
+            let parent = node.parent_node()?;
+            let siblings = parent.child_nodes();
+            let index = (0..siblings.length())
+                .position(|i| siblings.item(i).as_ref() == Some(&node))?;
+            path.push(index);
+            node = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    // Sounds are named by URL on the web.
+    fn play_sounds(sounds: Vec<UiSound>) {
+        for sound in sounds {
+            if let Ok(audio) = HtmlAudioElement::new_with_src(&sound.sound) {
+                let _ = audio.play();
+            }
+        }
+    }
+
+    // Phones have one motor, so only the length of a pattern is kept.
+    fn vibrate(patterns: Vec<HapticPattern>) {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        for pattern in patterns {
+            let millis = pattern.duration.as_millis() as u32;
+            window.navigator().vibrate_with_duration(millis);
+        }
+    }
+
+    fn send<Model: 'static, Msg: 'static>(
+        state: &State<Model, Msg>,
+        msgs: Vec<Msg>,
+    ) {
+        let mut mounted = state.borrow_mut();
+        let mut events = vec![];
+        for msg in msgs {
+            mounted.dirty = true;
+            if let Some((target, to_event)) = &mounted.emit {
+                if let Some(event) = to_event(&msg) {
+                    events.push((target.clone(), event));
+                }
+            }
+            for future in mounted.program.update(msg).into_futures() {
+                let state = state.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Some(msg) = future.await {
+                        send(&state, vec![msg]);
+                        render(&state).unwrap_or_else(report);
+                    }
+                });
+            }
+        }
+        // Sent once the program's free again, in case a listener sets one
+        // of the element's attributes.
+        drop(mounted);
+        for (target, (name, detail)) in events {
+            let init = CustomEventInit::new();
+            init.set_detail(&detail);
+            init.set_bubbles(true);
+            init.set_composed(true);
+            CustomEvent::new_with_event_init_dict(&name, &init)
+                .and_then(|event| target.dispatch_event(&event))
+                .map(drop)
+                .unwrap_or_else(report);
+        }
+    }
+
+    fn render<Model: 'static, Msg: 'static>(
+        state: &State<Model, Msg>,
+    ) -> Result<(), JsValue> {
+        let mut guard = state.borrow_mut();
+        let mounted = &mut *guard;
+        if !mounted.dirty {
+            return Ok(());
+        }
+        // Left dirty while the program's holding off, paused or over its
+        // frame budget, to render when it's ready.
+        mounted.program.render();
+        if mounted.program.needs_render() {
+            return Ok(());
+        }
+        let node = mounted.program.node();
+        // Left dirty after an error too, to build it again next time.
+        mounted.backend.render(node)?;
+        mounted.dirty = false;
+
+        let mut events = HashSet::new();
+        collect_events(node, &mut events);
+        if mounted.program.plays_sounds() || mounted.program.plays_haptics() {
+            events.extend(["mouseenter".to_string(), "click".to_string()]);
+        }
+        for event in events {
+            if mounted.listening.contains(&event) {
+                continue;
+            }
+            if event == "intersect" {
+                // Browsers without intersection observers never report it.
+                mounted.intersections = observe_intersections(state).ok();
+            } else {
+                let container = &mounted.backend.container;
+                mounted.listeners.push(listen(state, container, &event)?);
+            }
+            mounted.listening.insert(event);
+        }
+        if let Some(intersections) = &mut mounted.intersections {
+            intersections.update(&mounted.backend, node);
+        }
+        Ok(())
+    }
+
+    // The DOM has no `intersect` event, so an intersection observer reports
+    // it instead, for each element listening for it.
+    struct Intersections {
+        observer: IntersectionObserver,
+        observed: Vec<Element>,
+        _callback: Closure<dyn Fn(Array)>,
+    }
+
+    impl Drop for Intersections {
+        fn drop(&mut self) {
+            self.observer.disconnect();
+        }
+    }
+
+    impl Intersections {
+        // Observe the elements of `node` newly listening for `intersect`,
+        // and let go of the ones no longer in the page.
+        fn update<Msg>(&mut self, backend: &WebBackend<Msg>, node: &Node<Msg>) {
+            let observer = &self.observer;
+            self.observed.retain(|element| {
+                let connected = element.is_connected();
+                if !connected {
+                    observer.unobserve(element);
+                }
+                connected
+            });
+            let mut paths = vec![];
+            intersect_paths(node, &mut vec![], &mut paths);
+            for path in paths {
+                let element = match backend.find(&path) {
+                    Some(node) => node.unchecked_into::<Element>(),
+                    None => continue,
+                };
+                if !self.observed.contains(&element) {
+                    self.observer.observe(&element);
+                    self.observed.push(element);
+                }
+            }
+        }
+    }
+
+    // The paths of the nodes under `node` with `intersect` listeners.
+    fn intersect_paths<Msg>(
+        node: &Node<Msg>,
+        path: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if node.events.iter().any(|l| l.event == "intersect") {
+            out.push(path.clone());
+        }
+        for (i, child) in node.children.iter().enumerate() {
+            if let NodeType::Node(child) | NodeType::KeyedNode(_, child) = child
+            {
+                path.push(i);
+                intersect_paths(child, path, out);
+                path.pop();
+            }
+        }
+    }
+
+    // An observer dispatching `intersect` to the observed elements, each
+    // time a tenth more or less of one is showing. Like `mouseenter`, it
+    // doesn't bubble.
+    fn observe_intersections<Model: 'static, Msg: 'static>(
+        state: &State<Model, Msg>,
+    ) -> Result<Intersections, JsValue> {
+        let weak = Rc::downgrade(state);
+        let callback = Closure::<dyn Fn(Array)>::new(move |entries: Array| {
+            let state = match weak.upgrade() {
+                Some(state) => state,
+                None => return,
+            };
+            let msgs = {
+                let mut mounted = match state.try_borrow_mut() {
+                    Ok(mounted) => mounted,
+                    Err(_) => return,
+                };
+                let container = mounted.backend.container.clone();
+                let mut msgs = vec![];
+                for entry in entries.iter() {
+                    let entry: IntersectionObserverEntry =
+                        entry.unchecked_into();
+                    let path = match path_to(&container, entry.target().into())
+                    {
+                        Some(path) => path,
+                        None => continue,
+                    };
+                    let data = EventData::Intersection {
+                        ratio: entry.intersection_ratio() as f32,
+                    };
+                    msgs.extend(mounted.program.dispatch(
+                        &path,
+                        "intersect",
+                        &data,
+                    ));
+                }
+                msgs
+            };
+            send(&state, msgs);
+            render(&state).unwrap_or_else(report);
+        });
+        let init = IntersectionObserverInit::new();
+        let steps: Array = (0..=10)
+            .map(|i| JsValue::from_f64(i as f64 / 10.0))
+            .collect();
+        init.set_threshold(&steps);
+        let observer = IntersectionObserver::new_with_options(
+            callback.as_ref().unchecked_ref(),
+            &init,
+        )?;
+        Ok(Intersections {
+            observer,
+            observed: vec![],
+            _callback: callback,
+        })
+    }
+
+    fn collect_events<Msg>(node: &Node<Msg>, events: &mut HashSet<String>) {
+        events.extend(node.events.iter().map(|l| l.event.to_string()));
+        for child in &node.children {
+            if let NodeType::Node(child) | NodeType::KeyedNode(_, child) = child
+            {
+                collect_events(child, events);
+            }
+        }
+    }
+
+    // Listen for `event` on the container, in the capture phase so events
+    // that don't bubble, like `focus`, are seen too. The listeners are
+    // then run from the target up to the root, as the DOM would.
+    fn listen<Model: 'static, Msg: 'static>(
+        state: &State<Model, Msg>,
+        container: &web_sys::Node,
+        event: &str,
+    ) -> Result<Listener, JsValue> {
+        let weak = Rc::downgrade(state);
+        let name = event.to_string();
+        let container_ = container.clone();
+        let closure = Closure::<dyn Fn(Event)>::new(move |event: Event| {
+            let state = match weak.upgrade() {
+                Some(state) => state,
+                None => return,
+            };
+            let target: Option<web_sys::Node> =
+                event.target().and_then(|t| t.dyn_into().ok());
+            let path =
+                match target.clone().and_then(|t| path_to(&container_, t)) {
+                    Some(path) => path,
+                    None => return,
+                };
+            let data = if name == "caret" {
+                match target.and_then(|t| caret_data(&t.dyn_into().ok()?)) {
+                    Some(data) => data,
+                    None => return,
+                }
+            } else {
+                event_data(&event)
+            };
+            // Clicks with a modifier key held open links in a new tab or
+            // window, and are left to the browser.
+            let plain = event.dyn_ref::<MouseEvent>().map_or(false, |m| {
+                let modified = m.ctrl_key()
+                    || m.meta_key()
+                    || m.shift_key()
+                    || m.alt_key();
+                m.button() == 0 && !modified
+            });
+            let (msgs, prevent) = {
+                // Events fired while the DOM is being patched, like the
+                // `blur` of a removed node, are dropped.
+                let mut mounted = match state.try_borrow_mut() {
+                    Ok(mounted) => mounted,
+                    Err(_) => return,
+                };
+                let program = &mut mounted.program;
+                if let Some(interaction) = Interaction::from_event(&name) {
+                    program.interacted(&path, interaction);
+                }
+                let msgs = match (name.as_str(), &data) {
+                    ("keydown", EventData::Key { key, shift }) => {
+                        program.key_down(key, *shift)
+                    }
+                    ("focus", _) => program.focus(&path),
+                    // These don't bubble, as moving between children isn't
+                    // leaving or entering their parent.
+                    ("mouseenter", _) | ("mouseleave", _) => {
+                        program.dispatch(&path, &name, &data)
+                    }
+                    _ => (0..=path.len())
+                        .rev()
+                        .flat_map(|len| {
+                            program.dispatch(&path[..len], &name, &data)
+                        })
+                        .collect(),
+                };
+                play_sounds(program.take_sounds());
+                vibrate(program.take_haptics());
+                let sent = !msgs.is_empty();
+                let node = program.node();
+                (msgs, prevents_default(node, &path, &name, plain, sent))
+            };
+            if prevent {
+                event.prevent_default();
+            }
+            send(&state, msgs);
+            render(&state).unwrap_or_else(report);
+        });
+        // The caret isn't reported by the DOM, so it's looked up after
+        // anything that could have moved it.
+        let events = match event {
+            "caret" => vec!["input", "keyup", "pointerup", "focus"],
+            event => vec![event],
+        };
+        let targets = events
+            .into_iter()
+            .map(|event| (container.clone().into(), event.to_string()))
+            .collect();
+        Listener::new(targets, true, closure)
+    }
+
+    // Tell the program when the page is hidden or shown, and when the
+    // window loses or gains focus, until it's dropped.
+    fn watch_window<Model: 'static, Msg: 'static>(
+        weak: Weak<RefCell<Mounted<Model, Msg>>>,
+    ) -> Result<Listener, JsValue> {
+        let window = window()?;
+        let document = window
+            .document()
+            .ok_or_else(|| JsValue::from_str("no document"))?;
+        let document_ = document.clone();
+        let closure = Closure::<dyn Fn(Event)>::new(move |event: Event| {
+            let state = match weak.upgrade() {
+                Some(state) => state,
+                None => return,
+            };
+            let msgs = {
+                let program = &mut state.borrow_mut().program;
+                match event.type_().as_str() {
+                    "focus" => {
+                        program.window_focused(true);
+                        vec![]
+                    }
+                    "blur" => {
+                        program.window_focused(false);
+                        vec![]
+                    }
+                    _ => program.visibility_changed(if document_.hidden() {
+                        Visibility::Hidden
+                    } else {
+                        Visibility::Visible
+                    }),
+                }
+            };
+            send(&state, msgs);
+            render(&state).unwrap_or_else(report);
+        });
+        let targets = vec![
+            (document.into(), "visibilitychange".to_string()),
+            (window.clone().into(), "focus".to_string()),
+            (window.into(), "blur".to_string()),
+        ];
+        Listener::new(targets, false, closure)
+    }
+
+    // Run the subscriptions each animation frame, until the program's
+    // dropped.
+    fn frame<Model: 'static, Msg: 'static>(
+        weak: Weak<RefCell<Mounted<Model, Msg>>>,
+    ) -> Frames {
+        let callback = Closure::new(move |now: f64| {
+            let state = match weak.upgrade() {
+                Some(state) => state,
+                None => return,
+            };
+            let performance = window().ok().and_then(|w| w.performance());
+            let start = performance.as_ref().map_or(0.0, |p| p.now());
+            let msgs = {
+                let mut mounted = state.borrow_mut();
+                let delta = mounted.last_frame.map_or(0.0, |last| now - last);
+                mounted.last_frame = Some(now);
+                let mut msgs = mounted
+                    .program
+                    .tick(Duration::from_secs_f64(delta.max(0.0) / 1000.0));
+                msgs.extend(mounted.program.route_changes());
+                msgs
+            };
+            send(&state, msgs);
+            render(&state).unwrap_or_else(report);
+            let end = performance.as_ref().map_or(start, |p| p.now());
+            let elapsed = (end - start).max(0.0) / 1000.0;
+            let msgs = state
+                .borrow_mut()
+                .program
+                .frame_time(Duration::from_secs_f64(elapsed));
+            send(&state, msgs);
+            let mounted = state.borrow();
+            if let Some(frames) = &mounted.frames {
+                frames.request().unwrap_or_else(report);
+            }
+        });
+        Frames {
+            callback,
+            request: Cell::new(0),
+        }
+    }
+
+    // Render `program` into `container`, and listen for its events.
+    fn mount_in<Model: 'static, Msg: 'static>(
+        container: Element,
+        mut program: Program<Model, Msg>,
+    ) -> Result<State<Model, Msg>, JsValue> {
+        let backend = if program.in_shadow_root() {
+            WebBackend::in_shadow_root(container)?
+        } else {
+            WebBackend::new(container)
+        };
+        let state = Rc::new(RefCell::new(Mounted {
+            program,
+            backend,
+            listening: HashSet::new(),
+            listeners: vec![],
+            frames: None,
+            intersections: None,
+            dirty: true,
+            last_frame: None,
+            emit: None,
+        }));
+        render(&state)?;
+        let frames = frame(Rc::downgrade(&state));
+        frames.request()?;
+        let watching = watch_window(Rc::downgrade(&state))?;
+        let mut mounted = state.borrow_mut();
+        mounted.frames = Some(frames);
+        mounted.listeners.push(watching);
+        drop(mounted);
+        Ok(state)
+    }
+
+    /// Run `program` in the browser, in the element matching `selector`.
+    /// Its view is rendered there, with the static stylesheet as part of
+    /// the tree, and re-rendered by patching the DOM as messages arrive
+    /// from its event listeners, subscriptions and commands.
+    ///
+    /// With the `shadow_root` option, it's rendered in a shadow root on
+    /// the element instead.
+    pub fn mount<Model, Msg>(
+        selector: &str,
+        program: Program<Model, Msg>,
+    ) -> Result<(), JsValue>
+    where
+        Model: 'static,
+        Msg: 'static,
+    {
+        let document =
+            web_sys::window()
+                .and_then(|window| window.document())
+                .ok_or_else(|| JsValue::from_str("no document to mount in"))?;
+        let container =
+            document.query_selector(selector)?.ok_or_else(|| {
+                JsValue::from_str(&format!("nothing matches `{}`", selector))
+            })?;
+        // The program runs as long as the page.
+        mem::forget(mount_in(container, program)?);
+        Ok(())
+    }
+
+    /// A program run in each instance of a custom element, taking the
+    /// element's attributes as messages and sending its own messages out
+    /// as DOM events.
+    pub struct CustomElement<Model, Msg> {
+        program: fn() -> Program<Model, Msg>,
+        attributes: Vec<(String, FromAttribute<Msg>)>,
+        events: ToEvent<Msg>,
+    }
+
+    impl<Model, Msg> CustomElement<Model, Msg> {
+        pub fn new(program: fn() -> Program<Model, Msg>) -> Self {
+            Self {
+                program,
+                attributes: vec![],
+                events: |_| None,
+            }
+        }
+
+        /// Send the message `to_msg` makes of the attribute `name` when
+        /// it's set, changed or removed.
+        pub fn attribute(
+            mut self,
+            name: &str,
+            to_msg: fn(Option<String>) -> Msg,
+        ) -> Self {
+            self.attributes.push((name.to_string(), to_msg));
+            self
+        }
+
+        /// Dispatch a `CustomEvent` from the element for the messages
+        /// `to_event` names, with its detail, before they're handled. The
+        /// events bubble out of shadow roots.
+        pub fn events(
+            mut self,
+            to_event: fn(&Msg) -> Option<(String, JsValue)>,
+        ) -> Self {
+            self.events = to_event;
+            self
+        }
+    }
+
+    // Makes the element's class, which calls back into Rust. Attributes
+    // are reported before the element's connected when it's upgraded, so
+    // an instance is started by whichever comes first.
+    const CLASS_FACTORY: &str = "
+        return (connect, change, disconnect, observed) =>
+            class extends HTMLElement {
+                static get observedAttributes() { return observed; }
+                connectedCallback() { connect(this); }
+                disconnectedCallback() { disconnect(this); }
+                attributeChangedCallback(name, _, value) {
+                    change(this, name, value);
+                }
+            };
+    ";
+
+    /// Register `element` as the custom element `name`, e.g. `my-widget`,
+    /// so pages can use it as `<my-widget>` without any Rust of their own.
+    /// Each instance runs its own program, from when it's added to the page
+    /// to when it's removed.
+    pub fn define_custom_element<Model, Msg>(
+        name: &str,
+        element: CustomElement<Model, Msg>,
+    ) -> Result<(), JsValue>
+    where
+        Model: 'static,
+        Msg: 'static,
+    {
+        let element = Rc::new(element);
+        // The state of each instance on the page.
+        let instances: Instances<Model, Msg> = Rc::default();
+        let instance = {
+            let element = element.clone();
+            let instances = instances.clone();
+            Rc::new(move |host: &Element| {
+                let start = || {
+                    let state = mount_in(host.clone(), (element.program)())?;
+                    state.borrow_mut().emit =
+                        Some((host.clone(), element.events));
+                    Ok(state)
+                };
+                instance_on(&instances, host, start, report)
+            })
+        };
+
+        let start = instance.clone();
+        let connect = Closure::<dyn Fn(Element)>::new(move |host: Element| {
+            start(&host);
+        });
+        let attributes = element.clone();
+        let change = Closure::<dyn Fn(Element, String, JsValue)>::new(
+            move |host: Element, name: String, value: JsValue| {
+                if let Some(state) = instance(&host) {
+                    let msgs = attributes
+                        .attributes
+                        .iter()
+                        .filter(|(n, _)| *n == name)
+                        .map(|(_, to_msg)| to_msg(value.as_string()))
+                        .collect();
+                    send(&state, msgs);
+                    render(&state).unwrap_or_else(report);
+                }
+            },
+        );
+        let disconnect =
+            Closure::<dyn Fn(Element)>::new(move |host: Element| {
+                instances.borrow_mut().retain(|(h, _)| *h != host);
+            });
+        let observed: Array = element
+            .attributes
+            .iter()
+            .map(|(name, _)| JsValue::from_str(name))
+            .collect();
+
+        let factory: Function = Function::new_no_args(CLASS_FACTORY)
+            .call0(&JsValue::NULL)?
+            .into();
+        // The class keeps the callbacks for as long as the page does.
+        let args = Array::of4(
+            &connect.into_js_value(),
+            &change.into_js_value(),
+            &disconnect.into_js_value(),
+            &observed,
+        );
+        let class: Function =
+            Reflect::apply(&factory, &JsValue::NULL, &args)?.into();
+        web_sys::window()
+            .ok_or_else(|| JsValue::from_str("no window to define in"))?
+            .custom_elements()
+            .define(name, &class)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_diff() {
+    use crate::vdom::{node, text, Attribute};
+    use smallvec::smallvec;
+
+    let tree = |class: &str, items: &[&str]| -> Node<()> {
+        node(
+            "ul".into(),
+            smallvec![Attribute::Class(class.to_string())],
+            items
+                .iter()
+                .map(|item| {
+                    NodeType::Node(node(
+                        "li".into(),
+                        smallvec![],
+                        vec![text(item.to_string())],
+                    ))
+                })
+                .collect(),
+        )
+    };
+    let old = tree("a", &["one", "two", "three"]);
+    assert!(diff(&old, &old).is_empty());
+
+    let new = tree("b", &["one", "2"]);
+    let patches = diff(&old, &new);
+    assert_eq!(patches.len(), 3);
+    assert!(matches!(patches[0], Patch::Attrs(ref p, _, _) if p.is_empty()));
+    assert_eq!(patches[1], Patch::Text(vec![1, 0], "2"));
+    assert_eq!(patches[2], Patch::Truncate(vec![], 2));
+
+    let longer = tree("a", &["one", "two", "three", "four"]);
+    assert!(matches!(
+        diff(&old, &longer)[..],
+        [Patch::Append(ref p, NodeType::Node(_))] if p.is_empty()
+    ));
+}
+
+#[test]
+fn test_instance_on() {
+    let instances = RefCell::new(vec![]);
+    let mut started = 0;
+    let mut start = |ok: bool| {
+        started += 1;
+        if ok {
+            Ok(started)
+        } else {
+            Err(started)
+        }
+    };
+    let mut errors = vec![];
+
+    assert_eq!(instance_on(&instances, &"a", || start(true), drop), Some(1));
+    assert_eq!(instance_on(&instances, &"a", || start(true), drop), Some(1));
+    // Removed from the page and added again.
+    instances.borrow_mut().retain(|(host, _)| *host != "a");
+    assert_eq!(instance_on(&instances, &"a", || start(true), drop), Some(2));
+
+    // Failing to start is reported, and tried again.
+    let report = |error| errors.push(error);
+    assert_eq!(instance_on(&instances, &"b", || start(false), report), None);
+    assert_eq!(instance_on(&instances, &"b", || start(true), drop), Some(4));
+    assert_eq!(errors, vec![3]);
+}
+
+#[test]
+fn test_link_click_stays_on_page() {
+    use crate::element::layout;
+    use crate::model::Element;
+    use crate::router::{link_to, Route};
+    use crate::test::find_by_text;
+
+    #[derive(Clone)]
+    struct Home;
+    impl Route for Home {
+        fn parse(_: &str) -> Option<Self> {
+            Some(Home)
+        }
+        fn to_path(&self) -> String {
+            "/".to_string()
+        }
+    }
+
+    let link = link_to(vec![], Home, Element::Text("Home".into()), |_| ());
+    let root = layout(vec![], link);
+    let (path, _) = find_by_text(&root, "Home").unwrap();
+    // The router is told instead of the browser following the link.
+    assert!(prevents_default(&root, &path, "click", true, true));
+    // But a new tab is still opened with a modifier key held.
+    assert!(!prevents_default(&root, &path, "click", false, true));
+    assert!(!prevents_default(&root, &path, "mousedown", true, true));
+}
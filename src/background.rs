@@ -0,0 +1,179 @@
+use crate::{
+    element::BlendMode,
+    flag::Flag,
+    model::{props_hash, Attribute, Color, FloatClass, Property, Style},
+};
+
+pub fn color<Msg>(color: Color) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::bg_color(),
+        Style::Colored(
+            format!("bg-{}", color.format_color_class()),
+            "background-color".into(),
+            color,
+        ),
+    )
+}
+
+/// A linear gradient through `steps`, evenly spaced. `angle` is in
+/// radians, with 0 going from the bottom up and turning clockwise.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub struct Gradient {
+    pub angle: f32,
+    pub steps: Vec<Color>,
+}
+
+/// A gradient background. With a single step it's a plain `color`, and
+/// with none, no background.
+pub fn gradient<Msg>(gradient: Gradient) -> Attribute<Msg> {
+    let Gradient { angle, steps } = gradient;
+    match steps.as_slice() {
+        [] => Attribute::None,
+        [only] => color(*only),
+        _ => Attribute::Style(
+            Flag::bg_gradient(),
+            Style::Single(
+                format!(
+                    "bg-grad-{}-{}",
+                    angle.float_class(),
+                    steps
+                        .iter()
+                        .map(|step| step.format_color_class())
+                        .collect::<Vec<String>>()
+                        .join("-")
+                ),
+                "background-image".into(),
+                format!(
+                    "linear-gradient({}rad, {})",
+                    angle,
+                    steps
+                        .iter()
+                        .map(|step| step.format_color())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            ),
+        ),
+    }
+}
+
+// `src` as a CSS `url()`, quoted, with the characters that would end or
+// break the string escaped.
+pub(crate) fn css_url(src: &str) -> String {
+    let mut url = String::with_capacity(src.len() + 7);
+    url.push_str("url(\"");
+    for c in src.chars() {
+        match c {
+            '"' | '\\' => {
+                url.push('\\');
+                url.push(c);
+            }
+            '\n' => url.push_str("\\a "),
+            c => url.push(c),
+        }
+    }
+    url.push_str("\")");
+    url
+}
+
+// The image at `src` as a background, placed as `placement` says. The
+// class is named by a hash of the url, so it stays the same between
+// renders without putting the url in the class.
+fn image_with<Msg>(src: &str, placement: &str) -> Attribute<Msg> {
+    let prop = Property(
+        "background".into(),
+        format!("{} {}", css_url(src), placement),
+    );
+    let class = format!("bg-img-{:x}", props_hash(std::slice::from_ref(&prop)));
+    Attribute::Style(Flag::bg_image(), Style::Single(class, prop.0, prop.1))
+}
+
+/// An image covering the whole background, cropped to fit and centered.
+pub fn image<Msg>(src: &str) -> Attribute<Msg> {
+    image_with(src, "center / cover no-repeat")
+}
+
+/// An image as big as fits in the background without being cropped,
+/// centered.
+pub fn uncropped<Msg>(src: &str) -> Attribute<Msg> {
+    image_with(src, "center / contain no-repeat")
+}
+
+/// An image repeated across and down the background.
+pub fn tiled<Msg>(src: &str) -> Attribute<Msg> {
+    image_with(src, "repeat")
+}
+
+/// An image repeated across the background.
+pub fn tiled_x<Msg>(src: &str) -> Attribute<Msg> {
+    image_with(src, "repeat-x")
+}
+
+/// An image repeated down the background.
+pub fn tiled_y<Msg>(src: &str) -> Attribute<Msg> {
+    image_with(src, "repeat-y")
+}
+
+/// Blend this element's background layers with each other, e.g. to tint
+/// an image with the background color.
+pub fn blend_mode<Msg>(mode: BlendMode) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::bg_blend_mode(),
+        Style::Single(
+            format!("bg-blend-{}", mode.as_str()),
+            "background-blend-mode".into(),
+            mode.as_str().to_string(),
+        ),
+    )
+}
+
+#[test]
+fn test_background() {
+    use crate::element::{el, extract_styles};
+    use crate::model::Element;
+
+    let red = Color::new(1.0, 0.0, 0.0, 1.0);
+    let blue = Color::new(0.0, 0.0, 1.0, 1.0);
+    assert!(matches!(
+        gradient::<()>(Gradient {
+            angle: 0.0,
+            steps: vec![],
+        }),
+        Attribute::None
+    ));
+    assert!(matches!(
+        gradient::<()>(Gradient {
+            angle: 0.0,
+            steps: vec![red],
+        }),
+        Attribute::Style(_, Style::Colored(..))
+    ));
+
+    let view = el::<()>(
+        vec![
+            color(red),
+            gradient(Gradient {
+                angle: 1.5,
+                steps: vec![red, blue],
+            }),
+            tiled_x("stripes.png"),
+        ],
+        Element::Empty,
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("background-color: rgba(255,0,0,1)"), "{}", css);
+    assert!(css.contains(
+        "background-image: linear-gradient(1.5rad, rgba(255,0,0,1), rgba(0,0,255,1))"
+    ));
+    assert!(css.contains("background: url(\"stripes.png\") repeat-x"));
+    assert_eq!(css_url("a\"b\\c.png"), "url(\"a\\\"b\\\\c.png\")");
+
+    // The class of an image only depends on where it's from.
+    let class = |attr: Attribute<()>| match attr {
+        Attribute::Style(_, style) => style.name(),
+        _ => unreachable!(),
+    };
+    assert_eq!(class(image("a.png")), class(image("a.png")));
+    assert_ne!(class(image("a.png")), class(image("b.png")));
+    assert_ne!(class(image("a.png")), class(uncropped("a.png")));
+}
@@ -0,0 +1,1651 @@
+use std::marker::PhantomData;
+
+#[cfg(feature = "bevy_picking")]
+use bevy::picking::{
+    events::{Click, Out, Over, Pointer},
+    PickSet, PickingBehavior,
+};
+use bevy::prelude::*;
+use bevy::render::{
+    camera::RenderTarget,
+    render_asset::RenderAssetUsages,
+    render_resource::{
+        Extent3d, TextureDimension, TextureFormat, TextureUsages,
+    },
+};
+use bevy::ui::UiSystem;
+use bevy::window::WindowRef;
+
+#[cfg(feature = "bevy_picking")]
+use crate::app::UiEvent;
+use crate::app::{UiMessage, UiProgram, UiRects};
+use crate::backend::{diff, Patch};
+use crate::element::{layout, layout_with, SnapAlign};
+use crate::focus::FocusState;
+use crate::model::{Attribute, Element, Opt};
+use crate::solver::{class_numbers, StyleSheet};
+use crate::vdom::{self, Attrs, EventData, NodeType, Rect, Rects};
+
+/// Shows the view of the `UiPlugin` with the same `Model` and `Msg` as Bevy
+/// UI nodes, one entity for each node of the rendered tree, and keeps
+/// `UiRects` up to date with where Bevy laid them out.
+///
+/// The CSS the view renders to is translated to Bevy's flexbox, so most
+/// layouts look the same as in a browser, but anything without a Bevy
+/// equivalent, like shadows and transitions, is left out.
+///
+/// With the `bevy_picking` feature, elements with event attributes are
+/// pick targets, and their clicks and hovers are sent on as `UiEvent`s.
+/// Everything else lets the pointer through to what's under it, so a
+/// click on a button's label goes to the button.
+///
+/// Views can also be shown on textures with `ui_surface`, e.g. for screens
+/// in the game world, and in other windows with `layout_for_window`.
+pub struct DeclarativeUiPlugin<Model, Msg>(PhantomData<fn() -> (Model, Msg)>);
+
+impl<Model, Msg> Default for DeclarativeUiPlugin<Model, Msg> {
+    fn default() -> Self {
+        DeclarativeUiPlugin(PhantomData)
+    }
+}
+
+impl<Model, Msg> DeclarativeUiPlugin<Model, Msg> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<Model, Msg> Plugin for DeclarativeUiPlugin<Model, Msg>
+where
+    Model: Send + Sync + 'static,
+    Msg: Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UiEntities::<Msg> {
+            root: None,
+            last: None,
+            sheet: StyleSheet::default(),
+        })
+        .add_event::<SurfaceHit>()
+        .add_event::<UiWindowEvent>()
+        .add_systems(
+            PreUpdate,
+            (
+                surface_hits::<Msg>,
+                window_events::<Msg>,
+                closed_windows::<Msg>,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                render_ui::<Model, Msg>.before(UiSystem::Prepare),
+                snap_scrolls.before(UiSystem::Layout),
+                (ui_rects::<Msg>, window_rects::<Msg>)
+                    .after(UiSystem::Layout)
+                    .after(TransformSystem::TransformPropagate),
+            ),
+        );
+        #[cfg(feature = "bevy_picking")]
+        app.add_systems(PreUpdate, picked::<Msg>.after(PickSet::Focus));
+    }
+}
+
+/// The node of the rendered tree an entity shows.
+#[derive(Component, Debug, Clone)]
+pub struct UiElement {
+    pub tag: String,
+    pub attrs: Attrs,
+}
+
+impl UiElement {
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attrs.iter().flat_map(vdom::Attribute::classes)
+    }
+
+    /// Where in the view's code the node was made, as `file:line:column`,
+    /// with the `debug-locations` feature.
+    pub fn source_location(&self) -> Option<&str> {
+        self.attrs
+            .iter()
+            .filter_map(vdom::Attribute::name_value)
+            .find_map(|(k, v)| if k == "data-at" { Some(v) } else { None })
+    }
+}
+
+// The entities showing the view, and the tree they were last updated to.
+#[derive(Resource)]
+pub(crate) struct UiEntities<Msg> {
+    pub(crate) root: Option<Entity>,
+    pub(crate) last: Option<vdom::Node<Msg>>,
+    pub(crate) sheet: StyleSheet,
+}
+
+fn parse_val(value: &str) -> Option<Val> {
+    let value = value.trim();
+    if let Some(px) = value.strip_suffix("px") {
+        px.parse().ok().map(Val::Px)
+    } else if let Some(percent) = value.strip_suffix('%') {
+        percent.parse().ok().map(Val::Percent)
+    } else {
+        None
+    }
+}
+
+// One to four lengths, in the order CSS gives them.
+fn parse_rect(value: &str) -> Option<UiRect> {
+    let vals: Option<Vec<Val>> =
+        value.split_whitespace().map(parse_val).collect();
+    Some(match vals?[..] {
+        [all] => UiRect::all(all),
+        [y, x] => UiRect::axes(x, y),
+        [top, x, bottom] => UiRect::new(x, x, top, bottom),
+        [top, right, bottom, left] => UiRect::new(left, right, top, bottom),
+        _ => return None,
+    })
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let inner = value
+        .trim()
+        .strip_prefix("rgba(")
+        .or_else(|| value.trim().strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let parts: Option<Vec<f32>> =
+        inner.split(',').map(|p| p.trim().parse().ok()).collect();
+    match parts?[..] {
+        [r, g, b] => Some(Color::srgb_u8(r as u8, g as u8, b as u8)),
+        [r, g, b, a] => Some(Color::srgba(r / 255.0, g / 255.0, b / 255.0, a)),
+        _ => None,
+    }
+}
+
+/// The Bevy layout of a node with `classes`, in a parent with
+/// `parent` classes.
+fn node_style(
+    sheet: &StyleSheet,
+    tag: &str,
+    classes: &[&str],
+    parent: &[&str],
+) -> Node {
+    let has = |class: &str| classes.contains(&class);
+    let in_row = parent.contains(&"r");
+    let mut node = Node::default();
+    if tag == "style" {
+        node.display = Display::None;
+        return node;
+    }
+    node.flex_direction = if has("r") {
+        FlexDirection::Row
+    } else {
+        FlexDirection::Column
+    };
+    if has("wrp") {
+        node.flex_wrap = FlexWrap::Wrap;
+    }
+    if has("ui") {
+        node.width = Val::Percent(100.0);
+        node.height = Val::Percent(100.0);
+    }
+
+    // Filling the parent grows along a row or column, and stretches
+    // across it.
+    let portion = |prefix| {
+        class_numbers(classes, prefix)
+            .and_then(|n| n.first().copied())
+            .unwrap_or(1.0)
+    };
+    if has("wf") || has("wfp") {
+        if in_row {
+            node.flex_grow = portion("width-fill-");
+            node.flex_basis = Val::Px(0.0);
+        } else {
+            node.align_self = AlignSelf::Stretch;
+        }
+    }
+    if has("hf") || has("hfp") {
+        if in_row {
+            node.align_self = AlignSelf::Stretch;
+        } else {
+            node.flex_grow = portion("height-fill-");
+            node.flex_basis = Val::Px(0.0);
+        }
+    }
+    let length = |name| sheet.get(classes, name).and_then(parse_val);
+    node.width = length("width").unwrap_or(node.width);
+    node.height = length("height").unwrap_or(node.height);
+    node.min_width = length("min-width").unwrap_or(node.min_width);
+    node.max_width = length("max-width").unwrap_or(node.max_width);
+    node.min_height = length("min-height").unwrap_or(node.min_height);
+    node.max_height = length("max-height").unwrap_or(node.max_height);
+    let rect = |name| sheet.get(classes, name).and_then(parse_rect);
+    node.padding = rect("padding").unwrap_or(node.padding);
+    node.border = rect("border-width").unwrap_or(node.border);
+    if let Some(spacing) = class_numbers(classes, "spacing-") {
+        if let [x, y] = spacing[..] {
+            node.column_gap = Val::Px(x);
+            node.row_gap = Val::Px(y);
+        }
+    }
+
+    // Aligning along the parent's direction takes up the free space with
+    // margins, and across it aligns the node itself.
+    let (along, across) = if in_row {
+        (["al", "cx", "ar"], ["at", "cy", "ab"])
+    } else {
+        (["at", "cy", "ab"], ["al", "cx", "ar"])
+    };
+    let mut margin = |start: bool, end: bool| {
+        let (first, second) = if in_row {
+            (&mut node.margin.left, &mut node.margin.right)
+        } else {
+            (&mut node.margin.top, &mut node.margin.bottom)
+        };
+        if start {
+            *first = Val::Auto;
+        }
+        if end {
+            *second = Val::Auto;
+        }
+    };
+    if has(along[1]) {
+        margin(true, true);
+    } else if has(along[2]) || has("acr") || has("acb") {
+        margin(true, false);
+    }
+    if has(across[0]) {
+        node.align_self = AlignSelf::FlexStart;
+    } else if has(across[1]) {
+        node.align_self = AlignSelf::Center;
+    } else if has(across[2]) {
+        node.align_self = AlignSelf::FlexEnd;
+    }
+    // `align_self` wins over both.
+    let own = [
+        ("self-start", AlignSelf::FlexStart),
+        ("self-center", AlignSelf::Center),
+        ("self-end", AlignSelf::FlexEnd),
+        ("self-stretch", AlignSelf::Stretch),
+        ("self-baseline", AlignSelf::Baseline),
+    ];
+    if let Some((_, align)) = own.iter().find(|(c, _)| has(c)) {
+        node.align_self = *align;
+    }
+
+    // Nearby elements are placed around their parent, out of its flow.
+    let nearby = [
+        ("a", "bottom"),
+        ("b", "top"),
+        ("or", "left"),
+        ("ol", "right"),
+    ];
+    if has("fr") || has("bh") {
+        node.position_type = PositionType::Absolute;
+        node.width = Val::Percent(100.0);
+        node.height = Val::Percent(100.0);
+    } else if let Some((_, side)) = nearby.iter().find(|(c, _)| has(c)) {
+        node.position_type = PositionType::Absolute;
+        match *side {
+            "bottom" => node.bottom = Val::Percent(100.0),
+            "top" => node.top = Val::Percent(100.0),
+            "left" => node.left = Val::Percent(100.0),
+            _ => node.right = Val::Percent(100.0),
+        }
+    }
+    if has("sb") {
+        node.overflow = Overflow::scroll();
+    } else if has("sbx") {
+        node.overflow = Overflow::scroll_x();
+    } else if has("sby") {
+        node.overflow = Overflow::scroll_y();
+    } else if has("cp") || has("oh") {
+        node.overflow = Overflow::clip();
+    } else if has("cpx") {
+        node.overflow = Overflow::clip_x();
+    } else if has("cpy") {
+        node.overflow = Overflow::clip_y();
+    }
+    node
+}
+
+// How the children of a `scroll_snap` element line up when it comes to
+// rest, from the class after `prefix`: `snap-` for the element's own
+// alignment, and `snap-self-` for a child's `snap_align`.
+fn snap_alignment(classes: &[&str], prefix: &str) -> Option<SnapAlign> {
+    classes
+        .iter()
+        .find_map(|class| match class.strip_prefix(prefix)? {
+            "start" => Some(SnapAlign::Start),
+            "center" => Some(SnapAlign::Center),
+            "end" => Some(SnapAlign::End),
+            "none" => Some(SnapAlign::None),
+            _ => None,
+        })
+}
+
+// Where a `scroll_snap` element scrolled to `offset` comes to rest: the
+// nearest offset lining up one of its children, given as their start,
+// size and alignment along the axis, with the `viewport` it scrolls.
+fn snap_offset(
+    offset: f32,
+    viewport: f32,
+    children: &[(f32, f32, SnapAlign)],
+) -> Option<f32> {
+    children
+        .iter()
+        .filter_map(|&(start, size, align)| match align {
+            SnapAlign::Start => Some(start),
+            SnapAlign::Center => Some(start + (size - viewport) / 2.0),
+            SnapAlign::End => Some(start + size - viewport),
+            SnapAlign::None => None,
+        })
+        .map(|target| target.max(0.0))
+        .min_by(|a, b| (a - offset).abs().total_cmp(&(b - offset).abs()))
+}
+
+/// How long, in seconds, a `scroll_snap` element has to be left at the
+/// same offset before it comes to rest on a child.
+const SNAP_SETTLE: f32 = 0.15;
+
+// Where a `scroll_snap` element was scrolled to, and for how long.
+#[derive(Component, Default)]
+struct SnapSettle {
+    offset: Vec2,
+    still: f32,
+}
+
+// Bring each `scroll_snap` element to rest on a child once it's stopped
+// being scrolled, as browsers do.
+fn snap_scrolls(
+    time: Res<Time>,
+    mut scrollers: Query<(
+        &UiElement,
+        &ComputedNode,
+        &Children,
+        &mut ScrollPosition,
+        &mut SnapSettle,
+    )>,
+    children: Query<(&ComputedNode, &Transform, Option<&Children>)>,
+    elements: Query<&UiElement>,
+) {
+    let own = |entity: Entity| {
+        let classes: Vec<&str> = elements.get(entity).ok()?.classes().collect();
+        snap_alignment(&classes, "snap-self-")
+    };
+    for (element, node, kids, mut scroll, mut settle) in &mut scrollers {
+        let offset = Vec2::from(&*scroll);
+        if offset != settle.offset {
+            *settle = SnapSettle { offset, still: 0.0 };
+            continue;
+        }
+        settle.still += time.delta_secs();
+        if settle.still < SNAP_SETTLE {
+            continue;
+        }
+        let classes: Vec<&str> = element.classes().collect();
+        let x = classes.contains(&"snap-x");
+        let along = |v: Vec2| if x { v.x } else { v.y };
+        let align =
+            snap_alignment(&classes, "snap-").unwrap_or(SnapAlign::Start);
+        let scale = node.inverse_scale_factor();
+        let viewport = node.size() * scale;
+        let snaps: Vec<(f32, f32, SnapAlign)> = kids
+            .iter()
+            .filter_map(|&child| {
+                let (child_node, transform, inner) =
+                    children.get(child).ok()?;
+                let size = child_node.size() * scale;
+                // A child's transform is of its center, from the center of
+                // the element, moved back by the offset.
+                let start = transform.translation.truncate() * scale
+                    + (viewport - size) / 2.0
+                    + offset;
+                // Children can be wrapped in a bare node to lay them out,
+                // which lines up as they do.
+                let align = own(child)
+                    .or_else(|| own(*inner?.first()?))
+                    .unwrap_or(align);
+                Some((along(start), along(size), align))
+            })
+            .collect();
+        let target = match snap_offset(along(offset), along(viewport), &snaps) {
+            Some(target) if target != along(offset) => target,
+            _ => continue,
+        };
+        if x {
+            scroll.offset_x = target;
+        } else {
+            scroll.offset_y = target;
+        }
+        settle.offset = Vec2::from(&*scroll);
+    }
+}
+
+// The sheet an `image_from_atlas` node is drawn from, and the part of it
+// in pixels that it shows.
+fn atlas_tile(element: &UiElement) -> Option<(String, bevy::math::Rect)> {
+    let attr = |name| {
+        element
+            .attrs
+            .iter()
+            .filter_map(vdom::Attribute::name_value)
+            .find_map(|(k, v)| if k == name { Some(v) } else { None })
+    };
+    let src = attr("data-atlas")?;
+    let rect = attr("data-atlas-rect")?
+        .split(' ')
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()?;
+    match rect[..] {
+        [x, y, w, h] => {
+            Some((src.to_string(), bevy::math::Rect::new(x, y, x + w, y + h)))
+        }
+        _ => None,
+    }
+}
+
+fn classes_of(world: &World, entity: Entity) -> Vec<String> {
+    world
+        .get::<UiElement>(entity)
+        .map(|e| e.classes().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+// The value of an inherited CSS property, like the text color, for a node
+// in `parent`.
+fn inherited<'a>(
+    world: &World,
+    sheet: &'a StyleSheet,
+    parent: Option<Entity>,
+    name: &str,
+) -> Option<&'a str> {
+    let mut entity = parent;
+    while let Some(e) = entity {
+        let classes = classes_of(world, e);
+        let classes: Vec<&str> = classes.iter().map(String::as_str).collect();
+        if let Some(value) = sheet.get(&classes, name) {
+            return Some(value);
+        }
+        entity = world.get::<Parent>(e).map(Parent::get);
+    }
+    None
+}
+
+// Set the components of the node `entity` shows, and restyle its children,
+// whose layout depends on it.
+fn style_entity(world: &mut World, sheet: &StyleSheet, entity: Entity) {
+    let parent = world.get::<Parent>(entity).map(Parent::get);
+    if world.get::<Text>(entity).is_some() {
+        let color = inherited(world, sheet, parent, "color")
+            .and_then(parse_color)
+            .unwrap_or(Color::BLACK);
+        let size = inherited(world, sheet, parent, "font-size")
+            .and_then(parse_val)
+            .and_then(|val| match val {
+                Val::Px(px) => Some(px),
+                _ => None,
+            });
+        let mut e = world.entity_mut(entity);
+        e.insert(TextColor(color));
+        if let (Some(size), Some(mut font)) = (size, e.get_mut::<TextFont>()) {
+            font.font_size = size;
+        }
+        return;
+    }
+    let tag = match world.get::<UiElement>(entity) {
+        Some(element) => element.tag.clone(),
+        None => return,
+    };
+    let own = classes_of(world, entity);
+    let own: Vec<&str> = own.iter().map(String::as_str).collect();
+    let parent_classes =
+        parent.map(|p| classes_of(world, p)).unwrap_or_default();
+    let parent_classes: Vec<&str> =
+        parent_classes.iter().map(String::as_str).collect();
+    let node = node_style(sheet, &tag, &own, &parent_classes);
+    let color = |name| sheet.get(&own, name).and_then(parse_color);
+    let background = color("background-color").unwrap_or(Color::NONE);
+    let border = color("border-color").unwrap_or(Color::NONE);
+    let radius = sheet
+        .get(&own, "border-radius")
+        .and_then(parse_val)
+        .map_or(BorderRadius::ZERO, BorderRadius::all);
+    let snaps = own.contains(&"snap-x") || own.contains(&"snap-y");
+    // Without an asset server, as in tests, the tile's rect is still set.
+    let image = world.get::<UiElement>(entity).and_then(atlas_tile).map(
+        |(src, rect)| ImageNode {
+            rect: Some(rect),
+            ..ImageNode::new(
+                world
+                    .get_resource::<AssetServer>()
+                    .map_or_else(Handle::default, |assets| assets.load(src)),
+            )
+        },
+    );
+    let mut e = world.entity_mut(entity);
+    e.insert((
+        node,
+        BackgroundColor(background),
+        BorderColor(border),
+        radius,
+    ));
+    match image {
+        Some(image) => {
+            e.insert(image);
+        }
+        None => {
+            e.remove::<ImageNode>();
+        }
+    }
+    if !snaps {
+        e.remove::<SnapSettle>();
+    } else if !e.contains::<SnapSettle>() {
+        e.insert(SnapSettle::default());
+    }
+    let children: Vec<Entity> = world
+        .get::<Children>(entity)
+        .map(|c| c.to_vec())
+        .unwrap_or_default();
+    for child in children {
+        style_entity(world, sheet, child);
+    }
+}
+
+// Styled afterwards, once the whole subtree is there to inherit from.
+fn spawn_node<Msg>(world: &mut World, node: &vdom::Node<Msg>) -> Entity {
+    let entity = world
+        .spawn(UiElement {
+            tag: node.tag.to_string(),
+            attrs: node.attrs.clone(),
+        })
+        .id();
+    for child in &node.children {
+        spawn_child(world, child, Some(entity));
+    }
+    entity
+}
+
+fn spawn_child<Msg>(
+    world: &mut World,
+    child: &NodeType<Msg>,
+    parent: Option<Entity>,
+) -> Entity {
+    let entity = match child {
+        NodeType::Text(text) => world.spawn(Text::new(text.clone())).id(),
+        NodeType::Node(node) | NodeType::KeyedNode(_, node) => {
+            spawn_node(world, node)
+        }
+    };
+    if let Some(parent) = parent {
+        world.entity_mut(parent).add_child(entity);
+    }
+    entity
+}
+
+fn entity_at(world: &World, root: Entity, path: &[usize]) -> Option<Entity> {
+    path.iter().try_fold(root, |entity, i| {
+        world.get::<Children>(entity)?.get(*i).copied()
+    })
+}
+
+// Put `new`, just spawned, at the end of `path`, in place of the entity
+// there.
+fn replace(
+    world: &mut World,
+    sheet: &StyleSheet,
+    root: &mut Entity,
+    path: &[usize],
+    new: Entity,
+) {
+    let old = match entity_at(world, *root, path) {
+        Some(old) => old,
+        None => {
+            world.entity_mut(new).despawn_recursive();
+            return;
+        }
+    };
+    match path.split_last() {
+        Some((i, parent)) => {
+            if let Some(parent) = entity_at(world, *root, parent) {
+                world.entity_mut(parent).insert_children(*i, &[new]);
+            }
+        }
+        None => *root = new,
+    }
+    world.entity_mut(old).despawn_recursive();
+    style_entity(world, sheet, new);
+}
+
+fn apply<Msg>(
+    world: &mut World,
+    sheet: &StyleSheet,
+    root: &mut Entity,
+    patch: Patch<'_, Msg>,
+) {
+    match patch {
+        Patch::Replace(path, node) => {
+            let new = spawn_node(world, node);
+            replace(world, sheet, root, &path, new)
+        }
+        Patch::Text(path, text) => {
+            let entity = entity_at(world, *root, &path);
+            match entity.and_then(|e| world.get_mut::<Text>(e)) {
+                Some(mut current) => current.0 = text.to_string(),
+                None => {
+                    let new = world.spawn(Text::new(text.to_string())).id();
+                    replace(world, sheet, root, &path, new)
+                }
+            }
+        }
+        Patch::Attrs(path, _, attrs) => {
+            if let Some(entity) = entity_at(world, *root, &path) {
+                if let Some(mut element) = world.get_mut::<UiElement>(entity) {
+                    element.attrs = attrs.clone();
+                }
+                style_entity(world, sheet, entity);
+            }
+        }
+        Patch::Append(path, child) => {
+            if let Some(parent) = entity_at(world, *root, &path) {
+                let child = spawn_child(world, child, Some(parent));
+                style_entity(world, sheet, child);
+            }
+        }
+        Patch::Truncate(path, len) => {
+            let children = entity_at(world, *root, &path)
+                .and_then(|e| world.get::<Children>(e))
+                .map(|c| c.iter().skip(len).copied().collect::<Vec<_>>())
+                .unwrap_or_default();
+            for child in children {
+                world.entity_mut(child).despawn_recursive();
+            }
+        }
+    }
+}
+
+// Bring the entities up to date with the last rendered view.
+fn render_ui<Model, Msg>(world: &mut World)
+where
+    Model: Send + Sync + 'static,
+    Msg: Send + Sync + 'static,
+{
+    let node = {
+        let program = &world.resource::<UiProgram<Model, Msg>>().0;
+        let entities = world.resource::<UiEntities<Msg>>();
+        if entities.last.as_ref() == Some(program.node()) {
+            return;
+        }
+        program.node().clone()
+    };
+    world.resource_scope(|world, mut entities: Mut<UiEntities<Msg>>| {
+        let entities = &mut *entities;
+        entities.sheet.update(&node);
+        let sheet = &entities.sheet;
+        match (entities.root, &entities.last) {
+            (Some(mut root), Some(last)) => {
+                for patch in diff(last, &node) {
+                    apply(world, sheet, &mut root, patch);
+                }
+                entities.root = Some(root);
+            }
+            _ => {
+                let root = spawn_node(world, &node);
+                style_entity(world, sheet, root);
+                entities.root = Some(root);
+            }
+        }
+        #[cfg(feature = "bevy_picking")]
+        if let Some(root) = entities.root {
+            pick_targets(world, root, &node);
+        }
+        entities.last = Some(node);
+    });
+}
+
+// Only elements listening for events can be picked. Listeners aren't
+// patched, so they're gone over after every render.
+#[cfg(feature = "bevy_picking")]
+fn pick_targets<Msg>(
+    world: &mut World,
+    entity: Entity,
+    node: &vdom::Node<Msg>,
+) {
+    let behavior = if node.events.is_empty() {
+        PickingBehavior::IGNORE
+    } else {
+        PickingBehavior::default()
+    };
+    world.entity_mut(entity).insert(behavior);
+    let children = match world.get::<Children>(entity) {
+        Some(children) => children.to_vec(),
+        None => return,
+    };
+    for (child, entity) in node.children.iter().zip(children) {
+        match child {
+            NodeType::Node(n) | NodeType::KeyedNode(_, n) => {
+                pick_targets(world, entity, n)
+            }
+            NodeType::Text(_) => {
+                world.entity_mut(entity).insert(PickingBehavior::IGNORE);
+            }
+        }
+    }
+}
+
+// The path from the root to `entity`, if it's one of the view's.
+#[cfg(feature = "bevy_picking")]
+fn path_of(
+    root: Entity,
+    mut entity: Entity,
+    parents: &Query<&Parent>,
+    children: &Query<&Children>,
+) -> Option<Vec<usize>> {
+    let mut path = vec![];
+    while entity != root {
+        let parent = parents.get(entity).ok()?.get();
+        let siblings = children.get(parent).ok()?;
+        path.push(siblings.iter().position(|c| *c == entity)?);
+        entity = parent;
+    }
+    path.reverse();
+    Some(path)
+}
+
+#[cfg(feature = "bevy_picking")]
+type PointerEvents<'w, 's> = (
+    EventReader<'w, 's, Pointer<Click>>,
+    EventReader<'w, 's, Pointer<Over>>,
+    EventReader<'w, 's, Pointer<Out>>,
+);
+
+// Clicks and hovers from bevy_picking, as the events a browser would send.
+#[cfg(feature = "bevy_picking")]
+fn picked<Msg: Send + Sync + 'static>(
+    entities: Res<UiEntities<Msg>>,
+    windows: Query<&UiWindow<Msg>>,
+    (mut clicks, mut overs, mut outs): PointerEvents,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    mut events: EventWriter<UiEvent>,
+    mut window_events: EventWriter<UiWindowEvent>,
+) {
+    let targets = overs
+        .read()
+        .map(|e| (e.target, "mouseenter"))
+        .chain(outs.read().map(|e| (e.target, "mouseleave")))
+        .chain(clicks.read().map(|e| (e.target, "click")))
+        .collect::<Vec<_>>();
+    for (target, event) in targets {
+        let main = entities
+            .root
+            .and_then(|root| path_of(root, target, &parents, &children));
+        if let Some(path) = main {
+            events.send(UiEvent {
+                path,
+                event: event.to_string(),
+                data: EventData::None,
+            });
+            continue;
+        }
+        for ui in &windows {
+            if let Some(path) = path_of(ui.root, target, &parents, &children) {
+                window_events.send(UiWindowEvent {
+                    window: ui.window,
+                    path,
+                    event: event.to_string(),
+                    data: EventData::None,
+                });
+            }
+        }
+    }
+}
+
+type LaidOut<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static ComputedNode,
+        &'static GlobalTransform,
+        Option<&'static Children>,
+    ),
+>;
+
+// Where Bevy laid out each node under `root`, in logical pixels.
+fn rects_from(
+    root: Entity,
+    nodes: &LaidOut,
+    elements: &Query<(), With<UiElement>>,
+) -> Rects {
+    fn walk(
+        entity: Entity,
+        path: &mut Vec<usize>,
+        nodes: &LaidOut,
+        elements: &Query<(), With<UiElement>>,
+        out: &mut Rects,
+    ) {
+        let (computed, transform, children) = match nodes.get(entity) {
+            Ok(node) => node,
+            Err(_) => return,
+        };
+        if elements.contains(entity) {
+            let scale = computed.inverse_scale_factor();
+            let size = computed.size() * scale;
+            let center = transform.translation().truncate() * scale;
+            out.insert(
+                path.clone(),
+                Rect {
+                    x: center.x - size.x / 2.0,
+                    y: center.y - size.y / 2.0,
+                    width: size.x,
+                    height: size.y,
+                },
+            );
+        }
+        for (i, child) in children.into_iter().flatten().enumerate() {
+            path.push(i);
+            walk(*child, path, nodes, elements, out);
+            path.pop();
+        }
+    }
+    let mut out = Rects::new();
+    walk(root, &mut vec![], nodes, elements, &mut out);
+    out
+}
+
+fn ui_rects<Msg: Send + Sync + 'static>(
+    entities: Res<UiEntities<Msg>>,
+    nodes: LaidOut,
+    elements: Query<(), With<UiElement>>,
+    mut rects: ResMut<UiRects>,
+) {
+    let root = match entities.root {
+        Some(root) => root,
+        None => return,
+    };
+    let out = rects_from(root, &nodes, &elements);
+    if rects.0 != out {
+        rects.0 = out;
+    }
+}
+
+// Spawn the entities for a view of its own, drawn by `camera`, or by the
+// default camera if `None`.
+pub(crate) fn spawn_view<Msg>(
+    world: &mut World,
+    sheet: &mut StyleSheet,
+    node: &vdom::Node<Msg>,
+    camera: Option<Entity>,
+) -> Entity {
+    sheet.update(node);
+    let root = spawn_node(world, node);
+    style_entity(world, sheet, root);
+    if let Some(camera) = camera {
+        world.entity_mut(root).insert(TargetCamera(camera));
+    }
+    #[cfg(feature = "bevy_picking")]
+    pick_targets(world, root, node);
+    root
+}
+
+// Bring the entities from `spawn_view` up to date with `new`.
+pub(crate) fn patch_view<Msg>(
+    world: &mut World,
+    sheet: &mut StyleSheet,
+    root: &mut Entity,
+    old: &vdom::Node<Msg>,
+    new: &vdom::Node<Msg>,
+    camera: Option<Entity>,
+) {
+    sheet.update(new);
+    let before = *root;
+    for patch in diff(old, new) {
+        apply(world, sheet, root, patch);
+    }
+    if let (true, Some(camera)) = (*root != before, camera) {
+        world.entity_mut(*root).insert(TargetCamera(camera));
+    }
+    #[cfg(feature = "bevy_picking")]
+    pick_targets(world, *root, new);
+}
+
+/// A view shown on a texture instead of a window, made by `ui_surface`.
+#[derive(Component)]
+pub struct UiSurface<Msg> {
+    pub image: Handle<Image>,
+    pub size: UVec2,
+    camera: Entity,
+    root: Entity,
+    node: vdom::Node<Msg>,
+    sheet: StyleSheet,
+}
+
+/// Show `element` on a new texture `size` pixels across, to put on a
+/// material like any other image, e.g. for a monitor in the game world.
+///
+/// Change what it shows with `update_surface`, and forward where it's
+/// pointed at with `SurfaceHit`.
+pub fn ui_surface<Msg: Send + Sync + 'static>(
+    world: &mut World,
+    size: UVec2,
+    element: Element<Msg>,
+) -> Handle<Image> {
+    let extent = Extent3d {
+        width: size.x,
+        height: size.y,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image::new_fill(
+        extent,
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    let image = world.resource_mut::<Assets<Image>>().add(image);
+    let camera = world
+        .spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone()),
+                // Drawn before the cameras that show it.
+                order: -1,
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                ..default()
+            },
+        ))
+        .id();
+    let node = layout(vec![], element);
+    let mut sheet = StyleSheet::default();
+    let root = spawn_view(world, &mut sheet, &node, Some(camera));
+    world.spawn(UiSurface {
+        image: image.clone(),
+        size,
+        camera,
+        root,
+        node,
+        sheet,
+    });
+    image
+}
+
+fn find_surface<Msg: Send + Sync + 'static>(
+    world: &mut World,
+    image: &Handle<Image>,
+) -> Option<Entity> {
+    world
+        .query::<(Entity, &UiSurface<Msg>)>()
+        .iter(world)
+        .find(|(_, surface)| surface.image == *image)
+        .map(|(entity, _)| entity)
+}
+
+/// Show `element` on the surface from `ui_surface` instead, patching the
+/// entities showing it like the main view.
+pub fn update_surface<Msg: Send + Sync + 'static>(
+    world: &mut World,
+    image: &Handle<Image>,
+    element: Element<Msg>,
+) {
+    let entity = match find_surface::<Msg>(world, image) {
+        Some(entity) => entity,
+        None => return,
+    };
+    let mut surface = match world.entity_mut(entity).take::<UiSurface<Msg>>() {
+        Some(surface) => surface,
+        None => return,
+    };
+    let node = layout(vec![], element);
+    let UiSurface {
+        camera,
+        root,
+        node: old,
+        sheet,
+        ..
+    } = &mut surface;
+    patch_view(world, sheet, root, old, &node, Some(*camera));
+    *old = node;
+    world.entity_mut(entity).insert(surface);
+}
+
+/// Stop showing the surface from `ui_surface`, despawning its entities.
+/// The texture stays until its handles are dropped.
+pub fn remove_surface<Msg: Send + Sync + 'static>(
+    world: &mut World,
+    image: &Handle<Image>,
+) {
+    let entity = match find_surface::<Msg>(world, image) {
+        Some(entity) => entity,
+        None => return,
+    };
+    if let Some(surface) = world.entity_mut(entity).take::<UiSurface<Msg>>() {
+        world.entity_mut(surface.root).despawn_recursive();
+        world.entity_mut(surface.camera).despawn();
+    }
+    world.entity_mut(entity).despawn();
+}
+
+/// Something pointing at a surface from `ui_surface`, at `uv` on its
+/// texture, e.g. where a ray from the cursor hit the mesh showing it.
+///
+/// `event` is what a browser would send, like `click` or `mouseenter`,
+/// and the messages of the element pointed at go out as `UiMessage`s.
+#[derive(Event, Debug, Clone)]
+pub struct SurfaceHit {
+    pub image: Handle<Image>,
+    pub uv: Vec2,
+    pub event: String,
+}
+
+// The innermost element at `(x, y)`, the last drawn if several are.
+pub(crate) fn path_at(rects: &Rects, x: f32, y: f32) -> Option<Vec<usize>> {
+    rects
+        .iter()
+        .filter(|(_, r)| {
+            x >= r.x && x < r.x + r.width && y >= r.y && y < r.y + r.height
+        })
+        .map(|(path, _)| path)
+        .max_by_key(|path| path.len())
+        .cloned()
+}
+
+fn surface_hits<Msg: Send + Sync + 'static>(
+    mut hits: EventReader<SurfaceHit>,
+    surfaces: Query<&UiSurface<Msg>>,
+    nodes: LaidOut,
+    elements: Query<(), With<UiElement>>,
+    mut messages: EventWriter<UiMessage<Msg>>,
+) {
+    for hit in hits.read() {
+        let surface = match surfaces.iter().find(|s| s.image == hit.image) {
+            Some(surface) => surface,
+            None => continue,
+        };
+        let point = hit.uv * surface.size.as_vec2();
+        let rects = rects_from(surface.root, &nodes, &elements);
+        let path = match path_at(&rects, point.x, point.y) {
+            Some(path) => path,
+            None => continue,
+        };
+        // Run from the element up to the root, as the DOM would, except
+        // for those that don't bubble.
+        let lengths = match hit.event.as_str() {
+            "mouseenter" | "mouseleave" => path.len()..=path.len(),
+            _ => 0..=path.len(),
+        };
+        for len in lengths.rev() {
+            let msgs = surface.node.dispatch(
+                &path[..len],
+                &hit.event,
+                &EventData::None,
+            );
+            messages.send_batch(msgs.into_iter().map(UiMessage));
+        }
+    }
+}
+
+/// A view shown in a window of its own by `layout_for_window`, apart from
+/// the main view, with its own stylesheet, focus and layout, e.g. for an
+/// editor's tool windows. It's kept on the camera drawing it.
+#[derive(Component)]
+pub struct UiWindow<Msg> {
+    pub window: Entity,
+    root: Entity,
+    node: vdom::Node<Msg>,
+    sheet: StyleSheet,
+    focus: FocusState,
+    rects: Rects,
+}
+
+impl<Msg> UiWindow<Msg> {
+    pub fn node(&self) -> &vdom::Node<Msg> {
+        &self.node
+    }
+
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focus.focused()
+    }
+
+    /// Where the view was last laid out, in the window's logical pixels.
+    pub fn rects(&self) -> &Rects {
+        &self.rects
+    }
+
+    /// The messages of the `event` listeners on the node at `path`, as for
+    /// `Program::dispatch`, keeping track of focus like `UiPlugin` does for
+    /// the main view: `focus` events move it, and key presses go wherever
+    /// it is, moving it with `Tab` and the arrow keys.
+    pub fn dispatch(
+        &mut self,
+        path: &[usize],
+        event: &str,
+        data: &EventData,
+    ) -> Vec<Msg> {
+        match (event, data) {
+            ("keydown", EventData::Key { key, shift }) => {
+                let mut focus = self.focus.clone();
+                if focus.key_down(&self.node, key, *shift) {
+                    match focus.focused() {
+                        Some(path) => self.focus(path),
+                        None => vec![],
+                    }
+                } else {
+                    match self.focus.focused() {
+                        Some(path) => self.node.dispatch(path, event, data),
+                        None => vec![],
+                    }
+                }
+            }
+            ("focus", _) => self.focus(path),
+            _ => self.node.dispatch(path, event, data),
+        }
+    }
+
+    fn focus(&mut self, path: &[usize]) -> Vec<Msg> {
+        let mut msgs = match self.focus.focused() {
+            Some(old) if old == path => return vec![],
+            Some(old) => self.node.dispatch(old, "blur", &EventData::None),
+            None => vec![],
+        };
+        self.focus.focus(&self.node, path);
+        msgs.extend(self.node.dispatch(path, "focus", &EventData::None));
+        msgs
+    }
+}
+
+/// Show `element` in `window`, laid out as by `layout_with` but apart from
+/// the main view and those of other windows. Call it again whenever the
+/// view changes, and the entities showing it are patched like the main
+/// view's.
+///
+/// Clicks and hovers on it are sent with `bevy_picking`, and anything else
+/// can be sent as `UiWindowEvent`s, with their messages going out as
+/// `UiMessage`s. The view is despawned when the window is.
+pub fn layout_for_window<Msg: Send + Sync + 'static>(
+    world: &mut World,
+    window: Entity,
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    element: Element<Msg>,
+) {
+    let mut node = layout_with(opts, attrs, element);
+    let camera = world
+        .query::<(Entity, &UiWindow<Msg>)>()
+        .iter(world)
+        .find(|(_, ui)| ui.window == window)
+        .map(|(camera, _)| camera);
+    let ui = camera.and_then(|c| world.entity_mut(c).take::<UiWindow<Msg>>());
+    match (camera, ui) {
+        (Some(camera), Some(mut ui)) => {
+            ui.focus.sync(&mut node);
+            let UiWindow {
+                root,
+                node: old,
+                sheet,
+                ..
+            } = &mut ui;
+            patch_view(world, sheet, root, old, &node, Some(camera));
+            *old = node;
+            world.entity_mut(camera).insert(ui);
+        }
+        _ => {
+            // Drawn over the window's other cameras, if it has any.
+            let shared = world.query::<&Camera>().iter(world).any(|c| {
+                matches!(
+                    c.target,
+                    RenderTarget::Window(WindowRef::Entity(w)) if w == window
+                )
+            });
+            let (order, clear_color) = if shared {
+                (1, ClearColorConfig::None)
+            } else {
+                (0, ClearColorConfig::Default)
+            };
+            let camera = world
+                .spawn((
+                    Camera2d,
+                    Camera {
+                        target: RenderTarget::Window(WindowRef::Entity(window)),
+                        order,
+                        clear_color,
+                        ..default()
+                    },
+                ))
+                .id();
+            let mut focus = FocusState::default();
+            focus.sync(&mut node);
+            let mut sheet = StyleSheet::default();
+            let root = spawn_view(world, &mut sheet, &node, Some(camera));
+            world.entity_mut(camera).insert(UiWindow {
+                window,
+                root,
+                node,
+                sheet,
+                focus,
+                rects: Rects::new(),
+            });
+        }
+    }
+}
+
+/// An interaction with the view of `window`, like `UiEvent` for the main
+/// view.
+#[derive(Event, Debug, Clone)]
+pub struct UiWindowEvent {
+    pub window: Entity,
+    pub path: Vec<usize>,
+    pub event: String,
+    pub data: EventData,
+}
+
+fn window_events<Msg: Send + Sync + 'static>(
+    mut events: EventReader<UiWindowEvent>,
+    mut windows: Query<&mut UiWindow<Msg>>,
+    mut messages: EventWriter<UiMessage<Msg>>,
+) {
+    for event in events.read() {
+        let mut ui =
+            match windows.iter_mut().find(|ui| ui.window == event.window) {
+                Some(ui) => ui,
+                None => continue,
+            };
+        let msgs = ui.dispatch(&event.path, &event.event, &event.data);
+        messages.send_batch(msgs.into_iter().map(UiMessage));
+    }
+}
+
+fn window_rects<Msg: Send + Sync + 'static>(
+    mut windows: Query<&mut UiWindow<Msg>>,
+    nodes: LaidOut,
+    elements: Query<(), With<UiElement>>,
+) {
+    for mut ui in &mut windows {
+        let rects = rects_from(ui.root, &nodes, &elements);
+        if ui.rects != rects {
+            ui.rects = rects;
+        }
+    }
+}
+
+fn closed_windows<Msg: Send + Sync + 'static>(
+    mut commands: Commands,
+    cameras: Query<(Entity, &UiWindow<Msg>)>,
+    windows: Query<(), With<Window>>,
+) {
+    for (camera, ui) in &cameras {
+        if !windows.contains(ui.window) {
+            commands.entity(ui.root).despawn_recursive();
+            commands.entity(camera).despawn();
+        }
+    }
+}
+
+#[test]
+fn test_render_ui() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::{
+        app::Program,
+        cmd::Cmd,
+        element::{el, height, padding, px, row, spacing, width},
+        model::Element,
+    };
+
+    let mut world = World::new();
+    let program = Program::new(
+        || "Hello".to_string(),
+        |model: &mut String, msg: String| {
+            *model = msg;
+            Cmd::none()
+        },
+        |model| {
+            row(
+                vec![width(px(200)), padding(10), spacing(5)],
+                vec![
+                    el(vec![height(px(30))], Element::Text(model.clone())),
+                    Element::Text("!".to_string()),
+                ],
+            )
+        },
+    );
+    world.insert_resource(UiProgram(program));
+    world.insert_resource(UiEntities::<String> {
+        root: None,
+        last: None,
+        sheet: StyleSheet::default(),
+    });
+    world.run_system_once(render_ui::<String, String>).unwrap();
+
+    let texts = |world: &mut World| {
+        let mut texts: Vec<String> = world
+            .query::<&Text>()
+            .iter(world)
+            .map(|t| t.0.clone())
+            .filter(|t| !t.contains('{'))
+            .collect();
+        texts.sort();
+        texts
+    };
+    assert_eq!(texts(&mut world), vec!["!", "Hello"]);
+    let row = world
+        .query::<(&UiElement, &Node)>()
+        .iter(&world)
+        .find(|(e, _)| e.classes().any(|c| c == "r"))
+        .map(|(_, node)| node.clone())
+        .unwrap();
+    assert_eq!(row.flex_direction, FlexDirection::Row);
+    assert_eq!(row.width, Val::Px(200.0));
+    assert_eq!(row.column_gap, Val::Px(5.0));
+
+    let count =
+        |world: &mut World| world.query::<&UiElement>().iter(world).count();
+    let before = count(&mut world);
+    {
+        let mut program = world.resource_mut::<UiProgram<String, String>>();
+        let _ = program.0.update("Bye".to_string());
+        program.0.render();
+    }
+    world.run_system_once(render_ui::<String, String>).unwrap();
+    assert_eq!(texts(&mut world), vec!["!", "Bye"]);
+    assert_eq!(count(&mut world), before);
+}
+
+#[test]
+fn test_atlas_tile() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::{
+        app::Program,
+        cmd::Cmd,
+        element::{image_from_atlas, Atlas},
+    };
+
+    let mut world = World::new();
+    let program = Program::new(
+        || 5,
+        |index: &mut u32, msg: u32| {
+            *index = msg;
+            Cmd::none()
+        },
+        |index| {
+            let atlas = Atlas {
+                src: "icons.png".to_string(),
+                tile_width: 16,
+                tile_height: 16,
+                columns: 4,
+                rows: 2,
+            };
+            image_from_atlas(&atlas, *index, vec![])
+        },
+    );
+    world.insert_resource(UiProgram(program));
+    world.insert_resource(UiEntities::<u32> {
+        root: None,
+        last: None,
+        sheet: StyleSheet::default(),
+    });
+    world.run_system_once(render_ui::<u32, u32>).unwrap();
+    let rects = |world: &mut World| {
+        world
+            .query::<&ImageNode>()
+            .iter(world)
+            .map(|image| image.rect)
+            .collect::<Vec<_>>()
+    };
+    let rect = bevy::math::Rect::new;
+    assert_eq!(rects(&mut world), vec![Some(rect(16.0, 16.0, 32.0, 32.0))]);
+
+    {
+        let mut program = world.resource_mut::<UiProgram<u32, u32>>();
+        let _ = program.0.update(2);
+        program.0.render();
+    }
+    world.run_system_once(render_ui::<u32, u32>).unwrap();
+    assert_eq!(rects(&mut world), vec![Some(rect(32.0, 0.0, 48.0, 16.0))]);
+}
+
+#[cfg(feature = "bevy_picking")]
+#[test]
+fn test_picking() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::{
+        app::Program, cmd::Cmd, element::el, events::on_click, model::Element,
+    };
+
+    let mut world = World::new();
+    let program = Program::new(
+        || (),
+        |_, _: ()| Cmd::none(),
+        |_| {
+            el(
+                vec![on_click(())],
+                el(vec![], Element::Text("OK".to_string())),
+            )
+        },
+    );
+    world.insert_resource(UiProgram(program));
+    world.insert_resource(UiEntities::<()> {
+        root: None,
+        last: None,
+        sheet: StyleSheet::default(),
+    });
+    world.run_system_once(render_ui::<(), ()>).unwrap();
+
+    let root = world.resource::<UiEntities<()>>().root.unwrap();
+    let button = entity_at(&world, root, &[0, 2, 0]).unwrap();
+    let label = entity_at(&world, root, &[0, 2, 0, 0]).unwrap();
+    let picking = |entity| world.get::<PickingBehavior>(entity).cloned();
+    assert_eq!(picking(button), Some(PickingBehavior::default()));
+    assert_eq!(picking(label), Some(PickingBehavior::IGNORE));
+    assert_eq!(picking(root), Some(PickingBehavior::IGNORE));
+
+    let path = world
+        .run_system_once(
+            move |parents: Query<&Parent>, children: Query<&Children>| {
+                path_of(root, button, &parents, &children)
+            },
+        )
+        .unwrap();
+    assert_eq!(path, Some(vec![0, 2, 0]));
+}
+
+#[test]
+fn test_ui_surface() {
+    use crate::{element::el, events::on_click};
+
+    let mut world = World::new();
+    world.init_resource::<Assets<Image>>();
+    let view = |label: &str| {
+        el(
+            vec![on_click(label.to_string())],
+            Element::Text(label.to_string()),
+        )
+    };
+    let image = ui_surface(&mut world, UVec2::new(256, 128), view("Start"));
+    let size = world
+        .resource::<Assets<Image>>()
+        .get(&image)
+        .unwrap()
+        .size();
+    assert_eq!(size, UVec2::new(256, 128));
+    let surface = world.query::<&UiSurface<String>>().single(&world);
+    let (root, camera) = (surface.root, surface.camera);
+    assert_eq!(world.get::<TargetCamera>(root).unwrap().entity(), camera);
+    let target = &world.get::<Camera>(camera).unwrap().target;
+    assert!(matches!(target, RenderTarget::Image(i) if *i == image));
+
+    update_surface(&mut world, &image, view("Stop"));
+    let texts: Vec<String> = world
+        .query::<&Text>()
+        .iter(&world)
+        .map(|t| t.0.clone())
+        .filter(|t| !t.contains('{'))
+        .collect();
+    assert_eq!(texts, vec!["Stop"]);
+
+    let rect = |x, y, width, height| Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+    let mut rects = Rects::new();
+    rects.insert(vec![], rect(0.0, 0.0, 256.0, 128.0));
+    rects.insert(vec![0], rect(10.0, 10.0, 100.0, 50.0));
+    rects.insert(vec![1], rect(50.0, 10.0, 100.0, 50.0));
+    assert_eq!(path_at(&rects, 20.0, 20.0), Some(vec![0]));
+    assert_eq!(path_at(&rects, 60.0, 20.0), Some(vec![1]));
+    assert_eq!(path_at(&rects, 200.0, 100.0), Some(vec![]));
+    assert_eq!(path_at(&rects, 300.0, 100.0), None);
+
+    remove_surface::<String>(&mut world, &image);
+    assert_eq!(world.query::<&UiElement>().iter(&world).count(), 0);
+    assert!(world.get_entity(camera).is_err());
+}
+
+#[test]
+fn test_layout_for_window() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::{
+        element::column,
+        input::{button, ButtonConfig},
+    };
+
+    let view = |label: &str| {
+        let button = |msg: &str| {
+            button(
+                vec![],
+                ButtonConfig {
+                    on_press: Some(msg.to_string()),
+                    label: Element::Text(format!("{} {}", label, msg)),
+                },
+            )
+        };
+        column(vec![], vec![button("a"), button("b")])
+    };
+    let mut world = World::new();
+    let window = world.spawn(Window::default()).id();
+    let other = world.spawn(Window::default()).id();
+    layout_for_window(&mut world, window, vec![], vec![], view("Tool"));
+    layout_for_window(&mut world, other, vec![], vec![], view("Other"));
+
+    let (camera, root) = {
+        let mut uis = world.query::<(Entity, &UiWindow<String>)>();
+        let (camera, ui) = uis
+            .iter(&world)
+            .find(|(_, ui)| ui.window == window)
+            .unwrap();
+        (camera, ui.root)
+    };
+    assert_eq!(world.get::<TargetCamera>(root).unwrap().entity(), camera);
+    let target = &world.get::<Camera>(camera).unwrap().target;
+    assert!(matches!(
+        target,
+        RenderTarget::Window(WindowRef::Entity(w)) if *w == window
+    ));
+
+    // Each window has focus of its own.
+    let key = |world: &mut World, key: &str| {
+        let mut ui = world.get_mut::<UiWindow<String>>(camera).unwrap();
+        let data = EventData::Key {
+            key: key.to_string(),
+            shift: false,
+        };
+        ui.dispatch(&[], "keydown", &data)
+    };
+    key(&mut world, "Tab");
+    key(&mut world, "Tab");
+    assert_eq!(key(&mut world, "Enter"), ["b"]);
+    let focused = world
+        .query::<&UiWindow<String>>()
+        .iter(&world)
+        .map(|ui| ui.focused().is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(focused.iter().filter(|f| **f).count(), 1);
+
+    let count =
+        |world: &mut World| world.query::<&UiElement>().iter(world).count();
+    let before = count(&mut world);
+    layout_for_window(&mut world, window, vec![], vec![], view("Tools"));
+    assert_eq!(count(&mut world), before);
+
+    world.entity_mut(window).despawn();
+    world.run_system_once(closed_windows::<String>).unwrap();
+    assert_eq!(count(&mut world), before / 2);
+    assert!(world.get_entity(camera).is_err());
+}
+
+#[test]
+fn test_scroll_snap() {
+    use crate::element::{
+        el, px, scroll_snap, scrollbar_y, snap_align, SnapAxis,
+    };
+
+    // Children 100 high, in a viewport 150 high.
+    let children = |align| {
+        (0..4)
+            .map(|i| (i as f32 * 100.0, 100.0, align))
+            .collect::<Vec<_>>()
+    };
+    let starts = children(SnapAlign::Start);
+    assert_eq!(snap_offset(40.0, 150.0, &starts), Some(0.0));
+    assert_eq!(snap_offset(60.0, 150.0, &starts), Some(100.0));
+    let centers = children(SnapAlign::Center);
+    assert_eq!(snap_offset(60.0, 150.0, &centers), Some(75.0));
+    let ends = children(SnapAlign::End);
+    assert_eq!(snap_offset(180.0, 150.0, &ends), Some(150.0));
+    let mut skipped = starts.clone();
+    skipped[1].2 = SnapAlign::None;
+    assert_eq!(snap_offset(90.0, 150.0, &skipped), Some(0.0));
+    assert_eq!(snap_offset(90.0, 150.0, &children(SnapAlign::None)), None);
+
+    // The container scrolls, and its children say how they line up.
+    let mut world = World::new();
+    let mut sheet = StyleSheet::default();
+    let view = layout::<()>(
+        vec![],
+        el(
+            vec![scrollbar_y(), scroll_snap(SnapAxis::Y, SnapAlign::Center)],
+            el(
+                vec![
+                    snap_align(SnapAlign::End),
+                    crate::element::height(px(100)),
+                ],
+                Element::Empty,
+            ),
+        ),
+    );
+    let root = spawn_view(&mut world, &mut sheet, &view, None);
+    let scroller = world
+        .query::<(Entity, &UiElement)>()
+        .iter(&world)
+        .find(|(_, e)| e.classes().any(|c| c == "snap-y"))
+        .map(|(entity, _)| entity)
+        .unwrap();
+    assert!(world.get::<SnapSettle>(scroller).is_some());
+    let node = world.get::<Node>(scroller).unwrap();
+    assert_eq!(node.overflow, Overflow::scroll_y());
+    let classes: Vec<&str> = world
+        .get::<UiElement>(scroller)
+        .unwrap()
+        .classes()
+        .collect();
+    assert_eq!(snap_alignment(&classes, "snap-"), Some(SnapAlign::Center));
+    let aligned = world
+        .get::<Children>(scroller)
+        .unwrap()
+        .iter()
+        .flat_map(|&wrapper| world.get::<Children>(wrapper).unwrap().iter())
+        .filter_map(|&child| {
+            let element = world.get::<UiElement>(child)?;
+            snap_alignment(&element.classes().collect::<Vec<_>>(), "snap-self-")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(aligned, vec![SnapAlign::End]);
+    assert!(world.get::<SnapSettle>(root).is_none());
+}
@@ -0,0 +1,185 @@
+use crate::{
+    flag::Flag,
+    model::{Attribute, Color, Shadow, Style},
+    style::Classes,
+};
+
+pub fn color<Msg>(color: Color) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::border_color(),
+        Style::Colored(
+            format!("bc-{}", color.format_color_class()),
+            "border-color".into(),
+            color,
+        ),
+    )
+}
+
+/// The same border width in pixels on every side.
+pub fn width<Msg>(v: u32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::border_width(),
+        Style::BorderWidth(format!("b-{}", v), v, v, v, v),
+    )
+}
+
+/// `x` on the left and right, and `y` on the top and bottom.
+pub fn width_xy<Msg>(x: u32, y: u32) -> Attribute<Msg> {
+    if x == y {
+        return width(x);
+    }
+    Attribute::Style(
+        Flag::border_width(),
+        Style::BorderWidth(format!("b-{}-{}", x, y), y, x, y, x),
+    )
+}
+
+pub fn width_each<Msg>(
+    top: u32,
+    right: u32,
+    bottom: u32,
+    left: u32,
+) -> Attribute<Msg> {
+    if top == bottom && left == right {
+        return width_xy(left, top);
+    }
+    Attribute::Style(
+        Flag::border_width(),
+        Style::BorderWidth(
+            format!("b-{}-{}-{}-{}", top, right, bottom, left),
+            top,
+            right,
+            bottom,
+            left,
+        ),
+    )
+}
+
+pub fn solid<Msg>() -> Attribute<Msg> {
+    line_style(Classes::BorderSolid)
+}
+
+pub fn dashed<Msg>() -> Attribute<Msg> {
+    line_style(Classes::BorderDashed)
+}
+
+pub fn dotted<Msg>() -> Attribute<Msg> {
+    line_style(Classes::BorderDotted)
+}
+
+/// No border, whatever its width.
+pub fn none<Msg>() -> Attribute<Msg> {
+    line_style(Classes::BorderNone)
+}
+
+fn line_style<Msg>(class: Classes) -> Attribute<Msg> {
+    Attribute::Class(Flag::border_style(), class.to_string().to_string())
+}
+
+/// Round every corner with a radius of `radius` pixels.
+pub fn rounded<Msg>(radius: u32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::border_rount(),
+        Style::Single(
+            format!("br-{}", radius),
+            "border-radius".into(),
+            format!("{}px", radius),
+        ),
+    )
+}
+
+pub fn round_each<Msg>(
+    top_left: u32,
+    top_right: u32,
+    bottom_right: u32,
+    bottom_left: u32,
+) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::border_rount(),
+        Style::Single(
+            format!(
+                "br-{}-{}-{}-{}",
+                top_left, top_right, bottom_right, bottom_left
+            ),
+            "border-radius".into(),
+            format!(
+                "{}px {}px {}px {}px",
+                top_left, top_right, bottom_right, bottom_left
+            ),
+        ),
+    )
+}
+
+pub fn shadow<Msg>(shade: Shadow) -> Attribute<Msg> {
+    box_shadow(shade, false)
+}
+
+/// A shadow inside the element's border, as if it were sunken.
+pub fn inner_shadow<Msg>(shade: Shadow) -> Attribute<Msg> {
+    box_shadow(shade, true)
+}
+
+fn box_shadow<Msg>(shade: Shadow, inset: bool) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::shadows(),
+        Style::Shadows(
+            shade.box_shadow_class(inset),
+            shade.format_box_shadow(inset),
+        ),
+    )
+}
+
+/// A glow of `color` around the element, `size` pixels wide.
+pub fn glow<Msg>(color: Color, size: u8) -> Attribute<Msg> {
+    shadow(glow_shadow(color, size))
+}
+
+/// A glow of `color` inside the element's border, `size` pixels wide.
+pub fn inner_glow<Msg>(color: Color, size: u8) -> Attribute<Msg> {
+    inner_shadow(glow_shadow(color, size))
+}
+
+fn glow_shadow(color: Color, size: u8) -> Shadow {
+    Shadow {
+        color,
+        offset: (0, 0),
+        blur: size.saturating_mul(2),
+        size,
+    }
+}
+
+#[test]
+fn test_border() {
+    use crate::element::{el, extract_styles};
+    use crate::model::Element;
+
+    let name = |attr: Attribute<()>| match attr {
+        Attribute::Style(_, style) => style.name(),
+        Attribute::Class(_, class) => class,
+        _ => unreachable!(),
+    };
+    assert_eq!(name(width_each(2, 2, 2, 2)), "b-2");
+    assert_eq!(name(width_each(1, 3, 1, 3)), "b-3-1");
+    assert_eq!(name(width_each(1, 2, 3, 4)), "b-1-2-3-4");
+    assert_eq!(name(dashed()), Classes::BorderDashed.to_string());
+
+    let black = Color::new(0.0, 0.0, 0.0, 1.0);
+    let view = el::<()>(
+        vec![
+            width_each(1, 2, 3, 4),
+            color(black),
+            round_each(1, 2, 3, 4),
+            inner_glow(black, 3),
+        ],
+        Element::Empty,
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    for rule in &[
+        "border-width: 1px 2px 3px 4px",
+        "border-color: rgba(0,0,0,1)",
+        "border-radius: 1px 2px 3px 4px",
+        "box-shadow: inset 0px 0px 6px 3px rgba(0,0,0,1)",
+    ] {
+        assert!(css.contains(rule), "{} not in {}", rule, css);
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+/// How much the UI is cutting back to stay within its frame budget.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Degradation {
+    /// Everything is rendered as asked.
+    Full,
+    /// Transitions and animations are skipped.
+    Reduced,
+    /// Transitions are skipped and `Program::render` only renders the view
+    /// on every other call, batching bursts of updates. The whole view is
+    /// rendered each time, as what's offscreen isn't known.
+    Minimal,
+}
+
+// How many frames the level holds for before it can change again, so a
+// single slow frame, like the first, doesn't flip it back and forth.
+const SETTLE_FRAMES: u32 = 30;
+
+/// Tracks how long the UI takes each frame against a budget, raising the
+/// `Degradation` while it runs over and lowering it again once there's
+/// room to spare.
+#[derive(Debug, Clone)]
+pub struct FrameBudget {
+    budget: Duration,
+    average: Option<Duration>,
+    level: Degradation,
+    settled: u32,
+}
+
+impl FrameBudget {
+    pub fn new(budget: Duration) -> Self {
+        FrameBudget {
+            budget,
+            average: None,
+            level: Degradation::Full,
+            settled: 0,
+        }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn level(&self) -> Degradation {
+        self.level
+    }
+
+    /// The time taken by recent frames, smoothed over the last few.
+    pub fn average(&self) -> Duration {
+        self.average.unwrap_or_default()
+    }
+
+    /// Record how long a frame's UI work took, returning the new level if
+    /// it changed.
+    pub fn report(&mut self, elapsed: Duration) -> Option<Degradation> {
+        let average = match self.average {
+            Some(average) => (average * 7 + elapsed) / 8,
+            None => elapsed,
+        };
+        self.average = Some(average);
+        self.settled += 1;
+        if self.settled < SETTLE_FRAMES {
+            return None;
+        }
+        let level = if average > self.budget {
+            match self.level {
+                Degradation::Full => Degradation::Reduced,
+                _ => Degradation::Minimal,
+            }
+        } else if average < self.budget / 2 {
+            match self.level {
+                Degradation::Minimal => Degradation::Reduced,
+                _ => Degradation::Full,
+            }
+        } else {
+            self.level
+        };
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        self.settled = 0;
+        Some(level)
+    }
+}
+
+#[test]
+fn test_frame_budget() {
+    let ms = Duration::from_millis;
+    let mut budget = FrameBudget::new(ms(4));
+    let mut run = |elapsed, frames| {
+        (0..frames)
+            .filter_map(|_| budget.report(elapsed))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(ms(3), 100), vec![]);
+    assert_eq!(
+        run(ms(10), 100),
+        vec![Degradation::Reduced, Degradation::Minimal]
+    );
+    // Between half the budget and all of it, the level holds.
+    assert_eq!(run(ms(3), 100), vec![]);
+    assert_eq!(
+        run(ms(1), 100),
+        vec![Degradation::Reduced, Degradation::Full]
+    );
+
+    use crate::{app::Program, cmd::Cmd, model::Element, sub};
+
+    let mut program = Program::new(
+        || (),
+        |_, _: Degradation| Cmd::none(),
+        |_| Element::Empty,
+    )
+    .with_frame_budget(ms(4))
+    .with_subscriptions(|_| vec![sub::on_degradation_change(|level| level)]);
+    let msgs: Vec<_> =
+        (0..40).flat_map(|_| program.frame_time(ms(10))).collect();
+    assert_eq!(msgs, vec![Degradation::Reduced]);
+    assert!(program
+        .render()
+        .attrs
+        .iter()
+        .any(|a| a.classes().any(|c| c == "reduced")));
+    assert!(crate::style::rules().contains(".reduced *::after {"));
+}
@@ -0,0 +1,90 @@
+use std::future::Future;
+use std::pin::Pin;
+
+/// A future doing some async work, and producing a message once it's done
+/// unless it's only run for its effect.
+///
+/// On the web, futures run on the page's event loop and don't need to be
+/// `Send`, so things like `fetch` can be performed directly.
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxFuture<Msg> = Pin<Box<dyn Future<Output = Msg> + Send>>;
+#[cfg(target_arch = "wasm32")]
+pub type BoxFuture<Msg> = Pin<Box<dyn Future<Output = Msg>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// Work for the runtime to do on behalf of `update`, like an HTTP request
+/// or reading a file, whose results come back as messages.
+///
+/// With the `bevy` feature, commands are spawned on Bevy's
+/// `AsyncComputeTaskPool`, which runs them on `wasm-bindgen-futures` when
+/// compiled for the web.
+pub struct Cmd<Msg> {
+    futures: Vec<BoxFuture<Option<Msg>>>,
+}
+
+impl<Msg> Default for Cmd<Msg> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<Msg> Cmd<Msg> {
+    /// Nothing to do.
+    pub fn none() -> Self {
+        Cmd { futures: vec![] }
+    }
+
+    /// Run `future`, then send the message `to_msg` makes of its output.
+    pub fn perform<T, Fut, F>(future: Fut, to_msg: F) -> Self
+    where
+        Msg: 'static,
+        Fut: Future<Output = T> + MaybeSend + 'static,
+        F: FnOnce(T) -> Msg + MaybeSend + 'static,
+    {
+        Cmd {
+            futures: vec![Box::pin(async move { Some(to_msg(future.await)) })],
+        }
+    }
+
+    /// Run `future` for its effect alone, e.g. saving a file, without
+    /// sending a message when it's done.
+    pub fn run<Fut>(future: Fut) -> Self
+    where
+        Msg: 'static,
+        Fut: Future<Output = ()> + MaybeSend + 'static,
+    {
+        Cmd {
+            futures: vec![Box::pin(async move {
+                future.await;
+                None
+            })],
+        }
+    }
+
+    /// Run several commands at once. Their messages arrive in the order
+    /// they finish in, not the order they're listed in.
+    pub fn batch(cmds: Vec<Cmd<Msg>>) -> Self {
+        Cmd {
+            futures: cmds.into_iter().flat_map(|cmd| cmd.futures).collect(),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// The futures making up this command, for running it on an executor
+    /// of your own.
+    pub fn into_futures(self) -> Vec<BoxFuture<Option<Msg>>> {
+        self.futures
+    }
+}
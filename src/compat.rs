@@ -0,0 +1,107 @@
+//! What the element and style model uses from `std`, or without it, the
+//! same from crates that only need `alloc`.
+
+use ::core::hash::Hasher;
+use ::core::ops::{Deref, DerefMut};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+
+/// A lock for the model's statics. `std`'s is only poisoned by a panic
+/// while it's held, which is a bug, so locking it then panics too.
+pub(crate) struct Mutex<T>(
+    #[cfg(feature = "std")] std::sync::Mutex<T>,
+    #[cfg(not(feature = "std"))] spin::Mutex<T>,
+);
+
+impl<T> Mutex<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        #[cfg(feature = "std")]
+        {
+            Mutex(std::sync::Mutex::new(value))
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            Mutex(spin::Mutex::new(value))
+        }
+    }
+
+    pub(crate) fn lock(&self) -> impl DerefMut<Target = T> + '_ {
+        #[cfg(feature = "std")]
+        {
+            self.0.lock().unwrap()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.0.lock()
+        }
+    }
+}
+
+/// As `Mutex`, for statics that are mostly read.
+pub(crate) struct RwLock<T>(
+    #[cfg(feature = "std")] std::sync::RwLock<T>,
+    #[cfg(not(feature = "std"))] spin::RwLock<T>,
+);
+
+impl<T> RwLock<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        #[cfg(feature = "std")]
+        {
+            RwLock(std::sync::RwLock::new(value))
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            RwLock(spin::RwLock::new(value))
+        }
+    }
+
+    pub(crate) fn read(&self) -> impl Deref<Target = T> + '_ {
+        #[cfg(feature = "std")]
+        {
+            self.0.read().unwrap()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.0.read()
+        }
+    }
+
+    pub(crate) fn write(&self) -> impl DerefMut<Target = T> + '_ {
+        #[cfg(feature = "std")]
+        {
+            self.0.write().unwrap()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.0.write()
+        }
+    }
+}
+
+/// `f32::round`.
+pub(crate) fn round(x: f32) -> f32 {
+    #[cfg(feature = "std")]
+    {
+        x.round()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        libm::roundf(x)
+    }
+}
+
+/// A hasher giving the same value the same hash every time.
+pub(crate) fn hasher() -> impl Hasher {
+    #[cfg(feature = "std")]
+    {
+        std::collections::hash_map::DefaultHasher::new()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        #[allow(deprecated)]
+        ::core::hash::SipHasher::new()
+    }
+}
@@ -0,0 +1,367 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use bevy::window::PrimaryWindow;
+
+use crate::app::UiRects;
+use crate::bevy_render::{patch_view, path_at, spawn_view, UiEntities};
+use crate::element::{
+    align_right, column, el, fill, height, in_front, layout, padding,
+    padding_each, px, rgb, rgba, scrollbar_y, spacing, width,
+};
+use crate::model::{Attribute, Color, Element};
+use crate::solver::StyleSheet;
+use crate::text::ellipsize;
+use crate::vdom::{self, html_attributes, NodeType, Rects};
+use crate::{background, border, font};
+
+/// An overlay for looking into the running view: its element tree, and the
+/// attributes, layout and styles of the element under the pointer, which
+/// is outlined. It's opened and closed with `Devtools::hotkey`, F12 unless
+/// changed, and is itself a view made of the crate's own elements.
+///
+/// It goes with a `DeclarativeUiPlugin` with the same `Msg`.
+pub struct DevtoolsPlugin<Msg>(PhantomData<fn() -> Msg>);
+
+impl<Msg> Default for DevtoolsPlugin<Msg> {
+    fn default() -> Self {
+        DevtoolsPlugin(PhantomData)
+    }
+}
+
+impl<Msg> DevtoolsPlugin<Msg> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<Msg: Send + Sync + 'static> Plugin for DevtoolsPlugin<Msg> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Devtools>()
+            .init_resource::<DevtoolsView>()
+            .add_systems(Update, toggle_devtools)
+            .add_systems(
+                PostUpdate,
+                show_devtools::<Msg>.before(UiSystem::Prepare),
+            );
+    }
+}
+
+/// Whether the devtools are open, and the key that opens and closes them.
+#[derive(Resource, Debug, Clone)]
+pub struct Devtools {
+    pub open: bool,
+    pub hotkey: KeyCode,
+}
+
+impl Default for Devtools {
+    fn default() -> Self {
+        Devtools {
+            open: false,
+            hotkey: KeyCode::F12,
+        }
+    }
+}
+
+// The entities showing the panel, and the tree they were last updated to.
+#[derive(Resource, Default)]
+struct DevtoolsView {
+    root: Option<Entity>,
+    last: Option<vdom::Node<()>>,
+    sheet: StyleSheet,
+}
+
+// More lines than this in the element tree are left out.
+const MAX_LINES: usize = 400;
+
+fn toggle_devtools(
+    keys: Option<Res<ButtonInput<KeyCode>>>,
+    mut devtools: ResMut<Devtools>,
+) {
+    if keys.is_some_and(|keys| keys.just_pressed(devtools.hotkey)) {
+        devtools.open = !devtools.open;
+    }
+}
+
+fn show_devtools<Msg: Send + Sync + 'static>(world: &mut World) {
+    if !world.resource::<Devtools>().open {
+        let mut view = world.resource_mut::<DevtoolsView>();
+        view.last = None;
+        if let Some(root) = view.root.take() {
+            world.entity_mut(root).despawn_recursive();
+        }
+        return;
+    }
+    let cursor = world
+        .query_filtered::<&Window, With<PrimaryWindow>>()
+        .iter(world)
+        .next()
+        .and_then(Window::cursor_position);
+    let node = {
+        let entities = match world.get_resource::<UiEntities<Msg>>() {
+            Some(entities) => entities,
+            None => return,
+        };
+        let inspected = match &entities.last {
+            Some(inspected) => inspected,
+            None => return,
+        };
+        let empty = Rects::new();
+        let rects = world.get_resource::<UiRects>().map_or(&empty, |r| &r.0);
+        let hovered = cursor.and_then(|at| path_at(rects, at.x, at.y));
+        layout(
+            vec![],
+            panel(inspected, rects, hovered.as_deref(), &entities.sheet),
+        )
+    };
+    world.resource_scope(|world, mut view: Mut<DevtoolsView>| {
+        let view = &mut *view;
+        if view.last.as_ref() == Some(&node) {
+            return;
+        }
+        let root = match (view.root, &view.last) {
+            (Some(mut root), Some(last)) => {
+                patch_view(
+                    world,
+                    &mut view.sheet,
+                    &mut root,
+                    last,
+                    &node,
+                    None,
+                );
+                root
+            }
+            _ => spawn_view(world, &mut view.sheet, &node, None),
+        };
+        // Over the view, however it's layered.
+        world.entity_mut(root).insert(GlobalZIndex(i32::MAX));
+        view.root = Some(root);
+        view.last = Some(node);
+    });
+}
+
+fn text(content: String) -> Element<()> {
+    Element::Text(content)
+}
+
+fn heading(content: &str) -> Element<()> {
+    el(
+        vec![font::size(11), font::color(rgb(0.6, 0.7, 1.0))],
+        text(content.to_uppercase()),
+    )
+}
+
+// A line for `node` in the element tree, e.g. `div#save .s .e`.
+fn describe<Msg>(node: &vdom::Node<Msg>) -> String {
+    let mut line = node.tag.to_string();
+    if let Some(id) = node.attr("id") {
+        line.push('#');
+        line.push_str(id);
+    }
+    for class in node.attrs.iter().flat_map(vdom::Attribute::classes) {
+        line.push_str(" .");
+        line.push_str(class);
+    }
+    line
+}
+
+fn tree_lines<Msg>(
+    node: &vdom::Node<Msg>,
+    path: &mut Vec<usize>,
+    hovered: Option<&[usize]>,
+    lines: &mut Vec<Element<()>>,
+) {
+    if lines.len() >= MAX_LINES || node.tag == "style" {
+        return;
+    }
+    let is_hovered = hovered == Some(path.as_slice());
+    lines.push(el(
+        vec![
+            padding_each(1, 4, 1, 4 + 12 * path.len() as u32),
+            width(fill()),
+            if is_hovered {
+                background::color(rgba(0.3, 0.5, 1.0, 0.4))
+            } else {
+                Attribute::None
+            },
+        ],
+        text(describe(node)),
+    ));
+    for (i, child) in node.children.iter().enumerate() {
+        path.push(i);
+        match child {
+            NodeType::Node(n) | NodeType::KeyedNode(_, n) => {
+                tree_lines(n, path, hovered, lines)
+            }
+            NodeType::Text(content) if lines.len() < MAX_LINES => {
+                let short = ellipsize(content, 40);
+                lines.push(el(
+                    vec![
+                        padding_each(1, 4, 1, 4 + 12 * path.len() as u32),
+                        font::color(rgb(0.7, 0.7, 0.7)),
+                    ],
+                    text(format!("{:?}", short)),
+                ));
+            }
+            NodeType::Text(_) => {}
+        }
+        path.pop();
+    }
+}
+
+// The attributes, layout and styles of the node at `path`.
+fn details<Msg>(
+    node: &vdom::Node<Msg>,
+    path: &[usize],
+    rects: &Rects,
+    sheet: &StyleSheet,
+) -> Vec<Element<()>> {
+    let mut lines = vec![heading("Element"), text(describe(node))];
+    lines.push(text(format!("path {:?}", path)));
+    if let Some(at) = node.attr("data-at") {
+        lines.push(text(format!("made at {}", at)));
+    }
+    if let Some(rect) = rects.get(path) {
+        lines.push(heading("Layout"));
+        lines.push(text(format!(
+            "{} × {} at {}, {}",
+            rect.width, rect.height, rect.x, rect.y
+        )));
+    }
+    lines.push(heading("Attributes"));
+    for (key, value) in html_attributes(&node.attrs)
+        .into_iter()
+        .filter(|(key, _)| *key != "class")
+    {
+        lines.push(text(format!("{}=\"{}\"", key, value)));
+    }
+    if !node.events.is_empty() {
+        lines.push(text(format!("{} event listeners", node.events.len())));
+    }
+    lines.push(heading("Styles"));
+    let classes = node.attrs.iter().flat_map(vdom::Attribute::classes);
+    for class in classes {
+        for (name, value) in sheet.rules(class) {
+            lines.push(text(format!(".{} {}: {}", class, name, value)));
+        }
+    }
+    lines
+}
+
+fn panel_color() -> Color {
+    rgba(0.08, 0.09, 0.11, 0.92)
+}
+
+// The whole overlay: the panel on the right, and the outline of the
+// hovered element.
+fn panel<Msg>(
+    root: &vdom::Node<Msg>,
+    rects: &Rects,
+    hovered: Option<&[usize]>,
+    sheet: &StyleSheet,
+) -> Element<()> {
+    let mut tree = vec![];
+    tree_lines(root, &mut vec![], hovered, &mut tree);
+    let hovered_node = hovered.and_then(|path| Some((path, root.at(path)?)));
+    let mut sections = vec![heading("Elements"), column(vec![], tree)];
+    if let Some((path, node)) = hovered_node {
+        sections.extend(details(node, path, rects, sheet));
+    }
+    let outline = hovered.and_then(|path| rects.get(path)).map(|rect| {
+        // Placed with padding, which every backend lays out.
+        el(
+            vec![padding_each(rect.y as u32, 0, 0, rect.x as u32)],
+            el(
+                {
+                    let mut attrs = vec![
+                        width(px(rect.width as u64)),
+                        height(px(rect.height as u64)),
+                        background::color(rgba(0.3, 0.5, 1.0, 0.2)),
+                    ];
+                    attrs.extend([
+                        border::width(1),
+                        border::color(rgb(0.3, 0.5, 1.0)),
+                    ]);
+                    attrs
+                },
+                Element::Empty,
+            ),
+        )
+    });
+    let mut overlay = vec![width(fill()), height(fill())];
+    if let Some(outline) = outline {
+        overlay.push(in_front(outline));
+    }
+    el(
+        overlay,
+        el(
+            vec![
+                align_right(),
+                width(px(380)),
+                height(fill()),
+                padding(8),
+                scrollbar_y(),
+                background::color(panel_color()),
+                font::color(rgb(0.9, 0.9, 0.9)),
+                font::size(12),
+            ],
+            column(vec![spacing(4), width(fill())], sections),
+        ),
+    )
+}
+
+#[test]
+fn test_devtools() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::{element::id, render::to_html, test::find_by_id, vdom::Rect};
+
+    let node = layout(
+        vec![],
+        el::<()>(
+            vec![id("save".to_string()), padding(7)],
+            text("Save".to_string()),
+        ),
+    );
+    let (path, _) = find_by_id(&node, "save").unwrap();
+    let mut rects = Rects::new();
+    rects.insert(
+        path.clone(),
+        Rect {
+            x: 1.0,
+            y: 2.0,
+            width: 30.0,
+            height: 20.0,
+        },
+    );
+    let mut sheet = StyleSheet::default();
+    sheet.update(&node);
+
+    let html = to_html(&layout(vec![], panel(&node, &rects, None, &sheet)));
+    assert!(html.contains("div#save"));
+    assert!(!html.contains("30 × 20"));
+    let html =
+        to_html(&layout(vec![], panel(&node, &rects, Some(&path), &sheet)));
+    assert!(html.contains("30 × 20 at 1, 2"));
+    assert!(html.contains("id=\"save\""));
+    assert!(html.contains("padding: 7px"));
+
+    let mut world = World::new();
+    world.insert_resource(UiEntities::<()> {
+        root: None,
+        last: Some(node),
+        sheet,
+    });
+    world.init_resource::<Devtools>();
+    world.init_resource::<DevtoolsView>();
+    let count = |world: &mut World| world.query::<&Node>().iter(world).count();
+    world.run_system_once(show_devtools::<()>).unwrap();
+    assert_eq!(count(&mut world), 0);
+    world.resource_mut::<Devtools>().open = true;
+    world.run_system_once(show_devtools::<()>).unwrap();
+    assert!(count(&mut world) > 0);
+    world.resource_mut::<Devtools>().open = false;
+    world.run_system_once(show_devtools::<()>).unwrap();
+    assert_eq!(count(&mut world), 0);
+}
@@ -1,21 +1,36 @@
 use std::cmp;
+use std::future::Future;
 use std::ops::Neg;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::{
+    background::css_url,
+    cmd::{Cmd, MaybeSend},
+    events::{
+        on, on_click, on_context_menu, on_filtered, on_intersect_filtered,
+    },
     flag::{Field, Flag},
+    focus::{focus_trap, roving_group, Orientation as FocusOrientation},
     model::{
-        div, element, extract_spacing_and_padding, padding_class_name,
-        padding_class_name_float, render_root, root_style, spacing_class_name,
-        unwrap_decorations, Attribute, Children, Color, Coordinate,
-        Description, Element, FloatClass, FocusStyle, HAlign, HoverSetting,
-        LayoutContext, Length, Location, NodeName, Opt, PseudoClass,
-        RenderMode, Style, TransformComponent, VAlign,
+        decoration_properties, div, element, extract_spacing_and_padding,
+        get_spacing_length, lint_attributes, overrides, padding_class_name,
+        padding_class_name_float, props_hash, render_root, render_root_cached,
+        render_stylesheet, root_style, spacing_class_name, unwrap_decorations,
+        Attribute, Calc, Children, Color, Coordinate, Description, Element,
+        FloatClass, FocusStyle, ForcedColors, GridPosition, GridTemplate,
+        HAlign, HoverSetting, LayoutContext, Length, Location, NodeName, Opt,
+        Property, PseudoClass, PseudoElement, RenderCache, RenderMode, Style,
+        StyleSet, Subgrid, Track, TransformComponent, VAlign,
     },
+    solver::Solver,
     style::Classes,
     vdom,
-    vdom::{html, Node},
+    vdom::{html, EventData, Node},
 };
 
+pub use crate::sound::SoundMap;
+
 ///
 ///
 /// # Basic Elements
@@ -204,11 +219,11 @@ use crate::{
 /// Provide the red, green, and blue channels for the color.
 ///
 /// Each channel takes a value between 0 and 1.
-pub fn rgb(r: f32, g: f32, b: f32) -> Color {
+pub const fn rgb(r: f32, g: f32, b: f32) -> Color {
     Color { r, g, b, a: 1.0 }
 }
 
-pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
     Color { r, g, b, a }
 }
 
@@ -216,20 +231,20 @@ pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
 ///
 /// Each channel takes a value between 0 and 255.
 ///
-pub fn rgb255(r: u8, g: u8, b: u8) -> Color {
+pub const fn rgb255(r: u8, g: u8, b: u8) -> Color {
     Color {
         r: (r as f32) / 255.0,
-        g: (r as f32) / 255.0,
-        b: (r as f32) / 255.0,
+        g: (g as f32) / 255.0,
+        b: (b as f32) / 255.0,
         a: 1.0,
     }
 }
 
-pub fn rgba255(r: u8, g: u8, b: u8, a: f32) -> Color {
+pub const fn rgba255(r: u8, g: u8, b: u8, a: f32) -> Color {
     Color {
         r: (r as f32) / 255.0,
-        g: (r as f32) / 255.0,
-        b: (r as f32) / 255.0,
+        g: (g as f32) / 255.0,
+        b: (b as f32) / 255.0,
         a,
     }
 }
@@ -240,17 +255,17 @@ pub fn rgba255(r: u8, g: u8, b: u8, a: f32) -> Color {
 // /// Only decorations
 // type Decoration = Attribute;
 
-pub fn px(px: u64) -> Length {
+pub const fn px(px: u64) -> Length {
     Length::Px(px)
 }
 
 /// Shrink an element to fit its contents.
-pub fn shrink() -> Length {
+pub const fn shrink() -> Length {
     Length::Content
 }
 
 /// Fill the available space. The available space will be split evenly between elements that have width(fill()).
-pub fn fill() -> Length {
+pub const fn fill() -> Length {
     Length::Fill(1)
 }
 
@@ -281,20 +296,52 @@ pub fn max(i: u64, l: Length) -> Length {
 /// of the available space, while the second would get 3.
 ///
 /// **Also:** fill == fill_portion(1)
-pub fn fill_portion(i: u64) -> Length {
+pub const fn fill_portion(i: u64) -> Length {
     Length::Fill(i)
 }
 
 /// This is your top level node where you can turn Element into Html.
-pub fn layout(attrs: Vec<Attribute>, child: Element) -> Node {
+#[track_caller]
+pub fn layout<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
     layout_with(vec![], attrs, child)
 }
 
-pub fn layout_with(
+#[track_caller]
+pub fn layout_with<Msg>(
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
+    render_root(opts, root_attrs(attrs), child)
+}
+
+/// `layout`, reusing the node rendered last frame if the view hasn't
+/// changed.
+#[track_caller]
+pub fn layout_cached<Msg>(
+    cache: &mut RenderCache<Msg>,
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
+    layout_cached_with(cache, vec![], attrs, child)
+}
+
+/// `layout_with`, reusing the node rendered last frame if the view hasn't
+/// changed since.
+#[track_caller]
+pub fn layout_cached_with<Msg>(
+    cache: &mut RenderCache<Msg>,
     opts: Vec<Opt>,
-    attrs: Vec<Attribute>,
-    child: Element,
-) -> Node {
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
+    render_root_cached(cache, opts, root_attrs(attrs), child)
+}
+
+fn root_attrs<Msg>(attrs: Vec<Attribute<Msg>>) -> Vec<Attribute<Msg>> {
     let mut attr = vec![Attribute::html_class(format!(
         "{} {} {}",
         Classes::Root.to_string(),
@@ -304,8 +351,25 @@ pub fn layout_with(
 
     attr.extend(root_style());
     attr.extend(attrs);
+    attr
+}
+
+/// Collect every style used by `views` into a standalone stylesheet, e.g.
+/// at build time, to be served as a `.css` file next to pages rendered
+/// with `external_styles`.
+///
+/// The static stylesheet is included too, unless `no_static_style_sheet`
+/// is passed to `extract_styles_with`. Styles set by the attributes given
+/// to `layout` itself aren't, so set those on the view instead.
+pub fn extract_styles<Msg>(views: &[Element<Msg>]) -> String {
+    extract_styles_with(vec![], views)
+}
 
-    render_root(opts, attr, child)
+pub fn extract_styles_with<Msg>(
+    opts: Vec<Opt>,
+    views: &[Element<Msg>],
+) -> String {
+    render_stylesheet(opts, root_attrs(vec![]), views.to_vec())
 }
 
 /// Elm UI embeds two StyleSheets, one that is constant,
@@ -324,10 +388,46 @@ pub fn no_static_style_sheet() -> Opt {
     Opt::Render(RenderMode::NoStaicStyleSheet)
 }
 
+/// Don't embed any stylesheets, assuming one made by `extract_styles` is
+/// already on the page.
+pub fn external_styles() -> Opt {
+    Opt::ExternalStyles
+}
+
+/// Have the web backend mount the view in a shadow root on its container,
+/// so the page's CSS and the view's stylesheets don't affect each other,
+/// e.g. for a widget embedded in someone else's site.
+pub fn shadow_root() -> Opt {
+    Opt::ShadowRoot
+}
+
+/// Play `sounds` when buttons, links and other elements that can be
+/// clicked are hovered, clicked or focused, for the app to play from
+/// `Program::take_sounds`. Elements can set their own with
+/// `on_interaction_sound`.
+pub fn interaction_sounds(sounds: SoundMap) -> Opt {
+    Opt::InteractionSounds(sounds)
+}
+
 pub fn focus_style(fs: FocusStyle) -> Opt {
     Opt::Focus(fs)
 }
 
+/// Only draw focus styles when the browser would show a focus ring,
+/// i.e. for keyboard navigation but not for mouse clicks.
+///
+/// This switches the global focus style, `focused` and `focus_ring`
+/// over to `:focus-visible`.
+pub fn focus_visible_only() -> Opt {
+    Opt::FocusVisibleOnly
+}
+
+/// Choose how the layout adapts to forced colors modes like Windows High
+/// Contrast. Defaults to `ForcedColors::SystemColors`.
+pub fn forced_colors(strategy: ForcedColors) -> Opt {
+    Opt::ForcedColorsStrategy(strategy)
+}
+
 /// Disable all mouse_over styles.
 pub fn no_hover() -> Opt {
     Opt::Hover(HoverSetting::No)
@@ -342,8 +442,70 @@ pub fn force_hover() -> Opt {
     Opt::Hover(HoverSetting::Force)
 }
 
+/// A set of attributes kept to style several elements the same way, e.g.
+/// `let card = attrs![padding(16), spacing(8)]`.
+///
+/// When attributes set the same thing, like two `padding`s, the one
+/// listed last wins. So attributes passed to `apply` win over the set's,
+/// and a set merged in wins over the one it's merged into.
+pub struct AttrSet<Msg>(Vec<Attribute<Msg>>);
+
+impl<Msg> Clone for AttrSet<Msg> {
+    fn clone(&self) -> Self {
+        AttrSet(self.0.clone())
+    }
+}
+
+impl<Msg> Default for AttrSet<Msg> {
+    fn default() -> Self {
+        AttrSet(vec![])
+    }
+}
+
+impl<Msg> From<Vec<Attribute<Msg>>> for AttrSet<Msg> {
+    fn from(attrs: Vec<Attribute<Msg>>) -> Self {
+        AttrSet(attrs)
+    }
+}
+
+impl<Msg> From<AttrSet<Msg>> for Vec<Attribute<Msg>> {
+    fn from(set: AttrSet<Msg>) -> Self {
+        set.0
+    }
+}
+
+impl<Msg> AttrSet<Msg> {
+    /// This set with `other`'s attributes overriding its own.
+    pub fn merge(mut self, other: &AttrSet<Msg>) -> Self {
+        self.0
+            .retain(|attr| !other.0.iter().any(|o| overrides(o, attr)));
+        self.0.extend(other.0.iter().cloned());
+        self
+    }
+
+    /// The attributes for an element, with `attrs` overriding the set's.
+    pub fn apply(&self, attrs: Vec<Attribute<Msg>>) -> Vec<Attribute<Msg>> {
+        let mut all: Vec<_> = self
+            .0
+            .iter()
+            .filter(|attr| !attrs.iter().any(|a| overrides(a, attr)))
+            .cloned()
+            .collect();
+        all.extend(attrs);
+        all
+    }
+}
+
+/// An `AttrSet` of the attributes listed.
+#[macro_export]
+macro_rules! attrs {
+    ($($attr:expr),* $(,)?) => {
+        $crate::element::AttrSet::from(vec![$($attr),*])
+    };
+}
+
 /// When you want to render exactly nothing.
-fn none() -> Element {
+fn none<Msg>() -> Element<Msg> {
     Element::Empty
 }
 
@@ -353,7 +515,7 @@ fn none() -> Element {
 ///
 /// **Note** text does not wrap by default.
 /// In order to get text to wrap, check out paragraph!
-fn text(content: String) -> Element {
+fn text<Msg>(content: String) -> Element<Msg> {
     Element::Text(content)
 }
 
@@ -367,7 +529,7 @@ fn text(content: String) -> Element {
 ///
 ///     use element::{Element, rgb, el};
 ///
-///     fn my_element() -> Element {
+///     fn my_element<Msg>() -> Element<Msg> {
 ///         el([text(
 ///             "You've made a stylish element!"
 ///         )])
@@ -375,9 +537,17 @@ fn text(content: String) -> Element {
 ///         .border_color(rgb(0.0, 0.7, 0.0))
 ///     }
 ///
-pub fn el(attrs: Vec<Attribute>, child: Element) -> Element {
-    let mut attr =
-        vec![Attribute::Width(shrink()), Attribute::Height(shrink())];
+#[track_caller]
+pub fn el<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
+    let mut attr = vec![
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        source_location(),
+    ];
 
     attr.extend(attrs);
     let attrs = attr;
@@ -390,7 +560,12 @@ pub fn el(attrs: Vec<Attribute>, child: Element) -> Element {
     )
 }
 
-pub fn row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
+#[track_caller]
+pub fn row<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
     let mut attr = vec![
         Attribute::html_class(format!(
             "{} {}",
@@ -399,6 +574,7 @@ pub fn row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
         )),
         Attribute::Width(shrink()),
         Attribute::Height(shrink()),
+        source_location(),
     ];
 
     attr.extend(attrs);
@@ -412,7 +588,12 @@ pub fn row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
     )
 }
 
-pub fn column(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
+#[track_caller]
+pub fn column<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
     let mut attr = vec![
         Attribute::html_class(format!(
             "{} {}",
@@ -421,6 +602,7 @@ pub fn column(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
         )),
         Attribute::Width(shrink()),
         Attribute::Height(shrink()),
+        source_location(),
     ];
 
     attr.extend(attrs);
@@ -436,7 +618,13 @@ pub fn column(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
 
 /// Same as row, but will wrap if it takes up
 /// too much horizontal space.
-pub fn wrapped_row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
+#[track_caller]
+pub fn wrapped_row<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
+    attrs.push(source_location());
     let (padded, spaced) = extract_spacing_and_padding(attrs.clone());
 
     if let Some(Style::Spacing(name, x, y)) = spaced {
@@ -491,41 +679,24 @@ pub fn wrapped_row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
             let half_x = -(x as f32 / 2.0);
             let half_y = -(y as f32 / 2.0);
 
-            element(
-                LayoutContext::AsEl,
-                div(),
+            wrapped_row_inset(
                 attrs,
-                Children::Unkeyed(vec![element(
-                    LayoutContext::AsRow,
-                    div(),
-                    vec![
-                        Attribute::html_class(format!(
-                            "{} {} {}",
-                            Classes::ContentLeft.to_string(),
-                            Classes::ContentCenterY.to_string(),
-                            Classes::Wrapped.to_string(),
-                        )),
-                        Attribute::Attr(html::attributes::style(
-                            "margin".to_string(),
-                            format!("{}px {}px", half_y, half_x),
-                        )),
-                        Attribute::Attr(html::attributes::style(
-                            "width".to_string(),
-                            format!("calc(100% + {}px)", x),
-                        )),
-                        Attribute::Attr(html::attributes::style(
-                            "height".to_string(),
-                            format!("calc(100% + {}px)", y),
-                        )),
-                        Attribute::Style(
-                            Flag::spacing(),
-                            Style::Spacing(name, x, y),
-                        ),
-                    ],
-                    Children::Unkeyed(children),
-                )]),
+                children,
+                Style::Spacing(name, x, y),
+                format!("{}px {}px", half_y, half_x),
+                (format!("{}px", x), format!("{}px", y)),
             )
         }
+    } else if let Some(Style::SpacingLength(name, x, y)) = spaced {
+        // The size of a length isn't known here, so it can't be taken out
+        // of the padding.
+        wrapped_row_inset(
+            attrs,
+            children,
+            Style::SpacingLength(name, x.clone(), y.clone()),
+            format!("calc({} / -2) calc({} / -2)", y, x),
+            (x.to_string(), y.to_string()),
+        )
     } else {
         let mut attr = vec![
             Attribute::html_class(format!(
@@ -550,7 +721,49 @@ pub fn wrapped_row(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
     }
 }
 
-pub fn explain() -> Attribute {
+// A wrapped row inside an element, pulled out by half the `spacing` on
+// every side so the spacing doesn't show at the edges.
+fn wrapped_row_inset<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+    spacing: Style,
+    margin: String,
+    (x, y): (String, String),
+) -> Element<Msg> {
+    element(
+        LayoutContext::AsEl,
+        div(),
+        attrs,
+        Children::Unkeyed(vec![element(
+            LayoutContext::AsRow,
+            div(),
+            vec![
+                Attribute::html_class(format!(
+                    "{} {} {}",
+                    Classes::ContentLeft.to_string(),
+                    Classes::ContentCenterY.to_string(),
+                    Classes::Wrapped.to_string(),
+                )),
+                Attribute::Attr(html::attributes::style(
+                    "margin".to_string(),
+                    margin,
+                )),
+                Attribute::Attr(html::attributes::style(
+                    "width".to_string(),
+                    format!("calc(100% + {})", x),
+                )),
+                Attribute::Attr(html::attributes::style(
+                    "height".to_string(),
+                    format!("calc(100% + {})", y),
+                )),
+                Attribute::Style(Flag::spacing(), spacing),
+            ],
+            Children::Unkeyed(children),
+        )]),
+    )
+}
+
+pub fn explain<Msg>() -> Attribute<Msg> {
     Attribute::html_class("explain".to_string())
 }
 
@@ -598,11 +811,17 @@ pub fn explain() -> Attribute {
 ///
 /// **Note** `spacing` on a paragraph will set
 /// the pixel spacing between lines.
-pub fn paragraph(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
+#[track_caller]
+pub fn paragraph<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
     let mut attr = vec![
         Attribute::Describe(Description::Paragraph),
         Attribute::Width(fill()),
         spacing(5),
+        source_location(),
     ];
 
     attr.extend(attrs);
@@ -638,11 +857,14 @@ pub fn paragraph(attrs: Vec<Attribute>, children: Vec<Element>) -> Element {
 /// Which will result in something like:
 ///
 /// ![A text layout where an image is on the left.](https://mdgriffith.gitbooks.io/style-elements/content/assets/Screen%20Shot%202017-08-25%20at%208.42.39%20PM.png)
-pub fn text_column(
-    mut attrs: Vec<Attribute>,
-    children: Vec<Element>,
-) -> Element {
+#[track_caller]
+pub fn text_column<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
     attrs.push(width(min(500, max(750, fill()))));
+    attrs.push(source_location());
 
     element(
         LayoutContext::AsTextColumn,
@@ -652,6 +874,265 @@ pub fn text_column(
     )
 }
 
+pub struct PageConfig<Msg> {
+    pub page_height: u32,
+    pub children: Vec<Element<Msg>>,
+}
+
+// How wide a `text_column` grows, which its pages are measured at.
+const PAGE_WIDTH: f32 = 750.0;
+
+/// A `text_column` split across pages of `page_height` pixels, for book or
+/// report style layouts. Each page is a `text_column` with `attrs`, and
+/// when printed, each starts on a new sheet.
+///
+/// Children are kept whole, so one taller than a page gets a page to
+/// itself, which grows to fit it. Where they end is worked out by laying
+/// them out in a single `text_column` with `solver::Solver`.
+pub fn text_column_paged<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: PageConfig<Msg>,
+) -> Element<Msg> {
+    let PageConfig {
+        page_height,
+        children,
+    } = config;
+    let unpaged = layout(vec![], text_column(attrs.clone(), children.clone()));
+    let rects =
+        Solver::default().lay_out(&unpaged, PAGE_WIDTH, page_height as f32);
+    let mut pages: Vec<Vec<Element<Msg>>> = vec![];
+    let mut top = 0.0;
+    for (i, child) in children.into_iter().enumerate() {
+        // `layout` puts the column after the stylesheets, at `[0, 2, 0]`.
+        let (y, height) = rects
+            .get(&[0, 2, 0, i][..])
+            .map_or((top, 0.0), |rect| (rect.y, rect.height));
+        match pages.last_mut() {
+            Some(page) if y + height - top <= page_height as f32 => {
+                page.push(child)
+            }
+            _ => {
+                pages.push(vec![child]);
+                top = y;
+            }
+        }
+    }
+    let pages = pages
+        .into_iter()
+        .map(|page| {
+            let mut page_attrs = attrs.clone();
+            page_attrs.extend(vec![
+                height(min(page_height as u64, shrink())),
+                Attribute::html_class("page-break".to_string()),
+            ]);
+            text_column(page_attrs, page)
+        })
+        .collect();
+    column(vec![width(fill())], pages)
+}
+
+/// Flow the content of a `text_column` or `paragraph` into `count`
+/// columns, `gap` pixels apart, like a newspaper. The text fills the first
+/// column before moving on to the next, and the columns are as tall as
+/// they need to be to share it out evenly.
+///
+/// Where the text may break between columns is up to `column_break` and
+/// `span_columns` on the children.
+pub fn text_columns<Msg>(count: u32, gap: u32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::text_columns(),
+        Style::TextColumns(count.max(1), gap),
+    )
+}
+
+/// Where the text of `text_columns` may move on to the next column,
+/// around one of its children.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum ColumnBreak {
+    /// Keep the child whole, e.g. a figure with its caption.
+    Avoid,
+    /// Start the child at the top of a column.
+    Before,
+    /// Start what follows the child at the top of a column.
+    After,
+}
+
+/// Control how a child of `text_columns` is split between columns.
+pub fn column_break<Msg>(at: ColumnBreak) -> Attribute<Msg> {
+    Attribute::html_class(
+        match at {
+            ColumnBreak::Avoid => "col-break-avoid",
+            ColumnBreak::Before => "col-break-before",
+            ColumnBreak::After => "col-break-after",
+        }
+        .to_string(),
+    )
+}
+
+/// Stretch a child of `text_columns` across all of them, e.g. for a
+/// headline. The text before it is shared out among the columns above it,
+/// and the text after among those below.
+pub fn span_columns<Msg>() -> Attribute<Msg> {
+    Attribute::html_class("col-span".to_string())
+}
+
+/// A column of a `table`: its header, how wide it is, and how each record
+/// is shown in it.
+pub struct Column<Record, Msg> {
+    /// `Element::Empty` for no header. The header row is left out if no
+    /// column has one.
+    pub header: Element<Msg>,
+    pub width: Length,
+    pub view: Box<CellFn<Record, Msg>>,
+}
+
+pub type CellFn<Record, Msg> = dyn Fn(&Record) -> Element<Msg>;
+
+pub struct TableConfig<Record, Msg> {
+    pub data: Vec<Record>,
+    pub columns: Vec<Column<Record, Msg>>,
+}
+
+/// Show some tabular data.
+///
+/// Start with a list of records, and give a `Column` for each thing to
+/// show of them, e.g. a name and an age:
+///
+/// ```ignore
+/// table(vec![spacing(8)], TableConfig {
+///     data: persons,
+///     columns: vec![
+///         Column {
+///             header: Element::Text("Name".to_string()),
+///             width: fill(),
+///             view: Box::new(|p: &Person| Element::Text(p.name.clone())),
+///         },
+///         Column {
+///             header: Element::Text("Age".to_string()),
+///             width: fill(),
+///             view: Box::new(|p: &Person| Element::Text(p.age.to_string())),
+///         },
+///     ],
+/// })
+/// ```
+///
+/// Each record is a row, laid out on a grid so the cells of a column line
+/// up. `spacing` sets the space between cells.
+pub fn table<Record, Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TableConfig<Record, Msg>,
+) -> Element<Msg> {
+    let mut heads = Vec::with_capacity(config.columns.len());
+    let mut views = Vec::with_capacity(config.columns.len());
+    for col in config.columns {
+        heads.push((col.header, col.width));
+        views.push(col.view);
+    }
+    table_helper(attrs, config.data, heads, |col, _, record| {
+        views[col](record)
+    })
+}
+
+/// A column of an `indexed_table`, which is shown each record along with
+/// its index in the data.
+pub struct IndexedColumn<Record, Msg> {
+    pub header: Element<Msg>,
+    pub width: Length,
+    pub view: Box<IndexedCellFn<Record, Msg>>,
+}
+
+pub type IndexedCellFn<Record, Msg> = dyn Fn(usize, &Record) -> Element<Msg>;
+
+pub struct IndexedTableConfig<Record, Msg> {
+    pub data: Vec<Record>,
+    pub columns: Vec<IndexedColumn<Record, Msg>>,
+}
+
+/// Same as `table`, except the `view` of each column is also given the
+/// index of the record, counting from 0.
+pub fn indexed_table<Record, Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: IndexedTableConfig<Record, Msg>,
+) -> Element<Msg> {
+    let mut heads = Vec::with_capacity(config.columns.len());
+    let mut views = Vec::with_capacity(config.columns.len());
+    for col in config.columns {
+        heads.push((col.header, col.width));
+        views.push(col.view);
+    }
+    table_helper(attrs, config.data, heads, |col, index, record| {
+        views[col](index, record)
+    })
+}
+
+// A grid with a row of `heads` if any aren't empty, then a row per record,
+// the cell in each column drawn by `view(column, index, record)`.
+fn table_helper<Record, Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    data: Vec<Record>,
+    heads: Vec<(Element<Msg>, Length)>,
+    view: impl Fn(usize, usize, &Record) -> Element<Msg>,
+) -> Element<Msg> {
+    let spacing = get_spacing_length(&attrs, (Length::Px(0), Length::Px(0)));
+
+    let on_grid = |row: usize, col: usize, element: Element<Msg>| {
+        crate::model::element(
+            LayoutContext::AsEl,
+            div(),
+            vec![Attribute::Style(
+                Flag::grid_position(),
+                Style::GridPosition(GridPosition {
+                    row: row as u64,
+                    col: col as u64,
+                    width: 1,
+                    height: 1,
+                }),
+            )],
+            Children::Unkeyed(vec![element]),
+        )
+    };
+
+    let has_headers = heads
+        .iter()
+        .any(|(header, _)| !matches!(header, Element::Empty));
+    let first_row = if has_headers { 2 } else { 1 };
+    let template = grid_template(GridTemplate {
+        spacing,
+        columns: heads
+            .iter()
+            .map(|(_, width)| Track::Size(width.clone()))
+            .collect(),
+        rows: vec![Track::Size(Length::Content); data.len() + first_row - 1],
+        ..GridTemplate::default()
+    });
+
+    let columns = heads.len();
+    let mut cells = Vec::with_capacity((data.len() + 1) * columns);
+    if has_headers {
+        for (i, (header, _)) in heads.into_iter().enumerate() {
+            cells.push(on_grid(1, i + 1, header));
+        }
+    }
+    for (index, record) in data.iter().enumerate() {
+        for col in 0..columns {
+            cells.push(on_grid(
+                first_row + index,
+                col + 1,
+                view(col, index, record),
+            ));
+        }
+    }
+
+    let mut all = vec![width(fill()), template];
+    all.extend(attrs);
+    crate::model::element(
+        LayoutContext::AsGrid,
+        div(),
+        all,
+        Children::Unkeyed(cells),
+    )
+}
+
 /// Both a source and a description are required for images.
 ///
 /// The description is used for people using screen readers.
@@ -664,11 +1145,11 @@ pub fn text_column(
 /// So, take a moment to describe your image as you would to
 /// someone who has a harder time seeing.
 ///
-pub fn image(
-    attrs: Vec<Attribute>,
+pub fn image<Msg>(
+    attrs: Vec<Attribute<Msg>>,
     src: String,
     description: String,
-) -> Element {
+) -> Element<Msg> {
     let img_attrs = attrs
         .iter()
         .filter(|a| match *a {
@@ -677,7 +1158,7 @@ pub fn image(
             _ => false,
         })
         .map(|x| x.clone())
-        .collect::<Vec<Attribute>>();
+        .collect::<Vec<Attribute<Msg>>>();
 
     let mut img_attr = vec![
         Attribute::Attr(html::attributes::src(src)),
@@ -707,7 +1188,101 @@ pub fn image(
     )
 }
 
-pub fn link(attrs: Vec<Attribute>, url: String, label: Element) -> Element {
+/// A sprite sheet of equally sized tiles, in rows from the top left, for
+/// drawing icons from one texture.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Atlas {
+    pub src: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl Atlas {
+    /// The left, top, width and height of tile `index`, in pixels.
+    pub fn tile_rect(&self, index: u32) -> (u32, u32, u32, u32) {
+        let columns = self.columns.max(1);
+        (
+            index % columns * self.tile_width,
+            index / columns * self.tile_height,
+            self.tile_width,
+            self.tile_height,
+        )
+    }
+
+    /// The corners of tile `index` from the top left of the texture, from
+    /// 0 to 1, for renderers drawing it with UVs.
+    pub fn uv_rect(&self, index: u32) -> [f32; 4] {
+        let (x, y, w, h) = self.tile_rect(index);
+        let width = (self.columns.max(1) * self.tile_width) as f32;
+        let height = (self.rows.max(1) * self.tile_height) as f32;
+        [
+            x as f32 / width,
+            y as f32 / height,
+            (x + w) as f32 / width,
+            (y + h) as f32 / height,
+        ]
+    }
+}
+
+/// Tile `index` of `atlas`, e.g. one icon of a game's sprite sheet. It's
+/// the size of a tile unless `attrs` size it otherwise, and the tile is
+/// scaled to fit.
+///
+/// The node says which tile it is with `data-atlas` attributes, with the
+/// tile's `tile_rect` as `data-atlas-rect`, so a renderer with the atlas as
+/// a texture, like Bevy's, can draw just that part of it. In the browser,
+/// the sheet is a background image, positioned to show the tile.
+pub fn image_from_atlas<Msg>(
+    atlas: &Atlas,
+    index: u32,
+    attrs: Vec<Attribute<Msg>>,
+) -> Element<Msg> {
+    let percent = |i: u32, count: u32| {
+        if count > 1 {
+            i as f32 * 100.0 / (count - 1) as f32
+        } else {
+            0.0
+        }
+    };
+    let columns = atlas.columns.max(1);
+    let (x, y, w, h) = atlas.tile_rect(index);
+    let style = |name: &str, value: String| {
+        Attribute::Attr(html::attributes::style(name.to_string(), value))
+    };
+    let mut atlas_attrs = vec![
+        width(px(atlas.tile_width as u64)),
+        height(px(atlas.tile_height as u64)),
+        Attribute::Attr(vdom::Attribute::property("data-atlas", &atlas.src)),
+        Attribute::Attr(vdom::Attribute::property("data-atlas-index", index)),
+        Attribute::Attr(vdom::Attribute::property(
+            "data-atlas-rect",
+            format!("{} {} {} {}", x, y, w, h),
+        )),
+        style("background-image", css_url(&atlas.src)),
+        style(
+            "background-size",
+            format!("{}% {}%", columns * 100, atlas.rows.max(1) * 100),
+        ),
+        style(
+            "background-position",
+            format!(
+                "{}% {}%",
+                percent(index % columns, columns),
+                percent(index / columns, atlas.rows)
+            ),
+        ),
+    ];
+    atlas_attrs.extend(attrs);
+    el(atlas_attrs, Element::Empty)
+}
+
+pub fn link<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    url: String,
+    label: Element<Msg>,
+) -> Element<Msg> {
     let mut attr = vec![
         Attribute::Attr(html::attributes::href(url)),
         Attribute::Attr(html::attributes::rel(
@@ -734,11 +1309,11 @@ pub fn link(attrs: Vec<Attribute>, url: String, label: Element) -> Element {
     )
 }
 
-pub fn new_tablink(
-    attrs: Vec<Attribute>,
+pub fn new_tablink<Msg>(
+    attrs: Vec<Attribute<Msg>>,
     url: String,
-    label: Element,
-) -> Element {
+    label: Element<Msg>,
+) -> Element<Msg> {
     let mut attr = vec![
         Attribute::Attr(html::attributes::href(url)),
         Attribute::Attr(html::attributes::rel(
@@ -766,7 +1341,11 @@ pub fn new_tablink(
     )
 }
 
-pub fn download(attrs: Vec<Attribute>, url: String, label: Element) -> Element {
+pub fn download<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    url: String,
+    label: Element<Msg>,
+) -> Element<Msg> {
     let mut attr = vec![
         Attribute::Attr(html::attributes::href(url)),
         Attribute::Attr(html::attributes::download("".to_string())),
@@ -787,12 +1366,12 @@ pub fn download(attrs: Vec<Attribute>, url: String, label: Element) -> Element {
     )
 }
 
-pub fn download_as(
-    attrs: Vec<Attribute>,
+pub fn download_as<Msg>(
+    attrs: Vec<Attribute<Msg>>,
     url: String,
     file_name: String,
-    label: Element,
-) -> Element {
+    label: Element<Msg>,
+) -> Element<Msg> {
     let mut attr = vec![
         Attribute::Attr(html::attributes::href(url)),
         Attribute::Attr(html::attributes::download(file_name)),
@@ -813,107 +1392,687 @@ pub fn download_as(
     )
 }
 
-pub fn create_nearby(loc: Location, element: Element) -> Attribute {
-    match element {
-        Element::Empty => Attribute::None,
-        _ => Attribute::Nearby(loc, element),
-    }
+/// A link that stays hidden until it receives keyboard focus, letting
+/// keyboard and screen reader users jump straight past the navigation.
+///
+/// `target_id` should match the `id` set on the main region of the page,
+/// and the link should come first in the document so it is the first
+/// thing focused.
+pub fn skip_link<Msg>(target_id: String, label: String) -> Element<Msg> {
+    link(
+        vec![Attribute::html_class(
+            Classes::VisuallyHiddenFocusable.to_string().to_string(),
+        )],
+        format!("#{}", target_id),
+        text(label),
+    )
 }
 
-pub fn below(element: Element) -> Attribute {
-    create_nearby(Location::Below, element)
+/// How far a `refreshable` has been pulled down, kept in the model.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Default)]
+pub struct Pull {
+    start: Option<f32>,
+    distance: f32,
 }
 
-pub fn above(element: Element) -> Attribute {
-    create_nearby(Location::Above, element)
+impl Pull {
+    /// How far down the content has been pulled, in pixels.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
 }
 
-pub fn on_right(element: Element) -> Attribute {
-    create_nearby(Location::OnRight, element)
-}
+// How far the content has to be pulled before letting go refreshes it.
+const REFRESH_THRESHOLD: f32 = 64.0;
 
-pub fn on_left(element: Element) -> Attribute {
-    create_nearby(Location::OnLeft, element)
+pub struct RefreshConfig<Msg> {
+    pub on_refresh: Msg,
+    /// Whether a refresh is in progress, to keep the spinner showing.
+    pub refreshing: bool,
+    pub content: Element<Msg>,
+    /// The gesture so far. `update` should put it back to the default
+    /// when handling `on_refresh`.
+    pub pull: Pull,
+    pub on_pull: Arc<dyn Fn(Pull) -> Msg + Send + Sync>,
 }
 
-/// This will place an element in front of another.
-///
-/// **Note:** If you use this on a `layout` element,
-/// it will place the element as fixed to the viewport
-/// which can be useful for modals and overlays.
-pub fn in_front(element: Element) -> Attribute {
-    create_nearby(Location::InFront, element)
+/// A container that's refreshed by pulling its content down and letting
+/// go, as on a phone. A spinner shows in the gap the content leaves.
+pub fn refreshable<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: RefreshConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let RefreshConfig {
+        on_refresh,
+        refreshing,
+        content,
+        pull,
+        on_pull,
+    } = config;
+    let offset = if refreshing {
+        REFRESH_THRESHOLD
+    } else {
+        pull.distance
+    };
+
+    let start = on_pull.clone();
+    let down = on("pointerdown".to_string(), move |data| match data {
+        EventData::Pointer { y, .. } if !refreshing => start(Pull {
+            start: Some(*y),
+            distance: 0.0,
+        }),
+        _ => start(pull),
+    });
+    let drag = on_pull.clone();
+    let moved = on("pointermove".to_string(), move |data| match (data, pull) {
+        (
+            EventData::Pointer { y, .. },
+            Pull {
+                start: Some(start), ..
+            },
+        ) => drag(Pull {
+            start: Some(start),
+            // Resist the pull, so it doesn't feel loose.
+            distance: ((y - start) / 2.0).clamp(0.0, REFRESH_THRESHOLD * 2.0),
+        }),
+        _ => drag(pull),
+    });
+    let release = move |_: &EventData| {
+        if pull.start.is_some() && pull.distance >= REFRESH_THRESHOLD {
+            on_refresh.clone()
+        } else {
+            on_pull(Pull::default())
+        }
+    };
+    attrs.extend(vec![
+        down,
+        moved,
+        on("pointerup".to_string(), release.clone()),
+        on("pointercancel".to_string(), release),
+        // Keep the browser from scrolling the page instead.
+        Attribute::Style(
+            Flag::overscroll(),
+            Style::Single(
+                "overscroll-contain".to_string(),
+                "overscroll-behavior-y".into(),
+                "contain".to_string(),
+            ),
+        ),
+    ]);
+
+    let spinner = if refreshing || pull.distance > 0.0 {
+        el(
+            vec![
+                center_x(),
+                center_y(),
+                // Wind the arrow round as it's pulled.
+                rotate(
+                    pull.distance / REFRESH_THRESHOLD * std::f32::consts::PI,
+                ),
+                alpha(if refreshing {
+                    1.0
+                } else {
+                    pull.distance / REFRESH_THRESHOLD
+                }),
+            ],
+            Element::Text("\u{27f3}".to_string()),
+        )
+    } else {
+        Element::Empty
+    };
+    column(
+        attrs,
+        vec![
+            el(
+                vec![width(fill()), height(px(offset.round() as u64))],
+                spinner,
+            ),
+            content,
+        ],
+    )
 }
 
-/// This will place an element between the background
-/// and the content of an element.
-pub fn behind_content(element: Element) -> Attribute {
-    create_nearby(Location::Behind, element)
+/// Which call of `Suspense::load` content was loaded by, so content from a
+/// load that's since been started again is left out.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LoadId(u64);
+
+/// Content loaded asynchronously, kept in the model and shown with
+/// `suspense`.
+pub struct Suspense<Msg> {
+    content: Option<Element<Msg>>,
+    // How long the placeholder has been showing for.
+    shown: Duration,
+    min_display: Duration,
+    // The latest load, the only one whose content is shown.
+    loading: LoadId,
 }
 
-pub fn width(w: Length) -> Attribute {
-    Attribute::Width(w)
+impl<Msg> Default for Suspense<Msg> {
+    fn default() -> Self {
+        Suspense {
+            content: None,
+            shown: Duration::ZERO,
+            min_display: Duration::ZERO,
+            loading: LoadId::default(),
+        }
+    }
 }
 
-pub fn height(w: Length) -> Attribute {
-    Attribute::Height(w)
+impl<Msg> Suspense<Msg> {
+    /// Keep the placeholder up for at least `min_display` once loading
+    /// starts, so content that's almost immediate doesn't flash it.
+    pub fn with_min_display(min_display: Duration) -> Self {
+        Suspense {
+            min_display,
+            ..Suspense::default()
+        }
+    }
+
+    /// Start loading `future`, showing the placeholder until the message
+    /// `to_msg` makes of its content is handled by calling `resolve` with
+    /// the same `LoadId`. Loading again before then replaces this load.
+    pub fn load<Fut, F>(&mut self, future: Fut, to_msg: F) -> Cmd<Msg>
+    where
+        Msg: 'static,
+        Fut: Future<Output = Element<Msg>> + MaybeSend + 'static,
+        F: FnOnce(LoadId, Element<Msg>) -> Msg + MaybeSend + 'static,
+    {
+        self.content = None;
+        self.shown = Duration::ZERO;
+        self.loading.0 += 1;
+        let id = self.loading;
+        Cmd::perform(future, move |content| to_msg(id, content))
+    }
+
+    /// Show `content`, loaded by the load `id`. If it's been replaced by a
+    /// later load, the content is dropped and `false` returned.
+    pub fn resolve(&mut self, id: LoadId, content: Element<Msg>) -> bool {
+        if id != self.loading {
+            return false;
+        }
+        self.content = Some(content);
+        true
+    }
+
+    /// Count `delta` towards the minimum display time, e.g. from an
+    /// `every` subscription while `is_waiting`.
+    pub fn tick(&mut self, delta: Duration) {
+        self.shown += delta;
+    }
+
+    /// Whether the minimum display time hasn't passed yet.
+    pub fn is_waiting(&self) -> bool {
+        self.shown < self.min_display
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.content.is_some() && !self.is_waiting()
+    }
 }
 
-pub fn scale(n: f32) -> Attribute {
-    Attribute::TransformComponent(
-        Flag::scale(),
-        TransformComponent::Scale(Coordinate { x: n, y: n, z: 1.0 }),
-    )
+/// Show `pending` until `state` has finished loading, then its content.
+pub fn suspense<Msg>(
+    pending: Element<Msg>,
+    state: &Suspense<Msg>,
+) -> Element<Msg> {
+    match &state.content {
+        Some(content) if !state.is_waiting() => content.clone(),
+        _ => pending,
+    }
 }
 
-/// Angle is given in radians. [Here are some conversion functions if you want to use another unit.](https://package.elm-lang.org/packages/elm/core/latest/Basics#degrees)
-pub fn rotate(angle: f32) -> Attribute {
-    Attribute::TransformComponent(
-        Flag::rotate(),
-        TransformComponent::Rotate(
-            Coordinate {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-            },
-            angle,
+/// Animate changes to `property`, e.g. `"opacity"`, over 200ms. For
+/// `transform` and `opacity`, the element gets a `will-change` hint too.
+pub fn transition<Msg>(property: &str) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::transition(),
+        Style::Single(
+            format!("tr-{}", property),
+            "transition".into(),
+            format!("{} 200ms ease-out", property),
         ),
     )
 }
 
-pub fn move_up(y: f32) -> Attribute {
-    Attribute::TransformComponent(
-        Flag::move_y(),
-        TransformComponent::MoveY(y.neg()),
+/// Whether this element and everything in it can be clicked or touched.
+/// Elements that can't are clicked through.
+pub fn pointer_events<Msg>(on: bool) -> Attribute<Msg> {
+    let (class, value) = if on {
+        ("pe-auto", "auto")
+    } else {
+        ("pe-none", "none")
+    };
+    Attribute::Style(
+        Flag::pointer_events(),
+        Style::Single(
+            class.to_string(),
+            "pointer-events".into(),
+            value.to_string(),
+        ),
     )
 }
 
-pub fn move_down(y: f32) -> Attribute {
-    Attribute::TransformComponent(Flag::move_y(), TransformComponent::MoveY(y))
+/// The edge of the screen a `drawer` slides in from.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
 }
 
-pub fn move_left(x: f32) -> Attribute {
-    Attribute::TransformComponent(
-        Flag::move_x(),
-        TransformComponent::MoveX(x.neg()),
-    )
+pub struct DrawerConfig<Msg> {
+    pub side: Side,
+    pub open: bool,
+    /// Sent when the backdrop is clicked, `Escape` is pressed, or the
+    /// drawer is swiped back towards its edge.
+    pub on_dismiss: Msg,
+    pub content: Element<Msg>,
 }
 
-pub fn move_right(x: f32) -> Attribute {
-    Attribute::TransformComponent(Flag::move_x(), TransformComponent::MoveX(x))
-}
+// How far a drawer has to be swiped to close it.
+const SWIPE_DISTANCE: f32 = 64.0;
 
-pub fn padding(x: u32) -> Attribute {
-    let f = x as f32;
-    Attribute::Style(
-        Flag::padding(),
-        Style::Padding(format!("p-{}", x), f, f, f, f),
+/// How wide or tall a `drawer` is, from its edge, if it isn't given a size
+/// in pixels.
+pub const DRAWER_SIZE: u64 = 320;
+
+/// A panel that slides in from `side` of the screen over a backdrop, for
+/// navigation or settings. Put it `in_front` of the `layout` root so it
+/// covers the page. Size it with `attrs`, e.g. `width(px(320))` for one
+/// on the left, which is how far it's moved out of sight when closed. It's
+/// `DRAWER_SIZE` pixels across if it isn't given a size in pixels.
+///
+/// While it's open, focus is trapped inside it.
+pub fn drawer<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: DrawerConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let DrawerConfig {
+        side,
+        open,
+        on_dismiss,
+        content,
+    } = config;
+    // Its size from its edge, set here if it wasn't in pixels.
+    let across = |attr: &Attribute<Msg>| match (side, attr) {
+        (Side::Left | Side::Right, Attribute::Width(Length::Px(px)))
+        | (Side::Top | Side::Bottom, Attribute::Height(Length::Px(px))) => {
+            Some(*px)
+        }
+        _ => None,
+    };
+    let size = match attrs.iter().rev().find_map(across) {
+        Some(size) => size,
+        None => {
+            attrs.push(match side {
+                Side::Left | Side::Right => width(px(DRAWER_SIZE)),
+                Side::Top | Side::Bottom => height(px(DRAWER_SIZE)),
+            });
+            DRAWER_SIZE
+        }
+    } as f32;
+    let (align, stretch, closed) = match side {
+        Side::Left => (align_left(), height(fill()), move_left(size)),
+        Side::Right => (align_right(), height(fill()), move_right(size)),
+        Side::Top => (align_top(), width(fill()), move_up(size)),
+        Side::Bottom => (align_bottom(), width(fill()), move_down(size)),
+    };
+
+    let escape = on_dismiss.clone();
+    let swipe = on_dismiss.clone();
+    attrs.extend(vec![
+        align,
+        stretch,
+        transition("transform"),
+        focus_trap(open),
+        Attribute::Attr(vdom::Attribute::property("role", "dialog")),
+        Attribute::Attr(vdom::Attribute::property("aria-modal", "true")),
+        on_filtered("keydown".to_string(), move |data| match data {
+            EventData::Key { key, .. } if key == "Escape" => {
+                Some(escape.clone())
+            }
+            _ => None,
+        }),
+        // Listened for so the backend reports where a swipe starts, which
+        // the program hands to the `pointerup` listener.
+        on_filtered("pointerdown".to_string(), |_| None),
+        on_filtered("pointerup".to_string(), move |data| match data {
+            EventData::Release {
+                x,
+                y,
+                start: Some((x0, y0)),
+            } => {
+                // How far back towards its edge it was swiped.
+                let back = match side {
+                    Side::Left => x0 - x,
+                    Side::Right => x - x0,
+                    Side::Top => y0 - y,
+                    Side::Bottom => y - y0,
+                };
+                if back >= SWIPE_DISTANCE {
+                    Some(swipe.clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }),
+    ]);
+    // First, so it's composed last with the caller's transforms, and moves
+    // it out of sight whatever they move it by along the same axis.
+    if !open {
+        attrs.insert(0, closed);
+    }
+    let panel = el(attrs, content);
+
+    let backdrop = el(
+        vec![
+            width(fill()),
+            height(fill()),
+            Attribute::Style(
+                Flag::bg_color(),
+                Style::Colored(
+                    "bg-backdrop".to_string(),
+                    "background-color".into(),
+                    Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 0.4,
+                    },
+                ),
+            ),
+            alpha(if open { 1.0 } else { 0.0 }),
+            transition("opacity"),
+            on_click(on_dismiss),
+        ],
+        Element::Empty,
+    );
+    el(
+        vec![
+            width(fill()),
+            height(fill()),
+            in_front(panel),
+            // Closed, it's still there to slide out, but clicked through.
+            pointer_events(open),
+        ],
+        backdrop,
     )
 }
 
-pub fn padding_xy(x: u32, y: u32) -> Attribute {
-    if x == y {
-        let f = x as f32;
+pub struct ContextMenuConfig<Msg> {
+    /// Where the menu is open, in window coordinates, if it is.
+    pub open_at: Option<(f32, f32)>,
+    /// Sent with the pointer's position when the element is right-clicked.
+    pub on_open: Arc<dyn Fn(f32, f32) -> Msg + Send + Sync>,
+    /// Sent when a click lands anywhere, including on one of the items
+    /// after the item's own message, or `Escape` is pressed.
+    pub on_dismiss: Msg,
+    /// Builds the menu's items, only while it's open.
+    pub menu: Box<dyn FnOnce() -> Vec<Element<Msg>>>,
+}
+
+/// Open a menu at the pointer when this element is right-clicked, instead
+/// of the browser's own. The menu is laid out over the whole window, so it
+/// isn't clipped by the element, and its items are a roving group moved
+/// between with the arrow keys.
+pub fn context_menu<Msg>(config: ContextMenuConfig<Msg>) -> Vec<Attribute<Msg>>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let ContextMenuConfig {
+        open_at,
+        on_open,
+        on_dismiss,
+        menu,
+    } = config;
+    let mut attrs = vec![on_context_menu(move |x, y| on_open(x, y))];
+    let (x, y) = match open_at {
+        Some(at) => at,
+        None => return attrs,
+    };
+    let escape = on_dismiss.clone();
+    let menu = column(
+        vec![
+            move_right(x),
+            move_down(y),
+            focus_trap(true),
+            roving_group(FocusOrientation::Vertical),
+            Attribute::Attr(vdom::Attribute::property("role", "menu")),
+            on_filtered("keydown".to_string(), move |data| match data {
+                EventData::Key { key, .. } if key == "Escape" => {
+                    Some(escape.clone())
+                }
+                _ => None,
+            }),
+        ],
+        menu(),
+    );
+    let backdrop = el(
+        vec![
+            width(fill()),
+            height(fill()),
+            pointer_events(true),
+            on_click(on_dismiss),
+            in_front(menu),
+        ],
+        Element::Empty,
+    );
+    attrs.push(in_front(el(
+        vec![Attribute::Attr(vdom::Attribute::Class("modal".to_string()))],
+        backdrop,
+    )));
+    attrs
+}
+
+/// A section of a `scroll_spy`, and the link to it.
+pub struct SpySection<Msg> {
+    /// Set as the section's `id`, and passed to `on_active_change`.
+    pub id: String,
+    pub label: Element<Msg>,
+    pub content: Element<Msg>,
+}
+
+pub struct ScrollSpyConfig<Msg> {
+    pub sections: Vec<SpySection<Msg>>,
+    /// The id of the section in view, kept in the model.
+    pub active: Option<String>,
+    /// Added to the link of the active section, to highlight it.
+    pub highlight: Vec<Attribute<Msg>>,
+    /// Sent with a section's id when it scrolls into view or its link is
+    /// clicked.
+    pub on_active_change: Arc<dyn Fn(String) -> Msg + Send + Sync>,
+}
+
+// How much of a section has to be showing for it to become the active one.
+const SPY_THRESHOLD: f32 = 0.5;
+
+/// A table of contents that stays put beside its scrolling sections, with
+/// the link to the section in view highlighted.
+pub fn scroll_spy<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: ScrollSpyConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let ScrollSpyConfig {
+        sections,
+        active,
+        mut highlight,
+        on_active_change,
+    } = config;
+    let mut links = Vec::new();
+    let mut contents = Vec::new();
+    for section in sections {
+        let SpySection {
+            id: name,
+            label,
+            content,
+        } = section;
+        let mut link_attrs =
+            vec![pointer(), on_click(on_active_change(name.clone()))];
+        if active.as_ref() == Some(&name) {
+            link_attrs.push(Attribute::Attr(vdom::Attribute::property(
+                "aria-current",
+                "location",
+            )));
+            link_attrs.append(&mut highlight);
+        }
+        links.push(el(link_attrs, label));
+
+        let change = on_active_change.clone();
+        let section = name.clone();
+        contents.push(el(
+            vec![
+                id(name),
+                width(fill()),
+                on_intersect_filtered(move |ratio| {
+                    if ratio >= SPY_THRESHOLD {
+                        Some(change(section.clone()))
+                    } else {
+                        None
+                    }
+                }),
+            ],
+            content,
+        ));
+    }
+
+    attrs.extend(vec![width(fill()), height(fill())]);
+    row(
+        attrs,
+        vec![
+            column(
+                vec![
+                    align_top(),
+                    Attribute::Attr(vdom::Attribute::property(
+                        "role",
+                        "navigation",
+                    )),
+                ],
+                links,
+            ),
+            column(
+                vec![width(fill()), height(fill()), scrollbar_y()],
+                contents,
+            ),
+        ],
+    )
+}
+
+pub fn create_nearby<Msg>(
+    loc: Location,
+    element: Element<Msg>,
+) -> Attribute<Msg> {
+    match element {
+        Element::Empty => Attribute::None,
+        _ => Attribute::Nearby(loc, Box::new(element)),
+    }
+}
+
+pub fn below<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::Below, element)
+}
+
+pub fn above<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::Above, element)
+}
+
+pub fn on_right<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::OnRight, element)
+}
+
+pub fn on_left<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::OnLeft, element)
+}
+
+/// This will place an element in front of another.
+///
+/// **Note:** If you use this on a `layout` element,
+/// it will place the element as fixed to the viewport
+/// which can be useful for modals and overlays.
+pub fn in_front<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::InFront, element)
+}
+
+/// This will place an element between the background
+/// and the content of an element.
+pub fn behind_content<Msg>(element: Element<Msg>) -> Attribute<Msg> {
+    create_nearby(Location::Behind, element)
+}
+
+pub const fn width<Msg>(w: Length) -> Attribute<Msg> {
+    Attribute::Width(w)
+}
+
+pub const fn height<Msg>(w: Length) -> Attribute<Msg> {
+    Attribute::Height(w)
+}
+
+pub fn scale<Msg>(n: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(
+        Flag::scale(),
+        TransformComponent::Scale(Coordinate { x: n, y: n, z: 1.0 }),
+    )
+}
+
+/// Angle is given in radians. [Here are some conversion functions if you want to use another unit.](https://package.elm-lang.org/packages/elm/core/latest/Basics#degrees)
+pub fn rotate<Msg>(angle: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(
+        Flag::rotate(),
+        TransformComponent::Rotate(
+            Coordinate {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            angle,
+        ),
+    )
+}
+
+pub fn move_up<Msg>(y: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(
+        Flag::move_y(),
+        TransformComponent::MoveY(y.neg()),
+    )
+}
+
+pub fn move_down<Msg>(y: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(Flag::move_y(), TransformComponent::MoveY(y))
+}
+
+pub fn move_left<Msg>(x: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(
+        Flag::move_x(),
+        TransformComponent::MoveX(x.neg()),
+    )
+}
+
+pub fn move_right<Msg>(x: f32) -> Attribute<Msg> {
+    Attribute::TransformComponent(Flag::move_x(), TransformComponent::MoveX(x))
+}
+
+pub fn padding<Msg>(x: u32) -> Attribute<Msg> {
+    let f = x as f32;
+    Attribute::Style(
+        Flag::padding(),
+        Style::Padding(format!("p-{}", x), f, f, f, f),
+    )
+}
+
+pub fn padding_xy<Msg>(x: u32, y: u32) -> Attribute<Msg> {
+    if x == y {
+        let f = x as f32;
 
         Attribute::Style(
             Flag::padding(),
@@ -929,7 +2088,12 @@ pub fn padding_xy(x: u32, y: u32) -> Attribute {
     }
 }
 
-pub fn padding_each(top: u32, right: u32, bottom: u32, left: u32) -> Attribute {
+pub fn padding_each<Msg>(
+    top: u32,
+    right: u32,
+    bottom: u32,
+    left: u32,
+) -> Attribute<Msg> {
     if top == right && top == bottom && top == left {
         let f = top as f32;
 
@@ -951,38 +2115,38 @@ pub fn padding_each(top: u32, right: u32, bottom: u32, left: u32) -> Attribute {
     }
 }
 
-pub fn center_x() -> Attribute {
+pub fn center_x<Msg>() -> Attribute<Msg> {
     Attribute::AlignX(HAlign::CenterX)
 }
 
-pub fn center_y() -> Attribute {
+pub fn center_y<Msg>() -> Attribute<Msg> {
     Attribute::AlignY(VAlign::CenterY)
 }
 
-pub fn align_top() -> Attribute {
+pub fn align_top<Msg>() -> Attribute<Msg> {
     Attribute::AlignY(VAlign::Top)
 }
 
-pub fn align_bottom() -> Attribute {
+pub fn align_bottom<Msg>() -> Attribute<Msg> {
     Attribute::AlignY(VAlign::Bottom)
 }
 
-pub fn align_left() -> Attribute {
+pub fn align_left<Msg>() -> Attribute<Msg> {
     Attribute::AlignX(HAlign::Left)
 }
 
-pub fn align_right() -> Attribute {
+pub fn align_right<Msg>() -> Attribute<Msg> {
     Attribute::AlignX(HAlign::Right)
 }
 
-pub fn space_evenly() -> Attribute {
+pub fn space_evenly<Msg>() -> Attribute<Msg> {
     Attribute::Class(
         Flag::spacing(),
         Classes::SpaceEvenly.to_string().to_string(),
     )
 }
 
-pub fn spacing(x: u32) -> Attribute {
+pub fn spacing<Msg>(x: u32) -> Attribute<Msg> {
     Attribute::Style(
         Flag::spacing(),
         Style::Spacing(spacing_class_name(x, x), x, x),
@@ -994,16 +2158,56 @@ pub fn spacing(x: u32) -> Attribute {
 ///
 /// However for some layouts, like `textColumn`, you may want to
 /// set a different spacing for the x axis compared to the y axis.
-pub fn spacing_xy(x: u32, y: u32) -> Attribute {
+pub fn spacing_xy<Msg>(x: u32, y: u32) -> Attribute<Msg> {
     Attribute::Style(
         Flag::spacing(),
         Style::Spacing(spacing_class_name(x, y), x, y),
     )
 }
 
+/// Spacing of `percent` of the element's width between its children, so
+/// the gaps grow and shrink with it. As with CSS margins, the gaps between
+/// the rows of a `column` are a percentage of its width too.
+pub fn spacing_percent<Msg>(percent: f32) -> Attribute<Msg> {
+    spacing_calc_xy(Calc::percent(percent), Calc::percent(percent))
+}
+
+/// Spacing worked out by the browser, e.g.
+/// `spacing_calc(Calc::percent(2.0).plus(Calc::px(4.0)))`.
+pub fn spacing_calc<Msg>(space: Calc) -> Attribute<Msg> {
+    spacing_calc_xy(space.clone(), space)
+}
+
+pub fn spacing_calc_xy<Msg>(x: Calc, y: Calc) -> Attribute<Msg> {
+    let (x, y) = (Length::from(x), Length::from(y));
+    Attribute::Style(
+        Flag::spacing(),
+        Style::SpacingLength(
+            format!("spacing-{}-{}", x.class_name(), y.class_name()),
+            x,
+            y,
+        ),
+    )
+}
+
+/// Where an element is shown among its siblings in a `row`, `column` or
+/// `wrapped_row`, without moving it in the view, e.g. to show a sidebar
+/// after the content on a phone. Children are shown from the lowest
+/// `order` to the highest, those with the same in the order they're in,
+/// and the default is `0`.
+///
+/// Screen readers and the tab key still go through the children in the
+/// order of the view.
+pub fn order<Msg>(n: i32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::order(),
+        Style::Single(format!("order-{}", n), "order".into(), n.to_string()),
+    )
+}
+
 /// Make an element transparent and have it ignore any mouse
 /// or touch events, though it will stil take up space.
-pub fn transparent(on: bool) -> Attribute {
+pub fn transparent<Msg>(on: bool) -> Attribute<Msg> {
     if on {
         Attribute::Style(
             Flag::transparency(),
@@ -1021,7 +2225,7 @@ pub fn transparent(on: bool) -> Attribute {
 /// is transparent and 1.0 is fully opaque.
 ///
 /// Semantically equivalent to html opacity.
-pub fn alpha(o: f32) -> Attribute {
+pub fn alpha<Msg>(o: f32) -> Attribute<Msg> {
     let t = 1.0 - o.clamp(0.0, 1.0);
     Attribute::Style(
         Flag::transparency(),
@@ -1029,47 +2233,316 @@ pub fn alpha(o: f32) -> Attribute {
     )
 }
 
-pub fn scrollbars() -> Attribute {
+pub fn scrollbars<Msg>() -> Attribute<Msg> {
     Attribute::Class(
         Flag::overflow(),
         Classes::Scrollbars.to_string().to_string(),
     )
 }
 
-pub fn scrollbar_x() -> Attribute {
+pub fn scrollbar_x<Msg>() -> Attribute<Msg> {
     Attribute::Class(
         Flag::overflow(),
         Classes::ScrollbarsX.to_string().to_string(),
     )
 }
 
-pub fn scrollbar_y() -> Attribute {
+pub fn scrollbar_y<Msg>() -> Attribute<Msg> {
     Attribute::Class(
         Flag::overflow(),
         Classes::ScrollbarsY.to_string().to_string(),
     )
 }
 
-pub fn clip() -> Attribute {
+pub fn clip<Msg>() -> Attribute<Msg> {
     Attribute::Class(Flag::overflow(), Classes::Clip.to_string().to_string())
 }
 
-pub fn clip_x() -> Attribute {
+pub fn clip_x<Msg>() -> Attribute<Msg> {
     Attribute::Class(Flag::overflow(), Classes::ClipX.to_string().to_string())
 }
 
-pub fn clip_y() -> Attribute {
+pub fn clip_y<Msg>() -> Attribute<Msg> {
     Attribute::Class(Flag::overflow(), Classes::ClipY.to_string().to_string())
 }
 
+// The background-attachment trick: the covers scroll with the content and
+// hide the shadows, which stay put, once there's nothing more to scroll to.
+fn scroll_shadow_layers(background: Color, horizontal: bool) -> String {
+    let cover = background.format_color();
+    let clear = Color {
+        a: 0.0,
+        ..background
+    }
+    .format_color();
+    let shadow = "rgba(0,0,0,0.2), rgba(0,0,0,0)";
+    let (start, end, to, cover_size, shadow_size, at_start, at_end) =
+        if horizontal {
+            (
+                "left center",
+                "right center",
+                "to right",
+                "40px 100%",
+                "14px 100%",
+                "0 50%",
+                "100% 50%",
+            )
+        } else {
+            (
+                "center top",
+                "center bottom",
+                "to bottom",
+                "100% 40px",
+                "100% 14px",
+                "50% 0",
+                "50% 100%",
+            )
+        };
+    [
+        format!(
+            "linear-gradient({}, {} 30%, {}) {} / {} no-repeat local",
+            to, cover, clear, start, cover_size
+        ),
+        format!(
+            "linear-gradient({}, {}, {} 70%) {} / {} no-repeat local",
+            to, clear, cover, end, cover_size
+        ),
+        format!(
+            "radial-gradient(farthest-side at {}, {}) {} / {} no-repeat scroll",
+            at_start, shadow, start, shadow_size
+        ),
+        format!(
+            "radial-gradient(farthest-side at {}, {}) {} / {} no-repeat scroll",
+            at_end, shadow, end, shadow_size
+        ),
+        cover,
+    ]
+    .join(", ")
+}
+
+/// Shade the top and bottom edges of a scrolling element while there's
+/// more content past them. The shading is drawn as the element's
+/// background, so `background` should be the color it would otherwise
+/// have.
+pub fn scroll_shadows<Msg>(background: Color) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::bg_gradient(),
+        Style::Single(
+            format!("ssy-{}", background.format_color_class()),
+            "background".into(),
+            scroll_shadow_layers(background, false),
+        ),
+    )
+}
+
+/// Shade the left and right edges of a scrolling element while there's
+/// more content past them.
+pub fn scroll_shadows_x<Msg>(background: Color) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::bg_gradient(),
+        Style::Single(
+            format!("ssx-{}", background.format_color_class()),
+            "background".into(),
+            scroll_shadow_layers(background, true),
+        ),
+    )
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum SnapAxis {
+    X,
+    Y,
+}
+
+/// Which part of a child lines up with the scrolling element when it
+/// comes to rest.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum SnapAlign {
+    Start,
+    Center,
+    End,
+    /// Don't stop at this child, for `snap_align`.
+    None,
+}
+
+impl SnapAlign {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SnapAlign::Start => "start",
+            SnapAlign::Center => "center",
+            SnapAlign::End => "end",
+            SnapAlign::None => "none",
+        }
+    }
+}
+
+/// Always come to rest with one of this element's children lined up by
+/// `align` when scrolling along `axis`, e.g. a page at a time for a
+/// carousel. Use with `scrollbar_x` or `scrollbar_y`.
+pub fn scroll_snap<Msg>(axis: SnapAxis, align: SnapAlign) -> Attribute<Msg> {
+    let axis = match axis {
+        SnapAxis::X => "snap-x",
+        SnapAxis::Y => "snap-y",
+    };
+    Attribute::Class(
+        Flag::scroll_snap(),
+        format!("{} snap-{}", axis, align.as_str()),
+    )
+}
+
+/// Line this child up differently from the rest of its `scroll_snap`
+/// parent's children.
+pub fn snap_align<Msg>(align: SnapAlign) -> Attribute<Msg> {
+    Attribute::Class(
+        Flag::snap_align(),
+        format!("snap-self-{}", align.as_str()),
+    )
+}
+
+/// Where a child sits across its `row` or `column`: vertically in a row,
+/// horizontally in a column.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Alignment {
+    Start,
+    Center,
+    End,
+    /// Fill the row's height or the column's width, if the child's size
+    /// along it is `shrink`.
+    Stretch,
+    /// Line up the first line of the child's text with that of the other
+    /// children aligned by `Baseline`. Only makes sense in a row.
+    Baseline,
+}
+
+impl Alignment {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Alignment::Start => "start",
+            Alignment::Center => "center",
+            Alignment::End => "end",
+            Alignment::Stretch => "stretch",
+            Alignment::Baseline => "baseline",
+        }
+    }
+}
+
+/// Place this child across its `row` or `column` differently from how the
+/// container lines up the others, taking precedence over `align_top`,
+/// `center_x` and the like.
+pub fn align_self<Msg>(align: Alignment) -> Attribute<Msg> {
+    Attribute::Class(Flag::align_self(), format!("self-{}", align.as_str()))
+}
+
+/// Fill the height of the `row` or the width of the `column` this child
+/// is in, the same as `align_self(Alignment::Stretch)`.
+pub fn stretch<Msg>() -> Attribute<Msg> {
+    align_self(Alignment::Stretch)
+}
+
+/// Set the `id` of the rendered node, e.g. so a `skip_link` can target it.
+pub fn id<Msg>(i: String) -> Attribute<Msg> {
+    Attribute::Attr(html::attributes::id(i))
+}
+
+// Replace anything but letters, digits, `-` and `_`, so a name can't split
+// into several classes or end an attribute's name early.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Add a class of your own to the rendered node, for outside CSS or end to
+/// end tests to find it by. Unlike the classes this crate sets, it isn't
+/// tied to a style flag, so it never replaces one or is replaced by one.
+///
+/// Characters other than letters, digits, `-` and `_` become `-`, so
+/// `"my hook"` is the single class `my-hook`.
+pub fn html_class<Msg>(name: String) -> Attribute<Msg> {
+    let name = sanitize_name(&name);
+    if name.is_empty() {
+        Attribute::None
+    } else {
+        Attribute::Attr(html::attributes::class(name))
+    }
+}
+
+/// Set a `data-` attribute on the rendered node, as in
+/// `data_attr("test-id", "save")` for `data-test-id="save"`. The name is
+/// lower cased and cleaned up as in `html_class`; the value is escaped when
+/// it's rendered, so can be anything.
+pub fn data_attr<Msg>(name: &str, value: &str) -> Attribute<Msg> {
+    let name = sanitize_name(&name.to_ascii_lowercase());
+    if name.is_empty() {
+        Attribute::None
+    } else {
+        Attribute::Attr(vdom::Attribute::Data(
+            format!("data-{}", name),
+            value.to_string(),
+        ))
+    }
+}
+
+/// Where the element being made was made in the view's code, as a
+/// `data-at="src/view.rs:12:5"` attribute, with the `debug-locations`
+/// feature; without it, nothing. The element constructors add it
+/// themselves, so this is for components of your own, which should be
+/// `#[track_caller]` too for the location to be where they're used.
+#[track_caller]
+pub fn source_location<Msg>() -> Attribute<Msg> {
+    #[cfg(feature = "debug-locations")]
+    {
+        let at = std::panic::Location::caller();
+        Attribute::Attr(vdom::Attribute::property(
+            "data-at",
+            format!("{}:{}:{}", at.file(), at.line(), at.column()),
+        ))
+    }
+    #[cfg(not(feature = "debug-locations"))]
+    Attribute::None
+}
+
+/// Hide an element visually while keeping it available to screen readers.
+///
+/// Unlike `transparent`, the element takes up no space in the layout.
+pub fn visually_hidden<Msg>() -> Attribute<Msg> {
+    Attribute::html_class(Classes::VisuallyHidden.to_string().to_string())
+}
+
 /// Set the cursor to be a pointing hand when it's hovering over this element.
-pub fn pointer() -> Attribute {
+pub fn pointer<Msg>() -> Attribute<Msg> {
     Attribute::Class(
         Flag::cursor(),
         Classes::CursorPointer.to_string().to_string(),
     )
 }
 
+/// Make this attribute's style win over the host page's CSS, for views
+/// embedded into an existing site whose stylesheet targets every `div`.
+///
+/// The style gets a class of its own, doubled in its selectors for a
+/// higher specificity, e.g. `.p-4-imp.p-4-imp`, and its declarations are
+/// marked `!important`. Doubling alone beats the usual single-class
+/// selectors of a site, and `!important` beats its inline styles and ID
+/// selectors, short of its own `!important` rules.
+///
+/// Only attributes setting a style are changed. Widths, heights, alignment
+/// and the static classes, like `pointer`, are returned as they are.
+pub fn important<Msg>(attr: Attribute<Msg>) -> Attribute<Msg> {
+    match attr {
+        Attribute::Style(flag, style) => {
+            Attribute::Style(flag, Style::Important(Box::new(style)))
+        }
+        attr => attr,
+    }
+}
+
 #[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
 pub enum DeviceClass {
     Phone,
@@ -1102,6 +2575,16 @@ pub struct Device {
     orientation: Orientation,
 }
 
+impl Device {
+    pub fn class(&self) -> DeviceClass {
+        self.class
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
 /// Takes in a Window.Size and returns a device
 /// profile which can be used for responsiveness.
 ///
@@ -1162,31 +2645,1573 @@ pub fn modular(normal: f32, ratio: f32, rescale: i32) -> f32 {
     }
 }
 
-pub fn mouse_over(attrs: Vec<Attribute>) -> Attribute {
+/// Hint that an element's properties will be animated frequently,
+/// e.g. `promote_layer(vec!["transform".into(), "opacity".into()])`.
+///
+/// This renders a `will-change` rule for the given properties and,
+/// if the element isn't already transformed, a `translateZ(0)` so
+/// the element is composited on its own layer.
+///
+/// Elements animating transforms or opacity in `mouse_over`,
+/// `mouse_down` and `focused`, or with a `transition` on them, are given a
+/// `will-change` hint for you, so only reach for this for other properties
+/// or to hint more. It replaces that hint.
+pub fn promote_layer<Msg>(properties: Vec<String>) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::will_change(),
+        Style::Single(
+            format!("wc-{}", sanitize_name(&properties.join("-"))),
+            "will-change".into(),
+            properties.join(", "),
+        ),
+    )
+}
+
+pub fn mouse_over<Msg>(attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
     Attribute::Style(
         Flag::hover(),
         Style::PseudoSelector(PseudoClass::Hover, unwrap_decorations(attrs)),
     )
 }
 
-pub fn mouse_down(attrs: Vec<Attribute>) -> Attribute {
+pub fn mouse_down<Msg>(attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
     Attribute::Style(
         Flag::active(),
         Style::PseudoSelector(PseudoClass::Active, unwrap_decorations(attrs)),
     )
 }
 
-pub fn focused(attrs: Vec<Attribute>) -> Attribute {
+pub fn focused<Msg>(attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
     Attribute::Style(
         Flag::focus(),
         Style::PseudoSelector(PseudoClass::Focus, unwrap_decorations(attrs)),
     )
 }
 
-#[test]
-fn test_layout() {
-    layout(
-        vec![height(fill()), width(fill())],
-        Element::Text("Test".to_string()),
+/// Draw a focus ring on this element instead of the one set globally
+/// with `focus_style`.
+pub fn focus_ring<Msg>(ring: StyleSet) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::focus_ring(),
+        Style::PseudoSelector(PseudoClass::Focus, ring.styles()),
+    )
+}
+
+fn pseudo_element<Msg>(
+    element: PseudoElement,
+    attrs: Vec<Attribute<Msg>>,
+) -> Style {
+    let mut props = decoration_properties(unwrap_decorations(attrs));
+    // Without `content`, `::before` and `::after` aren't drawn at all.
+    if !props.iter().any(|Property(name, _)| name == "content") {
+        props.insert(0, Property("content".into(), "\"\"".to_string()));
+    }
+    Style::PseudoElement(element, props)
+}
+
+/// Draw a box before this element's content, styled by `attrs`, e.g. for
+/// a decorative marker. Give it text with `content`.
+pub fn before<Msg>(attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::before(),
+        pseudo_element(PseudoElement::Before, attrs),
+    )
+}
+
+/// Draw a box after this element's content, styled by `attrs`.
+pub fn after<Msg>(attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
+    Attribute::Style(Flag::after(), pseudo_element(PseudoElement::After, attrs))
+}
+
+fn css_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The text of a `before` or `after` box.
+pub fn content<Msg>(text: &str) -> Attribute<Msg> {
+    let prop = Property("content".into(), css_string(text));
+    let class = format!("ct-{:x}", props_hash(std::slice::from_ref(&prop)));
+    Attribute::Style(Flag::content(), Style::Single(class, prop.0, prop.1))
+}
+
+/// Start the counter `name` again from zero for this element and
+/// everything in it, e.g. for each level of an outline. Counter names are
+/// cleaned up as in `html_class`.
+pub fn counter_reset<Msg>(name: &str) -> Attribute<Msg> {
+    let name = sanitize_name(name);
+    Attribute::Style(
+        Flag::counter_reset(),
+        Style::Single(format!("cr-{}", name), "counter-reset".into(), name),
+    )
+}
+
+/// Count this element in the counter `name`.
+pub fn counter_increment<Msg>(name: &str) -> Attribute<Msg> {
+    let name = sanitize_name(name);
+    Attribute::Style(
+        Flag::counter_increment(),
+        Style::Single(format!("ci-{}", name), "counter-increment".into(), name),
+    )
+}
+
+/// A piece of a `marker`.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub enum Content {
+    Text(String),
+    /// The current value of a counter, e.g. `3`.
+    Counter(String),
+    /// The value of a counter and of the same counter in each enclosing
+    /// `counter_reset`, joined by a separator, e.g. `1.2.3`.
+    Counters(String, String),
+}
+
+impl Content {
+    fn value(&self) -> String {
+        match self {
+            Content::Text(text) => css_string(text),
+            Content::Counter(name) => {
+                format!("counter({})", sanitize_name(name))
+            }
+            Content::Counters(name, separator) => format!(
+                "counters({}, {})",
+                sanitize_name(name),
+                css_string(separator)
+            ),
+        }
+    }
+}
+
+/// Mark this element like a list item, e.g. with its step number. Usually
+/// it counts itself with `counter_increment`, so the numbering stays right
+/// as items are added and removed.
+///
+/// The marker is drawn with `before`, so an element can't have both.
+pub fn marker<Msg>(content: Vec<Content>) -> Attribute<Msg> {
+    let value = content
+        .iter()
+        .map(Content::value)
+        .collect::<Vec<String>>()
+        .join(" ");
+    Attribute::Style(
+        Flag::before(),
+        Style::PseudoElement(
+            PseudoElement::Before,
+            vec![
+                Property("content".into(), value),
+                Property("margin-right".into(), "0.5em".to_string()),
+            ],
+        ),
+    )
+}
+
+/// How an element's colors are combined with whatever is drawn behind it.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlendMode::Normal => "normal",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::Darken => "darken",
+            BlendMode::Lighten => "lighten",
+            BlendMode::ColorDodge => "color-dodge",
+            BlendMode::ColorBurn => "color-burn",
+            BlendMode::HardLight => "hard-light",
+            BlendMode::SoftLight => "soft-light",
+            BlendMode::Difference => "difference",
+            BlendMode::Exclusion => "exclusion",
+            BlendMode::Hue => "hue",
+            BlendMode::Saturation => "saturation",
+            BlendMode::Color => "color",
+            BlendMode::Luminosity => "luminosity",
+        }
+    }
+}
+
+/// Blend this element with what's behind it, e.g. `Screen` for a glow
+/// over a game scene. Use `isolate` on a parent to only blend with what's
+/// inside it.
+pub fn blend_mode<Msg>(mode: BlendMode) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::blend_mode(),
+        Style::Single(
+            format!("blend-{}", mode.as_str()),
+            "mix-blend-mode".into(),
+            mode.as_str().to_string(),
+        ),
+    )
+}
+
+/// Keep the blending of everything in this element inside it.
+pub fn isolate<Msg>() -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::isolate(),
+        Style::Single(
+            "isolate".to_string(),
+            "isolation".into(),
+            "isolate".to_string(),
+        ),
+    )
+}
+
+/// A shape to clip an element to.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub enum Shape {
+    /// The largest circle that fits in the element, e.g. for an avatar.
+    Circle,
+    /// The ellipse that touches each side of the element.
+    Ellipse,
+    /// The element's box with each corner rounded by a radius in pixels,
+    /// clockwise from the top left.
+    RoundedRect(u32, u32, u32, u32),
+    /// Points as fractions of the element's width and height, so
+    /// `(0.5, 0.0)` is the middle of the top edge.
+    Polygon(Vec<(f32, f32)>),
+}
+
+impl Shape {
+    fn clip_path(&self) -> String {
+        match self {
+            Shape::Circle => "circle(closest-side)".to_string(),
+            Shape::Ellipse => "ellipse(closest-side farthest-side)".to_string(),
+            Shape::RoundedRect(tl, tr, br, bl) => {
+                format!("inset(0 round {}px {}px {}px {}px)", tl, tr, br, bl)
+            }
+            Shape::Polygon(points) => format!(
+                "polygon({})",
+                points
+                    .iter()
+                    .map(|(x, y)| format!("{}% {}%", x * 100.0, y * 100.0))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Only draw the part of this element inside `shape`. Clicks outside it
+/// go to whatever is behind.
+pub fn clip_to<Msg>(shape: Shape) -> Attribute<Msg> {
+    let prop = Property("clip-path".into(), shape.clip_path());
+    let class = match &shape {
+        Shape::Circle => "clip-circle".to_string(),
+        Shape::Ellipse => "clip-ellipse".to_string(),
+        Shape::RoundedRect(tl, tr, br, bl) => {
+            format!("clip-rr-{}-{}-{}-{}", tl, tr, br, bl)
+        }
+        Shape::Polygon(_) => {
+            format!("clip-poly-{:x}", props_hash(std::slice::from_ref(&prop)))
+        }
+    };
+    Attribute::Style(Flag::clip_path(), Style::Single(class, prop.0, prop.1))
+}
+
+/// Lay out this element's children on a grid, e.g. for a card grid
+/// that fits as many columns as it has room for:
+///
+/// ```ignore
+/// grid_template(GridTemplate {
+///     columns: vec![Track::Repeat(
+///         Repeat::AutoFill,
+///         Length::Min(200, Box::new(Length::Fill(1))),
+///     )],
+///     auto_rows: Some(Length::Content),
+///     ..GridTemplate::default()
+/// })
+/// ```
+pub fn grid_template<Msg>(template: GridTemplate) -> Attribute<Msg> {
+    Attribute::Style(Flag::grid_template(), Style::GridTemplate(template))
+}
+
+/// A nested grid that lines up with its parent's tracks along `axes`,
+/// falling back to `template` where subgrids aren't supported.
+pub fn subgrid<Msg>(axes: Subgrid, template: GridTemplate) -> Attribute<Msg> {
+    grid_template(GridTemplate {
+        subgrid: Some(axes),
+        ..template
+    })
+}
+
+/// Name the cells of this element's grid, one list of names per row, so
+/// children can be placed with `grid_area`. A name spanning several cells
+/// is repeated in each, and `.` leaves a cell empty. Names are cleaned up
+/// as in `html_class`:
+///
+/// ```ignore
+/// grid_template_areas(&[
+///     &["header", "header"],
+///     &["sidebar", "main"],
+/// ])
+/// ```
+pub fn grid_template_areas<Msg>(areas: &[&[&str]]) -> Attribute<Msg> {
+    let value = areas
+        .iter()
+        .map(|row| {
+            let names = row
+                .iter()
+                .map(|name| area_name(name))
+                .collect::<Vec<String>>();
+            format!("\"{}\"", names.join(" "))
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let prop = Property("grid-template-areas".into(), value);
+    let class = format!("areas-{:x}", props_hash(std::slice::from_ref(&prop)));
+    Attribute::Style(Flag::grid_areas(), Style::Single(class, prop.0, prop.1))
+}
+
+/// Place this element in the cells named `name` by its parent's
+/// `grid_template_areas`, instead of at a `GridPosition`.
+pub fn grid_area<Msg>(name: &str) -> Attribute<Msg> {
+    let name = area_name(name);
+    Attribute::Style(
+        Flag::grid_position(),
+        Style::Single(format!("area-{}", name), "grid-area".into(), name),
+    )
+}
+
+// Area names are cleaned up as in `html_class`, the same way wherever
+// they're used so they still match, except for the dots of an empty cell.
+fn area_name(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c == '.') {
+        name.to_string()
+    } else {
+        sanitize_name(name)
+    }
+}
+
+#[test]
+fn test_layout() {
+    layout::<()>(
+        vec![height(fill()), width(fill())],
+        Element::Text("Test".to_string()),
+    );
+}
+
+#[test]
+fn test_promote_layer() {
+    let view = el::<()>(
+        vec![
+            promote_layer(vec!["opacity".to_string()]),
+            mouse_over(vec![scale(1.1), alpha(0.5)]),
+        ],
+        Element::Text("Card".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    // The hint given replaces the one for the hover state.
+    assert!(css.contains("will-change: opacity"), "{}", css);
+    assert!(!css.contains("will-change: opacity, transform"), "{}", css);
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.split(['"', ' ']).any(|c| c == "lyr"), "{}", html);
+
+    // Properties given together still make one class.
+    let view = el::<()>(
+        vec![promote_layer(vec!["transform, opacity".to_string()])],
+        Element::Text("Card".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains(".wc-transform--opacity {"), "{}", css);
+    assert!(css.contains("will-change: transform, opacity"), "{}", css);
+
+    let view = el::<()>(
+        vec![
+            mouse_over(vec![scale(1.1), alpha(0.5)]),
+            focused(vec![padding(2)]),
+        ],
+        Element::Text("Card".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    // On the element itself, so it's ready before the hover starts.
+    assert!(css.contains(".wc-opacity-transform {"), "{}", css);
+    assert!(css.contains("will-change: opacity, transform"), "{}", css);
+    let hinted = |state: &str| {
+        css.split('}')
+            .any(|rule| rule.contains(state) && rule.contains("will-change"))
+    };
+    assert!(!hinted(":hover") && !hinted(":focus"), "{}", css);
+
+    let view = el::<()>(
+        vec![transition("transform")],
+        Element::Text("Card".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("will-change: transform"), "{}", css);
+}
+
+#[test]
+fn test_skip_link() {
+    let view = layout::<()>(
+        vec![],
+        column(
+            vec![],
+            vec![
+                skip_link("main".to_string(), "Skip to content".to_string()),
+                el(
+                    vec![id("main".to_string())],
+                    el(
+                        vec![visually_hidden()],
+                        text("Only for readers".to_string()),
+                    ),
+                ),
+            ],
+        ),
+    );
+    assert!(crate::test::find_by_id(&view, "main").is_some());
+    let html = crate::render::to_html(&view);
+    assert!(html.contains("href=\"#main\""), "{}", html);
+    for class in &["vhf", "vh"] {
+        assert!(
+            html.split(['"', ' ']).any(|c| c == *class),
+            "{} not in {}",
+            class,
+            html
+        );
+    }
+    let rules = crate::style::rules();
+    let hidden = rules.split(".s.vhf:not(:focus) {").nth(1).unwrap();
+    let hidden = hidden.split('}').next().unwrap();
+    assert!(
+        hidden.contains("position:absolute !important;"),
+        "{}",
+        hidden
+    );
+    assert!(
+        hidden.contains("clip:rect(0, 0, 0, 0) !important;"),
+        "{}",
+        hidden
+    );
+}
+
+#[test]
+fn test_forced_colors() {
+    let views = vec![el::<()>(vec![], Element::Empty)];
+    let css = |opts| extract_styles_with(opts, &views);
+
+    let system = css(vec![]);
+    assert!(system.contains("@media (forced-colors: active)"));
+    assert!(system.contains(".sbt, .it { border: 1px solid ButtonText"));
+    assert!(system.contains(".s:focus, .s:focus .focusable"));
+
+    let visible = css(vec![focus_visible_only()]);
+    assert!(visible.contains(".s:focus-visible, .s:focus-visible .focusable"));
+
+    let preserve = css(vec![forced_colors(ForcedColors::Preserve)]);
+    assert!(preserve.contains(".ui, .ui * { forced-color-adjust: none; }"));
+    assert!(!preserve.contains("ButtonText"));
+
+    let ignore = css(vec![forced_colors(ForcedColors::Ignore)]);
+    assert!(!ignore.contains("forced-colors"));
+}
+
+#[test]
+fn test_extract_styles() {
+    let view = el::<()>(vec![padding(13)], Element::Text("Test".to_string()));
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains(".p-13"));
+
+    let node = layout_with(vec![external_styles()], vec![], view);
+    assert!(!format!("{:?}", node).contains("tag: \"style\""));
+}
+
+#[test]
+fn test_class_collisions_are_reported() {
+    use crate::model::{class_collisions, Style};
+
+    let color = |c: &str| {
+        Attribute::Style(
+            Flag::font_color(),
+            Style::Single(
+                "fc-collides".to_string(),
+                "color".into(),
+                c.to_string(),
+            ),
+        )
+    };
+    let view = |a: &str, b: &str| {
+        row::<()>(
+            vec![],
+            vec![
+                el(vec![color(a)], Element::Empty),
+                el(vec![color(b)], Element::Empty),
+            ],
+        )
+    };
+
+    let collisions = class_collisions(&view("red", "blue"));
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].class, "fc-collides");
+    assert!(collisions[0].first != collisions[0].second);
+
+    // Separate stylesheets, or the same style twice, don't collide.
+    assert!(class_collisions(&view("red", "red")).is_empty());
+    layout::<()>(vec![], el(vec![color("blue")], Element::Empty));
+    assert!(
+        class_collisions(&el(vec![color("red")], Element::Empty)).is_empty()
+    );
+}
+
+#[test]
+fn test_attribute_conflicts() {
+    use crate::model::take_attribute_conflicts;
+
+    // Other tests find conflicts too, so only those made here are looked
+    // at, by where their elements were made.
+    let view = |n| {
+        let at = format!("{}:{}:", file!(), line!() + 1);
+        let view = el::<()>(
+            vec![
+                width(px(n)),
+                align_left(),
+                padding(4),
+                width(fill()),
+                center_x(),
+                padding(4),
+            ],
+            Element::Empty,
+        );
+        (at, view)
+    };
+    let (at, _) = view(10);
+    let set: AttrSet<()> = crate::attrs![width(px(10)), spacing(4)];
+    row::<()>(set.apply(vec![width(fill())]), vec![]);
+
+    let conflicts: Vec<_> = take_attribute_conflicts()
+        .into_iter()
+        .filter(|c| c.at.starts_with(&at))
+        .collect();
+    let conflict = |property| {
+        let found: Vec<_> = conflicts
+            .iter()
+            .filter(|c| c.property == property)
+            .collect();
+        assert_eq!(found.len(), 1, "{:?}", conflicts);
+        (found[0].overridden.clone(), found[0].winner.clone())
+    };
+    assert_eq!(conflict("width"), ("Px(10)".into(), "Fill(1)".into()));
+    assert_eq!(
+        conflict("horizontal alignment"),
+        ("Left".into(), "CenterX".into())
+    );
+    let (overridden, winner) = conflict("padding");
+    assert_eq!(overridden, winner);
+
+    // Made again, as on every frame, they're not found again.
+    view(20);
+    let again = take_attribute_conflicts();
+    assert!(!again.iter().any(|c| c.at.starts_with(&at)), "{:?}", again);
+}
+
+#[test]
+fn test_table() {
+    let people = vec![("Ada", 36), ("Alan", 41)];
+    let name = |(name, _): &(&str, i32)| Element::Text(name.to_string());
+    let view = table::<_, ()>(
+        vec![spacing(4)],
+        TableConfig {
+            data: people.clone(),
+            columns: vec![
+                Column {
+                    header: Element::Text("Name".to_string()),
+                    width: px(100),
+                    view: Box::new(name),
+                },
+                Column {
+                    header: Element::Empty,
+                    width: fill(),
+                    view: Box::new(|(_, age): &(&str, i32)| {
+                        Element::Text(age.to_string())
+                    }),
+                },
+            ],
+        },
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("grid-template-columns: 100px 1fr;"));
+    let html = crate::render::to_html(&layout(vec![], view));
+    let at = |s: &str| html.find(s).unwrap();
+    assert!(at("grid-pos-1-1-1-1") < at("Name"));
+    assert!(at("Name") < at("Ada") && at("Ada") < at("36"));
+    assert!(at("grid-pos-3-2-1-1") < at("41"));
+
+    let view = indexed_table::<_, ()>(
+        vec![],
+        IndexedTableConfig {
+            data: people,
+            columns: vec![IndexedColumn {
+                header: Element::Empty,
+                width: shrink(),
+                view: Box::new(|i, (name, _)| {
+                    Element::Text(format!("{}. {}", i + 1, name))
+                }),
+            }],
+        },
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(
+        html.find("grid-pos-1-1-1-1").unwrap() < html.find("1. Ada").unwrap()
+    );
+    assert!(html.contains("2. Alan") && !html.contains("grid-pos-3"));
+}
+
+#[test]
+fn test_spacing_percent() {
+    let cells = || {
+        vec![
+            Element::Text("a".to_string()),
+            Element::Text("b".to_string()),
+        ]
+    };
+    let view = row::<()>(vec![spacing_percent(5.0)], cells());
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("margin-left: calc(5%)"), "{}", css);
+
+    let view = wrapped_row::<()>(
+        vec![spacing_calc(Calc::percent(2.0).plus(Calc::px(4.0)))],
+        cells(),
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.contains("calc(100% + calc(2% + 4px))"), "{}", html);
+    assert!(html.contains("calc(calc(2% + 4px) / -2)"), "{}", html);
+
+    let view = wrapped_row::<()>(vec![spacing_xy(10, 300)], cells());
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("margin: 150px 5px"), "{}", css);
+    assert!(css.contains("margin-top: -150px"), "{}", css);
+
+    let view = table::<_, ()>(
+        vec![spacing_percent(5.0)],
+        TableConfig {
+            data: vec![1],
+            columns: vec![Column {
+                header: Element::Empty,
+                width: fill(),
+                view: Box::new(|n: &i32| Element::Text(n.to_string())),
+            }],
+        },
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("grid-column-gap:calc(5%);"), "{}", css);
+}
+
+#[test]
+fn test_order() {
+    let view = row::<()>(
+        vec![],
+        vec![
+            el(vec![order(1)], Element::Text("sidebar".to_string())),
+            el(vec![order(-1)], Element::Text("content".to_string())),
+        ],
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("order: 1"), "{}", css);
+    assert!(css.contains("order: -1"), "{}", css);
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.find("order-1").unwrap() < html.find("order--1").unwrap());
+
+    let view = el::<()>(
+        vec![grid_area("main"), order(2)],
+        Element::Text("both".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("order: 2"), "{}", css);
+    assert!(css.contains("grid-area: main"), "{}", css);
+}
+
+#[test]
+fn test_align_self() {
+    let view = row::<()>(
+        vec![],
+        vec![
+            el(vec![stretch()], Element::Text("tall".to_string())),
+            el(
+                vec![align_bottom(), align_self(Alignment::Center)],
+                Element::Text("middle".to_string()),
+            ),
+            el(
+                vec![align_self(Alignment::Center), align_bottom()],
+                Element::Text("bottom".to_string()),
+            ),
+        ],
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    let count =
+        |class: &str| html.split(['"', ' ']).filter(|c| *c == class).count();
+    assert_eq!(count("self-stretch"), 1, "{}", html);
+    // Both are kept, with `align_self` winning in the stylesheet.
+    assert_eq!(count("self-center"), 2, "{}", html);
+    assert_eq!(count("ab"), 2, "{}", html);
+    assert!(crate::style::rules()
+        .contains(".self-stretch {align-self:stretch !important;}"));
+}
+
+#[test]
+fn test_text_columns() {
+    let view = text_column::<()>(
+        vec![text_columns(3, 24)],
+        vec![
+            paragraph(
+                vec![span_columns()],
+                vec![Element::Text("Headline".to_string())],
+            ),
+            paragraph(
+                vec![column_break(ColumnBreak::Avoid)],
+                vec![Element::Text("Story".to_string())],
+            ),
+        ],
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    for rule in &["column-count: 3", "column-gap: 24px"] {
+        assert!(css.contains(rule), "{} not in {}", rule, css);
+    }
+    let html = crate::render::to_html(&layout(vec![], view));
+    for class in &["cols-3-24", "col-span", "col-break-avoid"] {
+        assert!(
+            html.split(['"', ' ']).any(|c| c == *class),
+            "{} not in {}",
+            class,
+            html
+        );
+    }
+    let rules = crate::style::rules();
+    assert!(rules.contains(".col-span {column-span:all;}"));
+    assert!(rules.contains(".col-break-avoid {break-inside:avoid;}"));
+
+    // Not dropped for a `grid_template` on the same element.
+    let view = el::<()>(
+        vec![text_columns(2, 8), grid_template(GridTemplate::default())],
+        Element::Text("Story".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("column-count: 2"), "{}", css);
+}
+
+#[test]
+fn test_pseudo_elements() {
+    let view = el::<()>(
+        vec![
+            before(vec![content("»"), padding(4)]),
+            mouse_over(vec![after(vec![])]),
+        ],
+        Element::Text("Test".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("::before {"));
+    assert!(css.contains("content: \"»\""));
+    assert!(css.contains("-hv:hover::after {"));
+    assert!(css.contains("content: \"\""));
+}
+
+#[test]
+fn test_counters() {
+    let step = |label: &str| {
+        el::<()>(
+            vec![
+                counter_increment("step"),
+                marker(vec![
+                    Content::Counters("step".to_string(), ".".to_string()),
+                    Content::Text(")".to_string()),
+                ]),
+            ],
+            Element::Text(label.to_string()),
+        )
+    };
+    let view =
+        column(vec![counter_reset("step")], vec![step("One"), step("Two")]);
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("counter-reset: step"));
+    assert!(css.contains("counter-increment: step"));
+    assert!(css.contains("content: counters(step, \".\") \")\""));
+
+    // Names can't break out of the class or the declaration.
+    let view = el::<()>(
+        vec![
+            counter_reset("a b;color:red}"),
+            marker(vec![Content::Counter("a b;color:red}".to_string())]),
+        ],
+        Element::Empty,
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.contains("cr-a-b-color-red-"));
+    assert!(html.contains("counter-reset: a-b-color-red-;"));
+    assert!(html.contains("content: counter(a-b-color-red-)"));
+    assert!(!html.contains("color:red"));
+}
+
+#[test]
+fn test_grid_template() {
+    use crate::model::{AutoFlow, Repeat, Track};
+
+    let template = GridTemplate {
+        columns: vec![Track::Repeat(
+            Repeat::AutoFit,
+            Length::Min(200, Box::new(Length::Fill(1))),
+        )],
+        auto_rows: Some(Length::Px(120)),
+        auto_flow: Some(AutoFlow::RowDense),
+        ..GridTemplate::default()
+    };
+    let view = el::<()>(vec![grid_template(template)], Element::Empty);
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains(
+        "grid-template-columns: repeat(auto-fit, minmax(200px, 1fr));"
+    ));
+    assert!(css.contains("grid-auto-rows: 120px;"));
+    assert!(css.contains("grid-auto-flow: row dense;"));
+
+    let nested = subgrid::<()>(Subgrid::Columns, GridTemplate::default());
+    let view = el::<()>(vec![nested], Element::Empty);
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("{grid-template-columns: subgrid;}"));
+
+    // A subgrid and a plain grid with the same tracks get their own rules.
+    let view = row::<()>(
+        vec![],
+        vec![
+            el(vec![grid_template(GridTemplate::default())], Element::Empty),
+            el(
+                vec![subgrid(Subgrid::Rows, GridTemplate::default())],
+                Element::Empty,
+            ),
+        ],
+    );
+    assert!(crate::model::class_collisions(&view).is_empty());
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.contains("-sub-rows"));
+    assert!(html.contains("grid-template-rows: subgrid;"));
+}
+
+#[test]
+fn test_pull_to_refresh() {
+    #[derive(Debug, PartialEq, Clone)]
+    enum Msg {
+        Pull(Pull),
+        Refresh,
+    }
+
+    let view = |pull| {
+        refreshable(
+            vec![],
+            RefreshConfig {
+                on_refresh: Msg::Refresh,
+                refreshing: false,
+                content: Element::Text("Inbox".to_string()),
+                pull,
+                on_pull: Arc::new(Msg::Pull),
+            },
+        )
+    };
+    let send = |pull, event: &str, y| {
+        // Past the two stylesheets to the column.
+        let node = layout(vec![], view(pull));
+        node.dispatch(&[0, 2, 0], event, &EventData::Pointer { x: 0.0, y })
+            .remove(0)
+    };
+
+    let pull = match send(Pull::default(), "pointerdown", 100.0) {
+        Msg::Pull(pull) => pull,
+        msg => panic!("unexpected {:?}", msg),
+    };
+    let short = match send(pull, "pointermove", 140.0) {
+        Msg::Pull(pull) => pull,
+        msg => panic!("unexpected {:?}", msg),
+    };
+    assert_eq!(short.distance(), 20.0);
+    assert_eq!(send(short, "pointerup", 140.0), Msg::Pull(Pull::default()));
+
+    let far = match send(pull, "pointermove", 300.0) {
+        Msg::Pull(pull) => pull,
+        msg => panic!("unexpected {:?}", msg),
+    };
+    assert_eq!(send(far, "pointerup", 300.0), Msg::Refresh);
+}
+
+#[test]
+fn test_scroll_spy() {
+    let view = |active: Option<&str>| {
+        let section = |name: &str| SpySection {
+            id: name.to_string(),
+            label: Element::Text(name.to_string()),
+            content: Element::Empty,
+        };
+        layout(
+            vec![],
+            scroll_spy(
+                vec![],
+                ScrollSpyConfig {
+                    sections: vec![section("intro"), section("usage")],
+                    active: active.map(str::to_string),
+                    highlight: vec![],
+                    on_active_change: Arc::new(|id| id),
+                },
+            ),
+        )
+    };
+    let node = view(Some("intro"));
+    let html = crate::render::to_html(&node);
+    assert!(html.contains("aria-current=\"location\""));
+    assert!(!format!("{:?}", view(None)).contains("aria-current"));
+
+    // The scrolling column, then the second section.
+    let usage = [0, 2, 0, 1, 0, 1, 0];
+    let seen = |ratio| EventData::Intersection { ratio };
+    assert_eq!(
+        node.dispatch(&usage, "intersect", &seen(0.2)),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        node.dispatch(&usage, "intersect", &seen(0.8)),
+        vec!["usage".to_string()]
+    );
+    assert_eq!(
+        node.dispatch(&[0, 2, 0, 0, 0, 1, 0], "click", &EventData::None),
+        vec!["usage".to_string()]
+    );
+}
+
+#[test]
+fn test_text_column_paged() {
+    // Four lines each, at 75 characters to a line.
+    let paragraph = |words| Element::<()>::Text("word ".repeat(words));
+    let paged = |children| {
+        text_column_paged(
+            vec![],
+            PageConfig {
+                page_height: 170,
+                children,
+            },
+        )
+    };
+    let pages = paged((0..5).map(|_| paragraph(50)).collect());
+    let pages = format!("{:?}", pages).matches("page-break").count();
+    assert_eq!(pages, 3);
+
+    // One taller than a page gets a page of its own, grown to fit it.
+    let node = layout(vec![], paged(vec![paragraph(50), paragraph(200)]));
+    let rects = Solver::default().lay_out(&node, PAGE_WIDTH, 600.0);
+    assert_eq!(rects[&vec![0, 2, 0, 0]].height, 170.0);
+    assert_eq!(rects[&vec![0, 2, 0, 1]].height, 280.0);
+}
+
+#[test]
+fn test_layout_cached() {
+    let mut cache = RenderCache::default();
+    let view = |n: u32| el::<()>(vec![padding(n)], Element::Empty);
+
+    let first = layout_cached(&mut cache, vec![], view(1));
+    assert_eq!(layout_cached(&mut cache, vec![], view(1)), first);
+    assert_eq!(cache.len(), 1);
+
+    cache.next_frame();
+    layout_cached(&mut cache, vec![], view(2));
+    assert_eq!(cache.len(), 2);
+    // The first view wasn't used last frame, so it's forgotten.
+    cache.next_frame();
+    cache.next_frame();
+    assert!(cache.is_empty());
+
+    // A view with new closures for its handlers is reused, with the new
+    // handlers. The root's only child holds the two stylesheets followed by
+    // the wrapper around the `el`.
+    let mut cache = RenderCache::default();
+    let view = |n: u32| el(vec![padding(1), on_click(n)], Element::Empty);
+    let click =
+        |node: &Node<u32>| node.dispatch(&[0, 2, 0], "click", &EventData::None);
+    let first = layout_cached(&mut cache, vec![], view(1));
+    assert_eq!(click(&first), vec![1]);
+    cache.next_frame();
+    let second = layout_cached(&mut cache, vec![], view(2));
+    assert_eq!(second, first);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(click(&second), vec![2]);
+}
+
+#[test]
+fn test_static_bundle() {
+    use crate::model::static_bundle;
+    use crate::render::to_html;
+
+    let button = || el::<()>(static_bundle(vec![padding(123)]), Element::Empty);
+    let html =
+        to_html(&layout(vec![], column(vec![], vec![button(), button()])));
+    let sheets: Vec<&str> = html.split("<style>").skip(1).collect();
+    assert_eq!(sheets.len(), 2);
+    assert!(sheets[0].contains(".p-123"));
+    assert!(!sheets[1].contains(".p-123"));
+
+    // Only the layouts using the bundle have its styles.
+    let html = to_html(&layout(vec![], el::<()>(vec![], Element::Empty)));
+    assert!(!html.contains(".p-123"));
+}
+
+#[test]
+fn test_define_class() {
+    use crate::model::{define_class, use_class};
+    use crate::render::to_html;
+
+    define_class::<()>(
+        "btn-test",
+        vec![
+            padding(9),
+            Attribute::Class(Flag::font_weight(), "w7".to_string()),
+            mouse_over(vec![alpha(0.5)]),
+            width(fill()),
+        ],
+    );
+    // Later definitions don't change it.
+    define_class::<()>("btn-test", vec![padding(1)]);
+
+    let button = || el::<()>(vec![use_class("btn-test")], Element::Empty);
+    let html =
+        to_html(&layout(vec![], column(vec![], vec![button(), button()])));
+    assert!(html.contains("btn-test w7 btn-test-hv"));
+    let sheets: Vec<&str> = html.split("<style>").skip(1).collect();
+    assert!(sheets[0].contains(".btn-test {"));
+    assert!(sheets[0].contains("padding: 9px 9px 9px 9px"));
+    assert!(sheets[0].contains(".btn-test-hv:hover {"));
+    assert!(!sheets[0].contains("padding: 1px"));
+    assert!(!sheets[1].contains("p-9"));
+}
+
+#[test]
+fn test_const_colors() {
+    use crate::theme::{Theme, DARK};
+
+    const ORANGE: Color = rgb255(255, 128, 0);
+    static THEME: Theme = Theme { radius: 0, ..DARK };
+    assert_eq!(ORANGE, rgb(1.0, 128.0 / 255.0, 0.0));
+    assert_eq!(rgba255(0, 0, 255, 0.5), Color::new(0.0, 0.0, 1.0, 0.5));
+    assert_eq!(THEME.palette, DARK.palette);
+}
+
+#[test]
+fn test_suspense() {
+    use std::future::ready;
+    use std::task::{Context, Poll, Waker};
+
+    let text = |t: &str| Element::<LoadId>::Text(t.to_string());
+    let shows = |state: &Suspense<LoadId>, expected: &str| {
+        assert!(matches!(
+            suspense(text("Loading"), state),
+            Element::Text(t) if t == expected
+        ))
+    };
+    // The id the load's message carries, for `resolve`.
+    let load = |state: &mut Suspense<LoadId>| {
+        let cmd = state.load(ready(Element::Empty), |id, _| id);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut futures = cmd.into_futures();
+        match futures[0].as_mut().poll(&mut cx) {
+            Poll::Ready(Some(id)) => id,
+            _ => panic!("`ready` should resolve immediately"),
+        }
+    };
+
+    let mut state = Suspense::default();
+    let id = load(&mut state);
+    shows(&state, "Loading");
+    assert!(state.resolve(id, text("Done")));
+    shows(&state, "Done");
+
+    // Only the latest load's content is shown.
+    let first = load(&mut state);
+    let second = load(&mut state);
+    assert!(!state.resolve(first, text("Stale")));
+    shows(&state, "Loading");
+    assert!(state.resolve(second, text("Done")));
+    shows(&state, "Done");
+
+    let mut state = Suspense::with_min_display(Duration::from_millis(300));
+    let id = load(&mut state);
+    state.resolve(id, text("Done"));
+    state.tick(Duration::from_millis(200));
+    shows(&state, "Loading");
+    state.tick(Duration::from_millis(200));
+    assert!(state.is_ready());
+    shows(&state, "Done");
+}
+
+#[test]
+fn test_context_menu() {
+    let config = |open_at| ContextMenuConfig {
+        open_at,
+        on_open: Arc::new(|x, y| Some((x, y))),
+        on_dismiss: None,
+        menu: Box::new(|| vec![Element::Text("Copy".to_string())]),
+    };
+    let mut closed = context_menu(config(None));
+    assert_eq!(closed.len(), 1);
+    closed.push(id("target".to_string()));
+    let node = layout(vec![], el(closed, Element::Empty));
+    let (path, _) = crate::test::find_by_id(&node, "target").unwrap();
+    assert_eq!(
+        node.dispatch(
+            &path,
+            "contextmenu",
+            &EventData::Pointer { x: 10.0, y: 20.0 }
+        ),
+        vec![Some((10.0, 20.0))]
+    );
+
+    let open = layout(
+        vec![],
+        el(context_menu(config(Some((10.0, 20.0)))), Element::Empty),
+    );
+    let html = crate::render::to_html(&open);
+    assert!(html.contains("role=\"menu\""));
+    assert!(html.contains("Copy"));
+}
+
+#[test]
+fn test_attr_set() {
+    let base: AttrSet<()> = crate::attrs![padding(4), spacing(8)];
+    let tight = base.clone().merge(&crate::attrs![padding(2)]);
+    let classes = |attrs| {
+        let node = layout(vec![], el(attrs, Element::Text("x".to_string())));
+        let (path, _) = crate::test::find_by_text(&node, "x").unwrap();
+        // The text is wrapped in a node of its own, under the `el`.
+        let mut el = &node;
+        for i in &path[..path.len() - 1] {
+            if let vdom::NodeType::Node(child) = &el.children[*i] {
+                el = child;
+            }
+        }
+        el.attrs
+            .iter()
+            .flat_map(|a| a.classes())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    let has = |classes: &[String], class: &str| {
+        classes.iter().any(|c| c.split(' ').any(|c| c == class))
+    };
+    assert!(has(&classes(base.apply(vec![])), "p-4"));
+    assert!(has(&classes(base.apply(vec![padding(6)])), "p-6"));
+    let tight = classes(tight.into());
+    assert!(has(&tight, "p-2") && !has(&tight, "p-4"));
+}
+
+#[test]
+fn test_important() {
+    let node = layout::<()>(
+        vec![],
+        column(
+            vec![important(padding(4)), important(spacing(8))],
+            vec![Element::Text("x".to_string())],
+        ),
+    );
+    let html = crate::render::to_html(&node);
+    assert!(html.contains(".p-4-imp.p-4-imp {"));
+    assert!(html.contains("padding: 4px 4px 4px 4px !important;"));
+    assert!(!html.contains("!important !important"));
+    assert!(html.contains(".spacing-8-8-imp.spacing-8-8-imp"));
+    assert!(!html.contains(".spacing-8-8-imp.spacing-8-8-imp-imp"));
+    assert!(html.contains("margin-top: 8px !important;"));
+
+    let grid = layout::<()>(
+        vec![],
+        el(
+            vec![important(grid_template(GridTemplate::default()))],
+            Element::Empty,
+        ),
+    );
+    let html = crate::render::to_html(&grid);
+    assert!(html.contains("{display:grid !important;"));
+    assert!(!html.contains("grid-row-gap:0px;"));
+    assert!(matches!(important::<()>(pointer()), Attribute::Class(..)));
+}
+
+#[test]
+fn test_shadow_root() {
+    use crate::app::Program;
+
+    let program =
+        || Program::new(|| (), |_, _: ()| Cmd::none(), |_| Element::Empty);
+    assert!(!program().in_shadow_root());
+    let mut program = program().with_options(vec![shadow_root()]);
+    assert!(program.in_shadow_root());
+    // Only the backend sees it, not the page.
+    let html = crate::render::to_html(program.render());
+    assert!(!html.contains("data-shadow-root"));
+}
+
+#[test]
+fn test_image_from_atlas() {
+    use crate::render::to_html;
+
+    let atlas = Atlas {
+        src: "icons.png".to_string(),
+        tile_width: 16,
+        tile_height: 16,
+        columns: 4,
+        rows: 2,
+    };
+    assert_eq!(atlas.tile_rect(5), (16, 16, 16, 16));
+    assert_eq!(atlas.uv_rect(5), [0.25, 0.5, 0.5, 1.0]);
+    let html =
+        to_html(&layout(vec![], image_from_atlas::<()>(&atlas, 6, vec![])));
+    assert!(html.contains("data-atlas-index=\"6\""));
+    assert!(html.contains("data-atlas-rect=\"32 16 16 16\""));
+    assert!(html.contains("background-size: 400% 200%"));
+    assert!(html.contains("background-position: 66.666664% 100%"));
+}
+
+#[test]
+fn test_map() {
+    #[derive(Debug, PartialEq, Clone)]
+    enum Msg {
+        Counter(i32),
+        Reset,
+    }
+
+    let plus = el(vec![on_click(1), id("plus".to_string())], Element::Empty);
+    let counter = el(vec![on_click(0), below(plus)], Element::Empty);
+    let node = layout(
+        vec![on_click(()).map_attr(|_| Msg::Reset)],
+        counter.map(Msg::Counter),
+    );
+    let (path, _) = crate::test::find_by_id(&node, "plus").unwrap();
+    let clicked: Vec<_> = (0..=path.len())
+        .rev()
+        .flat_map(|len| node.dispatch(&path[..len], "click", &EventData::None))
+        .collect();
+    assert_eq!(clicked, vec![Msg::Counter(1), Msg::Counter(0), Msg::Reset]);
+}
+
+#[test]
+fn test_html_class_and_data_attr() {
+    use crate::render::to_html;
+
+    let node = layout(
+        vec![],
+        el::<()>(
+            vec![
+                id("hooked".to_string()),
+                html_class("my-app-hook".to_string()),
+                html_class("two words\"".to_string()),
+                html_class(String::new()),
+                data_attr("Test Id", "save \"draft\" = 1"),
+                data_attr("=", "x"),
+            ],
+            Element::Empty,
+        ),
+    );
+    let (_, el) = crate::test::find_by_id(&node, "hooked").unwrap();
+    assert!(el.attrs.iter().any(|a| a.classes().eq(["my-app-hook"])));
+    assert!(el.attrs.iter().any(|a| a.classes().eq(["two-words-"])));
+    // The app's own names aren't interned.
+    assert!(el.attrs.iter().any(|a| matches!(
+        a,
+        vdom::Attribute::Data(name, _) if name == "data-test-id"
+    )));
+    let html = to_html(&node);
+    assert!(html.contains("data-test-id=\"save &quot;draft&quot; = 1\""));
+    assert!(html.contains("data--=\"x\""));
+}
+
+#[cfg(feature = "debug-locations")]
+#[test]
+fn test_source_location() {
+    let node = layout(vec![], row::<()>(vec![id("here".to_string())], vec![]));
+    let line = line!() - 1;
+    let (_, row) = crate::test::find_by_id(&node, "here").unwrap();
+    let at = row.attr("data-at").unwrap();
+    assert!(at.starts_with(&format!("{}:{}:", file!(), line)));
+}
+
+#[test]
+fn test_blend_modes() {
+    let view = el::<()>(
+        vec![isolate()],
+        el(
+            vec![
+                blend_mode(BlendMode::ColorDodge),
+                crate::background::blend_mode(BlendMode::Multiply),
+            ],
+            Element::Empty,
+        ),
+    );
+    let html = crate::render::to_html(&layout(vec![], view.clone()));
+    assert!(html.contains("isolate"));
+    assert!(html.contains("blend-color-dodge"));
+    assert!(html.contains("bg-blend-multiply"));
+
+    let css = extract_styles(std::slice::from_ref(&view));
+    assert!(css.contains("isolation: isolate"));
+    assert!(css.contains("mix-blend-mode: color-dodge"));
+    assert!(css.contains("background-blend-mode: multiply"));
+}
+
+#[test]
+fn test_clip_to() {
+    let css = |shape: Shape| {
+        extract_styles(&[el::<()>(vec![clip_to(shape)], Element::Empty)])
+    };
+    assert!(css(Shape::Circle).contains("clip-path: circle(closest-side)"));
+    assert!(css(Shape::Ellipse)
+        .contains("clip-path: ellipse(closest-side farthest-side)"));
+    let rounded = css(Shape::RoundedRect(1, 2, 3, 4));
+    assert!(rounded.contains(".clip-rr-1-2-3-4"));
+    assert!(rounded.contains("clip-path: inset(0 round 1px 2px 3px 4px)"));
+
+    let triangle = Shape::Polygon(vec![(0.5, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+    assert!(css(triangle.clone())
+        .contains("clip-path: polygon(50% 0%, 100% 100%, 0% 100%)"));
+    // Different polygons get different classes.
+    let square =
+        Shape::Polygon(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+    let class = |shape: Shape| {
+        crate::render::to_html(&layout(
+            vec![],
+            el::<()>(vec![clip_to(shape)], Element::Empty),
+        ))
+        .split("clip-poly-")
+        .nth(1)
+        .map(|rest| {
+            rest.chars()
+                .take_while(char::is_ascii_hexdigit)
+                .collect::<String>()
+        })
+    };
+    assert!(class(triangle.clone()).is_some());
+    assert_ne!(class(triangle), class(square));
+}
+
+#[test]
+fn test_grid_areas() {
+    let view = el::<()>(
+        vec![grid_template_areas(&[
+            &["head er", "head er"],
+            &[".", "main\"}"],
+        ])],
+        el(vec![grid_area("main\"}")], Element::Empty),
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(
+        html.contains("grid-template-areas: \"head-er head-er\" \". main--\"")
+    );
+    assert!(html.contains("area-main--"));
+    assert!(html.contains("grid-area: main--;"));
+}
+
+#[test]
+fn test_scroll_shadows() {
+    let white = rgb(1.0, 1.0, 1.0);
+    let css = |attr: Attribute<()>| {
+        extract_styles(&[el(vec![scrollbar_y(), attr], Element::Empty)])
+    };
+
+    let y = css(scroll_shadows(white));
+    assert!(y.contains(&format!(".ssy-{}", white.format_color_class())));
+    // The covers scroll with the content, the shadows stay at the edges.
+    assert!(y.contains("to bottom"));
+    assert!(y.contains("center top / 100% 40px no-repeat local"));
+    assert!(y.contains("center bottom / 100% 14px no-repeat scroll"));
+
+    let x = css(scroll_shadows_x(white));
+    assert!(x.contains(&format!(".ssx-{}", white.format_color_class())));
+    assert!(x.contains("to right"));
+    assert!(x.contains("left center / 40px 100% no-repeat local"));
+    assert!(!x.contains("to bottom"));
+}
+
+#[test]
+fn test_scroll_snap_classes() {
+    let view = el::<()>(
+        vec![scrollbar_x(), scroll_snap(SnapAxis::X, SnapAlign::None)],
+        el(vec![snap_align(SnapAlign::Center)], Element::Empty),
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    assert!(html.contains("snap-x snap-none"));
+    assert!(html.contains("snap-self-center"));
+
+    // Every class they add has a rule.
+    let rules = crate::style::rules();
+    for class in ["snap-x", "snap-none", "snap-self-center"] {
+        assert!(rules.contains(&format!(".{}", class)), "{}", class);
+    }
+}
+
+#[test]
+fn test_drawer_swipe() {
+    use crate::{cmd::Cmd, test::TestHarness};
+
+    let view = |_: &u32| {
+        let config = DrawerConfig {
+            side: Side::Left,
+            open: true,
+            on_dismiss: 1,
+            content: text("menu".into()),
+        };
+        let panel = drawer(vec![id("panel".into()), width(px(320))], config);
+        el(vec![in_front(panel)], text("page".into()))
+    };
+    let update = |count: &mut u32, msg: u32| {
+        *count += msg;
+        Cmd::none()
+    };
+    let at = |x| EventData::Pointer { x, y: 10.0 };
+
+    // Far enough back towards the left edge.
+    let harness = TestHarness::new(|| 0, update, view)
+        .fire("panel", "pointerdown", at(300.0))
+        .fire("panel", "pointerup", at(200.0));
+    assert_eq!(*harness.model(), 1);
+
+    // Not far enough, then away from the edge.
+    let harness = harness
+        .fire("panel", "pointerdown", at(300.0))
+        .fire("panel", "pointerup", at(280.0))
+        .fire("panel", "pointerdown", at(200.0))
+        .fire("panel", "pointerup", at(300.0));
+    assert_eq!(*harness.model(), 1);
+
+    // Each program only sees its own pointer going down.
+    let mut first = TestHarness::new(|| 0, update, view);
+    let second = TestHarness::new(|| 0, update, view);
+    first = first.fire("panel", "pointerdown", at(300.0));
+    let second = second.fire("panel", "pointerup", at(200.0));
+    assert_eq!(*second.model(), 0);
+    let first = first.fire("panel", "pointerup", at(200.0));
+    assert_eq!(*first.model(), 1);
+}
+
+#[test]
+fn test_drawer_closed() {
+    let drawer = |side, attrs| {
+        let config = DrawerConfig {
+            side,
+            open: false,
+            on_dismiss: (),
+            content: text("menu".into()),
+        };
+        extract_styles(&[drawer(attrs, config)])
+    };
+    // Moved out by its size, along with the caller's own transforms.
+    let css = drawer(Side::Left, vec![width(px(280)), rotate(0.5)]);
+    assert!(css.contains("translate3d(-280px, 0px, 0px)"), "{}", css);
+    assert!(css.contains("rotate3d(0, 0, 1, 0.5rad)"), "{}", css);
+    let css = drawer(Side::Bottom, vec![move_right(4.0)]);
+    assert!(css.contains("translate3d(4px, 320px, 0px)"), "{}", css);
+    assert!(css.contains("height: 320px"), "{}", css);
+}
+
+#[test]
+fn test_gathered_children() {
+    use crate::render::to_html;
+
+    // Children keep their order, and their styles are all gathered, with
+    // nothing for an empty one.
+    let view = column::<()>(
+        vec![],
+        vec![
+            el(vec![padding(11)], text("alpha".into())),
+            Element::Empty,
+            text("bravo".into()),
+            el(vec![], text("charlie".into())),
+            el(vec![padding(12)], text("delta".into())),
+        ],
+    );
+    let html = to_html(&layout(vec![], view));
+    let order: Vec<usize> = ["alpha", "bravo", "charlie", "delta"]
+        .iter()
+        .filter_map(|t| html.find(t))
+        .collect();
+    assert_eq!(order.len(), 4);
+    assert!(order.windows(2).all(|w| w[0] < w[1]));
+    assert!(html.contains(".p-11") && html.contains(".p-12"));
+
+    // A `Vec` of attributes still makes a node, kept inline if it's short.
+    let class = vdom::html::attributes::class("x".into());
+    let node = vdom::node::<()>("div".into(), vec![class], vec![]);
+    assert!(!node.attrs.spilled());
+}
+
+#[test]
+fn test_image_attributes() {
+    use crate::render::to_html;
+    use crate::test::find_by_id;
+
+    let view = image::<()>(
+        vec![id("logo".to_string()), width(px(40))],
+        "/logo.png?w=40&theme=dark".to_string(),
+        String::new(),
+    );
+    let root = layout(vec![], view);
+    let (_, frame) = find_by_id(&root, "logo").unwrap();
+    // The `img` is in the wrapper every child of an `el` gets.
+    let img = match &frame.children[..] {
+        [vdom::NodeType::Node(wrapper)] => match &wrapper.children[..] {
+            [vdom::NodeType::Node(img)] => img,
+            children => panic!("{} children in the wrapper", children.len()),
+        },
+        children => panic!("{} children in the frame", children.len()),
+    };
+    assert_eq!(img.tag, "img");
+    // The `=` in the URL doesn't make it two attributes, and an empty
+    // description is still written.
+    assert_eq!(img.attr("src"), Some("/logo.png?w=40&theme=dark"));
+    assert_eq!(img.attr("alt"), Some(""));
+    assert!(img
+        .attrs
+        .iter()
+        .flat_map(vdom::Attribute::classes)
+        .all(|class| !class.contains('/')));
+    let html = to_html(&root);
+    assert!(
+        html.contains(" src=\"/logo.png?w=40&amp;theme=dark\" alt=\"\">"),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn test_gather_many_attributes() {
+    // Fifty paddings and widths, each with a class, between two classes.
+    // Made without `el`, which would warn about each one overridden.
+    let mut attrs = vec![
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        Attribute::html_class("first".to_string()),
+    ];
+    for n in 1..=50 {
+        attrs.push(padding(n));
+        attrs.push(width(px(n as u64)));
+        attrs.push(Attribute::html_class(format!("c{}", n)));
+    }
+    attrs.push(Attribute::html_class("last".to_string()));
+    let view = element::<()>(
+        LayoutContext::AsEl,
+        NodeName::div(),
+        attrs,
+        Children::Unkeyed(vec![Element::Text("many".to_string())]),
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    // The last padding and width are kept, and the classes are in order.
+    let classes = (1..=50).map(|n| format!("c{}", n)).collect::<Vec<_>>();
+    let expected = format!(
+        "class=\"hc p-50 we width-px-50 s e first {} last\"",
+        classes.join(" ")
     );
+    assert!(html.contains(&expected), "{}", html);
+    assert!(!html.contains("p-49"), "{}", html);
 }
@@ -0,0 +1,471 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{
+    model::Attribute,
+    vdom::{self, Caret, DefaultAction, EventData, Handler, Rate, Wheel},
+};
+
+/// Listen for any `event` on this element, building the message from the
+/// data the backend reports with it.
+pub fn on<Msg, F>(event: String, handler: F) -> Attribute<Msg>
+where
+    F: Fn(&EventData) -> Msg + Send + Sync + 'static,
+{
+    on_filtered(event, move |data| Some(handler(data)))
+}
+
+/// Listen for `event`, only sending a message when `handler` returns one,
+/// e.g. for a particular key.
+pub fn on_filtered<Msg, F>(event: String, handler: F) -> Attribute<Msg>
+where
+    F: Fn(&EventData) -> Option<Msg> + Send + Sync + 'static,
+{
+    Attribute::Event(vdom::on(event, Handler(Arc::new(handler))))
+}
+
+pub fn on_click<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on("click".to_string(), move |_| msg.clone())
+}
+
+/// Listen for the pointer moving onto this element. Unlike hovering over
+/// its children, entering them doesn't send it again.
+pub fn on_mouse_enter<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on("mouseenter".to_string(), move |_| msg.clone())
+}
+
+/// Listen for the pointer moving off this element.
+pub fn on_mouse_leave<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on("mouseleave".to_string(), move |_| msg.clone())
+}
+
+/// Listen for this element getting keyboard focus.
+pub fn on_focus<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on("focus".to_string(), move |_| msg.clone())
+}
+
+/// Listen for this element losing keyboard focus.
+pub fn on_lose_focus<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on("blur".to_string(), move |_| msg.clone())
+}
+
+/// Listen for keys pressed while this element has focus, with the key as
+/// the browser names it, e.g. `a` or `Enter`, and whether `Shift` was
+/// held. Keys `handler` returns `None` for are left alone.
+pub fn on_key_down<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(&str, bool) -> Option<Msg> + Send + Sync + 'static,
+{
+    on_filtered("keydown".to_string(), move |data| match data {
+        EventData::Key { key, shift } => handler(key, *shift),
+        _ => None,
+    })
+}
+
+fn with_rate<Msg>(rate: Rate, attr: Attribute<Msg>) -> Attribute<Msg> {
+    match attr {
+        Attribute::Event(mut listener) => {
+            listener.rate = rate;
+            Attribute::Event(listener)
+        }
+        attr => attr,
+    }
+}
+
+/// Only send the last message of this event listener, once it hasn't
+/// fired for `wait`, e.g. searching once the user stops typing with
+/// `debounced(Duration::from_millis(300), on_input(Msg::Search))`.
+///
+/// The message is held by the runner and sent from `Program::tick`.
+pub fn debounced<Msg>(wait: Duration, attr: Attribute<Msg>) -> Attribute<Msg> {
+    with_rate(Rate::Debounce(wait), attr)
+}
+
+/// Send the messages of this event listener at most once per `gap`, e.g.
+/// for `on_wheel`. Those in between are dropped, except the last, which
+/// is sent once the gap is over.
+pub fn throttled<Msg>(gap: Duration, attr: Attribute<Msg>) -> Attribute<Msg> {
+    with_rate(Rate::Throttle(gap), attr)
+}
+
+/// Have the backend skip its own action for the event of this listener as
+/// `action` says, e.g. the browser following a link, for the listener to
+/// handle it instead.
+pub fn with_default_action<Msg>(
+    action: DefaultAction,
+    attr: Attribute<Msg>,
+) -> Attribute<Msg> {
+    match attr {
+        Attribute::Event(mut listener) => {
+            listener.default = action;
+            Attribute::Event(listener)
+        }
+        attr => attr,
+    }
+}
+
+// What a program has seen of the pointer, to tell its listeners how far
+// it was dragged and how many times in a row it clicked. Backends call
+// `Program::dispatch` once for each node an event bubbles through, deepest
+// first, so an event seen again on an ancestor of the last path is the
+// same one and isn't counted twice.
+#[derive(Default)]
+pub(crate) struct Gestures {
+    // The last event's name, path, data, and the data it was given as.
+    last: Option<(String, Vec<usize>, EventData, EventData)>,
+    // Where the pointer last went down.
+    down: Option<(f32, f32)>,
+    // The clicks so far in one place.
+    clicks: Option<ClickRun>,
+}
+
+// Where a run of clicks was, and when each of them was.
+struct ClickRun {
+    x: f32,
+    y: f32,
+    times: Vec<Duration>,
+}
+
+// How far the pointer can move between the clicks of a double click.
+const CLICK_SLOP: f32 = 4.0;
+
+// The most clicks in a row that are remembered.
+const MAX_CLICKS: usize = 8;
+
+impl Gestures {
+    // The data to give the listeners of `event` on `path` instead of
+    // `data`, with `now` the program's clock.
+    pub(crate) fn see(
+        &mut self,
+        path: &[usize],
+        event: &str,
+        data: &EventData,
+        now: Duration,
+    ) -> EventData {
+        if let Some((name, last, raw, seen)) = &self.last {
+            if name == event
+                && raw == data
+                && path.len() < last.len()
+                && last.starts_with(path)
+            {
+                return seen.clone();
+            }
+        }
+        let seen = match (event, data) {
+            ("pointerdown", EventData::Pointer { x, y }) => {
+                self.down = Some((*x, *y));
+                data.clone()
+            }
+            ("pointerup", EventData::Pointer { x, y }) => EventData::Release {
+                x: *x,
+                y: *y,
+                start: self.down.take(),
+            },
+            ("click", EventData::Pointer { x, y }) => EventData::Click {
+                x: *x,
+                y: *y,
+                gaps: self.click(*x, *y, now),
+            },
+            _ => data.clone(),
+        };
+        self.last = Some((
+            event.to_string(),
+            path.to_vec(),
+            data.clone(),
+            seen.clone(),
+        ));
+        seen
+    }
+
+    // Record a click at `x`, `y`, returning the time between each click
+    // of the run it's part of, oldest first.
+    fn click(&mut self, x: f32, y: f32, now: Duration) -> Vec<Duration> {
+        let run = match self.clicks.as_mut() {
+            Some(run)
+                if (run.x - x).abs() <= CLICK_SLOP
+                    && (run.y - y).abs() <= CLICK_SLOP =>
+            {
+                run
+            }
+            _ => self.clicks.insert(ClickRun {
+                x,
+                y,
+                times: vec![],
+            }),
+        };
+        if run.times.len() == MAX_CLICKS {
+            run.times.remove(0);
+        }
+        run.times.push(now);
+        run.times.windows(2).map(|pair| pair[1] - pair[0]).collect()
+    }
+}
+
+/// Listen for `n` clicks in a row on this element, each no more than
+/// `within` after the one before, as for a triple click selecting a
+/// paragraph. Clicking more times than that doesn't send it again.
+pub fn on_n_click<Msg>(n: usize, within: Duration, msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on_filtered("click".to_string(), move |data| {
+        let gaps = match data {
+            EventData::Click { gaps, .. } => gaps,
+            _ => return None,
+        };
+        let clicks =
+            1 + gaps.iter().rev().take_while(|gap| **gap <= within).count();
+        if clicks == n {
+            Some(msg.clone())
+        } else {
+            None
+        }
+    })
+}
+
+/// Listen for two clicks in a row on this element, no more than 500ms
+/// apart, as for opening a file.
+pub fn on_double_click<Msg>(msg: Msg) -> Attribute<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    on_n_click(2, Duration::from_millis(500), msg)
+}
+
+/// Listen for this element being right-clicked, or long-pressed on a touch
+/// screen, with where the pointer was in the window. The backend keeps the
+/// browser's own menu from opening when a listener sends a message.
+pub fn on_context_menu<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(f32, f32) -> Msg + Send + Sync + 'static,
+{
+    on_filtered("contextmenu".to_string(), move |data| match data {
+        EventData::Pointer { x, y } => Some(handler(*x, *y)),
+        _ => None,
+    })
+}
+
+/// Listen for the wheel or touchpad scrolling over this element, e.g. to
+/// zoom when `Wheel::is_zoom`, or to scroll by something other than the
+/// browser's default.
+pub fn on_wheel<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(&Wheel) -> Msg + Send + Sync + 'static,
+{
+    on_filtered("wheel".to_string(), move |data| match data {
+        EventData::Wheel(wheel) => Some(handler(wheel)),
+        _ => None,
+    })
+}
+
+/// Listen for the caret moving in this text input, as it's typed in or
+/// moved with the keys or pointer.
+pub fn on_caret_move<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(Caret) -> Msg + Send + Sync + 'static,
+{
+    on_filtered("caret".to_string(), move |data| match data {
+        EventData::Caret(caret) => Some(handler(*caret)),
+        _ => None,
+    })
+}
+
+/// Listen for the text of this input changing, with its new text, as it's
+/// typed in or pasted into.
+pub fn on_input<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(String) -> Msg + Send + Sync + 'static,
+{
+    on_input_filtered(move |text| Some(handler(text)))
+}
+
+/// Like `on_input`, but only sending a message when `handler` returns one,
+/// e.g. once the text parses as a number.
+pub fn on_input_filtered<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(String) -> Option<Msg> + Send + Sync + 'static,
+{
+    on_filtered("input".to_string(), move |data| match data {
+        EventData::Text(text) => handler(text.clone()),
+        _ => None,
+    })
+}
+
+/// Listen for this element scrolling into or out of view. The backend
+/// reports `intersect` with how much of it is showing whenever that
+/// changes.
+pub fn on_intersect<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(f32) -> Msg + Send + Sync + 'static,
+{
+    on_intersect_filtered(move |ratio| Some(handler(ratio)))
+}
+
+/// Like `on_intersect`, but only sending a message when `handler` returns
+/// one, e.g. once enough of the element is showing.
+pub fn on_intersect_filtered<Msg, F>(handler: F) -> Attribute<Msg>
+where
+    F: Fn(f32) -> Option<Msg> + Send + Sync + 'static,
+{
+    on_filtered("intersect".to_string(), move |data| match data {
+        EventData::Intersection { ratio } => handler(*ratio),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_click_gaps() {
+    let ms = Duration::from_millis;
+    let mut gestures = Gestures::default();
+    let mut click = |path: &[usize], x, at| {
+        let data = EventData::Pointer { x, y: 10.0 };
+        match gestures.see(path, "click", &data, ms(at)) {
+            EventData::Click { gaps, .. } => gaps,
+            data => panic!("{:?}", data),
+        }
+    };
+    assert_eq!(click(&[0, 1], 10.0, 1000), vec![]);
+    // Bubbling to an ancestor is the same click.
+    assert_eq!(click(&[0], 10.0, 1000), vec![]);
+    assert_eq!(click(&[0, 1], 11.0, 1300), vec![ms(300)]);
+    assert_eq!(click(&[0, 1], 11.0, 2500), vec![ms(300), ms(1200)]);
+    // Somewhere else starts again.
+    assert_eq!(click(&[0, 1], 50.0, 2600), vec![]);
+}
+
+#[test]
+fn test_on_n_click() {
+    use crate::{
+        cmd::Cmd,
+        element::{el, id},
+        model::Element,
+        test::TestHarness,
+    };
+
+    let view = |_: &u32| {
+        el(
+            vec![id("target".into()), on_double_click(1)],
+            Element::Text("double".into()),
+        )
+    };
+    let update = |count: &mut u32, msg: u32| {
+        *count += msg;
+        Cmd::none()
+    };
+    let mut harness = TestHarness::new(|| 0, update, view).click("target");
+    harness.program().tick(Duration::from_millis(300));
+    let mut harness = harness.click("target");
+    assert_eq!(*harness.model(), 1);
+
+    // A third click isn't another double click, and a slow one isn't one.
+    harness.program().tick(Duration::from_millis(300));
+    let mut harness = harness.click("target");
+    harness.program().tick(Duration::from_millis(600));
+    let harness = harness.click("target");
+    assert_eq!(*harness.model(), 1);
+
+    // Each program counts its own clicks.
+    let harness = TestHarness::new(|| 0, update, view).click("target");
+    assert_eq!(*harness.model(), 0);
+}
+
+#[test]
+fn test_wheel() {
+    use crate::vdom::DeltaUnit;
+
+    let wheel = Wheel {
+        dx: 0.0,
+        dy: 3.0,
+        unit: DeltaUnit::Line,
+        ctrl: true,
+        shift: false,
+        alt: false,
+    };
+    assert_eq!(wheel.pixels(16.0, 600.0), (0.0, 48.0));
+    assert!(wheel.is_zoom());
+    let listener = match on_wheel(|wheel: &Wheel| wheel.dy) {
+        Attribute::Event(listener) => listener,
+        _ => unreachable!(),
+    };
+    assert_eq!((listener.handler.0)(&EventData::Wheel(wheel)), Some(3.0));
+    assert_eq!((listener.handler.0)(&EventData::None), None);
+}
+
+#[test]
+fn test_events() {
+    use crate::app::Program;
+    use crate::cmd::Cmd;
+    use crate::element::el;
+    use crate::focus::focusable;
+    use crate::model::Element;
+
+    #[derive(Debug, PartialEq, Clone)]
+    enum Msg {
+        Enter,
+        Leave,
+        Focus,
+        Blur,
+        Input(String),
+        Submit,
+    }
+
+    let mut program = Program::new(
+        Vec::new,
+        |msgs: &mut Vec<Msg>, msg| {
+            msgs.push(msg);
+            Cmd::none()
+        },
+        |_| {
+            el(
+                vec![
+                    focusable(),
+                    on_mouse_enter(Msg::Enter),
+                    on_mouse_leave(Msg::Leave),
+                    on_focus(Msg::Focus),
+                    on_lose_focus(Msg::Blur),
+                    on_input(Msg::Input),
+                    on_key_down(|key, _| match key {
+                        "Enter" => Some(Msg::Submit),
+                        _ => None,
+                    }),
+                ],
+                Element::Empty,
+            )
+        },
+    );
+    let path = [0, 2, 0];
+    let mut msgs = program.dispatch(&path, "mouseenter", &EventData::None);
+    msgs.extend(program.focus(&path));
+    let text = EventData::Text("hi".to_string());
+    msgs.extend(program.dispatch(&path, "input", &text));
+    msgs.extend(program.key_down("a", false));
+    msgs.extend(program.key_down("Enter", false));
+    msgs.extend(program.focus(&[]));
+    msgs.extend(program.dispatch(&path, "mouseleave", &EventData::None));
+    assert_eq!(
+        msgs,
+        vec![
+            Msg::Enter,
+            Msg::Focus,
+            Msg::Input("hi".to_string()),
+            Msg::Submit,
+            Msg::Blur,
+            Msg::Leave,
+        ]
+    );
+}
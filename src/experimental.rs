@@ -0,0 +1,14 @@
+//! Subsystems still finding their shape, whose APIs can change in any
+//! release: the backends showing views, the layout solver, frame budgeting,
+//! and tooling. Only public with the `experimental` feature, so depending
+//! on them is a choice made in `Cargo.toml` rather than by accident.
+
+pub use crate::{
+    anchor, backend, budget, haptic, solver, sound, storage, stress, tokens,
+};
+
+#[cfg(feature = "bevy_ui")]
+pub use crate::{bevy_render, devtools};
+
+#[cfg(feature = "golden")]
+pub use crate::golden;
@@ -1,43 +1,50 @@
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub struct Field(pub u32, pub u32);
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub struct Field(pub u32, pub u32, pub u32);
 
 impl Field {
-    pub fn none() -> Self {
-        Self(0, 0)
+    pub const fn none() -> Self {
+        Self(0, 0, 0)
     }
     pub fn merge(&mut self, field: Self) -> () {
         self.0 |= field.0;
         self.1 |= field.1;
+        self.2 |= field.2;
     }
     pub fn add(&mut self, flag: &Flag) -> () {
         match flag {
             Flag::Flag(first) => self.0 |= first,
             Flag::Second(second) => self.1 |= second,
+            Flag::Third(third) => self.2 |= third,
         };
     }
     pub fn present(&self, flag: &Flag) -> bool {
         match flag {
             Flag::Flag(first) => (first & self.0) == *first,
             Flag::Second(second) => (second & self.1) == *second,
+            Flag::Third(third) => (third & self.2) == *third,
         }
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum Flag {
     Flag(u32),
     Second(u32),
+    Third(u32),
 }
 
 impl Flag {
     pub fn value(&self) -> u32 {
         match self {
-            Self::Flag(first) => (*first as f32).log2().round() as u32,
-            Self::Second(second) => (*second as f32).log2().round() as u32 + 32,
+            Self::Flag(first) => first.trailing_zeros(),
+            Self::Second(second) => second.trailing_zeros() + 32,
+            Self::Third(third) => third.trailing_zeros() + 64,
         }
     }
     pub fn from(i: u32) -> Self {
-        if i > 31 {
+        if i > 63 {
+            Self::Third(1 << (i - 64))
+        } else if i > 31 {
             Self::Second(1 << (i - 32))
         } else {
             Self::Flag(1 << i)
@@ -45,145 +52,205 @@ impl Flag {
     }
     // Used for Style invalidation
     pub const fn transparency() -> Flag {
-        Flag::Flag(1)
+        Flag::Flag(1 << 1)
     }
     pub const fn padding() -> Flag {
-        Flag::Flag(2)
+        Flag::Flag(1 << 2)
     }
     pub const fn spacing() -> Flag {
-        Flag::Flag(3)
+        Flag::Flag(1 << 3)
     }
     pub const fn font_size() -> Flag {
-        Flag::Flag(4)
+        Flag::Flag(1 << 4)
     }
     pub const fn font_family() -> Flag {
-        Flag::Flag(5)
+        Flag::Flag(1 << 5)
     }
     pub const fn width() -> Flag {
-        Flag::Flag(6)
+        Flag::Flag(1 << 6)
     }
     pub const fn height() -> Flag {
-        Flag::Flag(7)
+        Flag::Flag(1 << 7)
     }
     pub const fn bg_color() -> Flag {
-        Flag::Flag(8)
+        Flag::Flag(1 << 8)
     }
     pub const fn bg_image() -> Flag {
-        Flag::Flag(9)
+        Flag::Flag(1 << 9)
     }
     pub const fn bg_gradient() -> Flag {
-        Flag::Flag(10)
+        Flag::Flag(1 << 10)
     }
     pub const fn border_style() -> Flag {
-        Flag::Flag(11)
+        Flag::Flag(1 << 11)
     }
     pub const fn font_alignment() -> Flag {
-        Flag::Flag(12)
+        Flag::Flag(1 << 12)
     }
     pub const fn font_weight() -> Flag {
-        Flag::Flag(13)
+        Flag::Flag(1 << 13)
     }
     pub const fn font_color() -> Flag {
-        Flag::Flag(14)
+        Flag::Flag(1 << 14)
     }
     pub const fn font_spacing() -> Flag {
-        Flag::Flag(15)
+        Flag::Flag(1 << 15)
     }
     pub const fn letter_spacing() -> Flag {
-        Flag::Flag(16)
+        Flag::Flag(1 << 16)
     }
     pub const fn border_rount() -> Flag {
-        Flag::Flag(17)
+        Flag::Flag(1 << 17)
     }
     pub const fn text_shadows() -> Flag {
-        Flag::Flag(18)
+        Flag::Flag(1 << 18)
     }
     pub const fn shadows() -> Flag {
-        Flag::Flag(19)
+        Flag::Flag(1 << 19)
     }
     pub const fn overflow() -> Flag {
-        Flag::Flag(20)
+        Flag::Flag(1 << 20)
     }
     pub const fn cursor() -> Flag {
-        Flag::Flag(21)
+        Flag::Flag(1 << 21)
     }
     pub const fn scale() -> Flag {
-        Flag::Flag(23)
+        Flag::Flag(1 << 23)
     }
     pub const fn rotate() -> Flag {
-        Flag::Flag(24)
+        Flag::Flag(1 << 24)
     }
     pub const fn move_x() -> Flag {
-        Flag::Flag(25)
+        Flag::Flag(1 << 25)
     }
     pub const fn move_y() -> Flag {
-        Flag::Flag(26)
+        Flag::Flag(1 << 26)
     }
     pub const fn border_width() -> Flag {
-        Flag::Flag(27)
+        Flag::Flag(1 << 27)
     }
     pub const fn border_color() -> Flag {
-        Flag::Flag(28)
+        Flag::Flag(1 << 28)
     }
     pub const fn align_y() -> Flag {
-        Flag::Flag(29)
+        Flag::Flag(1 << 29)
     }
     pub const fn align_x() -> Flag {
-        Flag::Flag(30)
+        Flag::Flag(1 << 30)
     }
     pub const fn focus() -> Flag {
-        Flag::Flag(31)
+        Flag::Flag(1 << 31)
     }
     pub const fn active() -> Flag {
-        Flag::Flag(32)
+        Flag::Second(1 << 0)
     }
     pub const fn hover() -> Flag {
-        Flag::Flag(33)
+        Flag::Second(1 << 1)
     }
     pub const fn grid_template() -> Flag {
-        Flag::Flag(34)
+        Flag::Second(1 << 2)
     }
     pub const fn grid_position() -> Flag {
-        Flag::Flag(35)
+        Flag::Second(1 << 3)
     }
     // Notes
     pub const fn height_content() -> Flag {
-        Flag::Flag(36)
+        Flag::Second(1 << 4)
     }
     pub const fn height_fill() -> Flag {
-        Flag::Flag(37)
+        Flag::Second(1 << 5)
     }
     pub const fn width_content() -> Flag {
-        Flag::Flag(38)
+        Flag::Second(1 << 6)
     }
     pub const fn width_fill() -> Flag {
-        Flag::Flag(39)
+        Flag::Second(1 << 7)
     }
     pub const fn align_right() -> Flag {
-        Flag::Flag(40)
+        Flag::Second(1 << 8)
     }
     pub const fn align_bottom() -> Flag {
-        Flag::Flag(41)
+        Flag::Second(1 << 9)
     }
     pub const fn center_x() -> Flag {
-        Flag::Flag(42)
+        Flag::Second(1 << 10)
     }
     pub const fn center_y() -> Flag {
-        Flag::Flag(43)
+        Flag::Second(1 << 11)
     }
     pub const fn width_between() -> Flag {
-        Flag::Flag(44)
+        Flag::Second(1 << 12)
     }
     pub const fn height_between() -> Flag {
-        Flag::Flag(45)
+        Flag::Second(1 << 13)
     }
     pub const fn behind() -> Flag {
-        Flag::Flag(46)
+        Flag::Second(1 << 14)
     }
     pub const fn height_text_area_content() -> Flag {
-        Flag::Flag(47)
+        Flag::Second(1 << 15)
     }
     pub const fn font_variant() -> Flag {
-        Flag::Flag(48)
+        Flag::Second(1 << 16)
+    }
+    pub const fn will_change() -> Flag {
+        Flag::Second(1 << 17)
+    }
+    pub const fn focus_ring() -> Flag {
+        Flag::Second(1 << 18)
+    }
+    pub const fn before() -> Flag {
+        Flag::Second(1 << 19)
+    }
+    pub const fn after() -> Flag {
+        Flag::Second(1 << 20)
+    }
+    pub const fn content() -> Flag {
+        Flag::Second(1 << 21)
+    }
+    pub const fn counter_reset() -> Flag {
+        Flag::Second(1 << 22)
+    }
+    pub const fn counter_increment() -> Flag {
+        Flag::Second(1 << 23)
+    }
+    pub const fn blend_mode() -> Flag {
+        Flag::Second(1 << 24)
+    }
+    pub const fn bg_blend_mode() -> Flag {
+        Flag::Second(1 << 25)
+    }
+    pub const fn isolate() -> Flag {
+        Flag::Second(1 << 26)
+    }
+    pub const fn clip_path() -> Flag {
+        Flag::Second(1 << 27)
+    }
+    pub const fn grid_areas() -> Flag {
+        Flag::Second(1 << 28)
+    }
+    pub const fn overscroll() -> Flag {
+        Flag::Second(1 << 29)
+    }
+    pub const fn transition() -> Flag {
+        Flag::Flag(1 << 22)
+    }
+    pub const fn pointer_events() -> Flag {
+        Flag::Flag(1 << 0)
+    }
+    pub const fn scroll_snap() -> Flag {
+        Flag::Second(1 << 30)
+    }
+    pub const fn snap_align() -> Flag {
+        Flag::Second(1 << 31)
+    }
+    pub const fn order() -> Flag {
+        Flag::Third(1 << 0)
+    }
+    pub const fn align_self() -> Flag {
+        Flag::Third(1 << 1)
+    }
+    pub const fn text_columns() -> Flag {
+        Flag::Third(1 << 2)
     }
 }
@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+
+use crate::{
+    model::Attribute,
+    vdom::{self, Node, NodeType, Rects},
+};
+
+/// Which arrow keys move focus within a `roving_group`.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Orientation {
+    /// Left and right, e.g. for a toolbar.
+    Horizontal,
+    /// Up and down, e.g. for a menu.
+    Vertical,
+    /// All four, e.g. for a grid of swatches.
+    Both,
+}
+
+impl Orientation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Horizontal => "horizontal",
+            Orientation::Vertical => "vertical",
+            Orientation::Both => "both",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "horizontal" => Some(Orientation::Horizontal),
+            "vertical" => Some(Orientation::Vertical),
+            "both" => Some(Orientation::Both),
+            _ => None,
+        }
+    }
+}
+
+/// Let this element take keyboard focus, in document order.
+pub fn focusable<Msg>() -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property("tabindex", "0"))
+}
+
+/// Visit this element before those without an order, in the order of `n`,
+/// when tabbing or when a gamepad first takes focus. Ties, and elements
+/// without one, go in document order.
+pub fn focus_order<Msg>(n: u32) -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property("data-focus-order", n))
+}
+
+/// A direction to move focus in by where elements are on screen, e.g. with
+/// a gamepad's D-pad.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum NavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Input from a controller, for moving around a UI without a pointer or
+/// keyboard.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum NavInput {
+    /// Move focus to the nearest element that way.
+    Move(NavDirection),
+    /// Click the focused element, e.g. with the A button.
+    Activate,
+    /// Press `Escape` on the focused element, e.g. with the B button, to
+    /// close a menu or dialog.
+    Back,
+}
+
+/// Make the focusable elements inside this one a single stop in the tab
+/// order, moving between them with the arrow keys. Tabbing back into the
+/// group returns to the one focused last.
+///
+/// Toolbars, menus and radio groups should all be built with this, so
+/// they behave the same way.
+pub fn roving_group<Msg>(orientation: Orientation) -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property(
+        "data-roving",
+        orientation.as_str(),
+    ))
+}
+
+/// While `active`, keep `Tab` and `Shift+Tab` cycling through the
+/// focusable elements inside this one, e.g. for a modal dialog. Focus
+/// moves inside when the trap becomes active, and goes back to the
+/// element that had it when the trap is deactivated or removed.
+pub fn focus_trap<Msg>(active: bool) -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property("data-focus-trap", active))
+}
+
+fn attr<'a, Msg>(node: &'a Node<Msg>, name: &str) -> Option<&'a str> {
+    node.attr(name)
+}
+
+fn is_focusable<Msg>(node: &Node<Msg>) -> bool {
+    match attr(node, "tabindex") {
+        Some(index) => index.parse::<i32>().is_ok_and(|i| i >= 0),
+        None => matches!(
+            node.tag.as_str(),
+            "a" | "button" | "input" | "textarea" | "select"
+        ),
+    }
+}
+
+// A focusable node, and the roving group it's in if any.
+struct Stop {
+    path: Vec<usize>,
+    group: Option<Vec<usize>>,
+    order: Option<u32>,
+}
+
+fn collect_stops<Msg>(
+    node: &Node<Msg>,
+    path: &mut Vec<usize>,
+    group: &Option<Vec<usize>>,
+    groups: &mut HashMap<Vec<usize>, Orientation>,
+    stops: &mut Vec<Stop>,
+) {
+    let group = match attr(node, "data-roving").and_then(Orientation::parse) {
+        Some(orientation) => {
+            groups.insert(path.clone(), orientation);
+            Some(path.clone())
+        }
+        None => group.clone(),
+    };
+    // A group's own node isn't one of its members.
+    if is_focusable(node) && group.as_ref() != Some(path) {
+        stops.push(Stop {
+            path: path.clone(),
+            group: group.clone(),
+            order: attr(node, "data-focus-order").and_then(|o| o.parse().ok()),
+        });
+    }
+    for (i, child) in node.children.iter().enumerate() {
+        if let NodeType::Node(n) | NodeType::KeyedNode(_, n) = child {
+            path.push(i);
+            collect_stops(n, path, &group, groups, stops);
+            path.pop();
+        }
+    }
+}
+
+// The last active trap in the document, so a trap opened from inside
+// another takes over from it.
+fn find_trap<Msg>(
+    node: &Node<Msg>,
+    path: &mut Vec<usize>,
+    found: &mut Option<Vec<usize>>,
+) {
+    if attr(node, "data-focus-trap") == Some("true") {
+        *found = Some(path.clone());
+    }
+    for (i, child) in node.children.iter().enumerate() {
+        if let NodeType::Node(n) | NodeType::KeyedNode(_, n) = child {
+            path.push(i);
+            find_trap(n, path, found);
+            path.pop();
+        }
+    }
+}
+
+fn node_at_mut<'a, Msg>(
+    node: &'a mut Node<Msg>,
+    path: &[usize],
+) -> Option<&'a mut Node<Msg>> {
+    match path {
+        [] => Some(node),
+        [i, rest @ ..] => match node.children.get_mut(*i) {
+            Some(NodeType::Node(n)) | Some(NodeType::KeyedNode(_, n)) => {
+                node_at_mut(n, rest)
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Which node has keyboard focus, by its path from the root of the view,
+/// and the member of each roving group that was focused last.
+#[derive(Debug, Default, Clone)]
+pub struct FocusState {
+    focused: Option<Vec<usize>>,
+    roving: HashMap<Vec<usize>, Vec<usize>>,
+    // The active focus trap, and what had focus before it was activated.
+    trap: Option<(Vec<usize>, Option<Vec<usize>>)>,
+}
+
+impl FocusState {
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focused.as_deref()
+    }
+
+    /// Move focus to the node at `path`, e.g. when it's clicked.
+    pub fn focus<Msg>(&mut self, root: &Node<Msg>, path: &[usize]) {
+        let (stops, _) = stops(root);
+        if let Some(group) = stops
+            .iter()
+            .find(|s| s.path == path)
+            .and_then(|s| s.group.clone())
+        {
+            self.roving.insert(group, path.to_vec());
+        }
+        self.focused = Some(path.to_vec());
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    // The node a roving group is tabbed into: the one focused last if it's
+    // still there, otherwise the first.
+    fn group_stop<'a>(
+        &self,
+        stops: &'a [Stop],
+        group: &[usize],
+    ) -> Option<&'a Stop> {
+        let members =
+            || stops.iter().filter(|s| s.group.as_deref() == Some(group));
+        self.roving
+            .get(group)
+            .and_then(|last| members().find(|s| &s.path == last))
+            .or_else(|| members().next())
+    }
+
+    /// Move focus in response to a key press, returning whether it did.
+    /// `Tab` moves through the tab order, and the arrow keys, `Home` and
+    /// `End` move within a roving group.
+    pub fn key_down<Msg>(
+        &mut self,
+        root: &Node<Msg>,
+        key: &str,
+        shift: bool,
+    ) -> bool {
+        let (stops, groups) = stops(root);
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|f| stops.iter().position(|s| &s.path == f));
+        let next = match key {
+            "Tab" => {
+                // Each roving group is a single stop.
+                let trap = self.trap.as_ref().map(|(trap, _)| trap);
+                let order: Vec<&Stop> = stops
+                    .iter()
+                    .filter(|s| trap.is_none_or(|t| s.path.starts_with(t)))
+                    .filter(|s| match &s.group {
+                        None => true,
+                        // Focusing a member makes it the group's stop.
+                        Some(group) => {
+                            self.group_stop(&stops, group).map(|g| &g.path)
+                                == Some(&s.path)
+                        }
+                    })
+                    .collect();
+                if order.is_empty() {
+                    return false;
+                }
+                let at = current.and_then(|c| {
+                    order.iter().position(|s| s.path == stops[c].path)
+                });
+                let n = order.len();
+                let i = match (at, shift) {
+                    (Some(i), false) => (i + 1) % n,
+                    (Some(i), true) => (i + n - 1) % n,
+                    (None, false) => 0,
+                    (None, true) => n - 1,
+                };
+                order[i].path.clone()
+            }
+            _ => {
+                let current = match current {
+                    Some(c) => &stops[c],
+                    None => return false,
+                };
+                let group = match &current.group {
+                    Some(group) => group,
+                    None => return false,
+                };
+                let orientation = groups[group];
+                let horizontal = orientation != Orientation::Vertical;
+                let vertical = orientation != Orientation::Horizontal;
+                let members: Vec<&Stop> = stops
+                    .iter()
+                    .filter(|s| s.group.as_ref() == Some(group))
+                    .collect();
+                let n = members.len();
+                let i = members
+                    .iter()
+                    .position(|s| s.path == current.path)
+                    .unwrap_or(0);
+                let i = match key {
+                    "ArrowRight" if horizontal => (i + 1) % n,
+                    "ArrowDown" if vertical => (i + 1) % n,
+                    "ArrowLeft" if horizontal => (i + n - 1) % n,
+                    "ArrowUp" if vertical => (i + n - 1) % n,
+                    "Home" => 0,
+                    "End" => n - 1,
+                    _ => return false,
+                };
+                members[i].path.clone()
+            }
+        };
+        self.focus(root, &next);
+        true
+    }
+
+    /// Move focus to the nearest focusable element in `direction` from the
+    /// focused one, by where they were laid out, returning whether it
+    /// moved. With nothing focused, the first element in focus order is.
+    ///
+    /// Elements are only reached within the active focus trap, and each
+    /// roving group is entered at the member focused last.
+    pub fn move_spatially<Msg>(
+        &mut self,
+        root: &Node<Msg>,
+        rects: &Rects,
+        direction: NavDirection,
+    ) -> bool {
+        let (stops, _) = stops(root);
+        let trap = self.trap.as_ref().map(|(trap, _)| trap);
+        let current = self.focused.clone();
+        let candidates = stops.iter().filter(|s| {
+            trap.is_none_or(|t| s.path.starts_with(t))
+                && Some(&s.path) != current.as_ref()
+                // Other members of the focused one's group are reached
+                // with it, but other groups only by their current stop.
+                && match &s.group {
+                    None => true,
+                    Some(group) => {
+                        let focused_group = current.as_ref().and_then(|c| {
+                            stops.iter().find(|s| &s.path == c)?.group.as_ref()
+                        });
+                        focused_group == Some(group)
+                            || self.group_stop(&stops, group).map(|g| &g.path)
+                                == Some(&s.path)
+                    }
+                }
+        });
+        let from = match current.as_ref().and_then(|c| rects.get(c)) {
+            Some(from) => *from,
+            None => {
+                let first = candidates.map(|s| s.path.clone()).next();
+                return match first {
+                    Some(path) => {
+                        self.focus(root, &path);
+                        true
+                    }
+                    None => false,
+                };
+            }
+        };
+        let (fx, fy) = from.center();
+        let best = candidates
+            .filter_map(|s| {
+                let rect = rects.get(&s.path)?;
+                let (x, y) = rect.center();
+                // How far it is that way, and how far off to the side.
+                let (along, across) = match direction {
+                    NavDirection::Right => (x - fx, y - fy),
+                    NavDirection::Left => (fx - x, y - fy),
+                    NavDirection::Down => (y - fy, x - fx),
+                    NavDirection::Up => (fy - y, x - fx),
+                };
+                if along <= 0.0 {
+                    return None;
+                }
+                // Straight ahead beats nearer but off to the side.
+                Some((along + across.abs() * 2.0, s))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((_, stop)) => {
+                let path = stop.path.clone();
+                self.focus(root, &path);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Bring the focus up to date with a newly rendered view: following
+    /// focus traps that have been activated or deactivated, and giving
+    /// only one member of each roving group a `tabindex` of `0`, so a host
+    /// that handles `Tab` itself still treats the group as one stop.
+    ///
+    /// Focus moved by a trap doesn't send `focus` or `blur` messages.
+    pub fn sync<Msg>(&mut self, root: &mut Node<Msg>) {
+        let mut trap = None;
+        find_trap(root, &mut vec![], &mut trap);
+        match (trap, self.trap.take()) {
+            (Some(trap), Some((old, invoker))) if trap == old => {
+                self.trap = Some((trap, invoker));
+            }
+            (Some(trap), old) => {
+                // A trap replacing another hands back to the same invoker.
+                let invoker = match old {
+                    Some((_, invoker)) => invoker,
+                    None => self.focused.clone(),
+                };
+                let inside =
+                    self.focused.as_ref().is_some_and(|f| f.starts_with(&trap));
+                if !inside {
+                    let (stops, _) = stops(root);
+                    if let Some(first) =
+                        stops.iter().find(|s| s.path.starts_with(&trap))
+                    {
+                        let path = first.path.clone();
+                        self.focus(root, &path);
+                    }
+                }
+                self.trap = Some((trap, invoker));
+            }
+            (None, Some((_, invoker))) => {
+                self.focused = invoker;
+            }
+            (None, None) => {}
+        }
+        self.apply_roving(root);
+    }
+
+    fn apply_roving<Msg>(&self, root: &mut Node<Msg>) {
+        let (stops, groups) = stops(root);
+        for group in groups.keys() {
+            let current =
+                self.group_stop(&stops, group).map(|s| s.path.clone());
+            for stop in stops.iter().filter(|s| s.group.as_ref() == Some(group))
+            {
+                let index = if Some(&stop.path) == current.as_ref() {
+                    "0"
+                } else {
+                    "-1"
+                };
+                if let Some(node) = node_at_mut(root, &stop.path) {
+                    node.attrs.retain(|a| {
+                        !matches!(a, vdom::Attribute::Property(k, _) if k == "tabindex")
+                    });
+                    node.attrs
+                        .push(vdom::Attribute::property("tabindex", index));
+                }
+            }
+        }
+    }
+}
+
+fn stops<Msg>(
+    root: &Node<Msg>,
+) -> (Vec<Stop>, HashMap<Vec<usize>, Orientation>) {
+    let mut groups = HashMap::new();
+    let mut stops = vec![];
+    collect_stops(root, &mut vec![], &None, &mut groups, &mut stops);
+    // Stable, so the rest keep to document order.
+    stops.sort_by_key(|s| s.order.unwrap_or(u32::MAX));
+    (stops, groups)
+}
+
+#[test]
+fn test_roving_group() {
+    use crate::element::{el, layout, row};
+    use crate::model::Element;
+
+    let button = |label: &str| {
+        el::<()>(vec![focusable()], Element::Text(label.to_string()))
+    };
+    let node = layout(
+        vec![],
+        row(
+            vec![],
+            vec![
+                button("Back"),
+                row(
+                    vec![roving_group(Orientation::Horizontal)],
+                    vec![button("Bold"), button("Italic"), button("Underline")],
+                ),
+                button("Done"),
+            ],
+        ),
+    );
+    let label = |focus: &FocusState| {
+        let (stops, _) = stops(&node);
+        stops
+            .iter()
+            .position(|s| Some(s.path.as_slice()) == focus.focused())
+    };
+
+    let mut focus = FocusState::default();
+    focus.key_down(&node, "Tab", false);
+    assert_eq!(label(&focus), Some(0));
+    focus.key_down(&node, "Tab", false);
+    assert_eq!(label(&focus), Some(1));
+    focus.key_down(&node, "ArrowRight", false);
+    focus.key_down(&node, "ArrowRight", false);
+    assert_eq!(label(&focus), Some(3));
+    assert!(!focus.key_down(&node, "ArrowUp", false));
+
+    // The group is left and re-entered at the member focused last.
+    focus.key_down(&node, "Tab", false);
+    assert_eq!(label(&focus), Some(4));
+    focus.key_down(&node, "Tab", true);
+    assert_eq!(label(&focus), Some(3));
+}
+
+#[test]
+fn test_focus_trap() {
+    use crate::element::{el, layout, row};
+    use crate::model::Element;
+
+    let view = |open: bool| {
+        let button = |label: &str| {
+            el::<()>(vec![focusable()], Element::Text(label.to_string()))
+        };
+        layout(
+            vec![],
+            row(
+                vec![],
+                vec![
+                    button("Open"),
+                    row(
+                        vec![focus_trap(open)],
+                        vec![button("OK"), button("Cancel")],
+                    ),
+                ],
+            ),
+        )
+    };
+    let (stops, _) = stops(&view(false));
+    let at = |focus: &FocusState| {
+        stops
+            .iter()
+            .position(|s| Some(s.path.as_slice()) == focus.focused())
+    };
+
+    let mut focus = FocusState::default();
+    let mut closed = view(false);
+    focus.key_down(&closed, "Tab", false);
+    assert_eq!(at(&focus), Some(0));
+
+    let mut open = view(true);
+    focus.sync(&mut open);
+    assert_eq!(at(&focus), Some(1));
+    focus.key_down(&open, "Tab", false);
+    focus.key_down(&open, "Tab", false);
+    assert_eq!(at(&focus), Some(1));
+
+    focus.sync(&mut closed);
+    assert_eq!(at(&focus), Some(0));
+}
+
+#[test]
+fn test_move_spatially() {
+    use crate::element::{column, el, layout, row};
+    use crate::model::Element;
+    use crate::vdom::Rect;
+
+    let button = |label: &str, attrs: Vec<Attribute<()>>| {
+        let mut attrs = attrs;
+        attrs.push(focusable());
+        el(attrs, Element::Text(label.to_string()))
+    };
+    // Play and Quit on top, with Options under Play.
+    let node = layout(
+        vec![],
+        column(
+            vec![],
+            vec![
+                row(
+                    vec![],
+                    vec![
+                        button("Play", vec![]),
+                        button("Quit", vec![focus_order(0)]),
+                    ],
+                ),
+                button("Options", vec![]),
+            ],
+        ),
+    );
+    let (stops, _) = stops(&node);
+    let paths: Vec<_> = stops.iter().map(|s| s.path.clone()).collect();
+    // Quit comes first, by its order.
+    let (quit, play, options) = (&paths[0], &paths[1], &paths[2]);
+    let rect = |x, y| Rect {
+        x,
+        y,
+        width: 100.0,
+        height: 40.0,
+    };
+    let rects: Rects = vec![
+        (play.clone(), rect(0.0, 0.0)),
+        (quit.clone(), rect(120.0, 0.0)),
+        (options.clone(), rect(10.0, 60.0)),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut focus = FocusState::default();
+    assert!(focus.move_spatially(&node, &rects, NavDirection::Down));
+    assert_eq!(focus.focused(), Some(quit.as_slice()));
+    focus.move_spatially(&node, &rects, NavDirection::Left);
+    assert_eq!(focus.focused(), Some(play.as_slice()));
+    focus.move_spatially(&node, &rects, NavDirection::Down);
+    assert_eq!(focus.focused(), Some(options.as_slice()));
+    assert!(!focus.move_spatially(&node, &rects, NavDirection::Down));
+    focus.move_spatially(&node, &rects, NavDirection::Right);
+    assert_eq!(focus.focused(), Some(quit.as_slice()));
+}
@@ -0,0 +1,327 @@
+use crate::{
+    flag::Flag,
+    model::{Attribute, Color, FloatClass, Font, Shadow, Style, Variant},
+    style::Classes,
+};
+
+pub fn color<Msg>(color: Color) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::font_color(),
+        Style::Colored(
+            format!("fc-{}", color.format_color_class()),
+            "color".into(),
+            color,
+        ),
+    )
+}
+
+/// Font size in pixels, up to 255.
+pub fn size<Msg>(size: u32) -> Attribute<Msg> {
+    Attribute::Style(Flag::font_size(), Style::FontSize(size.min(255) as u8))
+}
+
+/// The fonts to use, in order of preference, e.g.
+/// `family(vec![typeface("Helvetica"), sans_serif()])`.
+pub fn family<Msg>(families: Vec<Font>) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::font_family(),
+        Style::FontFamily(
+            families.iter().fold(String::from("ff-"), |current, font| {
+                font.render_class_name(current)
+            }),
+            families,
+        ),
+    )
+}
+
+/// A font installed on the user's machine, or loaded by the page.
+pub fn typeface(name: &str) -> Font {
+    Font::Typeface(name.to_string())
+}
+
+pub fn serif() -> Font {
+    Font::Serif
+}
+
+pub fn sans_serif() -> Font {
+    Font::SansSerif
+}
+
+pub fn monospace() -> Font {
+    Font::Monospace
+}
+
+/// A font loaded from the stylesheet at `url`, e.g. one from Google Fonts.
+pub fn external(name: &str, url: &str) -> Font {
+    Font::ImportFont(name.to_string(), url.to_string())
+}
+
+pub fn align_left<Msg>() -> Attribute<Msg> {
+    alignment(Classes::TextLeft)
+}
+
+pub fn align_right<Msg>() -> Attribute<Msg> {
+    alignment(Classes::TextRight)
+}
+
+pub fn center<Msg>() -> Attribute<Msg> {
+    alignment(Classes::TextCenter)
+}
+
+pub fn justify<Msg>() -> Attribute<Msg> {
+    alignment(Classes::TextJustify)
+}
+
+fn alignment<Msg>(class: Classes) -> Attribute<Msg> {
+    Attribute::Class(Flag::font_alignment(), class.to_string().to_string())
+}
+
+/// Extra space between letters, in pixels.
+pub fn letter_spacing<Msg>(offset: f32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::letter_spacing(),
+        Style::Single(
+            format!("ls-{}", offset.float_class()),
+            "letter-spacing".into(),
+            format!("{}px", offset),
+        ),
+    )
+}
+
+/// Extra space between words, in pixels.
+pub fn word_spacing<Msg>(offset: f32) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::font_spacing(),
+        Style::Single(
+            format!("ws-{}", offset.float_class()),
+            "word-spacing".into(),
+            format!("{}px", offset),
+        ),
+    )
+}
+
+pub fn underline<Msg>() -> Attribute<Msg> {
+    Attribute::html_class(Classes::Underline.to_string().to_string())
+}
+
+pub fn strike<Msg>() -> Attribute<Msg> {
+    Attribute::html_class(Classes::Strike.to_string().to_string())
+}
+
+pub fn italic<Msg>() -> Attribute<Msg> {
+    Attribute::html_class(Classes::Italic.to_string().to_string())
+}
+
+/// Undo an `italic` from a parent.
+pub fn unitalicized<Msg>() -> Attribute<Msg> {
+    Attribute::html_class(Classes::TextUnitalicized.to_string().to_string())
+}
+
+pub fn hairline<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextThin)
+}
+
+pub fn extra_light<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextExtraLight)
+}
+
+pub fn light<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextLight)
+}
+
+pub fn regular<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextNormalWeight)
+}
+
+pub fn medium<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextMedium)
+}
+
+pub fn semi_bold<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextSemiBold)
+}
+
+pub fn bold<Msg>() -> Attribute<Msg> {
+    weight(Classes::Bold)
+}
+
+pub fn extra_bold<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextExtraBold)
+}
+
+pub fn heavy<Msg>() -> Attribute<Msg> {
+    weight(Classes::TextHeavy)
+}
+
+fn weight<Msg>(class: Classes) -> Attribute<Msg> {
+    Attribute::Class(Flag::font_weight(), class.to_string().to_string())
+}
+
+/// A shadow behind the text. Its `size` isn't used, as text shadows can't
+/// be spread.
+pub fn shadow<Msg>(shade: Shadow) -> Attribute<Msg> {
+    Attribute::Style(
+        Flag::text_shadows(),
+        Style::Single(
+            shade.text_shadow_class(),
+            "text-shadow".into(),
+            shade.format_text_shadow(),
+        ),
+    )
+}
+
+/// A glow of `color` around the text, `size` pixels across.
+pub fn glow<Msg>(color: Color, size: u8) -> Attribute<Msg> {
+    shadow(Shadow {
+        color,
+        offset: (0, 0),
+        blur: size.saturating_mul(2),
+        size: 0,
+    })
+}
+
+/// Turn on an OpenType feature of the font, e.g. `variant(small_caps())`.
+/// Only fonts that have the feature are changed.
+pub fn variant<Msg>(var: Variant) -> Attribute<Msg> {
+    if var.is_small_caps() {
+        return Attribute::Style(
+            Flag::font_variant(),
+            Style::Single(
+                "v-smcp".to_string(),
+                "font-variant".into(),
+                "small-caps".to_string(),
+            ),
+        );
+    }
+    Attribute::Style(
+        Flag::font_variant(),
+        Style::Single(
+            format!("v-{}", var.name()),
+            "font-feature-settings".into(),
+            var.render(),
+        ),
+    )
+}
+
+/// Several `variant`s at once. A later `variant` on the same element
+/// replaces all of these, so they have to be set together.
+pub fn variant_list<Msg>(vars: Vec<Variant>) -> Attribute<Msg> {
+    let name = vars
+        .iter()
+        .map(|var| var.name())
+        .collect::<Vec<String>>()
+        .join("-");
+    let settings = vars
+        .iter()
+        .map(|var| var.render())
+        .collect::<Vec<String>>()
+        .join(", ");
+    Attribute::Style(
+        Flag::font_variant(),
+        Style::Single(
+            format!("v-{}", name),
+            "font-feature-settings".into(),
+            settings,
+        ),
+    )
+}
+
+/// Small capitals, where the font has them.
+pub fn small_caps() -> Variant {
+    Variant::Active("smcp".to_string())
+}
+
+/// A zero with a slash through it, to tell it from an `O`.
+pub fn slashed_zero() -> Variant {
+    Variant::Active("zero".to_string())
+}
+
+pub fn ligatures() -> Variant {
+    Variant::Active("liga".to_string())
+}
+
+/// Ordinal markers, like the `st` in `1st`, raised.
+pub fn ordinal() -> Variant {
+    Variant::Active("ordn".to_string())
+}
+
+/// Digits all the same width, for numbers that line up in columns.
+pub fn tabular_numbers() -> Variant {
+    Variant::Active("tnum".to_string())
+}
+
+/// Fractions written `1/2` drawn with the numbers stacked.
+pub fn stacked_fractions() -> Variant {
+    Variant::Active("afrc".to_string())
+}
+
+/// Fractions written `1/2` drawn with a diagonal slash.
+pub fn diagonal_fractions() -> Variant {
+    Variant::Active("frac".to_string())
+}
+
+/// One of the font's sets of flourished letters, if it has any.
+pub fn swash(index: u64) -> Variant {
+    Variant::Indexed("swsh".to_string(), index)
+}
+
+/// Turn any OpenType feature on or off by its four letter tag.
+pub fn feature(name: &str, on: bool) -> Variant {
+    if on {
+        Variant::Indexed(name.to_string(), 1)
+    } else {
+        Variant::Off(name.to_string())
+    }
+}
+
+/// Choose one of several alternates for an OpenType feature by its tag.
+pub fn indexed(name: &str, index: u64) -> Variant {
+    Variant::Indexed(name.to_string(), index)
+}
+
+#[test]
+fn test_font() {
+    use crate::element::{el, extract_styles};
+    use crate::model::Element;
+
+    let view = el::<()>(
+        vec![
+            color(Color::new(1.0, 0.0, 0.0, 1.0)),
+            size(18),
+            family(vec![typeface("Open Sans"), sans_serif()]),
+            bold(),
+            italic(),
+            center(),
+            letter_spacing(1.5),
+            word_spacing(4.0),
+            glow(Color::new(0.0, 0.0, 1.0, 1.0), 3),
+            variant_list(vec![small_caps(), tabular_numbers()]),
+        ],
+        Element::Text("Title".to_string()),
+    );
+    let css = extract_styles(std::slice::from_ref(&view));
+    for rule in &[
+        "font-family: \"Open Sans\" ,sans-serif",
+        "letter-spacing: 1.5px",
+        "word-spacing: 4px",
+        "text-shadow: 0px 0px 6px rgba(0,0,255,1)",
+        "font-feature-settings: \"smcp\", \"tnum\"",
+    ] {
+        assert!(css.contains(rule), "{} not in {}", rule, css);
+    }
+    let html = crate::render::to_html(&crate::element::layout(vec![], view));
+    for class in &[
+        "font-size-18",
+        "ff-open-sanssans-serif",
+        "i",
+        "tc",
+        "v-smcp-tnum",
+    ] {
+        assert!(
+            html.split(['"', ' ']).any(|c| c == *class),
+            "{} not in {}",
+            class,
+            html
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    i18n::{translate_or, translator, Locale},
+    model::Element,
+    sub::{every, Sub},
+};
+
+/// How `text_number` writes a number.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub struct NumberFormat {
+    pub min_decimals: usize,
+    pub max_decimals: usize,
+    /// Whether to separate thousands, e.g. `1,234,567`.
+    pub grouping: bool,
+    /// The locale to follow, instead of the current translator's.
+    pub locale: Option<Locale>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            min_decimals: 0,
+            max_decimals: 3,
+            grouping: true,
+            locale: None,
+        }
+    }
+}
+
+/// How `text_date` writes a date. Dates are shown in UTC.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub enum DateFormat {
+    /// `2024-03-09`, whatever the locale.
+    Iso,
+    /// The locale's numeric date, e.g. `3/9/2024` in `en-US`, `09.03.2024`
+    /// in `de`.
+    Short,
+    /// How long ago or how far off the date is, e.g. `3 minutes ago` or
+    /// `in 2 days`. Use `refresh_relative` to keep it up to date.
+    Relative,
+}
+
+fn current_locale(locale: &Option<Locale>) -> Locale {
+    locale
+        .clone()
+        .or_else(|| translator().map(|t| t.locale()))
+        .unwrap_or_else(|| Locale("en-US".to_string()))
+}
+
+// The group and decimal separators of a locale.
+fn separators(locale: &Locale) -> (&'static str, &'static str) {
+    match locale.language().as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => {
+            (".", ",")
+        }
+        // A narrow no-break space, so numbers don't wrap.
+        "fr" => ("\u{202f}", ","),
+        "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "nb" | "fi" | "hu" => {
+            ("\u{a0}", ",")
+        }
+        _ => (",", "."),
+    }
+}
+
+pub fn format_number(value: f64, format: &NumberFormat) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let (group, decimal) = separators(&current_locale(&format.locale));
+    let decimals = format.max_decimals.max(format.min_decimals);
+    let fixed = format!("{:.*}", decimals, value.abs());
+    let (int, all_frac) = match fixed.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (fixed.as_str(), ""),
+    };
+    let frac = all_frac.trim_end_matches('0');
+    let frac = if frac.len() < format.min_decimals {
+        &all_frac[..format.min_decimals]
+    } else {
+        frac
+    };
+    let int = if format.grouping {
+        let digits: Vec<char> = int.chars().collect();
+        digits
+            .rchunks(3)
+            .rev()
+            .map(|chunk| chunk.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(group)
+    } else {
+        int.to_string()
+    };
+    // Don't write `-0` for a number that rounds to zero.
+    let sign = if value < 0.0
+        && fixed.chars().any(|c| c.is_ascii_digit() && c != '0')
+    {
+        "-"
+    } else {
+        ""
+    };
+    if frac.is_empty() {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}{}{}", sign, int, decimal, frac)
+    }
+}
+
+// The year, month and day of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Seconds since the epoch, negative for times before it.
+fn unix_secs(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+fn format_relative(secs: i64) -> String {
+    let (n, unit) = match secs.abs() {
+        0..=44 => return translate_or("relative.now", 0, "just now"),
+        s @ 45..=2_699 => ((s + 30) / 60, "minute"),
+        s @ 2_700..=79_199 => ((s + 1_800) / 3_600, "hour"),
+        s @ 79_200..=2_246_399 => ((s + 43_200) / 86_400, "day"),
+        s @ 2_246_400..=28_511_999 => ((s + 1_296_000) / 2_592_000, "month"),
+        s => ((s + 15_768_000) / 31_536_000, "year"),
+    };
+    let units = if n == 1 {
+        unit.to_string()
+    } else {
+        format!("{}s", unit)
+    };
+    if secs > 0 {
+        translate_or(
+            &format!("relative.{}s.past", unit),
+            n,
+            &format!("{{count}} {} ago", units),
+        )
+    } else {
+        translate_or(
+            &format!("relative.{}s.future", unit),
+            n,
+            &format!("in {{count}} {}", units),
+        )
+    }
+}
+
+fn format_date_at(
+    value: SystemTime,
+    format: &DateFormat,
+    locale: &Locale,
+    now: SystemTime,
+) -> String {
+    let (y, m, d) = civil_from_days(unix_secs(value).div_euclid(86_400));
+    match format {
+        DateFormat::Iso => format!("{:04}-{:02}-{:02}", y, m, d),
+        DateFormat::Short => match locale.language().as_str() {
+            "en" if locale.0.ends_with("US") || locale.0 == "en" => {
+                format!("{}/{}/{}", m, d, y)
+            }
+            "de" | "ru" | "pl" | "cs" | "sk" | "uk" | "fi" | "nb" | "tr" => {
+                format!("{:02}.{:02}.{}", d, m, y)
+            }
+            "ja" | "zh" => format!("{}/{:02}/{:02}", y, m, d),
+            "sv" | "ko" | "lt" => format!("{:04}-{:02}-{:02}", y, m, d),
+            _ => format!("{:02}/{:02}/{}", d, m, y),
+        },
+        DateFormat::Relative => {
+            format_relative(unix_secs(now) - unix_secs(value))
+        }
+    }
+}
+
+pub fn format_date(value: SystemTime, format: &DateFormat) -> String {
+    format_date_at(value, format, &current_locale(&None), SystemTime::now())
+}
+
+/// A number written for the current locale, e.g. `1,234.5`.
+pub fn text_number<Msg>(value: f64, format: NumberFormat) -> Element<Msg> {
+    Element::Text(format_number(value, &format))
+}
+
+/// A date written for the current locale.
+pub fn text_date<Msg>(value: SystemTime, format: DateFormat) -> Element<Msg> {
+    Element::Text(format_date(value, &format))
+}
+
+/// Send `msg` often enough to keep `DateFormat::Relative` dates current.
+/// Any message will do, as the view is rendered again after each one.
+pub fn refresh_relative<Msg>(msg: Msg) -> Sub<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    every(Duration::from_secs(30), msg)
+}
+
+#[test]
+fn test_format_numbers_and_dates() {
+    let en = NumberFormat {
+        locale: Some(Locale("en-US".to_string())),
+        ..NumberFormat::default()
+    };
+    let de = NumberFormat {
+        locale: Some(Locale("de-DE".to_string())),
+        min_decimals: 2,
+        ..NumberFormat::default()
+    };
+    assert_eq!(format_number(1234567.125, &en), "1,234,567.125");
+    assert_eq!(format_number(-1234.5, &de), "-1.234,50");
+    assert_eq!(format_number(-0.0001, &en), "0");
+
+    let day = UNIX_EPOCH + Duration::from_secs(1_709_942_400);
+    let us = Locale("en-US".to_string());
+    let de = Locale("de".to_string());
+    assert_eq!(
+        format_date_at(day, &DateFormat::Iso, &us, day),
+        "2024-03-09"
+    );
+    assert_eq!(
+        format_date_at(day, &DateFormat::Short, &us, day),
+        "3/9/2024"
+    );
+    assert_eq!(
+        format_date_at(day, &DateFormat::Short, &de, day),
+        "09.03.2024"
+    );
+
+    let later = |secs| day + Duration::from_secs(secs);
+    let relative = |now| format_date_at(day, &DateFormat::Relative, &us, now);
+    assert_eq!(relative(later(10)), "just now");
+    assert_eq!(relative(later(180)), "3 minutes ago");
+    assert_eq!(relative(later(3_600)), "1 hour ago");
+    assert_eq!(
+        format_date_at(later(2 * 86_400), &DateFormat::Relative, &us, day),
+        "in 2 days"
+    );
+}
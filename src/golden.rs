@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::element::layout;
+use crate::model::Element;
+use crate::render::to_html;
+use crate::solver::Solver;
+use crate::vdom::Node;
+
+/// Why an element tree couldn't be checked against its golden image.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// No headless browser was found. Set `CHROME` to the path of one.
+    NoBrowser,
+    /// The browser ran, but didn't take a screenshot.
+    Browser(String),
+    Io(io::Error),
+    Png(String),
+    /// There's no golden at this path. Run with `UPDATE_GOLDENS` set to
+    /// save the rendering as it.
+    Missing(PathBuf),
+    /// The rendering isn't the size of the golden.
+    Size {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// Too many pixels look different. The rendering was saved next to the
+    /// golden, at `actual`.
+    Mismatch {
+        differing: usize,
+        total: usize,
+        actual: PathBuf,
+    },
+    /// The layout solver put elements somewhere else than the browser, as
+    /// listed.
+    Layout(Vec<String>),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoBrowser => write!(f, "no headless browser found"),
+            Self::Browser(e) => write!(f, "browser failed: {}", e),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Png(e) => write!(f, "invalid png: {}", e),
+            Self::Missing(path) => write!(
+                f,
+                "no golden at {}, set UPDATE_GOLDENS to save one",
+                path.display()
+            ),
+            Self::Size { expected, actual } => write!(
+                f,
+                "rendered at {}x{}, but the golden is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            Self::Mismatch {
+                differing,
+                total,
+                actual,
+            } => write!(
+                f,
+                "{} of {} pixels differ, see {}",
+                differing,
+                total,
+                actual.display()
+            ),
+            Self::Layout(differences) => write!(
+                f,
+                "laid out differently:\n  {}",
+                differences.join("\n  ")
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(e: io::Error) -> Self {
+        GoldenError::Io(e)
+    }
+}
+
+/// An RGBA image, 4 bytes a pixel.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn decode(png: &[u8]) -> Result<Image, GoldenError> {
+        let mut decoder = png::Decoder::new(png);
+        decoder.set_transformations(
+            png::Transformations::EXPAND | png::Transformations::STRIP_16,
+        );
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| GoldenError::Png(e.to_string()))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| GoldenError::Png(e.to_string()))?;
+        buf.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buf,
+            png::ColorType::Rgb => buf
+                .chunks(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            _ => buf.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+        };
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn encode(&self, out: impl io::Write) -> Result<(), GoldenError> {
+        let mut encoder = png::Encoder::new(out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut w| w.write_image_data(&self.pixels))
+            .map_err(|e| GoldenError::Png(e.to_string()))
+    }
+}
+
+// The brightness and chroma of a pixel blended onto white, in YIQ.
+fn yiq(p: &[u8]) -> (f32, f32, f32) {
+    let a = p[3] as f32 / 255.0;
+    let blend = |c: u8| 255.0 + (c as f32 - 255.0) * a;
+    let (r, g, b) = (blend(p[0]), blend(p[1]), blend(p[2]));
+    (
+        0.298_895 * r + 0.586_622 * g + 0.114_482 * b,
+        0.595_978 * r - 0.274_176 * g - 0.321_802 * b,
+        0.211_470 * r - 0.522_617 * g + 0.311_147 * b,
+    )
+}
+
+/// How many pixels of `actual` look different from `expected`. Colors are
+/// compared by how different they look, from 0 to 1, and differences at or
+/// under `threshold`, e.g. from antialiasing, are ignored.
+pub fn differing_pixels(
+    expected: &Image,
+    actual: &Image,
+    threshold: f32,
+) -> usize {
+    // The largest possible difference, between black and white.
+    const MAX_DELTA: f32 = 35215.0;
+    expected
+        .pixels
+        .chunks(4)
+        .zip(actual.pixels.chunks(4))
+        .filter(|(e, a)| {
+            let (e, a) = (yiq(e), yiq(a));
+            let (y, i, q) = (e.0 - a.0, e.1 - a.1, e.2 - a.2);
+            let delta = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+            delta / MAX_DELTA > threshold * threshold
+        })
+        .count()
+}
+
+fn browser() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("CHROME") {
+        return Some(path.into());
+    }
+    let names = ["chromium", "chromium-browser", "google-chrome", "chrome"];
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+// A directory of its own for each browser run, as tests run in parallel.
+fn temp_dir() -> PathBuf {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("golden-{}-{}", std::process::id(), run))
+}
+
+// Open `element` in a headless Chromium, at the size of a `width` by
+// `height` window, with `script` run once the page has loaded. The browser
+// is run with `args`, with `{page}` and `{dir}` in them replaced by the
+// paths of the page and a temporary directory, and `read` gets what it
+// printed and the directory before the directory is removed.
+fn run_browser<Msg, T>(
+    element: Element<Msg>,
+    width: u32,
+    height: u32,
+    script: &str,
+    args: &[&str],
+    read: impl FnOnce(&[u8], &std::path::Path) -> Result<T, GoldenError>,
+) -> Result<T, GoldenError> {
+    let browser = browser().ok_or(GoldenError::NoBrowser)?;
+    let dir = temp_dir();
+    fs::create_dir_all(&dir)?;
+    let page = dir.join("page.html");
+    fs::write(
+        &page,
+        format!(
+            "<!DOCTYPE html><html><body>{}<script>{}</script></body></html>",
+            to_html(&layout(vec![], element)),
+            script
+        ),
+    )?;
+    let output = Command::new(browser)
+        .args(["--headless", "--disable-gpu", "--hide-scrollbars"])
+        .arg(format!("--window-size={},{}", width, height))
+        .args(args.iter().map(|arg| {
+            arg.replace("{page}", &page.display().to_string())
+                .replace("{dir}", &dir.display().to_string())
+        }))
+        .output()?;
+    let result = read(&output.stdout, &dir).map_err(|e| match e {
+        GoldenError::Io(_) => GoldenError::Browser(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ),
+        e => e,
+    });
+    fs::remove_dir_all(&dir)?;
+    result
+}
+
+/// Render `element` to an image with a headless Chromium, at the size of a
+/// `width` by `height` window.
+pub fn render_image<Msg>(
+    element: Element<Msg>,
+    width: u32,
+    height: u32,
+) -> Result<Image, GoldenError> {
+    run_browser(
+        element,
+        width,
+        height,
+        "",
+        &["--screenshot={dir}/screenshot.png", "file://{page}"],
+        |_, dir| Image::decode(&fs::read(dir.join("screenshot.png"))?),
+    )
+}
+
+pub use crate::vdom::{Rect, Rects};
+
+/// A layout solver working out where nodes go without a browser, to be
+/// checked against the CSS that the same tree renders to with
+/// `check_layout`, e.g. `Solver`.
+pub trait LayoutSolver {
+    fn solve<Msg>(
+        &mut self,
+        root: &Node<Msg>,
+        width: f32,
+        height: f32,
+    ) -> Rects;
+}
+
+impl LayoutSolver for Solver {
+    fn solve<Msg>(
+        &mut self,
+        root: &Node<Msg>,
+        width: f32,
+        height: f32,
+    ) -> Rects {
+        self.lay_out(root, width, height)
+    }
+}
+
+// Collects the rectangles of the page's elements into `<pre id=rects>`,
+// which `--dump-dom` prints.
+const RECTS_SCRIPT: &str = "
+const rects = [];
+const walk = (node, path) => {
+  const r = node.getBoundingClientRect();
+  rects.push([path, r.x, r.y, r.width, r.height]);
+  node.childNodes.forEach((child, i) => {
+    if (child.nodeType === 1 && child.tagName !== 'STYLE') {
+      walk(child, path.concat([i]));
+    }
+  });
+};
+walk(document.body.firstElementChild, []);
+const out = document.createElement('pre');
+out.id = 'rects';
+out.textContent = JSON.stringify(rects);
+document.body.appendChild(out);
+";
+
+/// Lay out `element` with a headless Chromium, at the size of a `width` by
+/// `height` window.
+pub fn browser_layout<Msg>(
+    element: Element<Msg>,
+    width: u32,
+    height: u32,
+) -> Result<Rects, GoldenError> {
+    run_browser(
+        element,
+        width,
+        height,
+        RECTS_SCRIPT,
+        &["--dump-dom", "file://{page}"],
+        |dom, _| {
+            let dom = String::from_utf8_lossy(dom);
+            let json = dom
+                .split_once("<pre id=\"rects\">")
+                .and_then(|(_, rest)| rest.split_once("</pre>"))
+                .map(|(json, _)| json)
+                .ok_or_else(|| GoldenError::Browser(dom.to_string()))?;
+            let rects: Vec<(Vec<usize>, f32, f32, f32, f32)> =
+                serde_json::from_str(json)
+                    .map_err(|e| GoldenError::Browser(e.to_string()))?;
+            Ok(rects
+                .into_iter()
+                .map(|(path, x, y, width, height)| {
+                    (
+                        path,
+                        Rect {
+                            x,
+                            y,
+                            width,
+                            height,
+                        },
+                    )
+                })
+                .collect())
+        },
+    )
+}
+
+/// The nodes laid out more than `tolerance` pixels away from where they're
+/// `expected`, or only in one of the layouts, one per line.
+pub fn layout_differences(
+    expected: &Rects,
+    actual: &Rects,
+    tolerance: f32,
+) -> Vec<String> {
+    let mut out = vec![];
+    for (path, e) in expected {
+        match actual.get(path) {
+            None => out.push(format!("at {:?}: missing", path)),
+            Some(a) => {
+                let edges = [
+                    (e.x, a.x),
+                    (e.y, a.y),
+                    (e.width, a.width),
+                    (e.height, a.height),
+                ];
+                if edges.iter().any(|(e, a)| (e - a).abs() > tolerance) {
+                    out.push(format!(
+                        "at {:?}: laid out at {:?}, expected {:?}",
+                        path, a, e
+                    ));
+                }
+            }
+        }
+    }
+    for path in actual.keys().filter(|p| !expected.contains_key(*p)) {
+        out.push(format!("at {:?}: unexpected", path));
+    }
+    out
+}
+
+/// Lay out `element` with both `solver` and a headless Chromium, and check
+/// they put every node within `tolerance` pixels of the same place.
+pub fn check_layout<Msg>(
+    solver: &mut impl LayoutSolver,
+    element: Element<Msg>,
+    width: u32,
+    height: u32,
+    tolerance: f32,
+) -> Result<(), GoldenError> {
+    let node = layout(vec![], element.clone());
+    let actual = solver.solve(&node, width as f32, height as f32);
+    let expected = browser_layout(element, width, height)?;
+    let differences = layout_differences(&expected, &actual, tolerance);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(GoldenError::Layout(differences))
+    }
+}
+
+/// How an element tree is compared with its golden image.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    /// Where the goldens are kept, as `<name>.png`.
+    pub dir: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// The perceived difference a pixel can have before it counts as
+    /// different, from 0 to 1.
+    pub threshold: f32,
+    /// The share of pixels that can differ before the check fails.
+    pub tolerance: f32,
+}
+
+impl Default for Golden {
+    fn default() -> Self {
+        Golden {
+            // Cargo runs tests from the package being tested.
+            dir: env::var_os("CARGO_MANIFEST_DIR")
+                .map_or_else(|| PathBuf::from("."), PathBuf::from)
+                .join("tests/goldens"),
+            width: 800,
+            height: 600,
+            threshold: 0.1,
+            tolerance: 0.001,
+        }
+    }
+}
+
+impl Golden {
+    /// Render `element` and compare it with the golden called `name`.
+    ///
+    /// With `UPDATE_GOLDENS` set, the rendering is saved as the golden
+    /// instead. Without it, a missing golden fails the check.
+    pub fn check<Msg>(
+        &self,
+        name: &str,
+        element: Element<Msg>,
+    ) -> Result<(), GoldenError> {
+        let path = self.dir.join(name).with_extension("png");
+        let update = env::var_os("UPDATE_GOLDENS").is_some();
+        if !update && !path.is_file() {
+            return Err(GoldenError::Missing(path));
+        }
+        let actual = render_image(element, self.width, self.height)?;
+        if update {
+            fs::create_dir_all(&self.dir)?;
+            return actual.encode(File::create(&path)?);
+        }
+        let expected = Image::decode(&fs::read(&path)?)?;
+        if (expected.width, expected.height) != (actual.width, actual.height) {
+            return Err(GoldenError::Size {
+                expected: (expected.width, expected.height),
+                actual: (actual.width, actual.height),
+            });
+        }
+        let differing = differing_pixels(&expected, &actual, self.threshold);
+        let total = (actual.width * actual.height) as usize;
+        if differing as f32 > total as f32 * self.tolerance {
+            let saved = self.dir.join(format!("{}.actual.png", name));
+            actual.encode(File::create(&saved)?)?;
+            return Err(GoldenError::Mismatch {
+                differing,
+                total,
+                actual: saved,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_differing_pixels() {
+    let image = |pixels: &[[u8; 4]]| Image {
+        width: pixels.len() as u32,
+        height: 1,
+        pixels: pixels.concat(),
+    };
+    let expected =
+        image(&[[255, 255, 255, 255], [0, 0, 0, 255], [0, 0, 255, 255]]);
+    // Antialiasing noise is ignored, but black turning red isn't.
+    let actual =
+        image(&[[250, 250, 252, 255], [255, 0, 0, 255], [0, 0, 255, 255]]);
+    assert_eq!(differing_pixels(&expected, &actual, 0.1), 1);
+    // Fully transparent is the same as white.
+    let clear = image(&[[0, 0, 0, 0], [0, 0, 0, 255], [0, 0, 255, 255]]);
+    assert_eq!(differing_pixels(&expected, &clear, 0.1), 0);
+
+    let mut png = vec![];
+    expected.encode(&mut png).unwrap();
+    assert_eq!(Image::decode(&png).unwrap(), expected);
+}
+
+#[test]
+fn test_missing_golden() {
+    assert_ne!(temp_dir(), temp_dir());
+    if env::var_os("UPDATE_GOLDENS").is_some() {
+        return;
+    }
+    let golden = Golden {
+        dir: env::temp_dir().join("no-goldens"),
+        ..Golden::default()
+    };
+    match golden.check("missing", Element::<()>::Empty) {
+        Err(GoldenError::Missing(path)) => {
+            assert!(path.ends_with("missing.png"))
+        }
+        checked => panic!("expected a missing golden, got {:?}", checked),
+    }
+}
+
+#[test]
+fn test_layout_differences() {
+    let rect = |x, width| Rect {
+        x,
+        y: 0.0,
+        width,
+        height: 20.0,
+    };
+    let expected: Rects = vec![
+        (vec![], rect(0.0, 100.0)),
+        (vec![0], rect(0.0, 50.0)),
+        (vec![1], rect(50.0, 50.0)),
+    ]
+    .into_iter()
+    .collect();
+    let mut actual = expected.clone();
+    actual.insert(vec![0], rect(0.4, 49.6));
+    assert!(layout_differences(&expected, &actual, 0.5).is_empty());
+
+    actual.insert(vec![1], rect(52.0, 50.0));
+    actual.remove(&vec![]);
+    actual.insert(vec![2], rect(0.0, 0.0));
+    let differences = layout_differences(&expected, &actual, 0.5);
+    assert_eq!(differences.len(), 3);
+    assert_eq!(differences[0], "at []: missing");
+    assert!(differences[1].starts_with("at [1]: laid out at"));
+    assert_eq!(differences[2], "at [2]: unexpected");
+}
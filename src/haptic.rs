@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::{
+    model::Attribute,
+    vdom::{self, Node, NodeType},
+};
+
+/// A rumble of a gamepad, or a vibration of a phone, played when an
+/// element is pressed.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct HapticPattern {
+    /// How hard the low-frequency motor runs, from 0 to 1.
+    pub strong: f32,
+    /// How hard the high-frequency motor runs, from 0 to 1. Phones only
+    /// have one motor, so they just vibrate for the duration.
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+impl HapticPattern {
+    /// A light tap, as for stepping a slider.
+    pub const TICK: Self = HapticPattern {
+        strong: 0.0,
+        weak: 0.4,
+        duration: Duration::from_millis(20),
+    };
+    /// A firm press, as for buttons.
+    pub const CLICK: Self = HapticPattern {
+        strong: 0.3,
+        weak: 0.6,
+        duration: Duration::from_millis(40),
+    };
+    /// A heavy bump, as for confirming something that can't be undone.
+    pub const THUD: Self = HapticPattern {
+        strong: 0.8,
+        weak: 0.3,
+        duration: Duration::from_millis(120),
+    };
+
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(',');
+        Some(HapticPattern {
+            strong: parts.next()?.parse().ok()?,
+            weak: parts.next()?.parse().ok()?,
+            duration: Duration::from_millis(parts.next()?.parse().ok()?),
+        })
+    }
+}
+
+const HAPTIC_ATTR: &str = "data-haptic";
+
+/// Rumble the gamepad, or vibrate the phone, when this element is clicked
+/// or activated with a gamepad, e.g. for buttons and sliders. The backend
+/// plays the pattern on every connected gamepad, and other backends can
+/// play them from `Program::take_haptics`.
+pub fn haptic_on_press<Msg>(pattern: HapticPattern) -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property(
+        HAPTIC_ATTR,
+        format!(
+            "{},{},{}",
+            pattern.strong,
+            pattern.weak,
+            pattern.duration.as_millis()
+        ),
+    ))
+}
+
+// Whether any element in the view has a haptic pattern.
+pub(crate) fn has_haptics<Msg>(node: &Node<Msg>) -> bool {
+    node.attr(HAPTIC_ATTR).is_some()
+        || node.children.iter().any(|child| match child {
+            NodeType::Node(n) | NodeType::KeyedNode(_, n) => has_haptics(n),
+            NodeType::Text(_) => false,
+        })
+}
+
+/// The pattern to play for a press at `path`: that of the element pressed,
+/// the nearest one it's in with a pattern, stopping at the first that can
+/// be clicked, as the press goes to it.
+pub(crate) fn haptic_for<Msg>(
+    root: &Node<Msg>,
+    path: &[usize],
+) -> Option<HapticPattern> {
+    for len in (0..=path.len()).rev() {
+        let node = root.at(&path[..len])?;
+        if let Some(value) = node.attr(HAPTIC_ATTR) {
+            return HapticPattern::parse(value);
+        }
+        if node.events.iter().any(|l| l.event == "click") {
+            return None;
+        }
+    }
+    None
+}
+
+#[test]
+fn test_haptic_on_press() {
+    use crate::{
+        app::Program, cmd::Cmd, element::el, events::on_click, model::Element,
+        sound::Interaction,
+    };
+
+    let mut program = Program::new(
+        || (),
+        |_, _: ()| Cmd::none(),
+        |_| {
+            el(
+                vec![on_click(()), haptic_on_press(HapticPattern::THUD)],
+                el(
+                    vec![on_click(())],
+                    el(vec![], Element::Text("Cancel".to_string())),
+                ),
+            )
+        },
+    );
+    program.render();
+    assert!(program.plays_haptics());
+    program.interacted(&[0, 2, 0], Interaction::Click);
+    // The inner button has no pattern of its own.
+    program.interacted(&[0, 2, 0, 0, 0], Interaction::Click);
+    program.interacted(&[0, 2, 0], Interaction::Hover);
+    assert_eq!(program.take_haptics(), vec![HapticPattern::THUD]);
+    assert!(program.take_haptics().is_empty());
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    model::{Attribute, Element},
+    vdom::html::attributes,
+};
+
+/// A BCP 47 language tag, like `en-GB` or `ar`.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub struct Locale(pub String);
+
+impl Locale {
+    /// The language subtag, lowercased, e.g. `pt` for `pt-BR`.
+    pub fn language(&self) -> String {
+        self.0.split(['-', '_']).next().unwrap_or("").to_lowercase()
+    }
+
+    pub fn direction(&self) -> Direction {
+        match self.language().as_str() {
+            "ar" | "dv" | "fa" | "he" | "ks" | "ku" | "ps" | "sd" | "ug"
+            | "ur" | "yi" => Direction::Rtl,
+            _ => Direction::Ltr,
+        }
+    }
+
+    /// Which plural form `count` takes in this locale, following a subset
+    /// of the CLDR rules for whole numbers.
+    pub fn plural(&self, count: i64) -> Plural {
+        let n = count.unsigned_abs();
+        let (n10, n100) = (n % 10, n % 100);
+        match self.language().as_str() {
+            "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" | "tr" => {
+                Plural::Other
+            }
+            "fr" | "hi" | "fa" if n <= 1 => Plural::One,
+            "fr" | "hi" | "fa" => Plural::Other,
+            "ru" | "uk" | "be" | "sr" | "hr" | "bs" => {
+                if n10 == 1 && n100 != 11 {
+                    Plural::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    Plural::Few
+                } else {
+                    Plural::Many
+                }
+            }
+            "pl" => {
+                if n == 1 {
+                    Plural::One
+                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {
+                    Plural::Few
+                } else {
+                    Plural::Many
+                }
+            }
+            "cs" | "sk" => match n {
+                1 => Plural::One,
+                2..=4 => Plural::Few,
+                _ => Plural::Other,
+            },
+            "ar" => match (n, n100) {
+                (0, _) => Plural::Zero,
+                (1, _) => Plural::One,
+                (2, _) => Plural::Two,
+                (_, 3..=10) => Plural::Few,
+                (_, 11..=99) => Plural::Many,
+                _ => Plural::Other,
+            },
+            "he" => match n {
+                1 => Plural::One,
+                2 => Plural::Two,
+                _ => Plural::Other,
+            },
+            _ if n == 1 => Plural::One,
+            _ => Plural::Other,
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+/// The CLDR plural categories.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Plural {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl Plural {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Plural::Zero => "zero",
+            Plural::One => "one",
+            Plural::Two => "two",
+            Plural::Few => "few",
+            Plural::Many => "many",
+            Plural::Other => "other",
+        }
+    }
+}
+
+/// Looks up the messages shown by `text_key`, e.g. from Fluent or gettext
+/// files. A message can refer to its arguments as `{name}`.
+pub trait Translator: Send + Sync {
+    fn locale(&self) -> Locale;
+
+    /// The message for `key`, in the form for `plural` if it's given.
+    fn message(&self, key: &str, plural: Option<Plural>) -> Option<String>;
+}
+
+/// A translator backed by a map of keys to messages.
+///
+/// Plural forms are stored under the key followed by the category, e.g.
+/// `inbox.count.one` and `inbox.count.other`.
+pub struct Catalog {
+    locale: Locale,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn new(locale: Locale, messages: HashMap<String, String>) -> Self {
+        Self { locale, messages }
+    }
+}
+
+impl Translator for Catalog {
+    fn locale(&self) -> Locale {
+        self.locale.clone()
+    }
+
+    fn message(&self, key: &str, plural: Option<Plural>) -> Option<String> {
+        match plural {
+            Some(plural) => self
+                .messages
+                .get(&format!("{}.{}", key, plural.as_str()))
+                .or_else(|| self.messages.get(&format!("{}.other", key))),
+            None => self.messages.get(key),
+        }
+        .cloned()
+    }
+}
+
+static TRANSLATOR: RwLock<Option<Arc<dyn Translator>>> = RwLock::new(None);
+
+/// Use `translator` for the `text_key` elements of every view rendered
+/// from now on.
+pub fn set_translator(translator: Arc<dyn Translator>) {
+    *TRANSLATOR.write().unwrap() = Some(translator);
+}
+
+pub fn translator() -> Option<Arc<dyn Translator>> {
+    TRANSLATOR.read().unwrap().clone()
+}
+
+fn interpolate(message: &str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+fn translate_with(
+    translator: Option<&dyn Translator>,
+    key: &str,
+    count: Option<i64>,
+    args: &[(&str, String)],
+) -> String {
+    let plural = translator.zip(count).map(|(t, n)| t.locale().plural(n));
+    // A missing message shows its key, so it's easy to spot.
+    let message = translator
+        .and_then(|t| t.message(key, plural))
+        .unwrap_or_else(|| key.to_string());
+    match count {
+        Some(n) => {
+            let mut args = args.to_vec();
+            args.push(("count", n.to_string()));
+            interpolate(&message, &args)
+        }
+        None => interpolate(&message, args),
+    }
+}
+
+/// The message for `key` in the plural form for `count`, or `fallback` if
+/// there's no translator or it doesn't have one, for built in messages.
+pub(crate) fn translate_or(key: &str, count: i64, fallback: &str) -> String {
+    let message = translator()
+        .and_then(|t| t.message(key, Some(t.locale().plural(count))))
+        .unwrap_or_else(|| fallback.to_string());
+    interpolate(&message, &[("count", count.to_string())])
+}
+
+/// The message for `key` from the current translator.
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    translate_with(translator().as_deref(), key, None, args)
+}
+
+/// The message for `key` in the plural form for `count`, which is also
+/// available to it as `{count}`.
+pub fn translate_plural(
+    key: &str,
+    count: i64,
+    args: &[(&str, String)],
+) -> String {
+    translate_with(translator().as_deref(), key, Some(count), args)
+}
+
+/// Text looked up by `key`, e.g. `text_key("settings.title")`.
+pub fn text_key<Msg>(key: &str) -> Element<Msg> {
+    Element::Text(translate(key, &[]))
+}
+
+pub fn text_key_with<Msg>(key: &str, args: &[(&str, String)]) -> Element<Msg> {
+    Element::Text(translate(key, args))
+}
+
+pub fn text_plural<Msg>(
+    key: &str,
+    count: i64,
+    args: &[(&str, String)],
+) -> Element<Msg> {
+    Element::Text(translate_plural(key, count, args))
+}
+
+/// Lay out this element and everything in it in `direction`. A row laid
+/// out right-to-left starts from the right.
+pub fn dir<Msg>(direction: Direction) -> Attribute<Msg> {
+    let dir = match direction {
+        Direction::Ltr => "ltr",
+        Direction::Rtl => "rtl",
+    };
+    Attribute::Attr(attributes::style("dir".to_string(), dir.to_string()))
+}
+
+/// The direction of the current translator's locale, for the root of the
+/// layout.
+pub fn locale_dir<Msg>() -> Attribute<Msg> {
+    match translator() {
+        Some(t) => dir(t.locale().direction()),
+        None => Attribute::None,
+    }
+}
+
+/// The translator used for rendering, as a Bevy resource. Changing it
+/// renders the UI again.
+#[cfg(feature = "bevy")]
+#[derive(bevy::prelude::Resource, Clone)]
+pub struct Translations(pub Arc<dyn Translator>);
+
+#[test]
+fn test_translate_plurals() {
+    let catalog = Catalog::new(
+        Locale("ru".to_string()),
+        vec![
+            ("inbox.count.one", "{count} письмо для {name}"),
+            ("inbox.count.few", "{count} письма для {name}"),
+            ("inbox.count.many", "{count} писем для {name}"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect(),
+    );
+    let args = [("name", "Ann".to_string())];
+    let t = |n| translate_with(Some(&catalog), "inbox.count", Some(n), &args);
+
+    assert_eq!(t(1), "1 письмо для Ann");
+    assert_eq!(t(3), "3 письма для Ann");
+    assert_eq!(t(11), "11 писем для Ann");
+    assert_eq!(t(22), "22 письма для Ann");
+    assert_eq!(translate_with(None, "missing", None, &[]), "missing");
+}
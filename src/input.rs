@@ -1,6 +1,29 @@
+use std::f32::consts::FRAC_PI_4;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
 use crate::{
-    element::rgb,
-    model::{Attribute, Color, Description, Element},
+    background, border,
+    element::{
+        align_left, alpha, behind_content, center_x, center_y, clip, column,
+        el, fill, fill_portion, id, in_front, move_down, move_right, move_up,
+        padding_each, padding_xy, pointer, px, rgb, rgba, rotate, row,
+        scrollbar_y, shrink, spacing, spacing_xy, transparent,
+    },
+    events::{
+        on, on_click, on_filtered, on_input, on_input_filtered, on_key_down,
+    },
+    flag::Flag,
+    focus::focusable,
+    font,
+    model::{
+        element, Attribute, Children, Color, Description, Element,
+        LayoutContext, NodeName, Part, PseudoClass, Shadow, Style,
+    },
+    primitives::Length,
+    style::Classes,
+    vdom::{self, Caret, EventData},
 };
 
 /// Input elements have a lot of constraints!
@@ -207,24 +230,87 @@ use crate::{
 /// Alternatively, see if it's reasonable to _not_ display an input
 /// if you'd normally disable it. Is there an option where it's
 /// only visible when it's editable?
-pub struct Placeholder(Vec<Attribute>, Element);
+pub struct Placeholder<Msg>(Vec<Attribute<Msg>>, Element<Msg>);
 
-pub fn white() -> Color {
+pub const fn white() -> Color {
     rgb(1.0, 1.0, 1.0)
 }
 
-pub fn dark_grey() -> Color {
+pub const fn dark_grey() -> Color {
     rgb(186.0 / 255.0, 189.0 / 255.0, 182.00 / 255.0)
 }
 
-pub fn charcoal() -> Color {
+pub const fn charcoal() -> Color {
     rgb((136.0 / 255.0), (138.0 / 255.0), (133.0 / 255.0))
 }
 
-pub fn placeholder(attrs: Vec<Attribute>, el: Element) -> Placeholder {
+pub fn placeholder<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    el: Element<Msg>,
+) -> Placeholder<Msg> {
     Placeholder(attrs, el)
 }
 
+/// Restyle a part of the widget this is given to, as in
+/// `checkbox(vec![part(checkbox::box_(), vec![rounded(7)])], config)`. The
+/// attributes go after the widget's own, so they win.
+pub fn part<Msg>(part: Part, attrs: Vec<Attribute<Msg>>) -> Attribute<Msg> {
+    Attribute::Part(part, attrs)
+}
+
+// Take the overrides for `part` out of a widget's attributes.
+fn take_part<Msg>(
+    attrs: &mut Vec<Attribute<Msg>>,
+    part: Part,
+) -> Vec<Attribute<Msg>> {
+    let mut overrides = vec![];
+    attrs.retain(|attr| match attr {
+        Attribute::Part(p, part_attrs) if *p == part => {
+            overrides.extend(part_attrs.iter().cloned());
+            false
+        }
+        _ => true,
+    });
+    overrides
+}
+
+/// The parts of `text` and `multiline` that can be restyled with `part`.
+pub mod text {
+    use crate::model::Part;
+
+    /// The `<input>` or `<textarea>` itself, inside the box drawn around it.
+    pub fn field() -> Part {
+        Part("text-field")
+    }
+}
+
+/// The parts of `slider` that can be restyled with `part`.
+pub mod slider {
+    use crate::model::Part;
+
+    /// The bar the thumb slides along, which the slider's own attributes
+    /// also go on.
+    pub fn track() -> Part {
+        Part("slider-track")
+    }
+
+    /// The thumb, after the attributes of the slider's `Thumb`.
+    pub fn thumb() -> Part {
+        Part("slider-thumb")
+    }
+}
+
+/// The parts of `checkbox` that can be restyled with `part`.
+pub mod checkbox {
+    use crate::model::Part;
+
+    /// What holds the icon, next to the label.
+    pub fn box_() -> Part {
+        Part("checkbox-box")
+    }
+}
+
+/// Where a visible label goes, relative to its input.
 pub enum LabelLocation {
     OnRight,
     OnLeft,
@@ -232,13 +318,22 @@ pub enum LabelLocation {
     Below,
 }
 
-pub enum Label {
-    Label(LabelLocation, Vec<Attribute>, Element),
+/// What every input is named by, for people and screen readers alike:
+/// an element shown next to it, made with `label_above` and the like, or
+/// text only screen readers get, made with `label_hidden`.
+///
+/// The input and a visible label are put together in a `<label>`, in a
+/// column or row depending on where the label goes, so clicking the label
+/// focuses the input. Give the input an `id` for screen readers to name
+/// inputs that aren't form fields, like `checkbox` and `radio`, after a
+/// visible label too: it's labelled by the id with `-label` added.
+pub enum Label<Msg> {
+    Label(LabelLocation, Vec<Attribute<Msg>>, Box<Element<Msg>>),
     HiddenLabel(String),
 }
 
-impl Label {
-    pub fn is_stacked(label: Label) -> bool {
+impl<Msg> Label<Msg> {
+    pub fn is_stacked(label: &Label<Msg>) -> bool {
         match label {
             Label::Label(loc, _, _) => match loc {
                 LabelLocation::OnRight => false,
@@ -251,20 +346,36 @@ impl Label {
     }
 }
 
-pub fn label_right(attrs: Vec<Attribute>, el: Element) -> Label {
-    Label::Label(LabelLocation::OnRight, attrs, el)
+/// To the right of the input, as is usual for checkboxes.
+pub fn label_right<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    el: Element<Msg>,
+) -> Label<Msg> {
+    Label::Label(LabelLocation::OnRight, attrs, Box::new(el))
 }
 
-pub fn label_left(attrs: Vec<Attribute>, el: Element) -> Label {
-    Label::Label(LabelLocation::OnLeft, attrs, el)
+/// To the left of the input.
+pub fn label_left<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    el: Element<Msg>,
+) -> Label<Msg> {
+    Label::Label(LabelLocation::OnLeft, attrs, Box::new(el))
 }
 
-pub fn label_above(attrs: Vec<Attribute>, el: Element) -> Label {
-    Label::Label(LabelLocation::Above, attrs, el)
+/// Above the input, as is usual for text inputs.
+pub fn label_above<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    el: Element<Msg>,
+) -> Label<Msg> {
+    Label::Label(LabelLocation::Above, attrs, Box::new(el))
 }
 
-pub fn label_below(attrs: Vec<Attribute>, el: Element) -> Label {
-    Label::Label(LabelLocation::Below, attrs, el)
+/// Below the input.
+pub fn label_below<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    el: Element<Msg>,
+) -> Label<Msg> {
+    Label::Label(LabelLocation::Below, attrs, Box::new(el))
 }
 
 /// Sometimes you may need to have a label which is not visible,
@@ -279,15 +390,1802 @@ pub fn label_below(attrs: Vec<Attribute>, el: Element) -> Label {
 ///
 /// Basically, a hidden label works when there are other
 /// contextual clues that sighted people can pick up on.
-pub fn label_hidden(label: String) -> Label {
+pub fn label_hidden<Msg>(label: String) -> Label<Msg> {
     Label::HiddenLabel(label)
 }
 
-pub fn hidden_label_attr(label: Label) -> Attribute {
+// The id of a visible label, for the input to be labelled by. It's made
+// from the `id` among the input's attributes, as ids have to be unique and
+// labels needn't be. An input without one is only named by the `<label>`
+// it's put in.
+fn label_id<Msg>(
+    label: &Label<Msg>,
+    attrs: &[Attribute<Msg>],
+) -> Option<String> {
+    match label {
+        Label::Label(..) => attrs.iter().rev().find_map(|a| match a {
+            Attribute::Attr(vdom::Attribute::Property(name, id))
+                if *name == "id" =>
+            {
+                Some(format!("{}-label", id))
+            }
+            _ => None,
+        }),
+        Label::HiddenLabel(_) => None,
+    }
+}
+
+/// What names an input after its label: `aria-label` with the text of a
+/// hidden label, or `aria-labelledby` pointing at a visible one with the
+/// id `label_id`.
+pub fn label_attr<Msg>(
+    label: &Label<Msg>,
+    label_id: Option<&str>,
+) -> Attribute<Msg> {
+    match label_id {
+        Some(id) => {
+            Attribute::Attr(vdom::Attribute::property("aria-labelledby", id))
+        }
+        None => hidden_label_attr(label),
+    }
+}
+
+pub fn hidden_label_attr<Msg>(label: &Label<Msg>) -> Attribute<Msg> {
     match label {
         Label::HiddenLabel(text_label) => {
-            Attribute::Describe(Description::Label(text_label))
+            Attribute::Describe(Description::Label(text_label.clone()))
         }
         Label::Label(_, _, _) => Attribute::None,
     }
 }
+
+pub struct ButtonConfig<Msg> {
+    /// Sent when the button is pressed, or `None` for a button that does
+    /// nothing, which screen readers announce as disabled.
+    pub on_press: Option<Msg>,
+    pub label: Element<Msg>,
+}
+
+/// A button that sends `on_press` when it's clicked, or when `Enter` or
+/// space is pressed while it has focus.
+///
+/// It's focusable, and announced as a button by screen readers, so there
+/// is no need to add either.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn button<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: ButtonConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let mut button_attrs = vec![
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        Attribute::html_class(format!(
+            "{} {} {} {}",
+            Classes::ContentCenterX.to_string(),
+            Classes::ContentCenterY.to_string(),
+            Classes::SEButton.to_string(),
+            Classes::NoTextSelection.to_string()
+        )),
+        pointer(),
+        Attribute::Describe(Description::Button),
+        focusable(),
+    ];
+    match config.on_press {
+        Some(msg) => {
+            let on_key = msg.clone();
+            button_attrs.push(on_click(msg));
+            button_attrs.push(on_key_down(move |key, _| match key {
+                "Enter" | " " => Some(on_key.clone()),
+                _ => None,
+            }));
+        }
+        None => button_attrs.push(Attribute::Attr(vdom::Attribute::property(
+            "aria-disabled",
+            "true",
+        ))),
+    }
+    button_attrs.extend(attrs);
+    element(
+        LayoutContext::AsEl,
+        NodeName::div(),
+        button_attrs,
+        Children::Unkeyed(vec![config.label]),
+    )
+}
+
+pub struct TextConfig<Msg> {
+    pub on_change: Arc<dyn Fn(String) -> Msg + Send + Sync>,
+    pub text: String,
+    /// Shown while the text is empty.
+    pub placeholder: Option<Placeholder<Msg>>,
+    pub label: Label<Msg>,
+}
+
+pub struct MultilineConfig<Msg> {
+    pub on_change: Arc<dyn Fn(String) -> Msg + Send + Sync>,
+    pub text: String,
+    pub placeholder: Option<Placeholder<Msg>>,
+    pub label: Label<Msg>,
+    pub spellcheck: bool,
+}
+
+enum TextKind {
+    Input(&'static str),
+    TextArea,
+}
+
+struct TextInput {
+    kind: TextKind,
+    spellchecked: bool,
+    // The `autocomplete` hint for browsers filling in forms.
+    autofill: Option<&'static str>,
+}
+
+// Where each of the attributes given to a text input goes: on the label
+// wrapping everything, the box drawn around the text, the paragraph a
+// multiline input grows with, the input itself, or the placeholder over
+// it.
+struct Redistributed<Msg> {
+    full_parent: Vec<Attribute<Msg>>,
+    parent: Vec<Attribute<Msg>>,
+    wrapper: Vec<Attribute<Msg>>,
+    input: Vec<Attribute<Msg>>,
+    cover: Vec<Attribute<Msg>>,
+}
+
+fn is_fill(length: &Length) -> bool {
+    match length {
+        Length::Fill(_) => true,
+        Length::Min(_, l) | Length::Max(_, l) => is_fill(l),
+        _ => false,
+    }
+}
+
+fn is_pixel(length: &Length) -> bool {
+    match length {
+        Length::Px(_) => true,
+        Length::Min(_, l) | Length::Max(_, l) => is_pixel(l),
+        _ => false,
+    }
+}
+
+fn redistribute<Msg>(
+    stacked: bool,
+    attrs: &[Attribute<Msg>],
+) -> Redistributed<Msg> {
+    let mut els = Redistributed {
+        full_parent: vec![],
+        parent: vec![],
+        wrapper: vec![],
+        input: vec![],
+        cover: vec![],
+    };
+    for attr in attrs {
+        let a = || attr.clone();
+        match attr {
+            Attribute::None | Attribute::Part(..) => {}
+            Attribute::Nearby(..) | Attribute::Class(..) => {
+                els.parent.push(a())
+            }
+            Attribute::Width(w) if is_fill(w) => {
+                els.full_parent.push(a());
+                els.parent.push(a());
+                els.input.push(a());
+            }
+            Attribute::Width(_) if stacked => els.full_parent.push(a()),
+            Attribute::Width(_) => els.parent.push(a()),
+            Attribute::Height(h) if !stacked || is_fill(h) => {
+                els.full_parent.push(a());
+                els.parent.push(a());
+            }
+            Attribute::Height(_) => els.parent.push(a()),
+            Attribute::AlignX(_) | Attribute::AlignY(_) => {
+                els.full_parent.push(a())
+            }
+            Attribute::Style(_, Style::Spacing(..))
+            | Attribute::Style(_, Style::SpacingLength(..)) => {
+                els.full_parent.push(a());
+                els.parent.push(a());
+                els.input.push(a());
+                els.wrapper.push(a());
+            }
+            Attribute::Style(_, Style::Padding(..))
+            | Attribute::Style(_, Style::BorderWidth(..))
+            | Attribute::Style(_, Style::Transform(_)) => {
+                els.parent.push(a());
+                els.cover.push(a());
+            }
+            Attribute::Style(_, Style::FontSize(_))
+            | Attribute::Style(_, Style::FontFamily(..)) => {
+                els.full_parent.push(a())
+            }
+            Attribute::Style(..) => els.parent.push(a()),
+            Attribute::Attr(_)
+            | Attribute::Event(_)
+            | Attribute::Describe(_)
+            | Attribute::TransformComponent(..) => els.input.push(a()),
+        }
+    }
+    els
+}
+
+fn has_focus_style<Msg>(attr: &Attribute<Msg>) -> bool {
+    matches!(
+        attr,
+        Attribute::Style(_, Style::PseudoSelector(PseudoClass::Focus, _))
+    )
+}
+
+fn default_text_box_style<Msg>() -> Vec<Attribute<Msg>> {
+    let mut attrs = vec![padding_xy(12, 12), border::rounded(3)];
+    attrs.extend([border::width(1), border::color(dark_grey())]);
+    attrs.extend(vec![
+        background::color(white()),
+        spacing(5),
+        Attribute::Width(fill()),
+        Attribute::Height(shrink()),
+    ]);
+    attrs
+}
+
+fn render_placeholder<Msg>(
+    placeholder: Placeholder<Msg>,
+    mut for_placeholder: Vec<Attribute<Msg>>,
+    on: bool,
+) -> Element<Msg> {
+    let Placeholder(attrs, placeholder) = placeholder;
+    for_placeholder.extend(vec![
+        font::color(charcoal()),
+        Attribute::html_class(format!(
+            "{} {}",
+            Classes::NoTextSelection.to_string(),
+            Classes::PassPointerEvents.to_string()
+        )),
+        clip(),
+        border::color(rgba(0.0, 0.0, 0.0, 0.0)),
+        background::color(rgba(0.0, 0.0, 0.0, 0.0)),
+        Attribute::Height(fill()),
+        Attribute::Width(fill()),
+        alpha(if on { 1.0 } else { 0.0 }),
+    ]);
+    for_placeholder.extend(attrs);
+    el(for_placeholder, placeholder)
+}
+
+// Put `input` with its label, in a `<label>` so clicking the label focuses
+// the input.
+fn apply_label<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    label: Label<Msg>,
+    label_id: Option<String>,
+    input: Element<Msg>,
+) -> Element<Msg> {
+    let label_node = || NodeName::NodeName("label".to_string());
+    match label {
+        Label::HiddenLabel(_) => element(
+            LayoutContext::AsColumn,
+            label_node(),
+            attrs,
+            Children::Unkeyed(vec![input]),
+        ),
+        Label::Label(location, mut label_attrs, label) => {
+            label_attrs.extend(label_id.map(id));
+            let label = element(
+                LayoutContext::AsEl,
+                NodeName::div(),
+                label_attrs,
+                Children::Unkeyed(vec![*label]),
+            );
+            attrs.insert(
+                0,
+                Attribute::html_class(Classes::InputLabel.to_string().into()),
+            );
+            let (context, children) = match location {
+                LabelLocation::Above => {
+                    (LayoutContext::AsColumn, vec![label, input])
+                }
+                LabelLocation::Below => {
+                    (LayoutContext::AsColumn, vec![input, label])
+                }
+                LabelLocation::OnRight => {
+                    (LayoutContext::AsRow, vec![input, label])
+                }
+                LabelLocation::OnLeft => {
+                    (LayoutContext::AsRow, vec![label, input])
+                }
+            };
+            element(context, label_node(), attrs, Children::Unkeyed(children))
+        }
+    }
+}
+
+#[cfg_attr(feature = "debug-locations", track_caller)]
+fn text_helper<Msg: 'static>(
+    text_input: TextInput,
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let TextConfig {
+        on_change,
+        text,
+        placeholder,
+        label,
+    } = config;
+    let mut attrs = attrs;
+    let field = take_part(&mut attrs, text::field());
+    let label_id = label_id(&label, &attrs);
+    let mut with_defaults = default_text_box_style();
+    with_defaults.extend(attrs);
+    let multiline = matches!(text_input.kind, TextKind::TextArea);
+    let stacked = Label::is_stacked(&label);
+    let redistributed = redistribute(stacked, &with_defaults);
+    let height_constrained = multiline
+        && with_defaults
+            .iter()
+            .rev()
+            .find_map(|a| match a {
+                Attribute::Height(h) => Some(!matches!(h, Length::Content)),
+                _ => None,
+            })
+            .unwrap_or(false);
+    let parent_padding = with_defaults
+        .iter()
+        .rev()
+        .find_map(|a| match a {
+            Attribute::Style(_, Style::Padding(_, t, r, b, l)) => {
+                Some((*t, *r, *b, *l))
+            }
+            _ => None,
+        })
+        .unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let focus_class = if with_defaults.iter().any(has_focus_style) {
+        Attribute::None
+    } else {
+        Attribute::html_class(Classes::FocusedWithin.to_string().into())
+    };
+
+    let (node_name, mut input_attrs) = match text_input.kind {
+        TextKind::Input(input_type) => (
+            "input",
+            vec![
+                Attribute::Attr(vdom::Attribute::property("type", input_type)),
+                Attribute::html_class(Classes::InputText.to_string().into()),
+            ],
+        ),
+        TextKind::TextArea => {
+            let (t, r, b, l) = parent_padding;
+            // Moved up by half the line spacing, which the paragraph behind
+            // it adds above each line, so the text lines up with it.
+            let spacing_y = with_defaults.iter().find_map(|a| match a {
+                Attribute::Style(_, Style::Spacing(_, _, y)) => Some(*y),
+                _ => None,
+            });
+            (
+                "textarea",
+                vec![
+                    clip(),
+                    Attribute::Height(fill()),
+                    Attribute::html_class(
+                        Classes::InputMultiline.to_string().into(),
+                    ),
+                    spacing_y
+                        .map_or(Attribute::None, |y| move_up((y / 2) as f32)),
+                    padding_each(t as u32, r as u32, b as u32, l as u32),
+                    Attribute::Attr(vdom::html::attributes::style(
+                        "margin".to_string(),
+                        format!("{}px {}px {}px {}px", -t, -r, -b, -l),
+                    )),
+                    Attribute::Attr(vdom::html::attributes::style(
+                        "box-sizing".to_string(),
+                        "content-box".to_string(),
+                    )),
+                ],
+            )
+        }
+    };
+    input_attrs.extend(vec![
+        Attribute::Attr(vdom::Attribute::property("value", &text)),
+        on_input(move |text| on_change(text)),
+        label_attr(&label, label_id.as_deref()),
+        Attribute::Attr(vdom::Attribute::property(
+            "spellcheck",
+            text_input.spellchecked,
+        )),
+        text_input.autofill.map_or(Attribute::None, |autofill| {
+            Attribute::Attr(vdom::Attribute::property("autocomplete", autofill))
+        }),
+    ]);
+    input_attrs.extend(redistributed.input);
+    input_attrs.extend(field);
+    let input = element(
+        LayoutContext::AsEl,
+        NodeName::NodeName(node_name.to_string()),
+        input_attrs,
+        Children::Unkeyed(vec![]),
+    );
+
+    let wrapped = if multiline {
+        // The text is drawn again, invisibly, behind the textarea, for the
+        // input to grow with it.
+        let filler = if text.is_empty() {
+            match placeholder {
+                Some(placeholder) => {
+                    render_placeholder(placeholder, vec![], true)
+                }
+                None => Element::Text("\u{a0}".to_string()),
+            }
+        } else {
+            element(
+                LayoutContext::AsEl,
+                NodeName::NodeName("span".to_string()),
+                vec![Attribute::html_class(
+                    Classes::InputMultilineFiller.to_string().into(),
+                )],
+                Children::Unkeyed(vec![Element::Text(format!(
+                    "{}\u{a0}",
+                    text
+                ))]),
+            )
+        };
+        let mut paragraph_attrs = vec![
+            Attribute::Width(fill()),
+            Attribute::Height(fill()),
+            in_front(input),
+            Attribute::html_class(
+                Classes::InputMultilineParent.to_string().into(),
+            ),
+        ];
+        paragraph_attrs.extend(redistributed.wrapper);
+        let mut wrapper_attrs = vec![];
+        if height_constrained {
+            wrapper_attrs.push(scrollbar_y());
+        }
+        wrapper_attrs.extend(vec![
+            Attribute::Width(fill()),
+            focus_class,
+            Attribute::html_class(
+                Classes::InputMultilineWrapper.to_string().into(),
+            ),
+        ]);
+        wrapper_attrs.extend(redistributed.parent);
+        el(
+            wrapper_attrs,
+            element(
+                LayoutContext::AsParagraph,
+                NodeName::div(),
+                paragraph_attrs,
+                Children::Unkeyed(vec![filler]),
+            ),
+        )
+    } else {
+        let mut parent_attrs = vec![Attribute::Width(fill()), focus_class];
+        parent_attrs.extend(redistributed.parent);
+        if let Some(placeholder) = placeholder {
+            parent_attrs.push(behind_content(render_placeholder(
+                placeholder,
+                redistributed.cover,
+                text.is_empty(),
+            )));
+        }
+        el(parent_attrs, input)
+    };
+
+    let mut label_attrs = vec![
+        Attribute::Class(
+            Flag::cursor(),
+            Classes::CursorText.to_string().into(),
+        ),
+        if matches!(label, Label::HiddenLabel(_)) {
+            Attribute::None
+        } else {
+            spacing(5)
+        },
+        Attribute::Describe(Description::LivePolite),
+    ];
+    label_attrs.extend(redistributed.full_parent);
+    apply_label(label_attrs, label, label_id, wrapped)
+}
+
+/// A single line of text, sending `on_change` with the new text as it's
+/// typed. The input is styled with a border, padding and a white
+/// background by default, which `attrs` can override.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn text<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let text_input = TextInput {
+        kind: TextKind::Input("text"),
+        spellchecked: false,
+        autofill: None,
+    };
+    text_helper(text_input, attrs, config)
+}
+
+/// A `text` input with the browser's spellchecking turned on.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn spell_checked<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let text_input = TextInput {
+        kind: TextKind::Input("text"),
+        spellchecked: true,
+        autofill: None,
+    };
+    text_helper(text_input, attrs, config)
+}
+
+/// A `text` input for searching, which browsers may give a button for
+/// clearing it.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn search<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let text_input = TextInput {
+        kind: TextKind::Input("search"),
+        spellchecked: false,
+        autofill: None,
+    };
+    text_helper(text_input, attrs, config)
+}
+
+/// A `text` input for a username, which browsers can fill in.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn username<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let text_input = TextInput {
+        kind: TextKind::Input("text"),
+        spellchecked: false,
+        autofill: Some("username"),
+    };
+    text_helper(text_input, attrs, config)
+}
+
+/// A `text` input for an email address, which browsers can fill in, and
+/// on phones bring up a keyboard with an `@`.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn email<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: TextConfig<Msg>,
+) -> Element<Msg> {
+    let text_input = TextInput {
+        kind: TextKind::Input("email"),
+        spellchecked: false,
+        autofill: Some("email"),
+    };
+    text_helper(text_input, attrs, config)
+}
+
+pub struct PasswordConfig<Msg> {
+    pub on_change: Arc<dyn Fn(String) -> Msg + Send + Sync>,
+    pub text: String,
+    pub placeholder: Option<Placeholder<Msg>>,
+    pub label: Label<Msg>,
+    /// Show the password as text rather than dots.
+    pub show: bool,
+}
+
+#[cfg_attr(feature = "debug-locations", track_caller)]
+fn password<Msg: 'static>(
+    autofill: &'static str,
+    attrs: Vec<Attribute<Msg>>,
+    config: PasswordConfig<Msg>,
+) -> Element<Msg> {
+    let PasswordConfig {
+        on_change,
+        text,
+        placeholder,
+        label,
+        show,
+    } = config;
+    let text_input = TextInput {
+        kind: TextKind::Input(if show { "text" } else { "password" }),
+        spellchecked: false,
+        autofill: Some(autofill),
+    };
+    let config = TextConfig {
+        on_change,
+        text,
+        placeholder,
+        label,
+    };
+    text_helper(text_input, attrs, config)
+}
+
+/// A password being chosen, for which browsers can suggest a strong one
+/// and offer to save it.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn new_password<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: PasswordConfig<Msg>,
+) -> Element<Msg> {
+    password("new-password", attrs, config)
+}
+
+/// A password being entered to sign in, which browsers can fill in with
+/// one they saved.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn current_password<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: PasswordConfig<Msg>,
+) -> Element<Msg> {
+    password("current-password", attrs, config)
+}
+
+/// A text input that can be several lines long, growing to fit its text
+/// unless its height is set, when it scrolls instead.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn multiline<Msg: 'static>(
+    attrs: Vec<Attribute<Msg>>,
+    config: MultilineConfig<Msg>,
+) -> Element<Msg> {
+    let MultilineConfig {
+        on_change,
+        text,
+        placeholder,
+        label,
+        spellcheck,
+    } = config;
+    let text_input = TextInput {
+        kind: TextKind::TextArea,
+        spellchecked: spellcheck,
+        autofill: None,
+    };
+    let config = TextConfig {
+        on_change,
+        text,
+        placeholder,
+        label,
+    };
+    text_helper(text_input, attrs, config)
+}
+
+pub struct CheckboxConfig<Msg> {
+    /// Sent with whether the box is checked after it's clicked.
+    pub on_change: Arc<dyn Fn(bool) -> Msg + Send + Sync>,
+    /// Draws the box, checked or not, e.g. `default_checkbox`.
+    pub icon: Box<dyn Fn(bool) -> Element<Msg>>,
+    pub checked: bool,
+    pub label: Label<Msg>,
+}
+
+/// A checkbox, toggled by clicking it or its label, or by pressing
+/// `Enter` or space while it has focus.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn checkbox<Msg: 'static>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: CheckboxConfig<Msg>,
+) -> Element<Msg> {
+    let CheckboxConfig {
+        on_change,
+        icon,
+        checked,
+        label,
+    } = config;
+    let on_key = on_change.clone();
+    let box_ = take_part(&mut attrs, checkbox::box_());
+    let label_id = label_id(&label, &attrs);
+    let mut attributes = vec![
+        if matches!(label, Label::HiddenLabel(_)) {
+            Attribute::None
+        } else {
+            spacing(6)
+        },
+        on("click".to_string(), move |_| on_change(!checked)),
+        Attribute::Describe(Description::LivePolite),
+        on_key_down(move |key, _| match key {
+            "Enter" | " " => Some(on_key(!checked)),
+            _ => None,
+        }),
+        focusable(),
+        pointer(),
+        align_left(),
+        Attribute::Width(fill()),
+    ];
+    attributes.extend(attrs);
+    let mut box_attrs = vec![
+        Attribute::Attr(vdom::Attribute::property("role", "checkbox")),
+        Attribute::Attr(vdom::Attribute::property("aria-checked", checked)),
+        label_attr(&label, label_id.as_deref()),
+        center_y(),
+        Attribute::Height(fill()),
+        Attribute::Width(shrink()),
+    ];
+    box_attrs.extend(box_);
+    let checkbox = element(
+        LayoutContext::AsEl,
+        NodeName::div(),
+        box_attrs,
+        Children::Unkeyed(vec![icon(checked)]),
+    );
+    apply_label(attributes, label, label_id, checkbox)
+}
+
+/// The checkbox browsers usually draw: a blue box with a tick when it's
+/// checked, and a grey outline when it isn't.
+pub fn default_checkbox<Msg>(checked: bool) -> Element<Msg> {
+    let blue = rgb(59.0 / 255.0, 153.0 / 255.0, 252.0 / 255.0);
+    let light_grey = rgb(211.0 / 255.0, 211.0 / 255.0, 211.0 / 255.0);
+    let tick = el(
+        vec![
+            border::color(white()),
+            Attribute::Height(px(6)),
+            Attribute::Width(px(9)),
+            rotate(-FRAC_PI_4),
+            center_x(),
+            center_y(),
+            move_up(1.0),
+            transparent(!checked),
+            border_width_each(0, 0, 2, 2),
+        ],
+        Element::Empty,
+    );
+    let border_width = if checked { 0 } else { 1 };
+    let attrs = vec![
+        Attribute::html_class("focusable".to_string()),
+        Attribute::Width(px(14)),
+        Attribute::Height(px(14)),
+        font::color(white()),
+        center_y(),
+        font::size(9),
+        Attribute::Class(
+            Flag::font_alignment(),
+            Classes::TextCenter.to_string().into(),
+        ),
+        border::rounded(3),
+        border::color(if checked { blue } else { light_grey }),
+        border::shadow(Shadow {
+            offset: (0, 0),
+            blur: 1,
+            size: 1,
+            color: if checked {
+                rgba(238.0 / 255.0, 238.0 / 255.0, 238.0 / 255.0, 0.0)
+            } else {
+                rgb(238.0 / 255.0, 238.0 / 255.0, 238.0 / 255.0)
+            },
+        }),
+        background::color(if checked { blue } else { white() }),
+        border_width_each(
+            border_width,
+            border_width,
+            border_width,
+            border_width,
+        ),
+        in_front(tick),
+    ];
+    el(attrs, Element::Empty)
+}
+
+/// How the thumb of a `slider` looks, made with `thumb` or `default_thumb`.
+pub struct Thumb<Msg>(Vec<Attribute<Msg>>);
+
+pub fn thumb<Msg>(attrs: Vec<Attribute<Msg>>) -> Thumb<Msg> {
+    Thumb(attrs)
+}
+
+/// A white circle with a grey border.
+pub fn default_thumb<Msg>() -> Thumb<Msg> {
+    let mut attrs = vec![
+        Attribute::Width(px(16)),
+        Attribute::Height(px(16)),
+        border::rounded(8),
+    ];
+    attrs.extend([border::width(1), border::color(rgb(0.5, 0.5, 0.5))]);
+    attrs.push(background::color(white()));
+    Thumb(attrs)
+}
+
+pub struct SliderConfig<Msg> {
+    /// Sent with the new value as the slider is moved.
+    pub on_change: Arc<dyn Fn(f32) -> Msg + Send + Sync>,
+    pub label: Label<Msg>,
+    pub min: f32,
+    pub max: f32,
+    /// The values the slider snaps to, or any value if `None`.
+    pub step: Option<f32>,
+    pub value: f32,
+    pub thumb: Thumb<Msg>,
+}
+
+/// A slider for choosing a number between `min` and `max`, by dragging the
+/// thumb or with the arrow keys, `Home` and `End` while it has focus.
+///
+/// `attrs` style the track, behind the thumb. A track taller than it is
+/// wide, as with `width(px(10))` and `height(fill())`, makes the slider
+/// vertical, with `max` at the top.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn slider<Msg: 'static>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: SliderConfig<Msg>,
+) -> Element<Msg> {
+    let SliderConfig {
+        on_change,
+        label,
+        min,
+        max,
+        step,
+        value,
+        thumb: Thumb(thumb_attrs),
+    } = config;
+    let track_part = take_part(&mut attrs, slider::track());
+    let thumb_part = take_part(&mut attrs, slider::thumb());
+    let label_id = label_id(&label, &attrs);
+    let track_width = attrs.iter().rev().find_map(|a| match a {
+        Attribute::Width(w) => Some(w.clone()),
+        _ => None,
+    });
+    let track_height = attrs.iter().rev().find_map(|a| match a {
+        Attribute::Height(h) => Some(h.clone()),
+        _ => None,
+    });
+    let vertical = match (&track_width, &track_height) {
+        (Some(Length::Px(w)), Some(Length::Px(h))) => h > w,
+        (Some(Length::Px(_)), Some(Length::Fill(_))) => true,
+        _ => false,
+    };
+    let (spacing_x, spacing_y) = attrs
+        .iter()
+        .rev()
+        .find_map(|a| match a {
+            Attribute::Style(_, Style::Spacing(_, x, y)) => Some((*x, *y)),
+            _ => None,
+        })
+        .unwrap_or((5, 5));
+    let factor = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let width = track_width.clone().unwrap_or_else(fill);
+    let height = track_height.clone().unwrap_or(px(20));
+
+    let mut thumb_attrs = {
+        let mut all = vec![Attribute::html_class("focusable-thumb".into())];
+        all.extend(thumb_attrs);
+        all.extend(thumb_part);
+        all
+    };
+    // The thumb sits between two spaces filling the rest of the track in
+    // proportion to where the value is.
+    let portion = |f: f32| fill_portion((f * 10000.0).round() as u64);
+    let thumb_view = if vertical {
+        thumb_attrs.insert(0, center_x());
+        column(
+            vec![
+                Attribute::Width(width.clone()),
+                Attribute::Height(fill()),
+                center_x(),
+            ],
+            vec![
+                el(
+                    vec![Attribute::Height(portion(1.0 - factor))],
+                    Element::Empty,
+                ),
+                el(thumb_attrs, Element::Empty),
+                el(vec![Attribute::Height(portion(factor))], Element::Empty),
+            ],
+        )
+    } else {
+        thumb_attrs.insert(0, center_y());
+        row(
+            vec![
+                Attribute::Width(fill()),
+                Attribute::Height(track_height.clone().unwrap_or_else(fill)),
+                center_y(),
+            ],
+            vec![
+                el(vec![Attribute::Width(portion(factor))], Element::Empty),
+                el(thumb_attrs, Element::Empty),
+                el(
+                    vec![Attribute::Width(portion(1.0 - factor))],
+                    Element::Empty,
+                ),
+            ],
+        )
+    };
+
+    let step_by = step.unwrap_or((max - min) / 100.0);
+    let on_key = on_change.clone();
+    let input = element(
+        LayoutContext::AsEl,
+        NodeName::NodeName("input".to_string()),
+        vec![
+            // Followed by the track, whose thumb `FocusStyle` rings while
+            // the input has focus.
+            Attribute::html_class("ui-slide-bar".to_string()),
+            Attribute::Attr(vdom::Attribute::property("type", "range")),
+            Attribute::Attr(vdom::Attribute::property(
+                "step",
+                step.map_or("any".to_string(), |step| step.to_string()),
+            )),
+            Attribute::Attr(vdom::Attribute::property("min", min)),
+            Attribute::Attr(vdom::Attribute::property("max", max)),
+            Attribute::Attr(vdom::Attribute::property("value", value)),
+            Attribute::Attr(vdom::Attribute::property(
+                "orient",
+                if vertical { "vertical" } else { "horizontal" },
+            )),
+            label_attr(&label, label_id.as_deref()),
+            on_filtered("input".to_string(), move |data| match data {
+                EventData::Text(text) => {
+                    text.parse().ok().map(|v| on_change(v))
+                }
+                _ => None,
+            }),
+            on_key_down(move |key, _| {
+                let to = match key {
+                    "ArrowRight" | "ArrowUp" => value + step_by,
+                    "ArrowLeft" | "ArrowDown" => value - step_by,
+                    "Home" => min,
+                    "End" => max,
+                    _ => return None,
+                };
+                let to = to.clamp(min, max.max(min));
+                if to == value {
+                    None
+                } else {
+                    Some(on_key(to))
+                }
+            }),
+            Attribute::Width(width.clone()),
+            Attribute::Height(height.clone()),
+        ],
+        Children::Unkeyed(vec![]),
+    );
+    let mut track_attrs = vec![
+        Attribute::Width(width.clone()),
+        Attribute::Height(height.clone()),
+    ];
+    track_attrs.extend(attrs);
+    track_attrs.extend(track_part);
+    // After the slider's attributes, for the thumb to be in front of them.
+    track_attrs.push(behind_content(thumb_view));
+    let track = el(track_attrs, Element::Empty);
+
+    let label_attrs = vec![
+        if matches!(label, Label::HiddenLabel(_)) {
+            Attribute::None
+        } else {
+            spacing_xy(spacing_x, spacing_y)
+        },
+        Attribute::Describe(Description::LivePolite),
+        Attribute::Width(match &track_width {
+            None => fill(),
+            Some(Length::Px(_)) => shrink(),
+            Some(w) => w.clone(),
+        }),
+        Attribute::Height(match &track_height {
+            None | Some(Length::Px(_)) => shrink(),
+            Some(h) => h.clone(),
+        }),
+    ];
+    apply_label(
+        label_attrs,
+        label,
+        label_id,
+        row(
+            vec![Attribute::Width(width), Attribute::Height(height)],
+            vec![input, track],
+        ),
+    )
+}
+
+/// How a radio option is drawn, for custom options made with `option_with`.
+/// The arrow keys move the selection itself rather than a separate focus,
+/// so `radio` only draws options `Idle` or `Selected`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionState {
+    Idle,
+    Focused,
+    Selected,
+}
+
+/// One of the choices of a `radio`, made with `option` or `option_with`.
+pub struct RadioOption<T, Msg>(T, Box<dyn Fn(OptionState) -> Element<Msg>>);
+
+/// An option with the default radio icon next to `label`.
+pub fn option<T, Msg: 'static>(
+    value: T,
+    label: Element<Msg>,
+) -> RadioOption<T, Msg> {
+    RadioOption(
+        value,
+        Box::new(move |state| default_radio_option(label.clone(), state)),
+    )
+}
+
+/// An option drawn entirely by `view`, which is told whether it's selected.
+pub fn option_with<T, Msg>(
+    value: T,
+    view: impl Fn(OptionState) -> Element<Msg> + 'static,
+) -> RadioOption<T, Msg> {
+    RadioOption(value, Box::new(view))
+}
+
+fn default_radio_option<Msg>(
+    label: Element<Msg>,
+    state: OptionState,
+) -> Element<Msg> {
+    let grey = rgb(208.0 / 255.0, 208.0 / 255.0, 208.0 / 255.0);
+    let blue = rgb(59.0 / 255.0, 153.0 / 255.0, 252.0 / 255.0);
+    let (border_width, color) = match state {
+        OptionState::Idle | OptionState::Focused => (1, grey),
+        OptionState::Selected => (5, blue),
+    };
+    let icon = el(
+        vec![
+            Attribute::Width(px(14)),
+            Attribute::Height(px(14)),
+            background::color(white()),
+            border::rounded(7),
+            if state == OptionState::Selected {
+                Attribute::html_class("focusable".to_string())
+            } else {
+                Attribute::None
+            },
+            border_width_each(
+                border_width,
+                border_width,
+                border_width,
+                border_width,
+            ),
+            border::color(color),
+        ],
+        Element::Empty,
+    );
+    row(
+        vec![spacing(10), align_left(), Attribute::Width(shrink())],
+        vec![
+            icon,
+            el(
+                vec![
+                    Attribute::Width(fill()),
+                    Attribute::html_class("unfocusable".to_string()),
+                ],
+                label,
+            ),
+        ],
+    )
+}
+
+pub struct RadioConfig<T, Msg> {
+    /// Sent with the value of the option that's chosen.
+    pub on_change: Arc<dyn Fn(T) -> Msg + Send + Sync>,
+    pub options: Vec<RadioOption<T, Msg>>,
+    pub selected: Option<T>,
+    pub label: Label<Msg>,
+}
+
+enum Orientation {
+    Row,
+    Column,
+}
+
+/// A column of options, of which one can be chosen by clicking it or, with
+/// the group focused, by moving through them with the arrow keys.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn radio<T, Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: RadioConfig<T, Msg>,
+) -> Element<Msg>
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    Msg: 'static,
+{
+    radio_helper(Orientation::Column, attrs, config)
+}
+
+/// Like `radio`, with the options laid out in a row.
+#[cfg_attr(feature = "debug-locations", track_caller)]
+pub fn radio_row<T, Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: RadioConfig<T, Msg>,
+) -> Element<Msg>
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    Msg: 'static,
+{
+    radio_helper(Orientation::Row, attrs, config)
+}
+
+#[cfg_attr(feature = "debug-locations", track_caller)]
+fn radio_helper<T, Msg>(
+    orientation: Orientation,
+    attrs: Vec<Attribute<Msg>>,
+    config: RadioConfig<T, Msg>,
+) -> Element<Msg>
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    Msg: 'static,
+{
+    let RadioConfig {
+        on_change,
+        options,
+        selected,
+        label,
+    } = config;
+    let values: Vec<T> = options.iter().map(|o| o.0.clone()).collect();
+    // The options before and after the selected one, wrapping around, or the
+    // first if none is selected yet.
+    let position = values.iter().position(|v| Some(v) == selected.as_ref());
+    let prev_next = match position {
+        _ if values.is_empty() => None,
+        None => Some((values[0].clone(), values[0].clone())),
+        Some(i) => Some((
+            values[(i + values.len() - 1) % values.len()].clone(),
+            values[(i + 1) % values.len()].clone(),
+        )),
+    };
+
+    let stretched = matches!(orientation, Orientation::Column);
+    let rendered = options
+        .into_iter()
+        .map(|RadioOption(value, view)| {
+            let state = if Some(&value) == selected.as_ref() {
+                OptionState::Selected
+            } else {
+                OptionState::Idle
+            };
+            let on_change = on_change.clone();
+            el(
+                vec![
+                    pointer(),
+                    Attribute::Width(if stretched { fill() } else { shrink() }),
+                    on("click".to_string(), move |_| on_change(value.clone())),
+                    Attribute::Attr(vdom::Attribute::property(
+                        "aria-checked",
+                        state == OptionState::Selected,
+                    )),
+                    Attribute::Attr(vdom::Attribute::property("role", "radio")),
+                ],
+                view(state),
+            )
+        })
+        .collect();
+    let label_id = label_id(&label, &attrs);
+    let mut area_attrs = vec![label_attr(&label, label_id.as_deref())];
+    area_attrs.extend(attrs.iter().cloned());
+    let area = match orientation {
+        Orientation::Row => row(area_attrs, rendered),
+        Orientation::Column => column(area_attrs, rendered),
+    };
+
+    let mut group_attrs = vec![
+        align_left(),
+        focusable(),
+        Attribute::html_class(Classes::Focus.to_string().into()),
+        Attribute::Describe(Description::LivePolite),
+        Attribute::Attr(vdom::Attribute::property("role", "radiogroup")),
+    ];
+    group_attrs.extend(attrs.into_iter().filter(|a| match a {
+        Attribute::Width(l) | Attribute::Height(l) => is_fill(l),
+        _ => false,
+    }));
+    if let Some((prev, next)) = prev_next {
+        let nothing_selected = selected.is_none();
+        group_attrs.push(on_key_down(move |key, _| match key {
+            "ArrowLeft" | "ArrowUp" => Some(on_change(prev.clone())),
+            "ArrowRight" | "ArrowDown" => Some(on_change(next.clone())),
+            " " if nothing_selected => Some(on_change(prev.clone())),
+            _ => None,
+        }));
+    }
+    apply_label(group_attrs, label, label_id, area)
+}
+
+fn border_width_each<Msg>(
+    top: u32,
+    right: u32,
+    bottom: u32,
+    left: u32,
+) -> Attribute<Msg> {
+    let class = if top == right && top == bottom && top == left {
+        format!("b-{}", top)
+    } else {
+        format!("b-{}-{}-{}-{}", top, right, bottom, left)
+    };
+    Attribute::Style(
+        Flag::border_width(),
+        Style::BorderWidth(class, top, right, bottom, left),
+    )
+}
+
+/// A field of the model shown in an input, with the message that changes
+/// it, so a controlled input is one expression. It's usually made with
+/// `bind!`, as in `bind!(model.age, Msg::SetAge).attrs()`.
+///
+/// Text that doesn't parse as a `T`, like `4x` for a number, sends nothing,
+/// so the model keeps its last value while the input shows what was typed.
+pub struct Binding<T, Msg> {
+    value: T,
+    on_change: Arc<dyn Fn(T) -> Msg + Send + Sync>,
+}
+
+impl<T, Msg> Binding<T, Msg>
+where
+    T: Display + FromStr + 'static,
+    Msg: 'static,
+{
+    pub fn new<F>(value: T, on_change: F) -> Self
+    where
+        F: Fn(T) -> Msg + Send + Sync + 'static,
+    {
+        Binding {
+            value,
+            on_change: Arc::new(on_change),
+        }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The input's `value` and the listener sending its changes.
+    pub fn attrs(&self) -> Vec<Attribute<Msg>> {
+        let on_change = self.on_change.clone();
+        vec![
+            Attribute::Attr(vdom::Attribute::property("value", &self.value)),
+            on_input_filtered(move |text| text.parse().ok().map(&*on_change)),
+        ]
+    }
+}
+
+/// Bind a field of the model to an input, with the message sending its
+/// new value, like `bind!(model.name, Msg::NameChanged)`.
+#[macro_export]
+macro_rules! bind {
+    ($value:expr, $on_change:expr $(,)?) => {
+        $crate::input::Binding::new($value.clone(), $on_change)
+    };
+}
+
+/// Show `popover` just under the caret of this text input, e.g. a picker
+/// for the `@`-mention being typed. `caret` is the last one reported by
+/// `on_caret_move`, and nothing is shown without one.
+pub fn popover_at_caret<Msg>(
+    caret: Option<Caret>,
+    popover: Element<Msg>,
+) -> Attribute<Msg> {
+    match caret {
+        Some(caret) => in_front(el(
+            vec![move_right(caret.x), move_down(caret.y + caret.line_height)],
+            popover,
+        )),
+        None => Attribute::None,
+    }
+}
+
+/// The word being typed at `caret` if it starts with `trigger`, like the
+/// `ali` of `hi @ali` for `@`, with where the trigger is, in characters.
+/// A picker for it is usually shown while there is one.
+pub fn trigger_query(
+    text: &str,
+    caret: usize,
+    trigger: char,
+) -> Option<(usize, &str)> {
+    let end = text
+        .char_indices()
+        .nth(caret)
+        .map_or(text.len(), |(i, _)| i);
+    let before = &text[..end];
+    let start = before.rfind(|c: char| c.is_whitespace() || c == trigger)?;
+    if !before[start..].starts_with(trigger) {
+        return None;
+    }
+    // The trigger starts a word, so `a@b` isn't a mention.
+    if before[..start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| !c.is_whitespace())
+    {
+        return None;
+    }
+    let query = &before[start + trigger.len_utf8()..];
+    Some((before[..start].chars().count(), query))
+}
+
+#[test]
+fn test_trigger_query() {
+    assert_eq!(trigger_query("hi @ali", 7, '@'), Some((3, "ali")));
+    assert_eq!(trigger_query("hi @ali and", 5, '@'), Some((3, "a")));
+    assert_eq!(trigger_query("/cmd", 4, '/'), Some((0, "cmd")));
+    assert_eq!(trigger_query("hi @ali x", 9, '@'), None);
+    assert_eq!(trigger_query("mail a@b", 8, '@'), None);
+    assert_eq!(trigger_query("é @", 3, '@'), Some((2, "")));
+
+    let caret = Caret {
+        offset: 3,
+        x: 20.0,
+        y: 16.0,
+        line_height: 16.0,
+    };
+    assert!(matches!(
+        popover_at_caret::<()>(Some(caret), Element::Empty),
+        Attribute::Nearby(..)
+    ));
+    assert!(matches!(
+        popover_at_caret::<()>(None, Element::Empty),
+        Attribute::None
+    ));
+}
+
+#[test]
+fn test_binding() {
+    #[derive(Debug, PartialEq)]
+    enum Msg {
+        SetAge(u32),
+    }
+    struct Model {
+        age: u32,
+    }
+
+    let model = Model { age: 42 };
+    let binding = bind!(model.age, Msg::SetAge);
+    assert_eq!(*binding.value(), 42);
+    let attrs = binding.attrs();
+    assert!(matches!(
+        &attrs[0],
+        Attribute::Attr(vdom::Attribute::Property(name, value))
+            if name == "value" && value == "42"
+    ));
+    let listener = match &attrs[1] {
+        Attribute::Event(listener) => listener,
+        _ => unreachable!(),
+    };
+    let send =
+        |text: &str| listener.handler.handle(&EventData::Text(text.into()));
+    assert_eq!(send("43"), Some(Msg::SetAge(43)));
+    assert_eq!(send("4x"), None);
+}
+
+#[test]
+fn test_button() {
+    use crate::{app::Program, cmd::Cmd, render::to_html};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Msg {
+        Save,
+    }
+
+    fn view(ready: &bool) -> Element<Msg> {
+        button(
+            vec![],
+            ButtonConfig {
+                on_press: if *ready { Some(Msg::Save) } else { None },
+                label: Element::Text("Save".to_string()),
+            },
+        )
+    }
+
+    let mut program = Program::new(|| true, |_, _| Cmd::none(), view);
+    let html = to_html(program.render());
+    assert!(html.contains("role=\"button\""));
+    assert!(html.contains("tabindex=\"0\""));
+    assert!(html.contains(Classes::SEButton.to_string()));
+
+    let path = [0, 2, 0];
+    assert_eq!(
+        program.dispatch(&path, "click", &EventData::None),
+        [Msg::Save]
+    );
+    program.focus(&path);
+    assert_eq!(program.key_down("Enter", false), [Msg::Save]);
+    assert_eq!(program.key_down(" ", false), [Msg::Save]);
+    assert!(program.key_down("a", false).is_empty());
+
+    let mut disabled = Program::new(|| false, |_, _| Cmd::none(), view);
+    assert!(to_html(disabled.render()).contains("aria-disabled=\"true\""));
+    assert!(disabled
+        .dispatch(&path, "click", &EventData::None)
+        .is_empty());
+}
+
+#[test]
+fn test_text_inputs() {
+    use crate::{
+        cmd::Cmd,
+        element::id,
+        render::to_html,
+        test::{find_by_id, TestHarness},
+    };
+
+    struct Form {
+        name: String,
+    }
+
+    fn view(form: &Form) -> Element<String> {
+        text(
+            vec![id("name".to_string())],
+            TextConfig {
+                on_change: Arc::new(|text| text),
+                text: form.name.clone(),
+                placeholder: Some(placeholder(
+                    vec![],
+                    Element::Text("Your name".to_string()),
+                )),
+                label: label_above(vec![], Element::Text("Name".to_string())),
+            },
+        )
+    }
+
+    let form = TestHarness::new(
+        || Form {
+            name: String::new(),
+        },
+        |form, text| {
+            form.name = text;
+            Cmd::none()
+        },
+        view,
+    )
+    .render();
+    let (_, input) = find_by_id(form.node(), "name").unwrap();
+    assert_eq!(input.tag.as_str(), "input");
+    assert_eq!(input.attr("type"), Some("text"));
+    let it = Classes::InputText.to_string();
+    assert!(input.attrs.iter().any(|a| a.classes().eq([it])));
+    let html = to_html(form.node());
+    assert!(html.contains("<label") && html.contains(">Name<"));
+    assert!(html.contains(">Your name<"));
+
+    let form = form.type_text("name", "Ada").render();
+    assert_eq!(form.model().name, "Ada");
+    let (_, input) = find_by_id(form.node(), "name").unwrap();
+    assert_eq!(input.attr("value"), Some("Ada"));
+
+    let node = crate::element::layout(
+        vec![],
+        multiline(
+            vec![],
+            MultilineConfig {
+                on_change: Arc::new(|text| text),
+                text: "Two\nlines".to_string(),
+                placeholder: None,
+                label: label_hidden("Notes".to_string()),
+                spellcheck: true,
+            },
+        ),
+    );
+    let html = to_html(&node);
+    assert!(html.contains("<textarea") && html.contains("spellcheck=\"true\""));
+    assert!(html.contains("aria-label=\"Notes\""));
+    assert!(html.contains(Classes::InputMultilineFiller.to_string()));
+    assert!(html.contains("Two\nlines\u{a0}"));
+}
+
+#[test]
+fn test_checkbox() {
+    use crate::{
+        cmd::Cmd,
+        element::id,
+        test::{find_by_id, TestHarness},
+    };
+
+    struct Model {
+        guacamole: bool,
+    }
+
+    fn view(model: &Model) -> Element<bool> {
+        checkbox(
+            vec![id("guacamole".to_string())],
+            CheckboxConfig {
+                on_change: Arc::new(|checked| checked),
+                icon: Box::new(default_checkbox),
+                checked: model.guacamole,
+                label: label_right(
+                    vec![],
+                    Element::Text("Do you want Guacamole?".to_string()),
+                ),
+            },
+        )
+    }
+
+    let checked = |form: &TestHarness<Model, bool>| {
+        let html = crate::render::to_html(form.node());
+        assert!(html.contains("role=\"checkbox\""));
+        html.contains("aria-checked=\"true\"")
+    };
+    let form = TestHarness::new(
+        || Model { guacamole: false },
+        |model, checked| {
+            model.guacamole = checked;
+            Cmd::none()
+        },
+        view,
+    )
+    .render();
+    assert!(!checked(&form));
+    let form = form.click("guacamole").render();
+    assert!(form.model().guacamole);
+    assert!(checked(&form));
+    let form = form.focus("guacamole").key(" ").render();
+    assert!(!form.model().guacamole);
+    let (_, label) = find_by_id(form.node(), "guacamole").unwrap();
+    assert_eq!(label.tag.as_str(), "label");
+}
+
+#[test]
+fn test_parts() {
+    use crate::{element::layout, render::to_html};
+
+    let fancy = || vec![Attribute::html_class("fancy".to_string())];
+    let tag_with = |html: &str, s: &str| {
+        html.split('<')
+            .find(|tag| tag.contains(s))
+            .unwrap()
+            .to_string()
+    };
+
+    let html = to_html(&layout(
+        vec![],
+        checkbox(
+            vec![part(checkbox::box_(), fancy())],
+            CheckboxConfig {
+                on_change: Arc::new(|checked| checked),
+                icon: Box::new(default_checkbox),
+                checked: true,
+                label: label_hidden("Fancy".to_string()),
+            },
+        ),
+    ));
+    assert_eq!(html.matches("fancy").count(), 1);
+    assert!(tag_with(&html, "fancy").contains("role=\"checkbox\""));
+
+    let html = to_html(&layout(
+        vec![],
+        text(
+            vec![
+                part(text::field(), fancy()),
+                part(checkbox::box_(), fancy()),
+            ],
+            TextConfig {
+                on_change: Arc::new(|_| false),
+                text: "Hi".to_string(),
+                placeholder: None,
+                label: label_hidden("Greeting".to_string()),
+            },
+        ),
+    ));
+    assert_eq!(html.matches("fancy").count(), 1);
+    assert!(tag_with(&html, "fancy").starts_with("input"));
+}
+
+#[test]
+fn test_radio() {
+    use crate::{
+        cmd::Cmd,
+        element::id,
+        render::to_html,
+        test::{find_by_text, TestHarness},
+    };
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Lunch {
+        Burrito,
+        Taco,
+        Gyro,
+    }
+
+    struct Model {
+        lunch: Option<Lunch>,
+    }
+
+    fn view(model: &Model) -> Element<Lunch> {
+        radio(
+            vec![spacing(20)],
+            RadioConfig {
+                on_change: Arc::new(|lunch| lunch),
+                options: vec![
+                    option(Lunch::Burrito, Element::Text("Burrito".into())),
+                    option(Lunch::Taco, Element::Text("Taco!".into())),
+                    option_with(Lunch::Gyro, |state| {
+                        el(
+                            vec![id("gyro".to_string())],
+                            Element::Text(format!("Gyro ({:?})", state)),
+                        )
+                    }),
+                ],
+                selected: model.lunch,
+                label: label_above(vec![], Element::Text("Lunch".into())),
+            },
+        )
+    }
+
+    let form = TestHarness::new(
+        || Model { lunch: None },
+        |model, lunch| {
+            model.lunch = Some(lunch);
+            Cmd::none()
+        },
+        view,
+    )
+    .render();
+    let html = to_html(form.node());
+    assert!(html.contains("role=\"radiogroup\""));
+    assert_eq!(html.matches("role=\"radio\"").count(), 3);
+    assert!(!html.contains("aria-checked=\"true\""));
+    assert!(find_by_text(form.node(), "Gyro (Idle)").is_some());
+
+    // The group is the one stop when tabbing.
+    let form = form.key("Tab").key(" ").render();
+    assert_eq!(form.model().lunch, Some(Lunch::Burrito));
+    let form = form.key("ArrowUp").render();
+    assert_eq!(form.model().lunch, Some(Lunch::Gyro));
+    assert!(find_by_text(form.node(), "Gyro (Selected)").is_some());
+    let form = form.key("ArrowDown").key(" ").render();
+    assert_eq!(form.model().lunch, Some(Lunch::Burrito));
+    let form = form.click("gyro").render();
+    assert_eq!(form.model().lunch, Some(Lunch::Gyro));
+    assert_eq!(
+        to_html(form.node())
+            .matches("aria-checked=\"true\"")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_slider() {
+    use crate::{
+        cmd::Cmd,
+        element::id,
+        render::to_html,
+        test::{find_by_id, TestHarness},
+    };
+
+    struct Model {
+        volume: f32,
+    }
+
+    fn view(model: &Model) -> Element<f32> {
+        slider(
+            vec![
+                Attribute::Height(px(30)),
+                part(slider::thumb(), vec![id("knob".to_string())]),
+            ],
+            SliderConfig {
+                on_change: Arc::new(|volume| volume),
+                label: label_above(vec![], Element::Text("Volume".into())),
+                min: 0.0,
+                max: 10.0,
+                step: Some(2.5),
+                value: model.volume,
+                thumb: default_thumb(),
+            },
+        )
+    }
+
+    let form = TestHarness::new(
+        || Model { volume: 5.0 },
+        |model, volume| {
+            model.volume = volume;
+            Cmd::none()
+        },
+        view,
+    )
+    .render();
+    let html = to_html(form.node());
+    assert!(html.contains("type=\"range\" step=\"2.5\" min=\"0\" max=\"10\""));
+    assert!(html.contains("orient=\"horizontal\""));
+    let (_, knob) = find_by_id(form.node(), "knob").unwrap();
+    assert!(knob
+        .attrs
+        .iter()
+        .any(|a| a.classes().eq(["focusable-thumb"])));
+
+    let form = form.key("Tab").key("ArrowRight").render();
+    assert_eq!(form.model().volume, 7.5);
+    let form = form.key("End").key("ArrowUp").render();
+    assert_eq!(form.model().volume, 10.0);
+    let form = form.key("Home").key("ArrowLeft").render();
+    assert_eq!(form.model().volume, 0.0);
+    assert!(to_html(form.node()).contains("value=\"0\""));
+}
+
+#[test]
+fn test_labels() {
+    use crate::{element::layout, render::to_html};
+
+    fn checkbox_labelled(label: Label<bool>) -> vdom::Node<bool> {
+        checkbox_with(vec![id("extra".to_string())], label)
+    }
+    fn checkbox_with(
+        attrs: Vec<Attribute<bool>>,
+        label: Label<bool>,
+    ) -> vdom::Node<bool> {
+        layout(
+            vec![],
+            checkbox(
+                attrs,
+                CheckboxConfig {
+                    on_change: Arc::new(|checked| checked),
+                    icon: Box::new(default_checkbox),
+                    checked: false,
+                    label,
+                },
+            ),
+        )
+    }
+    let word = |w: &str| Element::Text(w.to_string());
+
+    let html = to_html(&checkbox_labelled(label_left(vec![], word("Extra"))));
+    assert!(html.contains("aria-labelledby=\"extra-label\""));
+    let label_at = html.find("id=\"extra-label\"").unwrap();
+    assert!(label_at < html.find("role=\"checkbox\"").unwrap());
+    assert!(html.contains("<label"));
+    // Inputs with the same label have their own label ids.
+    let other = to_html(&checkbox_with(
+        vec![id("other".to_string())],
+        label_left(vec![], word("Extra")),
+    ));
+    assert!(other.contains("aria-labelledby=\"other-label\""));
+    assert!(!other.contains("extra-label"));
+    // Without an id, only the `<label>` names the input.
+    let html =
+        to_html(&checkbox_with(vec![], label_left(vec![], word("Extra"))));
+    assert!(html.contains("<label"));
+    assert!(!html.contains("aria-labelledby"));
+    assert!(!html.contains("id=\""));
+
+    let html = to_html(&checkbox_labelled(label_below(vec![], word("Extra"))));
+    assert!(
+        html.find("role=\"checkbox\"").unwrap() < html.find("Extra").unwrap()
+    );
+
+    let html = to_html(&checkbox_labelled(label_hidden("Extra".to_string())));
+    assert!(html.contains("aria-label=\"Extra\""));
+    assert!(!html.contains("aria-labelledby"));
+}
+
+#[test]
+fn test_autofill() {
+    use crate::{element::layout, render::to_html};
+
+    let config = || TextConfig {
+        on_change: Arc::new(|text| text),
+        text: String::new(),
+        placeholder: None,
+        label: label_hidden("Field".to_string()),
+    };
+    let password_config = |show| PasswordConfig {
+        on_change: Arc::new(|text| text),
+        text: "hunter2".to_string(),
+        placeholder: None,
+        label: label_hidden("Password".to_string()),
+        show,
+    };
+    let input = |el: Element<String>| {
+        let html = to_html(&layout(vec![], el));
+        let start = html.find("<input").unwrap();
+        html[start..start + html[start..].find('>').unwrap()].to_string()
+    };
+
+    let tag = input(username(vec![], config()));
+    assert!(tag.contains("type=\"text\""));
+    assert!(tag.contains("autocomplete=\"username\""));
+    let tag = input(email(vec![], config()));
+    assert!(
+        tag.contains("type=\"email\"")
+            && tag.contains("autocomplete=\"email\"")
+    );
+    let tag = input(search(vec![], config()));
+    assert!(tag.contains("type=\"search\"") && !tag.contains("autocomplete"));
+    assert!(
+        input(spell_checked(vec![], config())).contains("spellcheck=\"true\"")
+    );
+    assert!(input(text(vec![], config())).contains("spellcheck=\"false\""));
+
+    let tag = input(new_password(vec![], password_config(false)));
+    assert!(tag.contains("type=\"password\""));
+    assert!(tag.contains("autocomplete=\"new-password\""));
+    let tag = input(current_password(vec![], password_config(true)));
+    assert!(tag.contains("type=\"text\""));
+    assert!(tag.contains("autocomplete=\"current-password\""));
+}
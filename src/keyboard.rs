@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use crate::{
+    element::{column, el, fill, padding, row, spacing, width},
+    events::on,
+    focus::focusable,
+    model::{Attribute, Element},
+    vdom::{self, Node},
+};
+
+/// The keys a text input wants from an on-screen keyboard, from its
+/// `inputmode` or `type`.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum KeyboardKind {
+    Text,
+    Number,
+    Email,
+    Url,
+    Search,
+}
+
+/// A key pressed on an on-screen keyboard.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum VirtualKey {
+    Char(char),
+    Backspace,
+    Enter,
+}
+
+impl VirtualKey {
+    /// Type the key into `text`, at the end. `Enter` only types into
+    /// multiline inputs, so it's left to the caller.
+    pub fn apply(&self, text: &mut String) {
+        match self {
+            VirtualKey::Char(c) => text.push(*c),
+            VirtualKey::Backspace => {
+                text.pop();
+            }
+            VirtualKey::Enter => {}
+        }
+    }
+}
+
+/// An on-screen keyboard provided by the platform, e.g. a console's or a
+/// kiosk's, shown while a text input has focus.
+///
+/// Keys it sends back are typed into the input it was shown for with
+/// `Program::virtual_key`.
+pub trait VirtualKeyboard: Send + Sync {
+    fn show(&mut self, kind: KeyboardKind);
+    fn hide(&mut self);
+}
+
+// Marks the built-in keyboard, so focusing its keys doesn't count as
+// leaving the input being typed into.
+const KEYBOARD_ATTR: &str = "data-virtual-keyboard";
+
+/// The kind of keyboard the node at `path` wants, if it's a text input.
+pub(crate) fn keyboard_kind<Msg>(
+    root: &Node<Msg>,
+    path: &[usize],
+) -> Option<KeyboardKind> {
+    let node = root.at(path)?;
+    let kind = |mode: &str| match mode {
+        "numeric" | "decimal" | "number" | "tel" => Some(KeyboardKind::Number),
+        "email" => Some(KeyboardKind::Email),
+        "url" => Some(KeyboardKind::Url),
+        "search" => Some(KeyboardKind::Search),
+        "text" | "password" => Some(KeyboardKind::Text),
+        _ => None,
+    };
+    if let Some(mode) = node.attr("inputmode") {
+        return if mode == "none" { None } else { kind(mode) };
+    }
+    match node.tag.as_str() {
+        "textarea" => Some(KeyboardKind::Text),
+        "input" => kind(node.attr("type").unwrap_or("text")),
+        _ => match node.attr("contenteditable") {
+            Some("true") | Some("") => Some(KeyboardKind::Text),
+            _ => None,
+        },
+    }
+}
+
+/// Whether the node at `path` is in the built-in keyboard.
+pub(crate) fn in_keyboard<Msg>(root: &Node<Msg>, path: &[usize]) -> bool {
+    (0..=path.len()).any(|len| {
+        root.at(&path[..len])
+            .is_some_and(|node| node.attr(KEYBOARD_ATTR).is_some())
+    })
+}
+
+/// How the built-in keyboard is drawn, and what it sends.
+pub struct KeyboardConfig<Msg> {
+    pub on_key: Arc<dyn Fn(VirtualKey) -> Msg + Send + Sync>,
+    pub kind: KeyboardKind,
+    /// Whether letters are upper case.
+    pub shifted: bool,
+    /// The attributes of each key.
+    pub key: Vec<Attribute<Msg>>,
+}
+
+const LETTERS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const DIGITS: [&str; 4] = ["123", "456", "789", ".0-"];
+
+/// A simple on-screen keyboard, for controller-only and touch screen apps
+/// on platforms without one. Show it while `on_virtual_keyboard` says a
+/// text input wants one, and type its keys into the focused input's text
+/// with `VirtualKey::apply`.
+///
+/// Its keys are focusable, so they can be reached with a gamepad, and
+/// focusing them doesn't count as leaving the input.
+pub fn keyboard<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    config: KeyboardConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: 'static,
+{
+    let KeyboardConfig {
+        on_key,
+        kind,
+        shifted,
+        key: key_attrs,
+    } = config;
+    let key = |label: String, key: VirtualKey| {
+        let on_key = on_key.clone();
+        let mut attrs = vec![
+            focusable(),
+            Attribute::Attr(vdom::Attribute::property("role", "button")),
+            on("click".to_string(), move |_| on_key(key)),
+        ];
+        attrs.extend(key_attrs.iter().cloned());
+        el(attrs, Element::Text(label))
+    };
+    let char_row = |chars: &str| {
+        row(
+            vec![spacing(4)],
+            chars
+                .chars()
+                .map(|c| {
+                    c.to_uppercase().next().filter(|_| shifted).unwrap_or(c)
+                })
+                .map(|c| key(c.to_string(), VirtualKey::Char(c)))
+                .collect(),
+        )
+    };
+    let mut rows: Vec<Element<Msg>> = match kind {
+        KeyboardKind::Number => DIGITS.iter().map(|r| char_row(r)).collect(),
+        _ => LETTERS.iter().map(|r| char_row(r)).collect(),
+    };
+    let extra = match kind {
+        KeyboardKind::Email => Some("@."),
+        KeyboardKind::Url => Some("/.:"),
+        _ => None,
+    };
+    let mut last = vec![key("⌫".to_string(), VirtualKey::Backspace)];
+    if kind != KeyboardKind::Number {
+        last.extend(extra.map(char_row));
+        last.push(key(" ".to_string(), VirtualKey::Char(' ')));
+    }
+    last.push(key("⏎".to_string(), VirtualKey::Enter));
+    rows.push(row(vec![spacing(4)], last));
+
+    let mut keyboard_attrs = vec![
+        width(fill()),
+        padding(8),
+        spacing(4),
+        Attribute::Attr(vdom::Attribute::property(KEYBOARD_ATTR, true)),
+    ];
+    keyboard_attrs.extend(attrs);
+    column(keyboard_attrs, rows)
+}
+
+#[test]
+fn test_keyboard() {
+    use crate::element::layout;
+    use crate::model::Element;
+    use crate::render::to_html;
+    use crate::vdom::EventData;
+
+    let mut text = "ab".to_string();
+    VirtualKey::Backspace.apply(&mut text);
+    VirtualKey::Char('c').apply(&mut text);
+    assert_eq!(text, "ac");
+
+    let config = |kind| KeyboardConfig {
+        on_key: Arc::new(|key| key),
+        kind,
+        shifted: true,
+        key: vec![],
+    };
+    let node = layout(vec![], keyboard(vec![], config(KeyboardKind::Email)));
+    let html = to_html(&node);
+    assert!(html.contains(">Q<") && html.contains(">@<"));
+    let (path, _) = crate::test::find_by_text(&node, "Q").unwrap();
+    assert!(in_keyboard(&node, &path));
+    let clicked: Vec<_> = (0..=path.len())
+        .flat_map(|len| node.dispatch(&path[..len], "click", &EventData::None))
+        .collect();
+    assert_eq!(clicked, vec![VirtualKey::Char('Q')]);
+
+    let input = |attrs: Vec<(&str, &str)>| {
+        let mut node = vdom::Node {
+            tag: "input".into(),
+            attrs: Default::default(),
+            events: Default::default(),
+            children: vec![],
+        };
+        node.attrs.extend(
+            attrs
+                .into_iter()
+                .map(|(name, value)| vdom::Attribute::property(name, value)),
+        );
+        keyboard_kind::<()>(&node, &[])
+    };
+    assert_eq!(input(vec![]), Some(KeyboardKind::Text));
+    assert_eq!(input(vec![("type", "email")]), Some(KeyboardKind::Email));
+    assert_eq!(input(vec![("type", "checkbox")]), None);
+    assert_eq!(
+        input(vec![("inputmode", "numeric")]),
+        Some(KeyboardKind::Number)
+    );
+    assert_eq!(input(vec![("inputmode", "none")]), None);
+}
@@ -0,0 +1,119 @@
+use crate::{
+    element::{shrink, source_location},
+    model::{
+        element, lint_attributes, Attribute, Children, Element, LayoutContext,
+        NodeName,
+    },
+    style::Classes,
+};
+
+/// An `el` whose child has a key. As with the other keyed layouts, a child
+/// whose key isn't the one rendered in its place last time is rendered
+/// afresh rather than patched, so state like the focus or the text typed
+/// into an input doesn't move to a different item when a list is
+/// reordered.
+#[track_caller]
+pub fn el<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    child: (String, Element<Msg>),
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
+    let mut attr = vec![
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        source_location(),
+    ];
+    attr.extend(attrs);
+
+    element(
+        LayoutContext::AsEl,
+        NodeName::div(),
+        attr,
+        Children::Keyed(vec![child]),
+    )
+}
+
+/// A `row` of keyed children, e.g. `(id.to_string(), view_item(item))`.
+#[track_caller]
+pub fn row<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<(String, Element<Msg>)>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
+    let mut attr = vec![
+        Attribute::html_class(format!(
+            "{} {}",
+            Classes::ContentLeft.to_string(),
+            Classes::ContentCenterY.to_string()
+        )),
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        source_location(),
+    ];
+    attr.extend(attrs);
+
+    element(
+        LayoutContext::AsRow,
+        NodeName::div(),
+        attr,
+        Children::Keyed(children),
+    )
+}
+
+/// A `column` of keyed children.
+#[track_caller]
+pub fn column<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<(String, Element<Msg>)>,
+) -> Element<Msg> {
+    lint_attributes(&attrs, std::panic::Location::caller());
+    let mut attr = vec![
+        Attribute::html_class(format!(
+            "{} {}",
+            Classes::ContentTop.to_string(),
+            Classes::ContentLeft.to_string()
+        )),
+        Attribute::Width(shrink()),
+        Attribute::Height(shrink()),
+        source_location(),
+    ];
+    attr.extend(attrs);
+
+    element(
+        LayoutContext::AsColumn,
+        NodeName::div(),
+        attr,
+        Children::Keyed(children),
+    )
+}
+
+#[test]
+fn test_keyed() {
+    use crate::backend::{diff, Patch};
+    use crate::element::layout;
+
+    let view = |names: &[&str]| {
+        layout::<()>(
+            vec![],
+            column(
+                vec![],
+                names
+                    .iter()
+                    .map(|n| (n.to_string(), Element::Text(n.to_string())))
+                    .collect(),
+            ),
+        )
+    };
+    let html = crate::render::to_html(&view(&["ada", "alan"]));
+    assert!(html.find("ada").unwrap() < html.find("alan").unwrap());
+
+    let (old, same, swapped) = (
+        view(&["ada", "alan"]),
+        view(&["ada", "alan"]),
+        view(&["alan", "ada"]),
+    );
+    assert!(diff(&old, &same).is_empty());
+    let patches = diff(&old, &swapped);
+    assert!(!patches.is_empty());
+    assert!(patches.iter().all(|p| matches!(p, Patch::Replace(..))));
+}
@@ -0,0 +1,100 @@
+use crate::{
+    element::{
+        column, drawer, el, fill, height, in_front, px, row, scrollbar_y,
+        width, Device, DeviceClass, DrawerConfig, Side,
+    },
+    model::{Attribute, Element},
+};
+
+/// What goes in an `app_shell`.
+pub struct ShellConfig<Msg> {
+    pub sidebar: Element<Msg>,
+    pub topbar: Element<Msg>,
+    pub content: Element<Msg>,
+    pub sidebar_width: u32,
+    /// Whether the sidebar is hidden. On a phone, it's shown as a drawer
+    /// when this is `false`.
+    pub collapsed: bool,
+    /// The device to lay the shell out for, from `classify_device`.
+    pub device: Device,
+    /// Sent when the sidebar's drawer is dismissed on a phone.
+    pub on_collapse: Msg,
+}
+
+/// An application's frame: a topbar across the top, a sidebar down the
+/// left and the content filling the rest, each scrolling on its own.
+///
+/// On a phone there isn't room for the sidebar beside the content, so it
+/// becomes a `drawer` instead, open while the shell isn't `collapsed`.
+pub fn app_shell<Msg>(
+    mut attrs: Vec<Attribute<Msg>>,
+    config: ShellConfig<Msg>,
+) -> Element<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    let ShellConfig {
+        sidebar,
+        topbar,
+        content,
+        sidebar_width,
+        collapsed,
+        device,
+        on_collapse,
+    } = config;
+    let sidebar_width = px(sidebar_width as u64);
+    let content =
+        el(vec![width(fill()), height(fill()), scrollbar_y()], content);
+    attrs.extend(vec![width(fill()), height(fill())]);
+
+    if device.class() == DeviceClass::Phone {
+        attrs.push(in_front(drawer(
+            vec![width(sidebar_width), scrollbar_y()],
+            DrawerConfig {
+                side: Side::Left,
+                open: !collapsed,
+                on_dismiss: on_collapse,
+                content: sidebar,
+            },
+        )));
+        return column(attrs, vec![topbar, content]);
+    }
+
+    let mut body = Vec::new();
+    if !collapsed {
+        body.push(el(
+            vec![width(sidebar_width), height(fill()), scrollbar_y()],
+            sidebar,
+        ));
+    }
+    body.push(content);
+    column(
+        attrs,
+        vec![topbar, row(vec![width(fill()), height(fill())], body)],
+    )
+}
+
+#[test]
+fn test_app_shell() {
+    use crate::element::{classify_device, extract_styles};
+
+    let shell = |w, h, collapsed| {
+        app_shell::<()>(
+            vec![],
+            ShellConfig {
+                sidebar: Element::Text("Nav".to_string()),
+                topbar: Element::Empty,
+                content: Element::Empty,
+                sidebar_width: 240,
+                collapsed,
+                device: classify_device(w, h),
+                on_collapse: (),
+            },
+        )
+    };
+    let phone = extract_styles(&[shell(390, 844, true)]);
+    assert!(phone.contains("translate3d(-240px, 0px, 0px)"), "{}", phone);
+    let desktop = extract_styles(&[shell(1440, 900, false)]);
+    assert!(!desktop.contains("translate3d"));
+    assert!(desktop.contains(".width-px-240"));
+}
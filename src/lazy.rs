@@ -0,0 +1,142 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use crate::model::Element;
+
+// The elements built by `lazy` views in this frame and the last, by the
+// hash of the view function and its arguments.
+#[derive(Default)]
+struct LazyCache {
+    previous: HashMap<u64, Box<dyn Any>>,
+    current: HashMap<u64, Box<dyn Any>>,
+}
+
+thread_local! {
+    static CACHE: RefCell<LazyCache> = RefCell::new(LazyCache::default());
+}
+
+/// Forget the elements that weren't used by the last frame. `Program` calls
+/// this before each render; call it yourself if rendering some other way,
+/// or the elements are kept for as long as the thread runs.
+pub fn next_frame() {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.previous = mem::take(&mut cache.current);
+    })
+}
+
+/// How many elements are kept by `lazy` views.
+pub fn cached() -> usize {
+    CACHE.with(|cache| {
+        let cache = cache.borrow();
+        cache.previous.len() + cache.current.len()
+    })
+}
+
+// The element kept for `key`, or the one made by `view` if there isn't one.
+fn memo<Msg: 'static>(
+    key: u64,
+    view: impl FnOnce() -> Element<Msg>,
+) -> Element<Msg> {
+    let found = CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(element) = cache.previous.remove(&key) {
+            cache.current.insert(key, element);
+        }
+        cache
+            .current
+            .get(&key)
+            .and_then(|element| element.downcast_ref::<Element<Msg>>())
+            .cloned()
+    });
+    if let Some(element) = found {
+        return element;
+    }
+    let element = view();
+    CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .current
+            .insert(key, Box::new(element.clone()))
+    });
+    element
+}
+
+fn key<Msg: 'static>(view: usize, args: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    TypeId::of::<Msg>().hash(&mut hasher);
+    view.hash(&mut hasher);
+    args.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// `view(arg)`, only called if it wasn't called with an equal `arg` in this
+/// frame or the last. Otherwise the element it made then is used again,
+/// without being built and styled a second time.
+///
+/// `view` has to be a function, or a closure that doesn't capture anything,
+/// as what it would capture couldn't be compared. Its result should only
+/// depend on `arg`.
+pub fn lazy<A: Hash, Msg: 'static>(
+    view: fn(A) -> Element<Msg>,
+    arg: A,
+) -> Element<Msg> {
+    memo(key::<Msg>(view as usize, &arg), || view(arg))
+}
+
+/// `lazy`, for a view taking two arguments.
+pub fn lazy2<A: Hash, B: Hash, Msg: 'static>(
+    view: fn(A, B) -> Element<Msg>,
+    a: A,
+    b: B,
+) -> Element<Msg> {
+    memo(key::<Msg>(view as usize, (&a, &b)), || view(a, b))
+}
+
+/// `lazy`, for a view taking three arguments.
+pub fn lazy3<A: Hash, B: Hash, C: Hash, Msg: 'static>(
+    view: fn(A, B, C) -> Element<Msg>,
+    a: A,
+    b: B,
+    c: C,
+) -> Element<Msg> {
+    memo(key::<Msg>(view as usize, (&a, &b, &c)), || view(a, b, c))
+}
+
+#[test]
+fn test_lazy() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn item(name: &str, count: u32) -> Element<()> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        crate::element::el(
+            vec![crate::element::padding(4)],
+            Element::Text(format!("{}: {}", name, count)),
+        )
+    }
+    let calls = || CALLS.load(Ordering::SeqCst);
+
+    let first = lazy2(item, "apples", 3);
+    assert_eq!(calls(), 1);
+    next_frame();
+    let again = lazy2(item, "apples", 3);
+    assert_eq!(calls(), 1);
+    assert_eq!(
+        crate::render::to_html(&crate::element::layout(vec![], first)),
+        crate::render::to_html(&crate::element::layout(vec![], again))
+    );
+    lazy2(item, "apples", 4);
+    assert_eq!(calls(), 2);
+
+    // Unused for a whole frame, so it's built again.
+    next_frame();
+    next_frame();
+    lazy2(item, "apples", 3);
+    assert_eq!(calls(), 3);
+    assert_eq!(cached(), 1);
+}
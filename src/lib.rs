@@ -1,8 +1,103 @@
+//! Declarative UIs in the style of elm-ui, for the web and for Bevy.
+//!
+//! The API kept stable between releases is in `stable`. The subsystems
+//! still changing are in `experimental`, behind the feature of the same
+//! name, and without it are only used by the crate itself. Everything else
+//! is the machinery they're built on.
+
 #![allow(unused)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod announcer;
+#[cfg(feature = "std")]
+pub mod app;
+#[cfg(feature = "std")]
+pub mod background;
+#[cfg(feature = "std")]
+pub mod border;
+#[cfg(feature = "std")]
+pub mod cmd;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod compat;
+#[cfg(feature = "std")]
 pub mod element;
+#[cfg(feature = "std")]
+pub mod events;
+#[cfg(feature = "experimental")]
+pub mod experimental;
 pub mod flag;
+#[cfg(feature = "std")]
+pub mod focus;
+#[cfg(feature = "std")]
+pub mod font;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod i18n;
+#[cfg(feature = "std")]
 pub mod input;
+#[cfg(feature = "std")]
+pub mod keyboard;
+#[cfg(feature = "std")]
+pub mod keyed;
+#[cfg(feature = "std")]
+pub mod layouts;
+#[cfg(feature = "std")]
+pub mod lazy;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod model;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod primitives;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod router;
+#[cfg(feature = "std")]
+pub mod slots;
+#[cfg(feature = "std")]
+pub mod stable;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub mod style;
-pub mod vdom;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod sub;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod symbol;
+#[cfg(feature = "std")]
+pub mod test;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod theme;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod vdom;
+
+// The subsystems `experimental` re-exports: public with that feature, and
+// otherwise only for the rest of the crate to build on.
+macro_rules! experimental_mods {
+    ($(#[cfg($($on:tt)*)] $name:ident;)*) => {$(
+        #[cfg(all($($on)*, feature = "experimental"))]
+        pub mod $name;
+        #[cfg(all($($on)*, not(feature = "experimental")))]
+        pub(crate) mod $name;
+    )*};
+}
+
+experimental_mods! {
+    #[cfg(feature = "std")] anchor;
+    #[cfg(feature = "std")] backend;
+    #[cfg(feature = "std")] budget;
+    #[cfg(feature = "bevy_ui")] bevy_render;
+    #[cfg(feature = "bevy_ui")] devtools;
+    #[cfg(feature = "golden")] golden;
+    #[cfg(feature = "std")] haptic;
+    #[cfg(feature = "std")] solver;
+    #[cfg(any(feature = "std", feature = "alloc"))] sound;
+    #[cfg(feature = "std")] storage;
+    #[cfg(feature = "std")] stress;
+    #[cfg(feature = "std")] tokens;
+}
@@ -1,23 +1,118 @@
-use std::collections::HashSet;
-
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+
+use smallvec::smallvec;
+
+use crate::compat::{hasher, HashMap, HashSet, Mutex};
+use crate::primitives::hash_floats;
+pub use crate::primitives::{Calc, Color, FloatClass, Length};
 use crate::flag::{Field, Flag};
+use crate::sound::SoundMap;
 use crate::style;
 use crate::style::Classes;
+use crate::symbol::Symbol;
+use crate::text::grapheme_count;
 use crate::vdom;
 use crate::vdom::html;
 use crate::vdom::html::attributes;
-use crate::vdom::{Node, NodeType};
+use crate::vdom::{MapFn, Node, NodeType};
 
 use self::vdom::property;
 
-#[derive(Debug, Clone)]
-pub enum Element {
-    Unstyled(FinalizeNodeArgs),
-    Styled(Styled),
+pub enum Element<Msg> {
+    Unstyled(FinalizeNodeArgs<Msg>),
+    Styled(Styled<Msg>),
     Text(String),
     Empty,
 }
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+
+// The generic types below implement `Clone` and `Debug` by hand, as deriving
+// them would require `Msg` to implement them too.
+impl<Msg> Clone for Element<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Unstyled(args) => Self::Unstyled(args.clone()),
+            Self::Styled(styled) => Self::Styled(styled.clone()),
+            Self::Text(txt) => Self::Text(txt.clone()),
+            Self::Empty => Self::Empty,
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for Element<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unstyled(args) => {
+                f.debug_tuple("Unstyled").field(args).finish()
+            }
+            Self::Styled(styled) => {
+                f.debug_tuple("Styled").field(styled).finish()
+            }
+            Self::Text(txt) => f.debug_tuple("Text").field(txt).finish(),
+            Self::Empty => write!(f, "Empty"),
+        }
+    }
+}
+
+// Handlers are closures, so elements are compared without them, as
+// listeners are.
+impl<Msg> PartialEq for Element<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unstyled(a), Self::Unstyled(b)) => a == b,
+            (Self::Styled(a), Self::Styled(b)) => a == b,
+            (Self::Text(a), Self::Text(b)) => a == b,
+            (Self::Empty, Self::Empty) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<Msg> Hash for Element<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::Unstyled(args) => args.hash(state),
+            Self::Styled(styled) => styled.hash(state),
+            Self::Text(txt) => txt.hash(state),
+            Self::Empty => {}
+        }
+    }
+}
+
+impl<Msg: 'static> Element<Msg> {
+    /// Turn the messages this element sends into another type, so a view
+    /// with messages of its own can be used in a parent with different
+    /// ones, e.g. `counter(model).map(Msg::Counter)`.
+    pub fn map<B: 'static>(
+        self,
+        f: impl Fn(Msg) -> B + Send + Sync + 'static,
+    ) -> Element<B> {
+        let f: Arc<MapFn<Msg, B>> = Arc::new(f);
+        self.map_with(&f)
+    }
+
+    fn map_with<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> Element<B> {
+        match self {
+            Self::Unstyled(args) => Element::Unstyled(args.map(f)),
+            Self::Styled(styled) => Element::Styled(Styled {
+                styles: styled.styles,
+                html: styled.html.map(f),
+            }),
+            Self::Text(txt) => Element::Text(txt),
+            Self::Empty => Element::Empty,
+        }
+    }
+}
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum EmbedStyle {
     NoStyleSheet,
     StaticRootAndynamic(OptStruct, Vec<Style>),
@@ -40,7 +135,7 @@ pub enum Aligned {
     Aligned(Option<HAlign>, Option<VAlign>),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum HAlign {
     Left,
     CenterX,
@@ -70,7 +165,7 @@ impl HAlign {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum VAlign {
     Top,
     CenterY,
@@ -105,17 +200,95 @@ pub enum Style {
     Style(String, Vec<Property>),
     FontFamily(String, Vec<Font>),
     FontSize(u8),
-    Single(String, String, String),
-    Colored(String, String, Color),
+    Single(String, Symbol, String),
+    Colored(String, Symbol, Color),
     Spacing(String, u32, u32),
+    /// Spacing in lengths other than pixels, see `element::spacing_percent`.
+    SpacingLength(String, Length, Length),
     BorderWidth(String, u32, u32, u32, u32),
     Padding(String, f32, f32, f32, f32),
+    /// Text flowed into a number of columns with a gap between them, see
+    /// `element::text_columns`.
+    TextColumns(u32, u32),
     GridTemplate(GridTemplate),
     GridPosition(GridPosition),
     Transform(Transform),
     PseudoSelector(PseudoClass, Vec<Style>),
     Transparency(String, f32),
     Shadows(String, String),
+    PseudoElement(PseudoElement, Vec<Property>),
+    /// A style overriding the host page's CSS, see `element::important`.
+    Important(Box<Style>),
+    /// A widget's style, written into the layout's static stylesheet rather
+    /// than its dynamic one, see `static_bundle`.
+    Static(Box<Style>),
+}
+
+impl Hash for Style {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::Style(selector, props) => {
+                selector.hash(state);
+                props.hash(state);
+            }
+            Self::FontFamily(name, fonts) => {
+                name.hash(state);
+                fonts.hash(state);
+            }
+            Self::FontSize(size) => size.hash(state),
+            Self::TextColumns(count, gap) => (count, gap).hash(state),
+            Self::Single(class, prop, value) => {
+                class.hash(state);
+                prop.hash(state);
+                value.hash(state);
+            }
+            Self::Colored(class, prop, color) => {
+                class.hash(state);
+                prop.hash(state);
+                color.hash(state);
+            }
+            Self::Spacing(class, x, y) => {
+                class.hash(state);
+                x.hash(state);
+                y.hash(state);
+            }
+            Self::SpacingLength(class, x, y) => {
+                class.hash(state);
+                x.hash(state);
+                y.hash(state);
+            }
+            Self::BorderWidth(class, top, right, bottom, left) => {
+                class.hash(state);
+                [top, right, bottom, left].hash(state);
+            }
+            Self::Padding(class, top, right, bottom, left) => {
+                class.hash(state);
+                hash_floats(&[*top, *right, *bottom, *left], state);
+            }
+            Self::GridTemplate(template) => template.hash(state),
+            Self::GridPosition(pos) => pos.hash(state),
+            Self::Transform(transform) => transform.hash(state),
+            Self::PseudoSelector(class, styles) => {
+                class.hash(state);
+                styles.hash(state);
+            }
+            Self::Transparency(name, opacity) => {
+                name.hash(state);
+                hash_floats(&[*opacity], state);
+            }
+            Self::Shadows(name, shadows) => {
+                name.hash(state);
+                shadows.hash(state);
+            }
+            Self::PseudoElement(element, props) => {
+                element.hash(state);
+                props.hash(state);
+            }
+            Self::Important(style) => style.hash(state),
+            Self::Static(style) => style.hash(state),
+        }
+    }
 }
 
 impl Style {
@@ -129,29 +302,13 @@ impl Style {
             Self::Single(class, _, _) => class.clone(),
             Self::Colored(class, _, _) => class.clone(),
             Self::Spacing(cls, _, _) => cls.clone(),
+            Self::SpacingLength(cls, _, _) => cls.clone(),
             Self::Padding(cls, _, _, _, _) => cls.clone(),
             Self::BorderWidth(cls, _, _, _, _) => cls.clone(),
-            Self::GridTemplate(template) => {
-                let rows = template
-                    .rows
-                    .iter()
-                    .map(|r| r.class_name())
-                    .collect::<Vec<String>>()
-                    .join("-");
-                let cols = template
-                    .columns
-                    .iter()
-                    .map(|c| c.class_name())
-                    .collect::<Vec<String>>()
-                    .join("-");
-                format!(
-                    "grid-rows-{}-cols-{}-space-x-{}-space-y-{}",
-                    rows,
-                    cols,
-                    template.spacing.0.class_name(),
-                    template.spacing.1.class_name(),
-                )
+            Self::TextColumns(count, gap) => {
+                format!("cols-{}-{}", count, gap)
             }
+            Self::GridTemplate(template) => template.class_name(),
             Self::GridPosition(pos) => format!(
                 "gp grid-pos-{}-{}-{}-{}",
                 pos.row, pos.col, pos.width, pos.height,
@@ -167,12 +324,22 @@ impl Style {
                     .iter()
                     .map(|s| match &s.name()[..] {
                         "" => String::new(),
-                        name => format!("{}-{}", s_name, name),
+                        name => format!("{}-{}", name, s_name),
                     })
                     .collect::<Vec<String>>()
                     .join(" ")
             }
             Self::Transform(x) => x.class().unwrap_or_default(),
+            Self::PseudoElement(element, props) => {
+                format!("{}-{:x}", element.class_prefix(), props_hash(props))
+            }
+            Self::Important(style) => style
+                .name()
+                .split_whitespace()
+                .map(|name| format!("{}-imp", name))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Self::Static(style) => style.name(),
         }
     }
     pub fn toplevel_val(&self) -> Option<(String, Vec<Font>)> {
@@ -201,7 +368,7 @@ impl Style {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub enum Transform {
     Untransformed,
     Moved(Coordinate),
@@ -405,25 +572,226 @@ impl Transform {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum PseudoClass {
     Focus,
     Hover,
     Active,
 }
-#[derive(Debug, Clone)]
-pub struct FinalizeNodeArgs {
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum PseudoElement {
+    Before,
+    After,
+    FirstLetter,
+    FirstLine,
+    Placeholder,
+    Selection,
+}
+
+impl PseudoElement {
+    pub fn selector(&self) -> &'static str {
+        match self {
+            Self::Before => "::before",
+            Self::After => "::after",
+            Self::FirstLetter => "::first-letter",
+            Self::FirstLine => "::first-line",
+            Self::Placeholder => "::placeholder",
+            Self::Selection => "::selection",
+        }
+    }
+
+    fn class_prefix(&self) -> &'static str {
+        match self {
+            Self::Before => "bf",
+            Self::After => "af",
+            Self::FirstLetter => "fl",
+            Self::FirstLine => "fln",
+            Self::Placeholder => "ph",
+            Self::Selection => "sel",
+        }
+    }
+}
+
+// FNV-1a, so a pseudo-element's class name is the same from run to run.
+pub(crate) fn props_hash(props: &[Property]) -> u64 {
+    props
+        .iter()
+        .flat_map(|Property(name, value)| {
+            name.bytes()
+                .chain([b':'])
+                .chain(value.bytes())
+                .chain([b';'])
+        })
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+fn text_columns_properties(count: u32, gap: u32) -> Vec<Property> {
+    vec![
+        Property("column-count".into(), count.to_string()),
+        Property("column-gap".into(), format!("{}px", gap)),
+    ]
+}
+
+/// The CSS properties of a set of decorations, for the styles that are
+/// a fixed list of properties. Layout styles like spacing and the grid
+/// depend on the element they're on, and are left out.
+pub fn decoration_properties(styles: Vec<Style>) -> Vec<Property> {
+    styles
+        .into_iter()
+        .flat_map(|style| match style {
+            Style::Style(_, props) => props,
+            Style::FontSize(i) => {
+                vec![Property("font-size".into(), format!("{}px", i))]
+            }
+            Style::Single(_, prop, val) => vec![Property(prop, val)],
+            Style::Colored(_, prop, color) => {
+                vec![Property(prop, color.format_color())]
+            }
+            Style::Padding(_, top, right, bottom, left) => vec![Property(
+                "padding".into(),
+                format!("{}px {}px {}px {}px", top, right, bottom, left),
+            )],
+            Style::BorderWidth(_, top, right, bottom, left) => {
+                vec![Property(
+                    "border-width".into(),
+                    format!("{}px {}px {}px {}px", top, right, bottom, left),
+                )]
+            }
+            Style::Transparency(_, transparency) => vec![Property(
+                "opacity".into(),
+                format!("{}", (1.0 - transparency).clamp(0.0, 1.0)),
+            )],
+            Style::Shadows(_, shadow) => {
+                vec![Property("box-shadow".into(), shadow)]
+            }
+            Style::TextColumns(count, gap) => {
+                text_columns_properties(count, gap)
+            }
+            Style::Transform(transform) => transform
+                .value()
+                .map(|val| Property("transform".into(), val))
+                .into_iter()
+                .collect(),
+            Style::Static(style) => decoration_properties(vec![*style]),
+            _ => vec![],
+        })
+        .collect()
+}
+
+pub struct FinalizeNodeArgs<Msg> {
     has: Field,
     node: NodeName,
-    attributes: Vec<vdom::Attribute>,
-    children: Children<Node>,
+    attributes: vdom::Attrs,
+    events: vdom::Listeners<Msg>,
+    children: Children<Node<Msg>>,
     embed_mode: Option<EmbedStyle>,
 }
 
-#[derive(Debug, Clone)]
-pub struct Styled {
+impl<Msg: 'static> FinalizeNodeArgs<Msg> {
+    fn map<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> FinalizeNodeArgs<B> {
+        FinalizeNodeArgs {
+            has: self.has,
+            node: self.node,
+            attributes: self.attributes,
+            events: self.events.into_iter().map(|l| l.map(f)).collect(),
+            children: match self.children {
+                Children::Unkeyed(nodes) => Children::Unkeyed(
+                    nodes.into_iter().map(|n| n.map(f)).collect(),
+                ),
+                Children::Keyed(nodes) => Children::Keyed(
+                    nodes.into_iter().map(|(k, n)| (k, n.map(f))).collect(),
+                ),
+            },
+            embed_mode: self.embed_mode,
+        }
+    }
+}
+
+impl<Msg> Clone for FinalizeNodeArgs<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            has: self.has.clone(),
+            node: self.node.clone(),
+            attributes: self.attributes.clone(),
+            events: self.events.clone(),
+            children: self.children.clone(),
+            embed_mode: self.embed_mode.clone(),
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for FinalizeNodeArgs<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FinalizeNodeArgs")
+            .field("has", &self.has)
+            .field("node", &self.node)
+            .field("attributes", &self.attributes)
+            .field("events", &self.events)
+            .field("children", &self.children)
+            .field("embed_mode", &self.embed_mode)
+            .finish()
+    }
+}
+
+impl<Msg> PartialEq for FinalizeNodeArgs<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        self.has == other.has
+            && self.node == other.node
+            && self.attributes == other.attributes
+            && self.events == other.events
+            && self.children == other.children
+            && self.embed_mode == other.embed_mode
+    }
+}
+
+impl<Msg> Hash for FinalizeNodeArgs<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.has.hash(state);
+        self.node.hash(state);
+        self.attributes.hash(state);
+        self.events.hash(state);
+        self.children.hash(state);
+        self.embed_mode.hash(state);
+    }
+}
+
+pub struct Styled<Msg> {
     styles: Vec<Style>,
-    html: FinalizeNodeArgs,
+    html: FinalizeNodeArgs<Msg>,
+}
+
+impl<Msg> Clone for Styled<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            styles: self.styles.clone(),
+            html: self.html.clone(),
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for Styled<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Styled")
+            .field("styles", &self.styles)
+            .field("html", &self.html)
+            .finish()
+    }
+}
+
+impl<Msg> PartialEq for Styled<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        self.styles == other.styles && self.html == other.html
+    }
+}
+
+impl<Msg> Hash for Styled<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.styles.hash(state);
+        self.html.hash(state);
+    }
 }
 
 pub struct AdjustmentRules {
@@ -474,10 +842,19 @@ pub struct Adjustment {
     descender: f32,
 }
 
+impl Hash for Adjustment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_floats(
+            &[self.capital, self.lowercase, self.baseline, self.descender],
+            state,
+        );
+    }
+}
+
 impl Adjustment {
     pub fn convert_to_size_rules(&self) -> AdjustmentSizeRules {
         let lines =
-            vec![self.capital, self.baseline, self.descender, self.lowercase];
+            [self.capital, self.baseline, self.descender, self.lowercase];
 
         let asc = lines
             .iter()
@@ -532,7 +909,7 @@ pub fn typeface_adjustment(typefaces: &Vec<Font>) -> Option<AdjustmentRules> {
     None
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum Font {
     Serif,
     SansSerif,
@@ -602,14 +979,14 @@ impl Font {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub struct FontWith {
     name: String,
     adjustment: Option<Adjustment>,
     variants: Vec<Variant>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum Variant {
     Active(String),
     Off(String),
@@ -617,21 +994,21 @@ pub enum Variant {
 }
 
 impl Variant {
-    fn render(&self) -> String {
+    pub(crate) fn render(&self) -> String {
         match self {
             Variant::Active(name) => format!("\"{}\"", name),
             Variant::Off(name) => format!("\"{}\" 0", name),
             Variant::Indexed(name, index) => format!("\"{}\" {}", name, index),
         }
     }
-    fn name(&self) -> String {
+    pub(crate) fn name(&self) -> String {
         match self {
             Variant::Active(name) => String::from(name),
             Variant::Off(name) => format!("{}-0", name),
             Variant::Indexed(name, index) => format!("{}-{}", name, index),
         }
     }
-    fn is_small_caps(&self) -> bool {
+    pub(crate) fn is_small_caps(&self) -> bool {
         match self {
             Variant::Active(name) => name == "smcp",
             Variant::Off(_) => false,
@@ -640,8 +1017,8 @@ impl Variant {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub struct Property(pub String, pub String);
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub struct Property(pub Symbol, pub String);
 
 #[derive(Debug, Default, PartialOrd, PartialEq, Clone, Copy)]
 pub struct Coordinate {
@@ -650,6 +1027,12 @@ pub struct Coordinate {
     pub z: f32,
 }
 
+impl Hash for Coordinate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_floats(&[self.x, self.y, self.z], state);
+    }
+}
+
 impl Coordinate {
     pub fn one() -> Self {
         Self {
@@ -663,10 +1046,16 @@ impl Coordinate {
 #[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
 pub struct Angle(f32);
 
-#[derive(Clone)]
-pub enum Attribute {
+impl Hash for Angle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_floats(&[self.0], state);
+    }
+}
+
+pub enum Attribute<Msg> {
     None, // NoAttribute
     Attr(vdom::Attribute),
+    Event(vdom::Listener<Msg>),
     Describe(Description),
     Class(Flag, String), // invalidation key and literal class
     Style(Flag, Style), // invalidation key "border-color" as opposed to "border-color-10-10-10" that will be the key for the class
@@ -674,17 +1063,140 @@ pub enum Attribute {
     AlignX(HAlign),
     Width(Length),
     Height(Length),
-    Nearby(Location, Element),
+    // Boxed, as an element is much larger than any other attribute.
+    Nearby(Location, Box<Element<Msg>>),
     TransformComponent(Flag, TransformComponent),
+    Part(Part, Vec<Attribute<Msg>>), // overrides for a part of a built-in widget, applied by the widget
+}
+
+impl<Msg> Clone for Attribute<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::None => Self::None,
+            Self::Attr(attr) => Self::Attr(attr.clone()),
+            Self::Event(listener) => Self::Event(listener.clone()),
+            Self::Describe(description) => Self::Describe(description.clone()),
+            Self::Class(flag, cls) => Self::Class(flag.clone(), cls.clone()),
+            Self::Style(flag, style) => {
+                Self::Style(flag.clone(), style.clone())
+            }
+            Self::AlignY(y) => Self::AlignY(y.clone()),
+            Self::AlignX(x) => Self::AlignX(x.clone()),
+            Self::Width(w) => Self::Width(w.clone()),
+            Self::Height(h) => Self::Height(h.clone()),
+            Self::Nearby(loc, el) => Self::Nearby(*loc, el.clone()),
+            Self::TransformComponent(flag, component) => {
+                Self::TransformComponent(flag.clone(), component.clone())
+            }
+            Self::Part(part, attrs) => Self::Part(*part, attrs.clone()),
+        }
+    }
+}
+
+impl<Msg> PartialEq for Attribute<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::None, Self::None) => true,
+            (Self::Attr(a), Self::Attr(b)) => a == b,
+            (Self::Event(a), Self::Event(b)) => a == b,
+            (Self::Describe(a), Self::Describe(b)) => a == b,
+            (Self::Class(fa, a), Self::Class(fb, b)) => fa == fb && a == b,
+            (Self::Style(fa, a), Self::Style(fb, b)) => fa == fb && a == b,
+            (Self::AlignY(a), Self::AlignY(b)) => a == b,
+            (Self::AlignX(a), Self::AlignX(b)) => a == b,
+            (Self::Width(a), Self::Width(b)) => a == b,
+            (Self::Height(a), Self::Height(b)) => a == b,
+            (Self::Nearby(la, a), Self::Nearby(lb, b)) => la == lb && a == b,
+            (
+                Self::TransformComponent(fa, a),
+                Self::TransformComponent(fb, b),
+            ) => fa == fb && a == b,
+            (Self::Part(pa, a), Self::Part(pb, b)) => pa == pb && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<Msg> Hash for Attribute<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::None => {}
+            Self::Attr(attr) => attr.hash(state),
+            Self::Event(listener) => listener.hash(state),
+            Self::Describe(description) => description.hash(state),
+            Self::Class(flag, cls) => {
+                flag.hash(state);
+                cls.hash(state);
+            }
+            Self::Style(flag, style) => {
+                flag.hash(state);
+                style.hash(state);
+            }
+            Self::AlignY(y) => y.hash(state),
+            Self::AlignX(x) => x.hash(state),
+            Self::Width(w) => w.hash(state),
+            Self::Height(h) => h.hash(state),
+            Self::Nearby(loc, el) => {
+                loc.hash(state);
+                el.hash(state);
+            }
+            Self::TransformComponent(flag, component) => {
+                flag.hash(state);
+                component.hash(state);
+            }
+            Self::Part(part, attrs) => {
+                part.hash(state);
+                attrs.hash(state);
+            }
+        }
+    }
 }
 
-impl Attribute {
+impl<Msg> Attribute<Msg> {
     pub fn html_class(cls: String) -> Self {
         Self::Attr(attributes::class(cls))
     }
 }
 
-impl Attribute {
+impl<Msg: 'static> Attribute<Msg> {
+    /// Turn the messages this attribute sends into another type, as
+    /// `Element::map` does for elements.
+    pub fn map_attr<B: 'static>(
+        self,
+        f: impl Fn(Msg) -> B + Send + Sync + 'static,
+    ) -> Attribute<B> {
+        let f: Arc<MapFn<Msg, B>> = Arc::new(f);
+        self.map_attr_with(&f)
+    }
+
+    fn map_attr_with<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> Attribute<B> {
+        match self {
+            Self::None => Attribute::None,
+            Self::Attr(attr) => Attribute::Attr(attr),
+            Self::Event(listener) => Attribute::Event(listener.map(f)),
+            Self::Describe(description) => Attribute::Describe(description),
+            Self::Class(flag, cls) => Attribute::Class(flag, cls),
+            Self::Style(flag, style) => Attribute::Style(flag, style),
+            Self::AlignY(y) => Attribute::AlignY(y),
+            Self::AlignX(x) => Attribute::AlignX(x),
+            Self::Width(w) => Attribute::Width(w),
+            Self::Height(h) => Attribute::Height(h),
+            Self::Nearby(loc, el) => {
+                Attribute::Nearby(loc, Box::new(el.map_with(f)))
+            }
+            Self::TransformComponent(flag, component) => {
+                Attribute::TransformComponent(flag, component)
+            }
+            Self::Part(part, attrs) => Attribute::Part(
+                part,
+                attrs.into_iter().map(|a| a.map_attr_with(f)).collect(),
+            ),
+        }
+    }
+}
+
+impl<Msg> Attribute<Msg> {
     pub fn only_styles(&self) -> Option<Style> {
         match self {
             Self::Style(_, style) => Some(style.clone()),
@@ -703,7 +1215,30 @@ pub enum TransformComponent {
     Scale(Coordinate),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+impl Hash for TransformComponent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::MoveX(x) => hash_floats(&[*x], state),
+            Self::MoveY(y) => hash_floats(&[*y], state),
+            Self::MoveZ(z) => hash_floats(&[*z], state),
+            Self::Move(coordinate) => coordinate.hash(state),
+            Self::Rotate(axis, angle) => {
+                axis.hash(state);
+                hash_floats(&[*angle], state);
+            }
+            Self::Scale(coordinate) => coordinate.hash(state),
+        }
+    }
+}
+
+/// A piece of a built-in widget that can be restyled on its own, like the
+/// box of a checkbox, named so the name stays the same when the widget's
+/// internals change.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Part(pub &'static str);
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum Description {
     Main,
     Navigation,
@@ -718,45 +1253,7 @@ pub enum Description {
     Paragraph,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub enum Length {
-    Px(u64),
-    Content,
-    Fill(u64),
-    Min(u64, Box<Length>),
-    Max(u64, Box<Length>),
-}
-
-impl std::fmt::Display for Length {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Length::Px(px) => write!(f, "{}px", px),
-            Length::Content => write!(f, "auto"),
-            Length::Fill(i) => write!(f, "{}fr", i),
-            Length::Min(min, len) => {
-                write!(f, "min{}{}", min, len)
-            }
-            Length::Max(max, len) => {
-                write!(f, "max{}{}", max, len)
-            }
-        }
-    }
-}
-
 impl Length {
-    pub fn class_name(&self) -> String {
-        match self {
-            Length::Px(px) => format!("{}px", px),
-            Length::Content => String::from("auto"),
-            Length::Fill(i) => format!("{}fr", i),
-            Length::Min(min, len) => {
-                format!("min{}{}", min, len)
-            }
-            Length::Max(max, len) => {
-                format!("max{}{}", max, len)
-            }
-        }
-    }
     pub fn render_width(&self) -> (Field, String, Vec<Style>) {
         match self {
             Length::Px(px) => (
@@ -764,7 +1261,7 @@ impl Length {
                 format!("{} width-px-{}", Classes::WidthExact.to_string(), px),
                 vec![Style::Single(
                     format!("width-px-{}", px),
-                    "width".to_string(),
+                    "width".into(),
                     format!("{}px", px),
                 )],
             ),
@@ -801,6 +1298,14 @@ impl Length {
                     )
                 }
             }
+            Length::Calc(expr) => {
+                let cls = format!("width-{}", self.class_name());
+                (
+                    Field::none(),
+                    format!("{} {}", Classes::WidthExact.to_string(), cls),
+                    vec![Style::Single(cls, "width".into(), expr.clone())],
+                )
+            }
             Length::Min(size, len) => {
                 let cls = format!("min-width-{}", size);
                 let mut style = vec![Style::Single(
@@ -840,7 +1345,7 @@ impl Length {
                 ),
                 vec![Style::Single(
                     format!("height-px-{}", px),
-                    "height".to_string(),
+                    "height".into(),
                     format!("{}px", px),
                 )],
             ),
@@ -877,6 +1382,14 @@ impl Length {
                     )
                 }
             }
+            Length::Calc(expr) => {
+                let cls = format!("height-{}", self.class_name());
+                (
+                    Field::none(),
+                    format!("{} {}", Classes::HeightExact.to_string(), cls),
+                    vec![Style::Single(cls, "height".into(), expr.clone())],
+                )
+            }
             Length::Min(size, len) => {
                 let cls = format!("min-height-{}", size);
                 let mut style = vec![Style::Single(
@@ -915,6 +1428,7 @@ impl Length {
             Self::Min(_, l) => l.is_content(),
             Self::Fill(_) => false,
             Self::Px(_) => false,
+            Self::Calc(_) => false,
         }
     }
 }
@@ -926,7 +1440,7 @@ pub enum Axis {
     All,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub enum Location {
     Above,
     Below,
@@ -936,48 +1450,7 @@ pub enum Location {
     Behind,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
-pub struct Color {
-    pub r: f32,
-    pub g: f32,
-    pub b: f32,
-    pub a: f32,
-}
-
-impl Color {
-    pub fn format_color(&self) -> String {
-        let Self { r, g, b, a } = self;
-        format!(
-            "rgba({},{},{},{})",
-            r.float_class(),
-            g.float_class(),
-            b.float_class(),
-            (*a as u32).to_string()
-        )
-    }
-    pub fn format_color_class(&self) -> String {
-        let Self { r, g, b, a } = self;
-        format!(
-            "rgba({},{},{},{})",
-            r.float_class(),
-            g.float_class(),
-            b.float_class(),
-            a.float_class()
-        )
-    }
-}
-
-pub trait FloatClass {
-    fn float_class(&self) -> String;
-}
-
-impl FloatClass for f32 {
-    fn float_class(&self) -> String {
-        ((self * 255.0).round() as u32).to_string()
-    }
-}
-
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub enum NodeName {
     Generic,
     NodeName(String),
@@ -1001,16 +1474,15 @@ impl NodeName {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub enum NearbyChildren {
+pub enum NearbyChildren<Msg> {
     None,
-    Behind(Vec<Node>),
-    InFront(Vec<Node>),
-    Both(NearbyChildrenBoth),
+    Behind(Vec<Node<Msg>>),
+    InFront(Vec<Node<Msg>>),
+    Both(NearbyChildrenBoth<Msg>),
 }
 
-impl NearbyChildren {
-    pub fn add_nearby_el(self, loc: &Location, el: &Element) -> Self {
+impl<Msg> NearbyChildren<Msg> {
+    pub fn add_nearby_el(self, loc: &Location, el: &Element<Msg>) -> Self {
         let nearby = nearby_el(loc, el);
         match self {
             NearbyChildren::None => match loc {
@@ -1063,62 +1535,262 @@ impl NearbyChildren {
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub struct NearbyChildrenBoth {
-    behind: Vec<Node>,
-    in_front: Vec<Node>,
+impl<Msg> Clone for NearbyChildren<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::None => Self::None,
+            Self::Behind(b) => Self::Behind(b.clone()),
+            Self::InFront(f) => Self::InFront(f.clone()),
+            Self::Both(both) => Self::Both(both.clone()),
+        }
+    }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub struct Gathered {
+pub struct NearbyChildrenBoth<Msg> {
+    behind: Vec<Node<Msg>>,
+    in_front: Vec<Node<Msg>>,
+}
+
+impl<Msg> Clone for NearbyChildrenBoth<Msg> {
+    fn clone(&self) -> Self {
+        Self {
+            behind: self.behind.clone(),
+            in_front: self.in_front.clone(),
+        }
+    }
+}
+
+pub struct Gathered<Msg> {
     node: NodeName,
-    attrs: Vec<vdom::Attribute>,
+    attrs: vdom::Attrs,
+    events: vdom::Listeners<Msg>,
+    // Styles are too big to keep inline, and move up through every parent.
     styles: Vec<Style>,
-    children: NearbyChildren,
+    children: NearbyChildren<Msg>,
     has: Field,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
 pub struct GridTemplate {
-    spacing: (Length, Length),
-    columns: Vec<Length>,
-    rows: Vec<Length>,
+    pub spacing: (Length, Length),
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    /// The size of rows that items are placed in beyond `rows`.
+    pub auto_rows: Option<Length>,
+    /// The size of columns that items are placed in beyond `columns`.
+    pub auto_columns: Option<Length>,
+    pub auto_flow: Option<AutoFlow>,
+    /// Use the parent grid's tracks instead of `columns` or `rows`, so the
+    /// children of nested grids line up with each other. The element has
+    /// to span the parent's tracks, e.g. with a `GridPosition`.
+    pub subgrid: Option<Subgrid>,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone)]
-pub struct GridPosition {
-    row: u64,
-    col: u64,
-    width: u64,
-    height: u64,
+impl Default for GridTemplate {
+    fn default() -> Self {
+        Self {
+            spacing: (Length::Px(0), Length::Px(0)),
+            columns: vec![],
+            rows: vec![],
+            auto_rows: None,
+            auto_columns: None,
+            auto_flow: None,
+            subgrid: None,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub enum Children<C> {
-    Unkeyed(Vec<C>),
-    Keyed(Vec<(String, C)>),
+impl GridTemplate {
+    fn class_name(&self) -> String {
+        let tracks = |tracks: &[Track]| {
+            tracks
+                .iter()
+                .map(|t| t.class_name())
+                .collect::<Vec<String>>()
+                .join("-")
+        };
+        let mut name = format!(
+            "grid-rows-{}-cols-{}-space-x-{}-space-y-{}",
+            tracks(&self.rows),
+            tracks(&self.columns),
+            self.spacing.0.class_name(),
+            self.spacing.1.class_name(),
+        );
+        if let Some(len) = &self.auto_rows {
+            name.push_str(&format!("-auto-rows-{}", len.class_name()));
+        }
+        if let Some(len) = &self.auto_columns {
+            name.push_str(&format!("-auto-cols-{}", len.class_name()));
+        }
+        if let Some(flow) = &self.auto_flow {
+            name.push_str(&format!(
+                "-flow-{}",
+                flow.as_str().replace(' ', "-")
+            ));
+        }
+        if let Some(subgrid) = &self.subgrid {
+            name.push_str(match subgrid {
+                Subgrid::Columns => "-sub-cols",
+                Subgrid::Rows => "-sub-rows",
+                Subgrid::Both => "-sub-both",
+            });
+        }
+        name
+    }
 }
 
-// #[derive(Debug, Clone)]
-// pub enum Child {
-//     Element(Element),
+/// A row or column size in a `GridTemplate`.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub enum Track {
+    Size(Length),
+    /// The same size over and over, e.g. `Repeat(Repeat::AutoFill, ..)`
+    /// for as many card-sized columns as fit.
+    Repeat(Repeat, Length),
+}
+
+impl Track {
+    fn class_name(&self) -> String {
+        match self {
+            Track::Size(len) => len.class_name(),
+            Track::Repeat(repeat, len) => {
+                let count = match repeat {
+                    Repeat::Count(n) => n.to_string(),
+                    Repeat::AutoFill => "fill".to_string(),
+                    Repeat::AutoFit => "fit".to_string(),
+                };
+                format!("rep{}{}", count, len.class_name())
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum Repeat {
+    Count(u32),
+    /// As many as fit, keeping empty tracks.
+    AutoFill,
+    /// As many as fit, collapsing empty tracks so the items stretch.
+    AutoFit,
+}
+
+/// Which of its parent's tracks a nested grid takes on.
+///
+/// Where `subgrid` isn't supported, including the old `-ms-grid`, the
+/// template's own `columns` and `rows` are used instead, so give them
+/// sizes close to the parent's.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum Subgrid {
+    Columns,
+    Rows,
+    Both,
+}
+
+/// Which way items without a `GridPosition` are placed.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum AutoFlow {
+    Row,
+    Column,
+    /// By row, filling in earlier gaps with later items that fit.
+    RowDense,
+    ColumnDense,
+}
+
+impl AutoFlow {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AutoFlow::Row => "row",
+            AutoFlow::Column => "column",
+            AutoFlow::RowDense => "row dense",
+            AutoFlow::ColumnDense => "column dense",
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub struct GridPosition {
+    /// The first row taken, counting from 1.
+    pub row: u64,
+    /// The first column taken, counting from 1.
+    pub col: u64,
+    /// How many columns it spans.
+    pub width: u64,
+    /// How many rows it spans.
+    pub height: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Children<C> {
+    Unkeyed(Vec<C>),
+    Keyed(Vec<(String, C)>),
+}
+
+// #[derive(Debug, Clone)]
+// pub enum Child {
+//     Element(Element),
 //     Node(Node),
 // }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub enum HoverSetting {
     No,
     Allow,
     Force,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+/// What to do when the browser is in a forced colors mode, such as
+/// Windows High Contrast, where it replaces the page's colors with a
+/// small user-chosen palette and drops backgrounds and shadows.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum ForcedColors {
+    /// Let the browser pick the colors, but give buttons and inputs a
+    /// border and replace shadow-based focus rings with an outline,
+    /// so they don't disappear along with the backgrounds and shadows.
+    SystemColors,
+    /// Keep the colors as styled. Only use this if the styles already
+    /// meet high contrast requirements.
+    Preserve,
+    /// Leave it entirely to the browser.
+    Ignore,
+}
+
+impl ForcedColors {
+    pub fn render(&self, focus_visible: bool) -> String {
+        match self {
+            Self::SystemColors => {
+                let (focus, within) = if focus_visible {
+                    ("focus-visible", ":has(:focus-visible)")
+                } else {
+                    ("focus", ":focus-within")
+                };
+                format!(
+                    "@media (forced-colors: active) {{\n  .{0}, .{1} {{ border: 1px solid ButtonText !important; }}\n  .{1} {{ border-color: FieldText !important; }}\n  .s:{2}, .s:{2} .focusable, .{3}{4} {{ outline: 2px solid Highlight !important; outline-offset: 2px; }}\n}}",
+                    Classes::SEButton.to_string(),
+                    Classes::InputText.to_string(),
+                    focus,
+                    Classes::FocusedWithin.to_string(),
+                    within,
+                )
+            }
+            Self::Preserve => format!(
+                "@media (forced-colors: active) {{\n  .{0}, .{0} * {{ forced-color-adjust: none; }}\n}}",
+                Classes::Root.to_string(),
+            ),
+            Self::Ignore => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub struct FocusStyle {
-    border_color: Option<Color>,
-    shadow: Option<Shadow>,
-    bg_color: Option<Color>,
+    pub border_color: Option<Color>,
+    pub shadow: Option<Shadow>,
+    pub bg_color: Option<Color>,
 }
 
+/// The decorations used to draw a focus ring, either for every element
+/// with `focus_style` or for a single one with `focus_ring`.
+pub type StyleSet = FocusStyle;
+
 impl Default for FocusStyle {
     fn default() -> Self {
         Self {
@@ -1140,70 +1812,115 @@ impl Default for FocusStyle {
 }
 
 impl FocusStyle {
-    pub fn render(&self) -> Vec<Style> {
-        vec![Style::Style(
-        format!(".{}:focus-within", Classes::FocusedWithin.to_string()),
+    pub fn render(&self, visible_only: bool) -> Vec<Style> {
+        let focus = if visible_only {
+            "focus-visible"
+        } else {
+            "focus"
+        };
+        let within = if visible_only {
+            ":has(:focus-visible)"
+        } else {
+            ":focus-within"
+        };
+        vec![
+            Style::Style(
+                format!(".{}{}", Classes::FocusedWithin.to_string(), within),
+                self.properties(),
+            ),
+            Style::Style(
+                format!(
+                    ".s:{0} .focusable, .s.focusable:{0}, .ui-slide-bar:{0} + .s .focusable-thumb",
+                    focus
+                ),
+                self.properties(),
+            ),
+        ]
+    }
+
+    /// The same decorations as `render`, as styles for a single element.
+    pub fn styles(&self) -> Vec<Style> {
         vec![
             self.border_color.map(|color| {
-                Property("border-color".to_string(), color.format_color())
+                Style::Colored(
+                    format!("bc-{}", color.format_color_class()),
+                    "border-color".into(),
+                    color,
+                )
             }),
             self.bg_color.map(|color| {
-                Property("background-color".to_string(), color.format_color())
+                Style::Colored(
+                    format!("bg-{}", color.format_color_class()),
+                    "background-color".into(),
+                    color,
+                )
             }),
             self.shadow.map(|shadow| {
-                Property(
-                    "box-shadow".to_string(),
+                Style::Shadows(
+                    shadow.box_shadow_class(false),
                     shadow.format_box_shadow(false),
                 )
             }),
-            Some(Property("outline".to_string(), "none".to_string())),
+            Some(Style::Single(
+                "outline-none".to_string(),
+                "outline".into(),
+                "none".to_string(),
+            )),
         ]
         .into_iter()
-        .filter_map(|x| x)
-        .collect::<Vec<Property>>(),
-    ), Style::Style(
-        ".s:focus .focusable, .s.focusable:focus, .ui-slide-bar:focus + .s.focusable-thumb".to_string(),
+        .flatten()
+        .collect()
+    }
+
+    fn properties(&self) -> Vec<Property> {
         vec![
             self.border_color.map(|color| {
-                Property("border-color".to_string(), color.format_color())
+                Property("border-color".into(), color.format_color())
             }),
             self.bg_color.map(|color| {
-                Property("background-color".to_string(), color.format_color())
+                Property("background-color".into(), color.format_color())
             }),
             self.shadow.map(|shadow| {
-                Property(
-                    "box-shadow".to_string(),
-                    shadow.format_box_shadow(false),
-                )
+                Property("box-shadow".into(), shadow.format_box_shadow(false))
             }),
-            Some(Property("outline".to_string(), "none".to_string())),
+            Some(Property("outline".into(), "none".to_string())),
         ]
         .into_iter()
-        .filter_map(|x| x)
-        .collect::<Vec<Property>>(),
-    )]
+        .flatten()
+        .collect()
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub enum RenderMode {
     Layout,
     NoStaicStyleSheet,
     WithVirtualCSS,
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub enum Opt {
     Hover(HoverSetting),
     Focus(FocusStyle),
     Render(RenderMode),
+    FocusVisibleOnly,
+    ForcedColorsStrategy(ForcedColors),
+    ExternalStyles,
+    /// Mount the view in a shadow root, see `element::shadow_root`.
+    ShadowRoot,
+    /// Play sounds as elements are interacted with, see
+    /// `element::interaction_sounds`.
+    InteractionSounds(SoundMap),
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub struct OptStruct {
     hover: HoverSetting,
     focus: FocusStyle,
     mode: RenderMode,
+    focus_visible: bool,
+    forced_colors: ForcedColors,
+    external_styles: bool,
 }
 
 impl Default for OptStruct {
@@ -1212,6 +1929,9 @@ impl Default for OptStruct {
             hover: HoverSetting::Allow,
             focus: FocusStyle::default(),
             mode: RenderMode::Layout,
+            focus_visible: false,
+            forced_colors: ForcedColors::SystemColors,
+            external_styles: false,
         }
     }
 }
@@ -1222,37 +1942,62 @@ impl OptStruct {
             Option<HoverSetting>,
             Option<FocusStyle>,
             Option<RenderMode>,
+            bool,
+            Option<ForcedColors>,
+            bool,
         ),
                        opt: &Opt| match opt {
-            Opt::Hover(_) => {
+            Opt::Hover(h) => {
                 if let None = strct.0 {
-                    strct.0 = Some(HoverSetting::Allow);
+                    strct.0 = Some(*h);
                     strct
                 } else {
                     strct
                 }
             }
-            Opt::Focus(_) => {
+            Opt::Focus(f) => {
                 if let None = strct.1 {
-                    strct.1 = Some(FocusStyle::default());
+                    strct.1 = Some(*f);
                     strct
                 } else {
                     strct
                 }
             }
-            Opt::Render(_) => {
+            Opt::Render(m) => {
                 if let None = strct.2 {
-                    strct.2 = Some(RenderMode::Layout);
+                    strct.2 = Some(*m);
+                    strct
+                } else {
+                    strct
+                }
+            }
+            Opt::FocusVisibleOnly => {
+                strct.3 = true;
+                strct
+            }
+            Opt::ForcedColorsStrategy(f) => {
+                if strct.4.is_none() {
+                    strct.4 = Some(*f);
                     strct
                 } else {
                     strct
                 }
             }
+            Opt::ExternalStyles => {
+                strct.5 = true;
+                strct
+            }
+            // Used by the backend mounting the view, and played by the
+            // program, so the view isn't affected.
+            Opt::ShadowRoot | Opt::InteractionSounds(_) => strct,
         };
         let and_finally = |strct: (
             Option<HoverSetting>,
             Option<FocusStyle>,
             Option<RenderMode>,
+            bool,
+            Option<ForcedColors>,
+            bool,
         )| {
             OptStruct {
                 hover: if let Some(h) = strct.0 {
@@ -1270,18 +2015,29 @@ impl OptStruct {
                 } else {
                     RenderMode::Layout
                 },
+                focus_visible: strct.3,
+                forced_colors: if let Some(f) = strct.4 {
+                    f
+                } else {
+                    ForcedColors::SystemColors
+                },
+                external_styles: strct.5,
             }
         };
-        and_finally(opts.iter().rev().fold((None, None, None), combine))
+        and_finally(
+            opts.iter()
+                .rev()
+                .fold((None, None, None, false, None, false), combine),
+        )
     }
 }
 
-#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
 pub struct Shadow {
-    color: Color,
-    offset: (u8, u8),
-    blur: u8,
-    size: u8,
+    pub color: Color,
+    pub offset: (u8, u8),
+    pub blur: u8,
+    pub size: u8,
 }
 
 impl Shadow {
@@ -1362,16 +2118,19 @@ impl Shadow {
 // unstyled =
 //     Unstyled << always
 
-pub fn finalize_node(
+pub fn finalize_node<Msg>(
     has: Field,
     node: NodeName,
-    attributes: Vec<vdom::Attribute>,
-    children: Children<Node>,
+    attributes: vdom::Attrs,
+    events: vdom::Listeners<Msg>,
+    children: Children<Node<Msg>>,
     embed_mode: EmbedStyle,
     parent_ctx: LayoutContext,
-) -> Node {
-    let create_node =
-        |node_name: String, attrs: Vec<vdom::Attribute>| match children {
+) -> Node<Msg> {
+    let create_node = |node_name: String,
+                       attrs: vdom::Attrs,
+                       events: vdom::Listeners<Msg>| {
+        let (mut node, is_keyed) = match children {
             Children::Keyed(keyed) => {
                 let keyed = match embed_mode {
                     EmbedStyle::NoStyleSheet => keyed,
@@ -1382,21 +2141,21 @@ pub fn finalize_node(
                         embed_keyed(true, opts, &styles, keyed)
                     }
                 };
-                vdom::keyed_node(
-                    node_name.clone(),
-                    node_name.clone(),
+                let node = vdom::node(
+                    node_name.as_str().into(),
                     attrs,
                     keyed
                         .into_iter()
                         .map(|(s, n)| NodeType::KeyedNode(s, n))
                         .collect(),
-                )
+                );
+                (node, true)
             }
             Children::Unkeyed(unkeyed) => {
                 let unkeyed = unkeyed
                     .into_iter()
                     .map(|n| NodeType::Node(n))
-                    .collect::<Vec<NodeType>>();
+                    .collect::<Vec<NodeType<Msg>>>();
                 let children = match embed_mode {
                     EmbedStyle::NoStyleSheet => unkeyed,
                     EmbedStyle::OnlyDynamic(opts, styles) => {
@@ -1410,26 +2169,38 @@ pub fn finalize_node(
                 let node = match &node_name[..] {
                     "div" => html::div(attrs, children),
                     "p" => html::p(attrs, children),
-                    _ => vdom::node(node_name, attrs, children),
+                    _ => vdom::node(node_name.as_str().into(), attrs, children),
                 };
-                NodeType::Node(node)
+                (node, false)
             }
         };
+        node.events = events;
+        if is_keyed {
+            NodeType::KeyedNode(node_name, node)
+        } else {
+            NodeType::Node(node)
+        }
+    };
 
     let html = match node {
-        NodeName::Generic => create_node("div".to_string(), attributes),
-        NodeName::NodeName(name) => create_node(name, attributes),
-        NodeName::Embedded(name, internal) => NodeType::Node(vdom::node(
-            name,
-            attributes,
-            vec![create_node(
-                internal,
-                vec![attributes::class(format!(
-                    "s {}",
-                    Classes::Single.to_string()
-                ))],
-            )],
-        )),
+        NodeName::Generic => create_node("div".to_string(), attributes, events),
+        NodeName::NodeName(name) => create_node(name, attributes, events),
+        NodeName::Embedded(name, internal) => {
+            let mut outer = vdom::node(
+                name.into(),
+                attributes,
+                vec![create_node(
+                    internal,
+                    smallvec![attributes::class(format!(
+                        "s {}",
+                        Classes::Single.to_string()
+                    ))],
+                    smallvec![],
+                )],
+            );
+            outer.events = events;
+            NodeType::Node(outer)
+        }
     };
 
     match parent_ctx {
@@ -1437,10 +2208,10 @@ pub fn finalize_node(
             if has.present(&Flag::width_fill())
                 && !has.present(&Flag::width_between())
             {
-                vdom::node("div".to_string(), vec![], vec![html])
+                vdom::node("div".into(), smallvec![], vec![html])
             } else if has.present(&Flag::align_right()) {
                 html::u(
-                    vec![attributes::class(format!(
+                    smallvec![attributes::class(format!(
                         "s {} {} {} {}",
                         Classes::Single.to_string(),
                         Classes::Container.to_string(),
@@ -1451,7 +2222,7 @@ pub fn finalize_node(
                 )
             } else if has.present(&Flag::center_x()) {
                 html::s(
-                    vec![attributes::class(format!(
+                    smallvec![attributes::class(format!(
                         "s {} {} {} {}",
                         Classes::Single.to_string(),
                         Classes::Container.to_string(),
@@ -1461,17 +2232,17 @@ pub fn finalize_node(
                     vec![html],
                 )
             } else {
-                vdom::node("div".to_string(), vec![], vec![html])
+                vdom::node("div".into(), smallvec![], vec![html])
             }
         }
         LayoutContext::AsColumn => {
             if has.present(&Flag::height_fill())
                 && !has.present(&Flag::height_between())
             {
-                vdom::node("div".to_string(), vec![], vec![html])
+                vdom::node("div".into(), smallvec![], vec![html])
             } else if has.present(&Flag::center_y()) {
                 html::u(
-                    vec![attributes::class(format!(
+                    smallvec![attributes::class(format!(
                         "s {} {} {} {}",
                         Classes::Single.to_string(),
                         Classes::Container.to_string(),
@@ -1482,7 +2253,7 @@ pub fn finalize_node(
                 )
             } else if has.present(&Flag::align_bottom()) {
                 html::s(
-                    vec![attributes::class(format!(
+                    smallvec![attributes::class(format!(
                         "s {} {} {} {}",
                         Classes::Single.to_string(),
                         Classes::Container.to_string(),
@@ -1492,70 +2263,73 @@ pub fn finalize_node(
                     vec![html],
                 )
             } else {
-                vdom::node("div".to_string(), vec![], vec![html])
+                vdom::node("div".into(), smallvec![], vec![html])
             }
         }
-        _ => vdom::node("div".to_string(), vec![], vec![html]),
+        _ => vdom::node("div".into(), smallvec![], vec![html]),
     }
 }
 
-pub fn embed_with(
+// The stylesheets embedded before an element's children: the static one if
+// `is_static`, then one for the `styles` it uses.
+fn stylesheets<Msg>(
     is_static: bool,
     opts: OptStruct,
-    styles: Vec<Style>,
-    children: Vec<NodeType>,
-) -> Vec<NodeType> {
+    styles: &[Style],
+) -> Vec<Node<Msg>> {
+    let statics = static_styles(styles);
     let style_sheet = styles
         .iter()
         .fold(
-            (HashSet::new(), opts.focus.render()),
+            (
+                static_classes(opts, &statics),
+                opts.focus.render(opts.focus_visible),
+            ),
             |(cache, existing), style| reduce_styles(style, cache, existing),
         )
         .1;
 
-    let dynamic_style_sheet = NodeType::Node(to_stylesheet(opts, style_sheet));
+    let dynamic_style_sheet = to_stylesheet(opts, style_sheet);
 
     if is_static {
-        let mut res =
-            vec![NodeType::Node(static_root(opts)), dynamic_style_sheet];
-        res.extend(children);
-        res
+        vec![static_root(opts, &statics), dynamic_style_sheet]
     } else {
-        let mut res = vec![dynamic_style_sheet];
-        res.extend(children);
-        res
+        vec![dynamic_style_sheet]
     }
 }
 
-pub fn embed_keyed(
+pub fn embed_with<Msg>(
     is_static: bool,
     opts: OptStruct,
-    styles: &Vec<Style>,
-    children: Vec<(String, Node)>,
-) -> Vec<(String, Node)> {
-    let style_sheet = styles
-        .iter()
-        .fold(
-            (HashSet::new(), opts.focus.render()),
-            |(cache, existing), style| reduce_styles(style, cache, existing),
-        )
-        .1;
-
-    let dynamic_style_sheet = to_stylesheet(opts, style_sheet);
+    styles: Vec<Style>,
+    children: Vec<NodeType<Msg>>,
+) -> Vec<NodeType<Msg>> {
+    let mut res: Vec<NodeType<Msg>> = stylesheets(is_static, opts, &styles)
+        .into_iter()
+        .map(NodeType::Node)
+        .collect();
+    res.extend(children);
+    res
+}
 
-    if is_static {
-        let mut res = vec![
-            (String::from("static-stylesheet"), static_root(opts)),
-            (String::from("dynamic-stylesheet"), dynamic_style_sheet),
-        ];
-        res.extend(children);
-        res
+pub fn embed_keyed<Msg>(
+    is_static: bool,
+    opts: OptStruct,
+    styles: &Vec<Style>,
+    children: Vec<(String, Node<Msg>)>,
+) -> Vec<(String, Node<Msg>)> {
+    let keys = if is_static {
+        &["static-stylesheet", "dynamic-stylesheet"][..]
     } else {
-        let mut res =
-            vec![(String::from("dynamic-stylesheet"), dynamic_style_sheet)];
-        res.extend(children);
-        res
-    }
+        &["dynamic-stylesheet"][..]
+    };
+    let mut res: Vec<(String, Node<Msg>)> = keys
+        .iter()
+        .map(|key| key.to_string())
+        .zip(stylesheets(is_static, opts, styles))
+        .collect();
+    res.extend(children);
+    res
 }
 
 pub fn reduce_styles_recursive(
@@ -1594,6 +2368,343 @@ pub fn reduce_styles(
     }
 }
 
+/// Two different styles in one stylesheet that were given the same class
+/// name, so only one of them takes effect.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub struct ClassCollision {
+    pub class: String,
+    pub first: Style,
+    pub second: Style,
+}
+
+/// The classes shared by two different styles in the stylesheet `view`
+/// would be rendered with.
+pub fn class_collisions<Msg>(view: &Element<Msg>) -> Vec<ClassCollision> {
+    match view {
+        Element::Styled(Styled { styles, .. }) => stylesheet_collisions(styles),
+        _ => vec![],
+    }
+}
+
+fn stylesheet_collisions(styles: &[Style]) -> Vec<ClassCollision> {
+    let mut seen: HashMap<String, &Style> = HashMap::new();
+    let mut collisions = vec![];
+    for style in styles {
+        // A widget's style is the same style wherever it's written.
+        let style = match style {
+            Style::Static(style) => style,
+            style => style,
+        };
+        let class = style.name();
+        match seen.get(&class) {
+            Some(first) if *first != style => {
+                let collision = ClassCollision {
+                    class,
+                    first: (*first).clone(),
+                    second: style.clone(),
+                };
+                if !collisions.contains(&collision) {
+                    collisions.push(collision);
+                }
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(class, style);
+            }
+        }
+    }
+    collisions
+}
+
+/// Two attributes of one element setting the same thing, only the later of
+/// which takes effect, like two widths, or `align_left` and `center_x`.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub struct AttributeConflict {
+    /// What both set, e.g. `width`.
+    pub property: &'static str,
+    /// The attribute that takes effect.
+    pub winner: String,
+    /// The attribute it overrides, the same as `winner` if it's only
+    /// redundant.
+    pub overridden: String,
+    /// Where the element was made.
+    pub at: String,
+}
+
+// The conflicts not yet taken, and the property and place of each found so
+// far, so a view rendered every frame only has each reported once.
+#[cfg(debug_assertions)]
+#[derive(Default)]
+struct Conflicts {
+    found: Vec<AttributeConflict>,
+    seen: HashSet<(&'static str, String)>,
+}
+
+#[cfg(debug_assertions)]
+static ATTRIBUTE_CONFLICTS: Mutex<Option<Conflicts>> = Mutex::new(None);
+
+// What an attribute sets that an element can only have one of.
+fn lint_property<Msg>(attr: &Attribute<Msg>) -> Option<&'static str> {
+    Some(match attr {
+        Attribute::Width(_) => "width",
+        Attribute::Height(_) => "height",
+        Attribute::AlignX(_) => "horizontal alignment",
+        Attribute::AlignY(_) => "vertical alignment",
+        Attribute::Style(_, Style::Padding(..)) => "padding",
+        Attribute::Style(_, Style::Spacing(..))
+        | Attribute::Style(_, Style::SpacingLength(..)) => "spacing",
+        Attribute::Style(flag, _) => {
+            [
+                (Flag::bg_color(), "background color"),
+                (Flag::font_color(), "font color"),
+                (Flag::font_size(), "font size"),
+                (Flag::border_width(), "border width"),
+                (Flag::border_color(), "border color"),
+                (Flag::border_rount(), "border radius"),
+            ]
+            .iter()
+            .find(|(f, _)| f == flag)?
+            .1
+        }
+        _ => return None,
+    })
+}
+
+/// Whether `later` overrides `attr` when both are on one element, so `attr`
+/// can be left out.
+pub(crate) fn overrides<Msg>(
+    later: &Attribute<Msg>,
+    attr: &Attribute<Msg>,
+) -> bool {
+    match lint_property(attr) {
+        Some(property) => lint_property(later) == Some(property),
+        None => false,
+    }
+}
+
+// What an attribute sets that an element can only have one of, and how
+// it's shown in warnings.
+#[cfg(debug_assertions)]
+fn lint_key<Msg>(attr: &Attribute<Msg>) -> Option<(&'static str, String)> {
+    let property = lint_property(attr)?;
+    let shown = match attr {
+        Attribute::Width(w) => format!("{:?}", w),
+        Attribute::Height(h) => format!("{:?}", h),
+        Attribute::AlignX(x) => format!("{:?}", x),
+        Attribute::AlignY(y) => format!("{:?}", y),
+        Attribute::Style(_, style) => format!("{:?}", style),
+        _ => return None,
+    };
+    Some((property, shown))
+}
+
+/// Report the attributes in `attrs` that are overridden by later ones, for
+/// an element made at `at`, as warnings and in `take_attribute_conflicts`.
+/// The element constructors check the attributes they're given. Only done
+/// in debug builds.
+pub fn lint_attributes<Msg>(
+    attrs: &[Attribute<Msg>],
+    at: &core::panic::Location,
+) {
+    #[cfg(debug_assertions)]
+    {
+        let keys: Vec<_> = attrs.iter().map(lint_key).collect();
+        for (i, key) in keys.iter().enumerate() {
+            let (property, overridden) = match key {
+                Some(key) => key,
+                None => continue,
+            };
+            let winner = keys[i + 1..]
+                .iter()
+                .flatten()
+                .rfind(|(p, _)| p == property);
+            if let Some((_, winner)) = winner {
+                let conflict = AttributeConflict {
+                    property,
+                    winner: winner.clone(),
+                    overridden: overridden.clone(),
+                    at: at.to_string(),
+                };
+                let mut conflicts = ATTRIBUTE_CONFLICTS.lock();
+                let conflicts = conflicts.get_or_insert_with(Default::default);
+                let key = (conflict.property, conflict.at.clone());
+                if conflicts.seen.insert(key) {
+                    if conflict.winner == conflict.overridden {
+                        log::warn!(
+                            "{} is set twice to {} on the element made at {}",
+                            conflict.property,
+                            conflict.winner,
+                            conflict.at,
+                        );
+                    } else {
+                        log::warn!(
+                            "{} is set to {} and then {} on the element made \
+                             at {}, so the first is ignored",
+                            conflict.property,
+                            conflict.overridden,
+                            conflict.winner,
+                            conflict.at,
+                        );
+                    }
+                    conflicts.found.push(conflict);
+                }
+            }
+        }
+    }
+}
+
+/// The attribute conflicts found since the last call. Each property of
+/// each place elements are made is only reported the first time.
+pub fn take_attribute_conflicts() -> Vec<AttributeConflict> {
+    #[cfg(debug_assertions)]
+    {
+        let mut conflicts = ATTRIBUTE_CONFLICTS.lock();
+        conflicts.as_mut().map_or(vec![], |c| mem::take(&mut c.found))
+    }
+    #[cfg(not(debug_assertions))]
+    vec![]
+}
+
+/// A widget's attributes, with their styles marked to go in the static
+/// stylesheet of whichever layout they're rendered in, rather than the
+/// dynamic one. Any style there is left out of the layout's dynamic
+/// stylesheet, which is worth it for the styles a widget gives every
+/// instance of itself.
+pub fn static_bundle<Msg>(attrs: Vec<Attribute<Msg>>) -> Vec<Attribute<Msg>> {
+    attrs
+        .into_iter()
+        .map(|attr| match attr {
+            Attribute::Style(flag, style @ Style::Static(_)) => {
+                Attribute::Style(flag, style)
+            }
+            Attribute::Style(flag, style) => {
+                Attribute::Style(flag, Style::Static(Box::new(style)))
+            }
+            attr => attr,
+        })
+        .collect()
+}
+
+// The classes made with `define_class`: by name, all the classes an
+// element using one gets, and the styles every static stylesheet has for
+// them. Along with the names used without being defined, which are only
+// warned about the first time, as views use them on every render.
+#[derive(Default)]
+struct NamedClasses {
+    classes: HashMap<String, String>,
+    styles: Vec<Style>,
+    undefined: HashSet<String>,
+}
+
+static NAMED_CLASSES: Mutex<Option<NamedClasses>> = Mutex::new(None);
+
+/// Make a class called `name` of the decorations in `attrs`, e.g. the
+/// colors, padding and hover styles of a kind of button, written once into
+/// the static stylesheet as a single rule. Elements get them all with
+/// `use_class(name)`, rather than a class and a rule in the dynamic
+/// stylesheet for each, which adds up for a component used many times.
+///
+/// Layout attributes like `width` and `spacing` depend on the element
+/// they're on, so they're left for it to be given itself. Classes like
+/// `font::bold`'s are added along with the named one. Only the first
+/// definition of a name is used, so classes are best defined at startup.
+pub fn define_class<Msg>(name: &str, attrs: Vec<Attribute<Msg>>) {
+    let selector = format!(".{}", name);
+    let mut props = vec![];
+    let mut pseudo: Vec<(PseudoClass, Vec<Property>)> = vec![];
+    let mut classes = vec![name.to_string()];
+    for attr in attrs {
+        match attr {
+            Attribute::Style(_, Style::PseudoSelector(class, styles)) => {
+                let found = pseudo.iter_mut().find(|(c, _)| *c == class);
+                let props = decoration_properties(styles);
+                match found {
+                    Some((_, existing)) => existing.extend(props),
+                    None => pseudo.push((class, props)),
+                }
+            }
+            Attribute::Style(_, style) => {
+                props.extend(decoration_properties(vec![style]))
+            }
+            Attribute::Class(_, class) => classes.push(class),
+            _ => {}
+        }
+    }
+
+    let mut named = NAMED_CLASSES.lock();
+    let named = named.get_or_insert_with(Default::default);
+    if named.classes.contains_key(name) {
+        return;
+    }
+    let mut styles = vec![Style::Style(selector.clone(), props)];
+    for (class, props) in pseudo {
+        let suffix = match class {
+            PseudoClass::Focus => "fs",
+            PseudoClass::Hover => "hv",
+            PseudoClass::Active => "act",
+        };
+        classes.push(format!("{}-{}", name, suffix));
+        styles.push(Style::PseudoSelector(
+            class,
+            vec![Style::Style(selector.clone(), props)],
+        ));
+    }
+    named.styles.extend(styles);
+    named.classes.insert(name.to_string(), classes.join(" "));
+}
+
+/// Give this element the class made with `define_class(name, ..)`.
+pub fn use_class<Msg>(name: &str) -> Attribute<Msg> {
+    let mut named = NAMED_CLASSES.lock();
+    let named = named.get_or_insert_with(Default::default);
+    match named.classes.get(name) {
+        Some(classes) => Attribute::html_class(classes.clone()),
+        None => {
+            #[cfg(debug_assertions)]
+            if named.undefined.insert(name.to_string()) {
+                log::warn!("the class {} is used but never defined", name);
+            }
+            Attribute::html_class(name.to_string())
+        }
+    }
+}
+
+/// The styles of a layout's static stylesheet, once each: those of the
+/// classes made with `define_class`, then those of the widget bundles
+/// among the layout's `styles`.
+pub fn static_styles(styles: &[Style]) -> Vec<Style> {
+    let named = NAMED_CLASSES.lock();
+    let bundled = styles.iter().filter_map(|style| match style {
+        Style::Static(style) => Some(&**style),
+        _ => None,
+    });
+    let mut seen = HashSet::new();
+    named
+        .iter()
+        .flat_map(|named| named.styles.iter())
+        .chain(bundled)
+        .filter(|style| seen.insert(style.name()))
+        .cloned()
+        .collect()
+}
+
+// The classes a layout's dynamic stylesheet can leave out, as they're in
+// its static one. Without a static stylesheet, it needs all of them.
+fn static_classes(opts: OptStruct, statics: &[Style]) -> HashSet<String> {
+    match opts.mode {
+        RenderMode::NoStaicStyleSheet => HashSet::new(),
+        _ => statics.iter().map(Style::name).collect(),
+    }
+}
+
+/// The static stylesheet: the base rules and the layout's `statics`.
+pub fn static_rules(opts: OptStruct, statics: &[Style]) -> String {
+    let mut rules = style::rules();
+    // Writing to a `String` can't fail.
+    write_stylesheet(opts, statics, &mut rules).unwrap();
+    rules
+}
+
 pub fn reduce_recursive(
     found: Vec<Style>,
     styles: Vec<(String, Style)>,
@@ -1622,6 +2733,9 @@ pub fn reduce_recursive(
 }
 
 pub fn skippable(flag: &Flag, style: &Style) -> bool {
+    if let Style::Static(style) = style {
+        return skippable(flag, style);
+    }
     if flag == &Flag::border_width() {
         match style {
             Style::Single(_, _, val) => match &val[..] {
@@ -1649,687 +2763,273 @@ pub fn skippable(flag: &Flag, style: &Style) -> bool {
     }
 }
 
-pub fn gather_attr_recursive(
+/// Gather an element's attributes into its classes, HTML attributes and
+/// styles, and the nearby elements placed around it. Of attributes sharing
+/// a flag, the first is kept, so `element` passes them in reverse for the
+/// last one given to win.
+// gatherAttrRecursive, as a loop. Classes, attributes and styles are put at
+// whichever end of their deque the recursion would have put them, so they
+// come out in the same order.
+pub fn gather_attrs<Msg>(
     classes: String,
-    node: NodeName,
-    mut has: Field,
-    transform: Transform,
-    mut styles: Vec<Style>,
-    attrs: Vec<vdom::Attribute>,
-    mut children: NearbyChildren,
-    element_attrs: Vec<Attribute>,
-) -> Gathered {
+    mut node: NodeName,
+    element_attrs: Vec<Attribute<Msg>>,
+) -> Gathered<Msg> {
     use attributes::class;
-    match &element_attrs[..] {
-        [] => match transform.class() {
-            None => {
-                let mut classes = vec![class(classes)];
-                classes.extend(attrs);
-                Gathered {
-                    attrs: classes,
-                    styles,
-                    node,
-                    children,
-                    has,
-                }
-            }
-            Some(cls) => {
-                let classes = format!("{} {}", classes, cls);
-                let mut classes = vec![class(classes)];
-                classes.extend(attrs);
-                let mut transform = vec![Style::Transform(transform)];
-                transform.extend(styles);
-                Gathered {
-                    attrs: classes,
-                    styles: transform,
-                    node,
-                    children,
-                    has,
-                }
-            }
-        },
-        [attribute, remaining @ ..] => match attribute {
-            Attribute::None => gather_attr_recursive(
-                classes,
-                node,
-                has,
-                transform,
-                styles,
-                attrs,
-                children,
-                remaining.to_vec(),
-            ),
+    let mut has = Field::none();
+    let mut transform = untransformed();
+    let mut children = NearbyChildren::None;
+    let mut classes = VecDeque::from(vec![classes]);
+    let mut attrs: VecDeque<vdom::Attribute> = VecDeque::new();
+    let mut styles: VecDeque<Style> = VecDeque::new();
+    for attribute in element_attrs {
+        match attribute {
+            // Listeners are split off in `element` before gathering, and
+            // parts are taken by the widgets that have them.
+            Attribute::None | Attribute::Event(_) | Attribute::Part(..) => {}
             Attribute::Class(flag, exact_class_name) => {
-                if has.present(flag) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    let classes = format!("{} {}", exact_class_name, classes);
-                    has.add(flag);
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                if !has.present(&flag) {
+                    classes.push_front(exact_class_name);
+                    has.add(&flag);
                 }
             }
             Attribute::Attr(actual_attribute) => {
-                let mut att = vec![actual_attribute.clone()];
-                att.extend(attrs);
-                let attrs = att;
-                gather_attr_recursive(
-                    classes,
-                    node,
-                    has,
-                    transform,
-                    styles,
-                    attrs,
-                    children,
-                    remaining.to_vec(),
-                )
+                attrs.push_front(actual_attribute)
             }
             Attribute::Style(flag, style) => {
-                if has.present(flag) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else if skippable(flag, style) {
-                    has.add(flag);
-
-                    gather_attr_recursive(
-                        format!("{} {}", style.name(), classes),
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    let style_name = style.name();
-                    let mut style = vec![style.clone()];
-                    style.extend(styles);
-                    let styles = style;
-                    has.add(flag);
-                    gather_attr_recursive(
-                        format!("{} {}", style_name, classes),
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                if has.present(&flag) {
+                    continue;
+                }
+                has.add(&flag);
+                classes.push_front(style.name());
+                if !skippable(&flag, &style) {
+                    styles.push_front(style);
                 }
             }
             Attribute::TransformComponent(flag, component) => {
-                let transform = transform.compose(component);
-                has.add(flag);
-                gather_attr_recursive(
-                    classes,
-                    node,
-                    has,
-                    transform,
-                    styles,
-                    attrs,
-                    children,
-                    remaining.to_vec(),
-                )
+                transform = transform.compose(&component);
+                has.add(&flag);
             }
             Attribute::Width(width) => {
                 if has.present(&Flag::width()) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    match width {
-                        Length::Px(px) => {
-                            let classes = format!(
-                                "{} width-px-{} {}",
-                                Classes::WidthExact.to_string(),
-                                px,
-                                classes
-                            );
-                            has.add(&Flag::width());
-                            let mut style = vec![Style::Single(
-                                format!("width-px-{}", px),
-                                String::from("width"),
-                                format!("{}px", px),
-                            )];
-                            style.extend(styles);
-                            let styles = style;
-                            gather_attr_recursive(
-                                classes,
-                                node,
-                                has,
-                                transform,
-                                styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
-                        }
-                        Length::Content => {
-                            has.add(&Flag::width());
-                            has.add(&Flag::width_content());
-
-                            gather_attr_recursive(
-                                format!(
-                                    "{} {}",
-                                    classes,
-                                    Classes::WidthContent.to_string()
-                                ),
-                                node,
-                                has,
-                                transform,
-                                styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
-                        }
-                        Length::Fill(portion) => {
-                            if *portion == 1 {
-                                has.add(&Flag::width());
-                                has.add(&Flag::width_fill());
-                                gather_attr_recursive(
-                                    format!(
-                                        "{} {}",
-                                        classes,
-                                        Classes::WidthFill.to_string()
-                                    ),
-                                    node,
-                                    has,
-                                    transform,
-                                    styles,
-                                    attrs,
-                                    children,
-                                    remaining.to_vec(),
-                                )
-                            } else {
-                                has.add(&Flag::width());
-                                has.add(&Flag::width_fill());
-                                let classes = format!(
-                                    "{} {} width-fill-{}",
-                                    classes,
-                                    Classes::WidthFillPortion.to_string(),
-                                    portion
-                                );
-                                let mut style = vec![Style::Single(
-                                    format!(
-                                        "{}.{} > .width-fill{}",
-                                        Classes::Any.to_string(),
-                                        Classes::Row.to_string(),
-                                        portion,
-                                    ),
-                                    String::from("flex-grow"),
-                                    (portion * 100000).to_string(),
-                                )];
-                                style.extend(styles);
-                                let styles = style;
-                                gather_attr_recursive(
-                                    classes,
-                                    node,
-                                    has,
-                                    transform,
-                                    styles,
-                                    attrs,
-                                    children,
-                                    remaining.to_vec(),
-                                )
-                            }
-                        }
-                        _ => {
-                            let (add_to_flags, new_class, mut new_styles) =
-                                width.render_width();
-                            let classes = format!("{} {}", classes, new_class);
-                            new_styles.extend(styles);
-                            has.add(&Flag::width());
-                            has.merge(add_to_flags);
-                            gather_attr_recursive(
-                                classes,
-                                node,
-                                has,
-                                transform,
-                                new_styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
+                    continue;
+                }
+                has.add(&Flag::width());
+                match width {
+                    Length::Px(px) => {
+                        classes.push_front(format!(
+                            "{} width-px-{}",
+                            Classes::WidthExact.to_string(),
+                            px
+                        ));
+                        styles.push_front(Style::Single(
+                            format!("width-px-{}", px),
+                            "width".into(),
+                            format!("{}px", px),
+                        ));
+                    }
+                    Length::Content => {
+                        has.add(&Flag::width_content());
+                        classes.push_back(
+                            Classes::WidthContent.to_string().to_string(),
+                        );
+                    }
+                    Length::Fill(1) => {
+                        has.add(&Flag::width_fill());
+                        classes.push_back(
+                            Classes::WidthFill.to_string().to_string(),
+                        );
+                    }
+                    Length::Fill(portion) => {
+                        has.add(&Flag::width_fill());
+                        classes.push_back(format!(
+                            "{} width-fill-{}",
+                            Classes::WidthFillPortion.to_string(),
+                            portion
+                        ));
+                        styles.push_front(Style::Single(
+                            format!(
+                                "{}.{} > .width-fill{}",
+                                Classes::Any.to_string(),
+                                Classes::Row.to_string(),
+                                portion,
+                            ),
+                            "flex-grow".into(),
+                            (portion * 100000).to_string(),
+                        ));
+                    }
+                    _ => {
+                        let (add_to_flags, new_class, new_styles) =
+                            width.render_width();
+                        classes.push_back(new_class);
+                        for style in new_styles.into_iter().rev() {
+                            styles.push_front(style);
                         }
+                        has.merge(add_to_flags);
                     }
                 }
             }
             Attribute::Height(height) => {
                 if has.present(&Flag::height()) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    match height {
-                        Length::Px(px) => {
-                            let classes = format!(
-                                "{} height-px-{}px {}",
-                                Classes::HeightExact.to_string(),
-                                px,
-                                classes
-                            );
-                            has.add(&Flag::height());
-                            let mut style = vec![Style::Single(
-                                format!("height-px-{}", px),
-                                String::from("height"),
-                                format!("{}px", px),
-                            )];
-                            style.extend(styles);
-                            let styles = style;
-                            gather_attr_recursive(
-                                classes,
-                                node,
-                                has,
-                                transform,
-                                styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
-                        }
-                        Length::Content => {
-                            has.add(&Flag::height());
-                            has.add(&Flag::height_content());
-
-                            gather_attr_recursive(
-                                format!(
-                                    "{} {}",
-                                    Classes::HeightContent.to_string(),
-                                    classes
-                                ),
-                                node,
-                                has,
-                                transform,
-                                styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
-                        }
-                        Length::Fill(portion) => {
-                            if *portion == 1 {
-                                has.add(&Flag::height());
-                                has.add(&Flag::height_fill());
-                                gather_attr_recursive(
-                                    format!(
-                                        "{} {}",
-                                        Classes::HeightFill.to_string(),
-                                        classes
-                                    ),
-                                    node,
-                                    has,
-                                    transform,
-                                    styles,
-                                    attrs,
-                                    children,
-                                    remaining.to_vec(),
-                                )
-                            } else {
-                                has.add(&Flag::height());
-                                has.add(&Flag::height_fill());
-                                let classes = format!(
-                                    "{} {} height-fill-{}",
-                                    classes,
-                                    Classes::HeightFillPortion.to_string(),
-                                    portion
-                                );
-                                let mut style = vec![Style::Single(
-                                    format!(
-                                        "{}.{} > .height-fill{}",
-                                        Classes::Any.to_string(),
-                                        Classes::Column.to_string(),
-                                        portion,
-                                    ),
-                                    String::from("flex-grow"),
-                                    (portion * 100000).to_string(),
-                                )];
-                                style.extend(styles);
-                                let styles = style;
-                                gather_attr_recursive(
-                                    classes,
-                                    node,
-                                    has,
-                                    transform,
-                                    styles,
-                                    attrs,
-                                    children,
-                                    remaining.to_vec(),
-                                )
-                            }
-                        }
-                        _ => {
-                            let (add_to_flags, new_class, mut new_styles) =
-                                height.render_height();
-                            let classes = format!("{} {}", classes, new_class);
-                            new_styles.extend(styles);
-                            has.add(&Flag::height());
-                            has.merge(add_to_flags);
-                            gather_attr_recursive(
-                                classes,
-                                node,
-                                has,
-                                transform,
-                                new_styles,
-                                attrs,
-                                children,
-                                remaining.to_vec(),
-                            )
+                    continue;
+                }
+                has.add(&Flag::height());
+                match height {
+                    Length::Px(px) => {
+                        classes.push_front(format!(
+                            "{} height-px-{}",
+                            Classes::HeightExact.to_string(),
+                            px
+                        ));
+                        styles.push_front(Style::Single(
+                            format!("height-px-{}", px),
+                            "height".into(),
+                            format!("{}px", px),
+                        ));
+                    }
+                    Length::Content => {
+                        has.add(&Flag::height_content());
+                        classes.push_front(
+                            Classes::HeightContent.to_string().to_string(),
+                        );
+                    }
+                    Length::Fill(1) => {
+                        has.add(&Flag::height_fill());
+                        classes.push_front(
+                            Classes::HeightFill.to_string().to_string(),
+                        );
+                    }
+                    Length::Fill(portion) => {
+                        has.add(&Flag::height_fill());
+                        classes.push_back(format!(
+                            "{} height-fill-{}",
+                            Classes::HeightFillPortion.to_string(),
+                            portion
+                        ));
+                        styles.push_front(Style::Single(
+                            format!(
+                                "{}.{} > .height-fill{}",
+                                Classes::Any.to_string(),
+                                Classes::Column.to_string(),
+                                portion,
+                            ),
+                            "flex-grow".into(),
+                            (portion * 100000).to_string(),
+                        ));
+                    }
+                    _ => {
+                        let (add_to_flags, new_class, new_styles) =
+                            height.render_height();
+                        classes.push_back(new_class);
+                        for style in new_styles.into_iter().rev() {
+                            styles.push_front(style);
                         }
+                        has.merge(add_to_flags);
                     }
                 }
             }
             Attribute::Describe(description) => match description {
-                Description::Main => {
-                    let node = node.add("main".to_string());
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-                Description::Navigation => {
-                    let node = node.add("nav".to_string());
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-
+                Description::Main => node = node.add("main".to_string()),
+                Description::Navigation => node = node.add("nav".to_string()),
                 Description::ContentInfo => {
-                    let node = node.add("footer".to_string());
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                    node = node.add("footer".to_string())
                 }
                 Description::Complementary => {
-                    let node = node.add("aside".to_string());
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                    node = node.add("aside".to_string())
                 }
                 Description::Heading(i) => {
-                    if *i <= 1 {
-                        let node = node.add("h1".to_string());
-                        gather_attr_recursive(
-                            classes,
-                            node,
-                            has,
-                            transform,
-                            styles,
-                            attrs,
-                            children,
-                            remaining.to_vec(),
-                        )
-                    } else if *i < 7 {
-                        let node = node.add(format!("h{}", i));
-                        gather_attr_recursive(
-                            classes,
-                            node,
-                            has,
-                            transform,
-                            styles,
-                            attrs,
-                            children,
-                            remaining.to_vec(),
-                        )
-                    } else {
-                        let node = node.add("h6".to_string());
-                        gather_attr_recursive(
-                            classes,
-                            node,
-                            has,
-                            transform,
-                            styles,
-                            attrs,
-                            children,
-                            remaining.to_vec(),
-                        )
-                    }
-                }
-                Description::Paragraph => {
-                    // previously we rendered a <p> tag, though apparently
-                    // this invalidates the html if it has <div>s inside.
-                    // Since we can't guaranteee that there are no divs,
-                    // we need another strategy.
-                    // While it's not documented in many places,
-                    // there apparently is a paragraph aria role
-                    // https://github.com/w3c/aria/blob/11f85f41a5b621fdbe85fc9bcdcd270e653a48ba/common/script/roleInfo.js
-                    // Though we'll need to wait till it gets released
-                    // in an official wai-aria spec to use it.
-                    // If it's used at the moment, then Lighthouse
-                    // complains (likely rightfully) that role paragraph
-                    // is not recognized.
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-                Description::Button => {
-                    let mut att =
-                        vec![vdom::Attribute("role=button".to_string())];
-                    att.extend(attrs);
-                    let attrs = att;
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-
-                Description::Label(label) => {
-                    let mut att =
-                        vec![vdom::Attribute(format!("aria-label={}", label))];
-                    att.extend(attrs);
-                    let attrs = att;
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-                Description::LivePolite => {
-                    let mut att =
-                        vec![vdom::Attribute("aria-live=polite".to_string())];
-                    att.extend(attrs);
-                    let attrs = att;
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                }
-                Description::LiveAssertive => {
-                    let mut att =
-                        vec![vdom::Attribute("aria-live=polite".to_string())];
-                    att.extend(attrs);
-                    let attrs = att;
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                    node = node.add(format!("h{}", i.clamp(1, 6)))
                 }
+                // previously we rendered a <p> tag, though apparently
+                // this invalidates the html if it has <div>s inside.
+                // Since we can't guaranteee that there are no divs,
+                // we need another strategy.
+                // While it's not documented in many places,
+                // there apparently is a paragraph aria role
+                // https://github.com/w3c/aria/blob/11f85f41a5b621fdbe85fc9bcdcd270e653a48ba/common/script/roleInfo.js
+                // Though we'll need to wait till it gets released
+                // in an official wai-aria spec to use it.
+                // If it's used at the moment, then Lighthouse
+                // complains (likely rightfully) that role paragraph
+                // is not recognized.
+                Description::Paragraph => {}
+                Description::Button => attrs
+                    .push_front(vdom::Attribute::property("role", "button")),
+                Description::Label(label) => attrs
+                    .push_front(vdom::Attribute::property("aria-label", label)),
+                Description::LivePolite => attrs.push_front(
+                    vdom::Attribute::property("aria-live", "polite"),
+                ),
+                Description::LiveAssertive => attrs.push_front(
+                    vdom::Attribute::property("aria-live", "assertive"),
+                ),
             },
             Attribute::Nearby(loc, el) => {
-                let styles = match el {
-                    Element::Styled(styled) => {
-                        styles.extend(styled.styles.clone());
-                        styles
-                    }
-                    _ => styles,
-                };
-                let children = children.add_nearby_el(loc, el);
-                gather_attr_recursive(
-                    classes,
-                    node,
-                    has,
-                    transform,
-                    styles,
-                    attrs,
-                    children,
-                    remaining.to_vec(),
-                )
+                if let Element::Styled(styled) = &*el {
+                    styles.extend(styled.styles.iter().cloned());
+                }
+                children = children.add_nearby_el(&loc, &el);
             }
             Attribute::AlignX(x) => {
                 if has.present(&Flag::align_x()) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    has.add(&Flag::align_x());
-                    match x {
-                        HAlign::CenterX => has.add(&Flag::center_x()),
-                        HAlign::Right => has.add(&Flag::align_right()),
-                        HAlign::Left => (),
-                    }
-                    gather_attr_recursive(
-                        format!("{} {}", x.name(), classes),
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                    continue;
                 }
+                has.add(&Flag::align_x());
+                match x {
+                    HAlign::CenterX => has.add(&Flag::center_x()),
+                    HAlign::Right => has.add(&Flag::align_right()),
+                    HAlign::Left => (),
+                }
+                classes.push_front(x.name());
             }
             Attribute::AlignY(y) => {
                 if has.present(&Flag::align_y()) {
-                    gather_attr_recursive(
-                        classes,
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
-                } else {
-                    has.add(&Flag::align_y());
-                    match y {
-                        VAlign::CenterY => has.add(&Flag::center_y()),
-                        VAlign::Bottom => has.add(&Flag::align_bottom()),
-                        VAlign::Top => (),
-                    }
-                    gather_attr_recursive(
-                        format!("{} {}", y.name(), classes),
-                        node,
-                        has,
-                        transform,
-                        styles,
-                        attrs,
-                        children,
-                        remaining.to_vec(),
-                    )
+                    continue;
+                }
+                has.add(&Flag::align_y());
+                match y {
+                    VAlign::CenterY => has.add(&Flag::center_y()),
+                    VAlign::Bottom => has.add(&Flag::align_bottom()),
+                    VAlign::Top => (),
                 }
+                classes.push_front(y.name());
             }
-        },
+        }
+    }
+    // Those given with `promote_layer` are kept instead.
+    if !has.present(&Flag::will_change()) {
+        if let Some(hint) = layer_hint(styles.iter()) {
+            classes.push_back(hint.name());
+            styles.push_back(hint);
+        }
+    }
+    match transform.class() {
+        // A `translate3d` transform already promotes the element to its own
+        // layer, so only untransformed elements need the `translateZ(0)`
+        // hint.
+        None if has.present(&Flag::will_change()) => {
+            classes.push_front(Classes::Layer.to_string().to_string())
+        }
+        None => {}
+        Some(cls) => {
+            classes.push_back(cls);
+            styles.push_front(Style::Transform(transform));
+        }
+    }
+    let mut gathered: vdom::Attrs =
+        smallvec![class(classes.make_contiguous().join(" "))];
+    gathered.extend(attrs);
+    Gathered {
+        attrs: gathered,
+        events: smallvec![],
+        styles: styles.into(),
+        node,
+        children,
+        has,
     }
 }
 
-pub fn nearby_el(loc: &Location, el: &Element) -> Node {
+pub fn nearby_el<Msg>(loc: &Location, el: &Element<Msg>) -> Node<Msg> {
     let attrs = match loc {
         Location::Above => format!(
             "{} {} {}",
@@ -2368,20 +3068,22 @@ pub fn nearby_el(loc: &Location, el: &Element) -> Node {
             Classes::Behind.to_string(),
         ),
     };
-    let attrs = vec![html::attributes::class(attrs)];
+    let attrs = smallvec![html::attributes::class(attrs)];
     let items = match el {
-        Element::Empty => text_element(&"".to_string()),
+        Element::Empty => text_element(""),
         Element::Text(s) => text_element(s),
         Element::Unstyled(FinalizeNodeArgs {
             has,
             node,
             attributes,
+            events,
             children,
             embed_mode,
         }) => finalize_node(
             has.clone(),
             node.clone(),
             attributes.clone(),
+            events.clone(),
             children.clone(),
             embed_mode.clone().unwrap(),
             LayoutContext::AsEl,
@@ -2393,6 +3095,7 @@ pub fn nearby_el(loc: &Location, el: &Element) -> Node {
                     has,
                     node,
                     attributes,
+                    events,
                     children,
                     embed_mode,
                 },
@@ -2400,6 +3103,7 @@ pub fn nearby_el(loc: &Location, el: &Element) -> Node {
             has.clone(),
             node.clone(),
             attributes.clone(),
+            events.clone(),
             children.clone(),
             EmbedStyle::NoStyleSheet,
             LayoutContext::AsEl,
@@ -2453,23 +3157,25 @@ pub fn context_classes(context: &LayoutContext) -> String {
     }
 }
 
-pub fn element(
+pub fn element<Msg>(
     context: LayoutContext,
     node: NodeName,
-    mut attrs: Vec<Attribute>,
-    children: Children<Element>,
-) -> Element {
+    mut attrs: Vec<Attribute<Msg>>,
+    children: Children<Element<Msg>>,
+) -> Element<Msg> {
     attrs.reverse();
-    let rendered = gather_attr_recursive(
-        context_classes(&context),
-        node,
-        Field::none(),
-        untransformed(),
-        vec![],
-        vec![],
-        NearbyChildren::None,
-        attrs,
-    );
+    let (events, attrs): (Vec<Attribute<Msg>>, Vec<Attribute<Msg>>) = attrs
+        .into_iter()
+        .partition(|attr| matches!(attr, Attribute::Event(_)));
+    let mut rendered = gather_attrs(context_classes(&context), node, attrs);
+    rendered.events = events
+        .into_iter()
+        .rev()
+        .filter_map(|attr| match attr {
+            Attribute::Event(listener) => Some(listener),
+            _ => None,
+        })
+        .collect();
     create_element(context, children, rendered)
 }
 
@@ -2477,64 +3183,55 @@ pub fn untransformed() -> Transform {
     Transform::Untransformed
 }
 
-pub fn create_element(
+pub fn create_element<Msg>(
     context: LayoutContext,
-    children: Children<Element>,
-    mut rendered: Gathered,
-) -> Element {
-    let gather = |content: &mut (Vec<Node>, Vec<Style>),
-                  child: &mut Element| {
-        let (html, mut existing_styles) = content.to_owned();
-        // let html = html
-        //     .into_iter()
-        //     .map(|n| NodeType::Node(n))
-        //     .collect::<Vec<NodeType>>();
-        match child.clone() {
+    children: Children<Element<Msg>>,
+    mut rendered: Gathered<Msg>,
+) -> Element<Msg> {
+    // Each child is finalized in turn, onto the end of the children and
+    // styles gathered so far.
+    let gather = |content: &mut (Vec<Node<Msg>>, Vec<Style>),
+                  child: Element<Msg>| {
+        let (html, existing_styles) = content;
+        match child {
             Element::Unstyled(FinalizeNodeArgs {
                 has,
                 node,
                 attributes,
+                events,
                 children,
                 embed_mode,
-            }) => {
-                let mut nodes = vec![finalize_node(
-                    has,
-                    node,
-                    attributes,
-                    children,
-                    embed_mode.unwrap(),
-                    context,
-                )];
-                nodes.extend(html);
-                (nodes, existing_styles)
-            }
+            }) => html.push(finalize_node(
+                has,
+                node,
+                attributes,
+                events,
+                children,
+                embed_mode.unwrap(),
+                context,
+            )),
             Element::Styled(Styled {
-                mut styles,
+                styles,
                 html:
                     FinalizeNodeArgs {
                         has,
                         node,
                         attributes,
+                        events,
                         children,
-                        embed_mode,
+                        embed_mode: _,
                     },
             }) => {
-                let mut nodes = vec![finalize_node(
+                html.push(finalize_node(
                     has,
                     node,
                     attributes,
+                    events,
                     children,
                     EmbedStyle::NoStyleSheet,
                     context,
-                )];
-                nodes.extend(html);
-                let new_styles = if existing_styles.is_empty() {
-                    styles
-                } else {
-                    styles.extend(existing_styles);
-                    styles
-                };
-                (nodes, new_styles)
+                ));
+                existing_styles.extend(styles);
             }
             Element::Text(txt) => {
                 // TEXT OPTIMIZATION
@@ -2562,121 +3259,109 @@ pub fn create_element(
                 //     , existingStyles
                 //     )
                 // else
-                let mut h = if context == LayoutContext::AsEl {
-                    vec![text_element_fill(&txt)]
+                html.push(if context == LayoutContext::AsEl {
+                    text_element_fill(&txt)
                 } else {
-                    vec![text_element(&txt)]
-                };
-                h.extend(html);
-                (h, existing_styles)
+                    text_element(&txt)
+                });
             }
-            Element::Empty => (html, existing_styles),
+            Element::Empty => {}
         }
     };
-    let gather_keyed = |content: &mut (Vec<(String, Node)>, Vec<Style>),
-                        keyed: &mut (String, Element)| {
-        let (html, mut existing_styles) = content.to_owned();
-        let (key, child) = keyed.to_owned();
-        // let html = html
-        //     .into_iter()
-        //     .map(|(s, n)| NodeType::KeyedNode(s, n))
-        //     .collect::<Vec<NodeType>>();
-        match child {
-            Element::Unstyled(FinalizeNodeArgs {
-                has,
-                node,
-                attributes,
-                children,
-                embed_mode,
-            }) => {
-                let mut nodes = vec![(
+    let gather_keyed =
+        |content: &mut (Vec<(String, Node<Msg>)>, Vec<Style>),
+         (key, child): (String, Element<Msg>)| {
+            let (html, existing_styles) = content;
+            match child {
+                Element::Unstyled(FinalizeNodeArgs {
+                    has,
+                    node,
+                    attributes,
+                    events,
+                    children,
+                    embed_mode,
+                }) => html.push((
                     key,
                     finalize_node(
                         has,
                         node,
                         attributes,
+                        events,
                         children,
                         embed_mode.unwrap(),
                         context,
                     ),
-                )];
-                nodes.extend(html);
-                (nodes, existing_styles)
+                )),
+                Element::Styled(Styled {
+                    styles,
+                    html:
+                        FinalizeNodeArgs {
+                            has,
+                            node,
+                            attributes,
+                            events,
+                            children,
+                            embed_mode: _,
+                        },
+                }) => {
+                    html.push((
+                        key,
+                        finalize_node(
+                            has,
+                            node,
+                            attributes,
+                            events,
+                            children,
+                            EmbedStyle::NoStyleSheet,
+                            context,
+                        ),
+                    ));
+                    existing_styles.extend(styles);
+                }
+                Element::Text(txt) => {
+                    // TEXT OPTIMIZATION
+                    // You can have raw text if the element is an el,
+                    // and has `width-content` and `height-content`
+                    // Same if it's a column or row with one child and
+                    // width-content, height-content interferes with css grid
+                    // Maybe we could unpack text elements in a paragraph as
+                    // well, however, embedded elements that are larger than
+                    // the line height will overlap with exisitng text.
+                    // I don't think that's what we want.
+                    // if
+                    //     context
+                    //         == asEl
+                    //         || context
+                    //         == asParagraph
+                    // then
+                    //     ( ( key
+                    //       , VirtualDom.text
+                    //             str
+                    //       )
+                    //         :: htmls
+                    //     , existingStyles
+                    //     )
+                    // else
+                    html.push(if context == LayoutContext::AsEl {
+                        (key, text_element_fill(&txt))
+                    } else {
+                        (key, text_element(&txt))
+                    });
+                }
+                Element::Empty => {}
             }
-            Element::Styled(Styled {
-                mut styles,
-                html:
-                    FinalizeNodeArgs {
-                        has,
-                        node,
-                        attributes,
-                        children,
-                        embed_mode,
-                    },
-            }) => {
-                let mut nodes = vec![(
-                    key,
-                    finalize_node(
-                        has,
-                        node,
-                        attributes,
-                        children,
-                        EmbedStyle::NoStyleSheet,
-                        context,
-                    ),
-                )];
-                nodes.extend(html);
-                let new_styles = if existing_styles.is_empty() {
-                    styles
-                } else {
-                    styles.extend(existing_styles);
-                    styles
-                };
-                (nodes, new_styles)
+        };
+    match children {
+        Children::Keyed(c) => {
+            let len = c
+                .iter()
+                .filter(|(_, c)| !matches!(c, Element::Empty))
+                .count();
+            let mut content = (Vec::with_capacity(len), vec![]);
+            for keyed in c {
+                gather_keyed(&mut content, keyed);
             }
-            Element::Text(txt) => {
-                // TEXT OPTIMIZATION
-                // You can have raw text if the element is an el,
-                // and has `width-content` and `height-content`
-                // Same if it's a column or row with one child and
-                // width-content, height-content interferes with css grid
-                // Maybe we could unpack text elements in a paragraph as
-                // well, however, embedded elements that are larger than
-                // the line height will overlap with exisitng text.
-                // I don't think that's what we want.
-                // if
-                //     context
-                //         == asEl
-                //         || context
-                //         == asParagraph
-                // then
-                //     ( ( key
-                //       , VirtualDom.text
-                //             str
-                //       )
-                //         :: htmls
-                //     , existingStyles
-                //     )
-                // else
-                let mut h = if context == LayoutContext::AsEl {
-                    vec![(key, text_element_fill(&txt))]
-                } else {
-                    vec![(key, text_element(&txt))]
-                };
-                h.extend(html);
-                (h, existing_styles)
-            }
-            Element::Empty => (html, existing_styles),
-        }
-    };
-    match children {
-        Children::Keyed(c) => {
-            let (keyed, styles) = c
-                .into_iter()
-                .rev()
-                .fold((vec![], vec![]), |mut content, mut keyed| {
-                    gather_keyed(&mut content, &mut keyed)
-                });
+            let (keyed, styles) = content;
             let new_styles = if styles.is_empty() {
                 rendered.styles
             } else {
@@ -2684,7 +3369,7 @@ pub fn create_element(
                 rendered.styles
             };
             if new_styles.is_empty() {
-                let ck = Children::Keyed::<Node>(add_keyed_children(
+                let ck = Children::Keyed::<Node<Msg>>(add_keyed_children(
                     String::from("nearby-element-pls"),
                     keyed,
                     rendered.children,
@@ -2693,11 +3378,12 @@ pub fn create_element(
                     has: rendered.has,
                     node: rendered.node,
                     attributes: rendered.attrs,
+                    events: rendered.events,
                     children: ck,
                     embed_mode: Some(EmbedStyle::NoStyleSheet),
                 })
             } else {
-                let ck = Children::Keyed::<Node>(add_keyed_children(
+                let ck = Children::Keyed::<Node<Msg>>(add_keyed_children(
                     String::from("nearby-element-pls"),
                     keyed,
                     rendered.children,
@@ -2708,6 +3394,7 @@ pub fn create_element(
                         has: rendered.has,
                         node: rendered.node,
                         attributes: rendered.attrs,
+                        events: rendered.events,
                         children: ck,
                         embed_mode: None,
                     },
@@ -2715,12 +3402,13 @@ pub fn create_element(
             }
         }
         Children::Unkeyed(c) => {
-            let (unkeyed, styles) = c
-                .into_iter()
-                .rev()
-                .fold((vec![], vec![]), |mut content, mut child| {
-                    gather(&mut content, &mut child)
-                });
+            let len =
+                c.iter().filter(|c| !matches!(c, Element::Empty)).count();
+            let mut content = (Vec::with_capacity(len), vec![]);
+            for child in c {
+                gather(&mut content, child);
+            }
+            let (unkeyed, styles) = content;
             let new_styles = if styles.is_empty() {
                 rendered.styles
             } else {
@@ -2728,7 +3416,7 @@ pub fn create_element(
                 rendered.styles
             };
             if new_styles.is_empty() {
-                let ck = Children::Unkeyed::<Node>(add_children(
+                let ck = Children::Unkeyed::<Node<Msg>>(add_children(
                     unkeyed,
                     rendered.children,
                 ));
@@ -2736,11 +3424,12 @@ pub fn create_element(
                     has: rendered.has,
                     node: rendered.node,
                     attributes: rendered.attrs,
+                    events: rendered.events,
                     children: ck,
                     embed_mode: Some(EmbedStyle::NoStyleSheet),
                 })
             } else {
-                let ck = Children::Unkeyed::<Node>(add_children(
+                let ck = Children::Unkeyed::<Node<Msg>>(add_children(
                     unkeyed,
                     rendered.children,
                 ));
@@ -2751,6 +3440,7 @@ pub fn create_element(
                         has: rendered.has,
                         node: rendered.node,
                         attributes: rendered.attrs,
+                        events: rendered.events,
                         children: ck,
                         embed_mode: None,
                     },
@@ -2760,7 +3450,10 @@ pub fn create_element(
     }
 }
 
-pub fn add_children(mut existing: Vec<Node>, nc: NearbyChildren) -> Vec<Node> {
+pub fn add_children<Msg>(
+    mut existing: Vec<Node<Msg>>,
+    nc: NearbyChildren<Msg>,
+) -> Vec<Node<Msg>> {
     match nc {
         NearbyChildren::None => existing,
         NearbyChildren::Behind(mut b) => {
@@ -2782,18 +3475,18 @@ pub fn add_children(mut existing: Vec<Node>, nc: NearbyChildren) -> Vec<Node> {
     }
 }
 
-pub fn add_keyed_children(
+pub fn add_keyed_children<Msg>(
     key: String,
-    mut existing: Vec<(String, Node)>,
-    nc: NearbyChildren,
-) -> Vec<(String, Node)> {
+    mut existing: Vec<(String, Node<Msg>)>,
+    nc: NearbyChildren<Msg>,
+) -> Vec<(String, Node<Msg>)> {
     match nc {
         NearbyChildren::None => existing,
         NearbyChildren::Behind(mut b) => {
             let mut b = b
                 .iter()
                 .map(|x| (key.clone(), x.clone()))
-                .collect::<Vec<(String, Node)>>();
+                .collect::<Vec<(String, Node<Msg>)>>();
             b.extend(existing);
             b
         }
@@ -2801,7 +3494,7 @@ pub fn add_keyed_children(
             let mut f = f
                 .iter()
                 .map(|x| (key.clone(), x.clone()))
-                .collect::<Vec<(String, Node)>>();
+                .collect::<Vec<(String, Node<Msg>)>>();
             existing.extend(f);
             existing
         }
@@ -2812,43 +3505,47 @@ pub fn add_keyed_children(
             let mut behind = behind
                 .iter()
                 .map(|x| (key.clone(), x.clone()))
-                .collect::<Vec<(String, Node)>>();
+                .collect::<Vec<(String, Node<Msg>)>>();
             behind.extend(existing);
             let in_front = in_front
                 .iter()
                 .map(|x| (key.clone(), x.clone()))
-                .collect::<Vec<(String, Node)>>();
+                .collect::<Vec<(String, Node<Msg>)>>();
             behind.extend(in_front);
             behind
         }
     }
 }
 
-pub fn static_root(opts: OptStruct) -> Node {
+pub fn static_root<Msg>(opts: OptStruct, statics: &[Style]) -> Node<Msg> {
     match opts.mode {
         RenderMode::Layout => {
             // wrap the style node in a div to prevent `Dark Reader` from blowin up the dom.
             Node {
-                tag: "div".to_string(),
-                attrs: vec![],
+                tag: "div".into(),
+                attrs: smallvec![],
+                events: smallvec![],
                 children: vec![NodeType::Node(Node {
-                    tag: "style".to_string(),
-                    attrs: vec![],
-                    children: vec![vdom::text(style::rules())],
+                    tag: "style".into(),
+                    attrs: smallvec![],
+                    events: smallvec![],
+                    children: vec![vdom::text(static_rules(opts, statics))],
                 })],
             }
         }
         RenderMode::NoStaicStyleSheet => Node {
-            tag: "div".to_string(),
-            attrs: vec![],
+            tag: "div".into(),
+            attrs: smallvec![],
+            events: smallvec![],
             children: vec![vdom::text("".to_string())],
         },
         RenderMode::WithVirtualCSS => Node {
-            tag: "elm-ui-static-rules".to_string(),
-            attrs: vec![vdom::property(Property(
-                "rules".to_string(),
-                style::rules(),
+            tag: "elm-ui-static-rules".into(),
+            attrs: smallvec![vdom::property(Property(
+                "rules".into(),
+                static_rules(opts, statics),
             ))],
+            events: smallvec![],
             children: vec![],
         },
     }
@@ -2865,17 +3562,17 @@ pub fn add_when<T>(if_this: bool, x: T, to: Vec<T>) -> Vec<T> {
 }
 
 /// TODO: This doesn't reduce equivalent attributes completely.
-pub fn filter(attrs: Vec<Attribute>) -> Vec<Attribute> {
-    let f = |x: Attribute, y: (Vec<Attribute>, HashSet<String>)| {
+pub fn filter<Msg>(attrs: Vec<Attribute<Msg>>) -> Vec<Attribute<Msg>> {
+    let f = |x: Attribute<Msg>, y: (Vec<Attribute<Msg>>, HashSet<String>)| {
         let (found, mut has) = y;
         match x {
-            Attribute::None => (found, has),
+            Attribute::None | Attribute::Part(..) => (found, has),
             Attribute::Class(_, _) => {
                 let mut x = vec![x];
                 x.extend(found);
                 (x, has)
             }
-            Attribute::Attr(_) => {
+            Attribute::Attr(_) | Attribute::Event(_) => {
                 let mut x = vec![x];
                 x.extend(found);
                 (x, has)
@@ -2960,10 +3657,10 @@ pub fn filter(attrs: Vec<Attribute>) -> Vec<Attribute> {
         .0
 }
 
-pub fn get(
-    attrs: Vec<Attribute>,
-    mut is_attr: Box<dyn Fn(&Attribute) -> bool>,
-) -> Vec<Attribute> {
+pub fn get<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    is_attr: impl Fn(&Attribute<Msg>) -> bool,
+) -> Vec<Attribute<Msg>> {
     let attrs = filter(attrs);
     attrs.into_iter().rev().fold(vec![], |found, x| {
         if is_attr(&x) {
@@ -2976,8 +3673,8 @@ pub fn get(
     })
 }
 
-pub fn extract_spacing_and_padding(
-    attrs: Vec<Attribute>,
+pub fn extract_spacing_and_padding<Msg>(
+    attrs: Vec<Attribute<Msg>>,
 ) -> (Option<Style>, Option<Style>) {
     attrs
         .into_iter()
@@ -3000,6 +3697,9 @@ pub fn extract_spacing_and_padding(
                         Attribute::Style(_, Style::Spacing(name, x, y)) => {
                             Some(Style::Spacing(name.clone(), *x, *y))
                         }
+                        Attribute::Style(_, style @ Style::SpacingLength(..)) => {
+                            Some(style.clone())
+                        }
                         _ => None,
                     }
                 } else {
@@ -3009,7 +3709,10 @@ pub fn extract_spacing_and_padding(
         })
 }
 
-pub fn get_spacing(attrs: Vec<Attribute>, default: (u32, u32)) -> (u32, u32) {
+pub fn get_spacing<Msg>(
+    attrs: Vec<Attribute<Msg>>,
+    default: (u32, u32),
+) -> (u32, u32) {
     let res = attrs.into_iter().rev().fold(None, |acc, attr| {
         if let Some(x) = acc {
             Some(x)
@@ -3024,6 +3727,27 @@ pub fn get_spacing(attrs: Vec<Attribute>, default: (u32, u32)) -> (u32, u32) {
     res.unwrap_or_else(|| default)
 }
 
+/// Like `get_spacing`, but also finds spacing given as lengths, e.g. with
+/// `spacing_percent`.
+pub fn get_spacing_length<Msg>(
+    attrs: &[Attribute<Msg>],
+    default: (Length, Length),
+) -> (Length, Length) {
+    attrs
+        .iter()
+        .rev()
+        .find_map(|attr| match attr {
+            Attribute::Style(_, Style::Spacing(_, x, y)) => {
+                Some((Length::Px(*x as u64), Length::Px(*y as u64)))
+            }
+            Attribute::Style(_, Style::SpacingLength(_, x, y)) => {
+                Some((x.clone(), y.clone()))
+            }
+            _ => None,
+        })
+        .unwrap_or(default)
+}
+
 pub fn spacing_class_name(x: u32, y: u32) -> String {
     format!("spacing-{}-{}", x, y)
 }
@@ -3052,7 +3776,7 @@ pub fn padding_class_name_float(
     )
 }
 
-pub fn get_width(attrs: Vec<Attribute>) -> Option<Length> {
+pub fn get_width<Msg>(attrs: Vec<Attribute<Msg>>) -> Option<Length> {
     attrs.into_iter().rev().fold(None, |acc, attr| {
         if let Some(x) = acc {
             Some(x)
@@ -3066,7 +3790,7 @@ pub fn get_width(attrs: Vec<Attribute>) -> Option<Length> {
     })
 }
 
-pub fn get_height(attrs: Vec<Attribute>) -> Option<Length> {
+pub fn get_height<Msg>(attrs: Vec<Attribute<Msg>>) -> Option<Length> {
     attrs.into_iter().rev().fold(None, |acc, attr| {
         if let Some(x) = acc {
             Some(x)
@@ -3089,10 +3813,10 @@ pub fn text_element_classes() -> String {
     )
 }
 
-pub fn text_element(txt: &String) -> Node {
+pub fn text_element<Msg>(txt: &str) -> Node<Msg> {
     html::div(
-        vec![html::attributes::class(text_element_classes())],
-        vec![html::text(txt.clone())],
+        smallvec![html::attributes::class(text_element_classes())],
+        vec![html::text(txt.to_string())],
     )
 }
 
@@ -3105,19 +3829,36 @@ pub fn text_element_fill_classes() -> String {
     )
 }
 
-pub fn text_element_fill(txt: &String) -> Node {
+pub fn text_element_fill<Msg>(txt: &str) -> Node<Msg> {
     html::div(
-        vec![html::attributes::class(text_element_fill_classes())],
-        vec![html::text(txt.clone())],
+        smallvec![html::attributes::class(text_element_fill_classes())],
+        vec![html::text(txt.to_string())],
     )
 }
 
-pub fn render_root(
+#[track_caller]
+pub fn render_root<Msg>(
     opts: Vec<Opt>,
-    attrs: Vec<Attribute>,
-    child: Element,
-) -> Node {
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
+    render_root_with(opts, attrs, child, &mut stylesheets)
+}
+
+// A function making the stylesheets for a root, as `stylesheets` does.
+type Sheets<'a, Msg> =
+    dyn FnMut(bool, OptStruct, &[Style]) -> Vec<Node<Msg>> + 'a;
+
+// `render_root`, with its stylesheets made by `sheets`.
+#[track_caller]
+fn render_root_with<Msg>(
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+    sheets: &mut Sheets<'_, Msg>,
+) -> Node<Msg> {
     let opts = OptStruct::from_opts(opts);
+    let at = core::panic::Location::caller();
 
     let el = element(
         LayoutContext::AsEl,
@@ -3126,17 +3867,19 @@ pub fn render_root(
         Children::Unkeyed(vec![child]),
     );
 
-    match el {
+    let mut root = match el {
         Element::Unstyled(FinalizeNodeArgs {
             has,
             node,
             attributes,
+            events,
             children,
             embed_mode,
         }) => finalize_node(
             has,
             node,
             attributes,
+            events,
             children,
             embed_mode.unwrap(),
             LayoutContext::AsEl,
@@ -3148,27 +3891,354 @@ pub fn render_root(
                     has,
                     node,
                     attributes,
+                    events,
                     children,
                     embed_mode,
                 },
-        }) => finalize_node(
-            has,
-            node,
-            attributes,
-            children,
-            if let RenderMode::NoStaicStyleSheet = &opts.mode {
-                EmbedStyle::OnlyDynamic(opts, styles)
-            } else {
-                EmbedStyle::StaticRootAndynamic(opts, styles)
-            },
-            LayoutContext::AsEl,
-        ),
+        }) => {
+            #[cfg(debug_assertions)]
+            for collision in stylesheet_collisions(&styles) {
+                log::warn!(
+                    "class `{}` is used by two different styles in the layout rendered at {}: {:?} and {:?}",
+                    collision.class,
+                    at,
+                    collision.first,
+                    collision.second,
+                );
+            }
+            let mut root = finalize_node(
+                has,
+                node,
+                attributes,
+                events,
+                children,
+                EmbedStyle::NoStyleSheet,
+                LayoutContext::AsEl,
+            );
+            // The stylesheets go first in the node inside the bare wrapper
+            // the element's given, as `embed_with` would put them.
+            if !opts.external_styles {
+                let is_static =
+                    !matches!(opts.mode, RenderMode::NoStaicStyleSheet);
+                if let Some(NodeType::Node(inner)) = root.children.first_mut()
+                {
+                    let sheets = sheets(is_static, opts, &styles);
+                    inner
+                        .children
+                        .splice(0..0, sheets.into_iter().map(NodeType::Node));
+                }
+            }
+            root
+        }
         Element::Text(txt) => text_element(&txt),
-        Element::Empty => text_element(&"".to_string()),
+        Element::Empty => text_element(""),
+    };
+    root
+}
+
+// Entries used in this frame and the last, in buckets by hash, as
+// different entries can share one.
+struct Frames<T> {
+    previous: HashMap<u64, Vec<T>>,
+    current: HashMap<u64, Vec<T>>,
+}
+
+impl<T> Default for Frames<T> {
+    fn default() -> Self {
+        Self {
+            previous: HashMap::new(),
+            current: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Frames<T> {
+    fn next_frame(&mut self) {
+        self.previous = mem::take(&mut self.current);
+    }
+
+    fn len(&self) -> usize {
+        let buckets = self.previous.values().chain(self.current.values());
+        buckets.map(Vec::len).sum()
+    }
+
+    // The entry under `key` that `matches`, kept for the next frame.
+    fn get(&mut self, key: u64, matches: impl Fn(&T) -> bool) -> Option<&T> {
+        if let Some(bucket) = self.previous.get_mut(&key) {
+            if let Some(i) = bucket.iter().position(&matches) {
+                let entry = bucket.swap_remove(i);
+                self.current.entry(key).or_default().push(entry);
+            }
+        }
+        self.current.get(&key)?.iter().find(|entry| matches(entry))
+    }
+
+    fn insert(&mut self, key: u64, entry: T) {
+        self.current.entry(key).or_default().push(entry);
     }
 }
 
-pub fn root_style() -> Vec<Attribute> {
+// A view rendered by `render_root_cached`, with what it was rendered from.
+struct CachedView<Msg> {
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+    node: Node<Msg>,
+    // For each listener in the node, in order, the index of the one it came
+    // from among the view's, in the order `view_listeners` finds them.
+    sources: Vec<usize>,
+}
+
+// The stylesheets made for a root by `stylesheets`.
+struct CachedSheets<Msg> {
+    is_static: bool,
+    opts: OptStruct,
+    styles: Vec<Style>,
+    nodes: Vec<Node<Msg>>,
+}
+
+/// The nodes rendered in the last frame, kept by a backend so what hasn't
+/// changed since isn't rendered again.
+///
+/// A view that's the same as one rendered last frame reuses its node, given
+/// the handlers of the new view's listeners, and one that's changed still
+/// reuses the stylesheets if it uses the same styles. As the node then
+/// matches the one the backend has, `diff` leaves the backend's elements
+/// for it alone.
+pub struct RenderCache<Msg> {
+    views: Frames<CachedView<Msg>>,
+    sheets: Frames<CachedSheets<Msg>>,
+}
+
+impl<Msg> Default for RenderCache<Msg> {
+    fn default() -> Self {
+        Self {
+            views: Frames::default(),
+            sheets: Frames::default(),
+        }
+    }
+}
+
+impl<Msg> RenderCache<Msg> {
+    /// Start a new frame, forgetting whatever wasn't used in the last one.
+    pub fn next_frame(&mut self) {
+        self.views.next_frame();
+        self.sheets.next_frame();
+    }
+
+    /// How many views are kept.
+    pub fn len(&self) -> usize {
+        self.views.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = hasher();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// The listeners of a view, in the order they're found: on the `attrs`, then
+// in the `child`, each node's before its children's.
+fn view_listeners<'a, Msg>(
+    attrs: &'a [Attribute<Msg>],
+    child: &'a Element<Msg>,
+    out: &mut Vec<&'a vdom::Listener<Msg>>,
+) {
+    for attr in attrs {
+        match attr {
+            Attribute::Event(listener) => out.push(listener),
+            Attribute::Nearby(_, element) => view_listeners(&[], element, out),
+            Attribute::Part(_, attrs) => {
+                view_listeners(attrs, &Element::Empty, out)
+            }
+            _ => {}
+        }
+    }
+    let args = match child {
+        Element::Unstyled(args) => args,
+        Element::Styled(styled) => &styled.html,
+        Element::Text(_) | Element::Empty => return,
+    };
+    out.extend(&args.events);
+    let nodes: Vec<&Node<Msg>> = match &args.children {
+        Children::Unkeyed(nodes) => nodes.iter().collect(),
+        Children::Keyed(nodes) => nodes.iter().map(|(_, n)| n).collect(),
+    };
+    for node in nodes {
+        node_listeners(node, out);
+    }
+}
+
+fn node_listeners<'a, Msg>(
+    node: &'a Node<Msg>,
+    out: &mut Vec<&'a vdom::Listener<Msg>>,
+) {
+    out.extend(&node.events);
+    for child in &node.children {
+        if let NodeType::Node(n) | NodeType::KeyedNode(_, n) = child {
+            node_listeners(n, out);
+        }
+    }
+}
+
+fn node_listeners_mut<'a, Msg>(
+    node: &'a mut Node<Msg>,
+    out: &mut Vec<&'a mut vdom::Listener<Msg>>,
+) {
+    out.extend(&mut node.events);
+    for child in &mut node.children {
+        if let NodeType::Node(n) | NodeType::KeyedNode(_, n) = child {
+            node_listeners_mut(n, out);
+        }
+    }
+}
+
+fn same_handler<Msg>(
+    a: &vdom::Listener<Msg>,
+    b: &vdom::Listener<Msg>,
+) -> bool {
+    let ptr = |l: &vdom::Listener<Msg>| Arc::as_ptr(&l.handler.0) as *const ();
+    ptr(a) == ptr(b)
+}
+
+/// `render_root`, reusing what's in `cache` from this frame or the last
+/// for what hasn't changed.
+///
+/// Views are looked up by hash and then compared, so two that only share a
+/// hash are never mixed up. Listeners are compared without their handlers,
+/// so a view whose closures are made anew each frame is still reused, with
+/// the new closures.
+#[track_caller]
+pub fn render_root_cached<Msg>(
+    cache: &mut RenderCache<Msg>,
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    child: Element<Msg>,
+) -> Node<Msg> {
+    let key = hash_of((&opts, &attrs, &child));
+    let mut listeners = vec![];
+    view_listeners(&attrs, &child, &mut listeners);
+
+    let found = cache.views.get(key, |view| {
+        view.opts == opts && view.attrs == attrs && view.child == child
+    });
+    if let Some(view) = found {
+        let mut node = view.node.clone();
+        let mut stale = vec![];
+        node_listeners_mut(&mut node, &mut stale);
+        for (listener, source) in stale.into_iter().zip(&view.sources) {
+            listener.handler = listeners[*source].handler.clone();
+        }
+        return node;
+    }
+
+    let sheets = &mut cache.sheets;
+    let node = render_root_with(
+        opts.clone(),
+        attrs.clone(),
+        child.clone(),
+        &mut |is_static, opts, styles| {
+            let key = hash_of((is_static, opts, styles));
+            let found = sheets.get(key, |sheet| {
+                sheet.is_static == is_static
+                    && sheet.opts == opts
+                    && sheet.styles == styles
+            });
+            if let Some(sheet) = found {
+                return sheet.nodes.clone();
+            }
+            let nodes = stylesheets(is_static, opts, styles);
+            sheets.insert(
+                key,
+                CachedSheets {
+                    is_static,
+                    opts,
+                    styles: styles.to_vec(),
+                    nodes: nodes.clone(),
+                },
+            );
+            nodes
+        },
+    );
+
+    // Only kept if each of the node's handlers can be told apart and found
+    // in the view, to be replaced when it's reused.
+    let mut rendered = vec![];
+    node_listeners(&node, &mut rendered);
+    let sources: Option<Vec<usize>> = rendered
+        .iter()
+        .map(|listener| {
+            let mut found = listeners
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| same_handler(l, listener));
+            match (found.next(), found.next()) {
+                (Some((i, _)), None) => Some(i),
+                _ => None,
+            }
+        })
+        .collect();
+    if let Some(sources) = sources {
+        cache.views.insert(
+            key,
+            CachedView {
+                opts,
+                attrs,
+                child,
+                node: node.clone(),
+                sources,
+            },
+        );
+    }
+    node
+}
+
+/// The stylesheet `render_root` would embed for each of `children`, as one
+/// stylesheet with every style used by any of them.
+pub fn render_stylesheet<Msg>(
+    opts: Vec<Opt>,
+    attrs: Vec<Attribute<Msg>>,
+    children: Vec<Element<Msg>>,
+) -> String {
+    let opts = OptStruct::from_opts(opts);
+    let styles = children
+        .into_iter()
+        .flat_map(|child| {
+            match element(
+                LayoutContext::AsEl,
+                NodeName::div(),
+                attrs.clone(),
+                Children::Unkeyed(vec![child]),
+            ) {
+                Element::Styled(Styled { styles, .. }) => styles,
+                _ => vec![],
+            }
+        })
+        .collect::<Vec<Style>>();
+    let statics = static_styles(&styles);
+    let style_sheet = styles
+        .iter()
+        .fold(
+            (
+                static_classes(opts, &statics),
+                opts.focus.render(opts.focus_visible),
+            ),
+            |(cache, existing), style| reduce_styles(style, cache, existing),
+        )
+        .1;
+    let dynamic = to_stylesheet_str(opts, style_sheet);
+    match opts.mode {
+        RenderMode::NoStaicStyleSheet => dynamic,
+        _ => format!("{}{}", static_rules(opts, &statics), dynamic),
+    }
+}
+
+pub fn root_style<Msg>() -> Vec<Attribute<Msg>> {
     let families = vec![
         Font::Typeface("Open Sans".to_string()),
         Font::Typeface("Helvetica".to_string()),
@@ -3190,7 +4260,7 @@ pub fn root_style() -> Vec<Attribute> {
                     }
                     .format_color_class()
                 ),
-                "background-color".to_string(),
+                "background-color".into(),
                 Color {
                     r: 1.0,
                     g: 1.0,
@@ -3212,7 +4282,7 @@ pub fn root_style() -> Vec<Attribute> {
                     }
                     .format_color_class()
                 ),
-                "color".to_string(),
+                "color".into(),
                 Color {
                     r: 0.0,
                     g: 0.0,
@@ -3236,16 +4306,19 @@ pub fn root_style() -> Vec<Attribute> {
     ]
 }
 
-pub fn to_stylesheet(opts: OptStruct, stylesheet: Vec<Style>) -> Node {
+pub fn to_stylesheet<Msg>(
+    opts: OptStruct,
+    stylesheet: Vec<Style>,
+) -> Node<Msg> {
     match opts.mode {
         RenderMode::Layout | RenderMode::NoStaicStyleSheet => {
             // wrap the style node in a div to prevent `Dark Reader` from blowin up the dom.
             vdom::node(
-                "div".to_string(),
-                vec![],
+                "div".into(),
+                smallvec![],
                 vec![NodeType::Node(vdom::node(
-                    "style".to_string(),
-                    vec![],
+                    "style".into(),
+                    smallvec![],
                     vec![vdom::text(to_stylesheet_str(opts, stylesheet))],
                 ))],
             )
@@ -3253,9 +4326,9 @@ pub fn to_stylesheet(opts: OptStruct, stylesheet: Vec<Style>) -> Node {
         RenderMode::WithVirtualCSS => {
             // wrap the style node in a div to prevent `Dark Reader` from blowin up the dom.
             vdom::node(
-                "elm-ui-rules".to_string(),
-                vec![property(Property(
-                    "rules".to_string(),
+                "elm-ui-rules".into(),
+                smallvec![property(Property(
+                    "rules".into(),
                     encode_styles(opts, stylesheet),
                 ))],
                 vec![],
@@ -3264,165 +4337,154 @@ pub fn to_stylesheet(opts: OptStruct, stylesheet: Vec<Style>) -> Node {
     }
 }
 
-pub fn render_toplevel_vals(rules: &mut Vec<(String, Vec<Font>)>) -> String {
-    let with_import = |font: &Font| match font {
-        Font::ImportFont(_, url) => Some(format!("@import url('{}');", url)),
-        // Font::FontWith(with) => {
-        //     with.url.map(|x| Some(format!("@import url('{}');", x)))
-        // }
-        _ => None,
-    };
+// Where rendered rules go: straight into a stylesheet, or kept apart so
+// they can be encoded one by one.
+trait RuleSink: fmt::Write {
+    // Called after each whole rule.
+    fn end_rule(&mut self) -> fmt::Result;
 
-    let mut all_names =
-        rules.iter().map(|rule| &rule.0).collect::<Vec<&String>>();
-    let font_imports = |name: &String, typfaces: &Vec<Font>| {
-        typfaces
-            .iter()
-            .filter_map(|font| with_import(font))
-            .collect::<Vec<String>>()
-            .join("\n")
-    };
-    let mut font_adjustments =
-        |name: &String, typefaces: &Vec<Font>| -> String {
-            match typeface_adjustment(typefaces) {
-                None => all_names
-                    .iter()
-                    .map(|n| render_null_adjustment_rule(name, n))
-                    .collect::<Vec<String>>()
-                    .join(""),
-                Some(mut adj) => all_names
-                    .iter()
-                    .map(|n| render_font_adjustment_rule(name, &adj, n))
-                    .collect::<Vec<String>>()
-                    .join(""),
-            }
-        };
+    // Whether the declarations written are to be marked `!important`.
+    fn important(&self) -> bool {
+        false
+    }
+}
 
-    let mut x = rules
-        .iter()
-        .map(|(name, typefaces)| font_imports(name, typefaces))
-        .collect::<Vec<String>>()
-        .join("");
+struct Continuous<'a, W>(&'a mut W);
 
-    let y = rules
-        .iter()
-        .map(|(name, typefaces)| font_adjustments(name, typefaces))
-        .collect::<Vec<String>>()
-        .join("");
-    x.push_str(&y);
-    x
+impl<W: fmt::Write> fmt::Write for Continuous<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
 }
 
-pub fn render_null_adjustment_rule(target: &String, other: &String) -> String {
-    let name = if target == other {
-        target.clone()
-    } else {
-        format!("{} .{}", other, target)
-    };
+impl<W: fmt::Write> RuleSink for Continuous<'_, W> {
+    fn end_rule(&mut self) -> fmt::Result {
+        Ok(())
+    }
+}
 
-    let x = bracket(
-        &format!(
-            ".{}.{}, .{} .{}",
-            name,
-            Classes::SizeByCapital.to_string(),
-            name,
-            Classes::SizeByCapital.to_string()
-        ),
-        &vec![("line-height".to_string(), "1".to_string())],
-    );
+#[derive(Default)]
+struct Separate {
+    rules: Vec<String>,
+    current: String,
+    important: bool,
+}
 
-    let y = bracket(
-        &format!(
-            ".{}.{}> .{}, .{} .{} > .{}",
-            name,
-            Classes::SizeByCapital.to_string(),
-            Classes::Text.to_string(),
-            name,
-            Classes::SizeByCapital.to_string(),
-            Classes::Text.to_string()
-        ),
-        &vec![
-            ("vertical-align".to_string(), "0".to_string()),
-            ("line-height".to_string(), "1".to_string()),
-        ],
-    );
+impl fmt::Write for Separate {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.current.push_str(s);
+        Ok(())
+    }
+}
+
+impl RuleSink for Separate {
+    fn end_rule(&mut self) -> fmt::Result {
+        self.rules.push(mem::take(&mut self.current));
+        Ok(())
+    }
 
-    format!("{} {}", x, y)
+    fn important(&self) -> bool {
+        self.important
+    }
 }
 
-pub fn font_rule(
-    name: &String,
-    modifier: &String,
-    adjustments: &(Vec<(String, String)>, Vec<(String, String)>),
-) -> Vec<String> {
-    let (parent_adj, text_adj) = adjustments;
-    vec![
-        bracket(
-            &format!(".{}.{}, .{} .{}", name, modifier, name, modifier),
-            parent_adj,
-        ),
-        bracket(
-            &format!(
-                ".{}.{}> .{}, .{} .{} > .{}",
-                name,
-                modifier,
-                Classes::Text.to_string(),
-                name,
-                modifier,
-                Classes::Text.to_string()
-            ),
-            text_adj,
-        ),
-    ]
+// The font imports, then how text in each family is sized within every
+// other.
+fn write_toplevel_vals(
+    rules: &[(String, Vec<Font>)],
+    out: &mut dyn fmt::Write,
+) -> fmt::Result {
+    for (_, typefaces) in rules {
+        let urls = typefaces.iter().filter_map(|font| match font {
+            Font::ImportFont(_, url) => Some(url),
+            _ => None,
+        });
+        for (i, url) in urls.enumerate() {
+            if i > 0 {
+                out.write_char('\n')?;
+            }
+            write!(out, "@import url('{}');", url)?;
+        }
+    }
+
+    let capital = Classes::SizeByCapital.to_string();
+    let full_size = Classes::FullSize.to_string();
+    for (target, typefaces) in rules {
+        let adjustment = typeface_adjustment(typefaces);
+        for (other, _) in rules {
+            let name = Nested { target, other };
+            match &adjustment {
+                None => write_font_rule(
+                    out,
+                    &name,
+                    capital,
+                    &[("line-height", "1")],
+                    &[("vertical-align", "0"), ("line-height", "1")],
+                )?,
+                Some(AdjustmentRules { full, capital: cap }) => {
+                    write_font_rule(out, &name, capital, &cap.0, &cap.1)?;
+                    out.write_char(' ')?;
+                    write_font_rule(out, &name, full_size, &full.0, &full.1)?;
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
-pub fn render_font_adjustment_rule(
-    target: &String,
-    adjustment: &AdjustmentRules,
-    other: &String,
-) -> String {
-    let AdjustmentRules { full, capital } = adjustment;
-    let name = if target == other {
-        target.clone()
-    } else {
-        format!("{} .{}", other, target)
-    };
-    let mut x = font_rule(
-        &name,
-        &Classes::SizeByCapital.to_string().to_string(),
-        capital,
-    );
-    x.extend(font_rule(
-        &name,
-        &Classes::FullSize.to_string().to_string(),
-        full,
-    ));
-    x.join(" ")
+// The class `target`, or `target` within `other`.
+struct Nested<'a> {
+    target: &'a str,
+    other: &'a str,
 }
 
-pub fn bracket(selector: &String, rules: &Vec<(String, String)>) -> String {
-    format!(
-        "{} {{{}}}",
-        selector,
-        rules
-            .iter()
-            .map(|(name, val)| format!("{}: {};", name, val))
-            .collect::<Vec<String>>()
-            .join("")
+impl fmt::Display for Nested<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.target == self.other {
+            f.write_str(self.target)
+        } else {
+            write!(f, "{} .{}", self.other, self.target)
+        }
+    }
+}
+
+// The rules for `name` with the `modifier` class: `parent` for the element
+// and `text` for the text inside it.
+fn write_font_rule<K: fmt::Display, V: fmt::Display>(
+    out: &mut dyn fmt::Write,
+    name: &Nested,
+    modifier: &str,
+    parent: &[(K, V)],
+    text: &[(K, V)],
+) -> fmt::Result {
+    write_bracket(
+        out,
+        format_args!(".{0}.{1}, .{0} .{1}", name, modifier),
+        parent,
+    )?;
+    out.write_char(' ')?;
+    write_bracket(
+        out,
+        format_args!(
+            ".{0}.{1}> .{2}, .{0} .{1} > .{2}",
+            name,
+            modifier,
+            Classes::Text.to_string()
+        ),
+        text,
     )
 }
 
-pub fn render_props(
-    force: bool,
-    property: &Property,
-    existing: &String,
-) -> String {
-    let Property(k, v) = property;
-    if force {
-        format!("{}\n  {}: {} !important;", existing, k, v)
-    } else {
-        format!("{}\n  {}: {};", existing, k, v)
+fn write_bracket<K: fmt::Display, V: fmt::Display>(
+    out: &mut dyn fmt::Write,
+    selector: fmt::Arguments,
+    rules: &[(K, V)],
+) -> fmt::Result {
+    write!(out, "{} {{", selector)?;
+    for (name, val) in rules {
+        write!(out, "{}: {};", name, val)?;
     }
+    out.write_char('}')
 }
 
 pub fn encode_styles(opts: OptStruct, stylesheet: Vec<Style>) -> String {
@@ -3446,99 +4508,140 @@ pub fn encode_styles(opts: OptStruct, stylesheet: Vec<Style>) -> String {
 }
 
 pub fn to_stylesheet_str(opts: OptStruct, stylesheet: Vec<Style>) -> String {
-    let combine = stylesheet.iter().fold(
-        (vec![], vec![]),
-        |rendered: (Vec<String>, Vec<(String, Vec<Font>)>), style| {
-            let mut rules = rendered.0;
-            rules.extend(todo_render_style_rule(opts, style.clone(), None));
-
-            let top = if let Some(t) = style.toplevel_val() {
-                let mut t = vec![t];
-                t.extend(rendered.1);
-                t
-            } else {
-                rendered.1
-            };
+    let mut css = String::new();
+    // Writing to a `String` can't fail.
+    write_stylesheet(opts, &stylesheet, &mut css).unwrap();
+    css
+}
 
-            (rules, top)
-        },
-    );
-    let (rules, mut top) = combine;
-    let mut vals = render_toplevel_vals(&mut top);
-    vals.push_str(&rules.concat());
-    vals
+/// Write the rules for `stylesheet` out a rule at a time, rather than
+/// joining them all into one string first.
+pub fn write_stylesheet<W: fmt::Write>(
+    opts: OptStruct,
+    stylesheet: &[Style],
+    out: &mut W,
+) -> fmt::Result {
+    // Later font families come first.
+    let top = stylesheet
+        .iter()
+        .rev()
+        .filter_map(Style::toplevel_val)
+        .collect::<Vec<_>>();
+    write_toplevel_vals(&top, out)?;
+    let mut rules = Continuous(&mut *out);
+    for style in stylesheet {
+        write_style_rule(opts, style, &None, &mut rules)?;
+    }
+    out.write_str(&opts.forced_colors.render(opts.focus_visible))
+}
+
+// The declarations in `props`, for `write_style`.
+fn declarations(
+    props: &[Property],
+) -> impl Iterator<Item = (&Symbol, &String)> + Clone {
+    props.iter().map(|Property(k, v)| (k, v))
 }
 
-pub fn render_style(
+// The rules giving whatever `selector` matches `props`, in the `pseudo`
+// state.
+fn write_style<K, V, I>(
     opts: OptStruct,
     pseudo: &Option<PseudoClass>,
-    selector: String,
-    props: Vec<Property>,
-) -> Vec<String> {
-    if let Some(pseudo) = pseudo {
-        match pseudo {
-            PseudoClass::Hover => match opts.hover {
-                HoverSetting::No => vec![],
-                HoverSetting::Force => {
-                    let rprops = props.iter().fold(
-                        String::new(),
-                        |existing, property| {
-                            render_props(true, property, &existing)
-                        },
-                    );
-                    vec![format!("{}-hv {{{}\n}}", selector, rprops)]
-                }
-                HoverSetting::Allow => {
-                    let rprops = props.iter().fold(
-                        String::new(),
-                        |existing, property| {
-                            render_props(false, property, &existing)
-                        },
-                    );
-                    vec![format!("{}-hv:hover {{{}\n}}", selector, rprops)]
-                }
-            },
-            PseudoClass::Focus => {
-                let rprops =
-                    props.iter().fold(String::new(), |existing, property| {
-                        render_props(false, property, &existing)
-                    });
+    selector: &dyn fmt::Display,
+    props: I,
+    out: &mut dyn RuleSink,
+) -> fmt::Result
+where
+    K: fmt::Display,
+    V: fmt::Display,
+    I: IntoIterator<Item = (K, V)> + Clone,
+{
+    write_element_style(opts, pseudo, selector, "", props, out)
+}
 
-                vec![
-                    format!(
-                        "{}-fs:focus {{{}\n}}",
-                        selector,
-                        rprops,
-                    ),
-                    format!(
-                        ".s:focus {}-fs {{{}\n}}",
-                        selector,
-                        rprops,
-                    ),
-                    format!(
-                        "{}-fs:focus-within {{{}\n}}",
-                        selector,
-                        rprops,
-                    ),
-                    format!(
-                        ".ui-slide-bar:focus + .s .focusable-thumb{}-fs {{{}\n}}", selector, rprops
-                    )
-                ]
+// As `write_style`, for the pseudo-element `element` of what `selector`
+// matches. It has to come after any pseudo-class, so it goes on the end
+// of each selector rather than the class.
+fn write_element_style<K, V, I>(
+    opts: OptStruct,
+    pseudo: &Option<PseudoClass>,
+    selector: &dyn fmt::Display,
+    element: &str,
+    props: I,
+    out: &mut dyn RuleSink,
+) -> fmt::Result
+where
+    K: fmt::Display,
+    V: fmt::Display,
+    I: IntoIterator<Item = (K, V)> + Clone,
+{
+    let mut rule = |head: fmt::Arguments, force: bool| -> fmt::Result {
+        let force = force || out.important();
+        write!(out, "{}{} {{", head, element)?;
+        for (k, v) in props.clone() {
+            write!(out, "\n  {}: {}", k, v)?;
+            out.write_str(if force { " !important;" } else { ";" })?;
+        }
+        out.write_str("\n}")?;
+        out.end_rule()
+    };
+
+    match pseudo {
+        None => rule(format_args!("{}", selector), false)?,
+        Some(PseudoClass::Hover) => match opts.hover {
+            HoverSetting::No => {}
+            HoverSetting::Force => {
+                rule(format_args!("{}-hv", selector), true)?
             }
-            PseudoClass::Active => {
-                let rprops =
-                    props.iter().fold(String::new(), |existing, property| {
-                        render_props(false, property, &existing)
-                    });
-                vec![format!("{}-act:active {{{}\n}}", selector, rprops)]
+            HoverSetting::Allow => {
+                rule(format_args!("{}-hv:hover", selector), false)?
             }
+        },
+        Some(PseudoClass::Focus) => {
+            // `:focus-visible` is only matched when the browser decides
+            // a ring is warranted, i.e. keyboard rather than mouse focus.
+            let (focus, within) = if opts.focus_visible {
+                ("focus-visible", ":has(:focus-visible)")
+            } else {
+                ("focus", ":focus-within")
+            };
+
+            rule(format_args!("{}-fs:{}", selector, focus), false)?;
+            rule(format_args!(".s:{} {}-fs", focus, selector), false)?;
+            rule(format_args!("{}-fs{}", selector, within), false)?;
+            rule(
+                format_args!(
+                    ".ui-slide-bar:{} + .s .focusable-thumb{}-fs",
+                    focus, selector
+                ),
+                false,
+            )?;
         }
-    } else {
-        let rprops = props.iter().fold(String::new(), |existing, property| {
-            render_props(false, property, &existing)
-        });
-        vec![format!("{}-hv:hover {{{}\n}}", selector, rprops)]
+        Some(PseudoClass::Active) => {
+            rule(format_args!("{}-act:active", selector), false)?
+        }
+    }
+    Ok(())
+}
+
+// `rule` with the class `name` in its selectors replaced by its important
+// version, doubled: `.p-4 > .s` becomes `.p-4-imp.p-4-imp > .s`.
+fn important_class(rule: &str, name: &str) -> String {
+    let class = format!(".{}", name);
+    let doubled = format!(".{0}-imp.{0}-imp", name);
+    let mut out = String::with_capacity(rule.len());
+    let mut rest = rule;
+    while let Some(i) = rest.find(&class) {
+        let end = i + class.len();
+        // `.p-4` isn't in `.p-40`.
+        let whole = !rest[end..]
+            .starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_');
+        out.push_str(&rest[..i]);
+        out.push_str(if whole { &doubled } else { &class });
+        rest = &rest[end..];
     }
+    out.push_str(rest);
+    out
 }
 
 pub fn todo_render_style_rule(
@@ -3546,30 +4649,45 @@ pub fn todo_render_style_rule(
     rule: Style,
     pseudo: Option<PseudoClass>,
 ) -> Vec<String> {
+    let mut rules = Separate::default();
+    // Writing to a `String` can't fail.
+    write_style_rule(opts, &rule, &pseudo, &mut rules).unwrap();
+    rules.rules
+}
+
+fn write_style_rule(
+    opts: OptStruct,
+    rule: &Style,
+    pseudo: &Option<PseudoClass>,
+    out: &mut dyn RuleSink,
+) -> fmt::Result {
     match rule {
         Style::Style(selector, props) => {
-            render_style(opts, &pseudo, selector, props)
+            write_style(opts, pseudo, selector, declarations(props), out)
         }
-        Style::Shadows(name, prop) => render_style(
+        Style::Shadows(name, prop) => write_style(
             opts,
-            &pseudo,
-            format!(".{}", name),
-            vec![Property("box-shadow".to_string(), prop)],
+            pseudo,
+            &format_args!(".{}", name),
+            [("box-shadow", prop)],
+            out,
         ),
         Style::Transparency(name, transparency) => {
             let opacity = f32::max(0.0, f32::min(1.0, (1.0 - transparency)));
-            render_style(
+            write_style(
                 opts,
-                &pseudo,
-                format!(".{}", name),
-                vec![Property("opacity".to_string(), format!("{}", opacity))],
+                pseudo,
+                &format_args!(".{}", name),
+                [("opacity", opacity)],
+                out,
             )
         }
-        Style::FontSize(i) => render_style(
+        Style::FontSize(i) => write_style(
             opts,
-            &pseudo,
-            format!(".font-size-{}", i),
-            vec![Property("font-size".to_string(), format!("{}px", i))],
+            pseudo,
+            &format_args!(".font-size-{}", i),
+            [("font-size", format_args!("{}px", i))],
+            out,
         ),
         Style::FontFamily(name, typefaces) => {
             let features = typefaces
@@ -3578,18 +4696,18 @@ pub fn todo_render_style_rule(
                 .collect::<Vec<String>>()
                 .join(", ");
 
-            let families = vec![
-                Property(
-                    "font-family".to_string(),
+            let families = [
+                (
+                    "font-family",
                     typefaces
                         .iter()
                         .map(|f| f.name())
                         .collect::<Vec<String>>()
                         .join(" ,"),
                 ),
-                Property("font-feature-settings".to_string(), features),
-                Property(
-                    "font-variant".to_string(),
+                ("font-feature-settings", features),
+                (
+                    "font-variant",
                     if typefaces.iter().any(|f| f.has_small_caps()) {
                         "small-caps".to_string()
                     } else {
@@ -3598,363 +4716,418 @@ pub fn todo_render_style_rule(
                 ),
             ];
 
-            render_style(opts, &pseudo, format!(".{}", name), families)
+            write_style(opts, pseudo, &format_args!(".{}", name), families, out)
         }
-        Style::Single(class, prop, val) => render_style(
+        Style::Single(class, prop, val) => write_style(
             opts,
-            &pseudo,
-            format!(".{}", class),
-            vec![Property(prop, val)],
+            pseudo,
+            &format_args!(".{}", class),
+            [(prop, val)],
+            out,
         ),
-        Style::Colored(class, prop, color) => render_style(
+        Style::Colored(class, prop, color) => write_style(
             opts,
-            &pseudo,
-            format!(".{}", class),
-            vec![Property(prop, color.format_color())],
+            pseudo,
+            &format_args!(".{}", class),
+            [(prop, color.format_color())],
+            out,
+        ),
+        Style::Spacing(cls, x, y) => write_spacing_rules(
+            opts,
+            pseudo,
+            cls,
+            &SpacingValues {
+                x: format!("{}px", x),
+                y: format!("{}px", y),
+                half_x: format!("{}px", (*x as f32 / 2.0)),
+                half_y: format!("{}px", (*y as f32 / 2.0)),
+                neg_half_y: format!("{}px", -(*y as i32 / 2)),
+            },
+            out,
+        ),
+        Style::SpacingLength(cls, x, y) => write_spacing_rules(
+            opts,
+            pseudo,
+            cls,
+            &SpacingValues {
+                half_x: format!("calc({} / 2)", x),
+                half_y: format!("calc({} / 2)", y),
+                neg_half_y: format!("calc({} / -2)", y),
+                x: x.to_string(),
+                y: y.to_string(),
+            },
+            out,
+        ),
+        Style::TextColumns(count, gap) => write_style(
+            opts,
+            pseudo,
+            &format_args!(".cols-{}-{}", count, gap),
+            declarations(&text_columns_properties(*count, *gap)),
+            out,
+        ),
+        Style::Padding(cls, top, right, bottom, left) => write_style(
+            opts,
+            pseudo,
+            &format_args!(".{}", cls),
+            [(
+                "padding",
+                format_args!("{}px {}px {}px {}px", top, right, bottom, left),
+            )],
+            out,
+        ),
+        Style::BorderWidth(cls, top, right, bottom, left) => write_style(
+            opts,
+            pseudo,
+            &format_args!(".{}", cls),
+            [(
+                "border-width",
+                format_args!("{}px {}px {}px {}px", top, right, bottom, left),
+            )],
+            out,
         ),
-        Style::Spacing(cls, x, y) => {
-            let class = format!(".{}", cls);
-
-            let half_x = format!("{}px", (x as f32 / 2.0));
-
-            let half_y = format!("{}px", (x as f32 / 2.0));
-
-            let px_x = format!("{}px", x);
-
-            let px_y = format!("{}px", y);
-
-            let row = format!(".{}", Classes::Row.to_string());
-
-            let wrapped_row =
-                format!(".{}{}", Classes::Wrapped.to_string(), row);
-
-            let col = format!(".{}", Classes::Column.to_string());
-
-            let page = format!(".{}", Classes::Page.to_string());
-
-            let paragraph = format!(".{}", Classes::Paragraph.to_string());
-
-            let left = format!(".{}", Classes::AlignLeft.to_string());
-
-            let right = format!(".{}", Classes::AlignRight.to_string());
-
-            let any = format!(".{}", Classes::Any.to_string());
-
-            vec![
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {} + {}", class, row, any, any),
-                    vec![Property("margin-left".to_string(), px_x.clone())],
-                ),
-                // margins don't apply to last element of normal, unwrapped rows
-                // render_style(
-                //     opts,
-                //     pseudo,
-                //     format!(
-                //         "{}{} > {}:first-child",
-                //         class,
-                //         row,
-                //         any
-                //     ),
-                //     vec![Property("margin".to_string(), "0".to_string())]
-                // )
-                // For wrapped rows, margins always apply because
-                // we handle "canceling out" the other margins
-                // manually in the element.
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {}", class, wrapped_row, any),
-                    vec![Property(
-                        "margin".to_string(),
-                        format!("{} {}", half_y, half_x),
-                    )],
-                ),
-                // render_style(
-                //     opts,
-                //     pseudo
-                //     format!(
-                //         "{}{} > {}:last-child",
-                //         class
-                //         wrapped_row,
-                //         any
-                //     ),
-                //     vec![
-                //         Property("margin-right".to_string(), "0".to_string())
-                //     ]
-                // ),
-                // columns
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {} + {}", class, col, any, any),
-                    vec![Property("margin-top".to_string(), px_y.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {} + {}", class, page, any, any),
-                    vec![Property("margin-top".to_string(), px_y.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {}", class, page, left),
-                    vec![Property("margin-right".to_string(), px_x.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {}", class, page, right),
-                    vec![Property("margin-left".to_string(), px_x.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{}", class, paragraph),
-                    vec![Property(
-                        "line-height".to_string(),
-                        format!("calc(1em + {}px)", y),
-                    )],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("textarea{}{}", any, class),
-                    vec![
-                        Property(
-                            "line-height".to_string(),
-                            format!("calc(1em + {}px)", y),
-                        ),
-                        Property(
-                            "height".to_string(),
-                            format!("calc(100% + {}px)", y),
-                        ),
-                    ],
-                ),
-                // render_style(
-                //     opts,
-                //     &pseudo,
-                //     format!("{}{} > {}", class, paragraph, any),
-                //     vec![
-                //         Property("margin-right".to_string(), px_x),
-                //         Property("margin-bottom".to_string(), px_y)
-                //     ]
-                // )
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{}{} > {}", class, paragraph, left),
-                    vec![Property("margin-right".to_string(), px_x.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{} {} > {}", class, paragraph, right),
-                    vec![Property("margin-left".to_string(), px_x.clone())],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{} {}::after", class, paragraph),
-                    vec![
-                        Property("content".to_string(), "''".to_string()),
-                        Property("display".to_string(), "block".to_string()),
-                        Property("height".to_string(), "0".to_string()),
-                        Property("width".to_string(), "0".to_string()),
-                        Property(
-                            "margin-top".to_string(),
-                            format!("{}px", (-1 * (y as i8 / 2))),
-                        ),
-                    ],
-                ),
-                render_style(
-                    opts,
-                    &pseudo,
-                    format!("{} {}::before", class, paragraph),
-                    vec![
-                        Property("content".to_string(), "''".to_string()),
-                        Property("display".to_string(), "block".to_string()),
-                        Property("height".to_string(), "0".to_string()),
-                        Property("width".to_string(), "0".to_string()),
-                        Property(
-                            "margin-bottom".to_string(),
-                            format!("{}px", (-1 * (y as i8 / 2))),
-                        ),
-                    ],
-                ),
-            ]
-            .concat()
-        }
-        Style::Padding(cls, top, right, bottom, left) => {
-            let class = format!(".{}", cls);
-
-            render_style(
-                opts,
-                &pseudo,
-                class,
-                vec![Property(
-                    "padding".to_string(),
-                    format!("{} px {}px {}px {}px", top, right, bottom, left),
-                )],
-            )
-        }
-        Style::BorderWidth(cls, top, right, bottom, left) => {
-            let class = format!(".{}", cls);
-
-            render_style(
-                opts,
-                &pseudo,
-                class,
-                vec![Property(
-                    "border-width".to_string(),
-                    format!("{} px {}px {}px {}px", top, right, bottom, left),
-                )],
-            )
-        }
         Style::GridTemplate(template) => {
-            let grid_rows = template
-                .rows
-                .iter()
-                .map(|n| n.class_name())
-                .collect::<Vec<String>>()
-                .join("-");
-
-            let grid_cols = template
-                .columns
-                .iter()
-                .map(|n| n.class_name())
-                .collect::<Vec<String>>()
-                .join("-");
-
-            let class = format!(
-                ".grid-rows-{}-cols-{}-space-x-{}-space-y-{}",
-                grid_rows,
-                grid_cols,
-                template.spacing.0.class_name(),
-                template.spacing.1.class_name(),
-            );
+            let class = template.class_name();
+            let end = if out.important() { " !important;" } else { ";" };
 
             let to_grid_len = |l: &Length| to_grid_len_helper(&None, &None, l);
 
             let spacing_y = to_grid_len(&template.spacing.1);
 
-            let ms_cols = format!(
-                "-ms-grid-columns: {};",
-                template
-                    .columns
-                    .iter()
-                    .map(|l| to_grid_len(l))
-                    .collect::<Vec<String>>()
-                    .join(&spacing_y)
-            );
-
-            let ms_rows = format!(
-                "-ms-grid-rows: {};",
-                template
-                    .rows
+            // The old grid has no `repeat`, so counted repeats are written
+            // out and the rest are a single track.
+            let ms_tracks = |tracks: &[Track]| {
+                tracks
                     .iter()
-                    .map(|l| to_grid_len(l))
+                    .flat_map(|t| match t {
+                        Track::Size(l) => vec![to_grid_len(l)],
+                        Track::Repeat(Repeat::Count(n), l) => {
+                            vec![to_grid_len(l); *n as usize]
+                        }
+                        Track::Repeat(_, l) => vec![to_grid_len(l)],
+                    })
                     .collect::<Vec<String>>()
                     .join(&spacing_y)
-            );
-
-            let base =
-                format!("{}{{{}}}", class, format!("{}{}", ms_cols, ms_rows));
-
-            let cols = format!(
-                "grid-template-columns: {};",
-                template
-                    .columns
-                    .iter()
-                    .map(|l| to_grid_len(l))
-                    .collect::<Vec<String>>()
-                    .join(" ")
-            );
+            };
 
-            let rows = format!(
-                "grid-template-rows: {};",
-                template
-                    .rows
+            write!(
+                out,
+                ".{}{{-ms-grid-columns: {}{end}-ms-grid-rows: {}{end}}}",
+                class,
+                ms_tracks(&template.columns),
+                ms_tracks(&template.rows),
+                end = end,
+            )?;
+            out.end_rule()?;
+
+            let tracks = |tracks: &[Track]| {
+                tracks
                     .iter()
-                    .map(|l| to_grid_len(l))
+                    .map(|t| match t {
+                        Track::Size(l) => to_grid_len(l),
+                        Track::Repeat(repeat, l) => {
+                            let count = match repeat {
+                                Repeat::Count(n) => n.to_string(),
+                                Repeat::AutoFill => "auto-fill".to_string(),
+                                Repeat::AutoFit => "auto-fit".to_string(),
+                            };
+                            format!("repeat({}, {})", count, to_grid_len(l))
+                        }
+                    })
                     .collect::<Vec<String>>()
                     .join(" ")
-            );
-
-            let gap_x = format!(
-                "grid-column-gap:{};",
-                to_grid_len(&template.spacing.0)
-            );
-
-            let gap_y =
-                format!("grid-row-gap:{};", to_grid_len(&template.spacing.1));
+            };
 
-            let modern_grid = format!(
-                "{}{{{}}}",
+            write!(
+                out,
+                "@supports (display:grid) {{.{}{{display:grid{end}\
+                 grid-template-columns: {}{end}grid-template-rows: {}{end}\
+                 grid-column-gap:{}{end}grid-row-gap:{}{end}",
                 class,
-                format!("{}{}{}{}", cols, rows, gap_x, gap_y)
-            );
-
-            let supports =
-                format!("@supports (display:grid) {{{}}}", modern_grid);
-
-            vec![base, supports]
+                tracks(&template.columns),
+                tracks(&template.rows),
+                to_grid_len(&template.spacing.0),
+                to_grid_len(&template.spacing.1),
+                end = end,
+            )?;
+            if let Some(l) = &template.auto_rows {
+                write!(out, "grid-auto-rows: {}{}", to_grid_len(l), end)?;
+            }
+            if let Some(l) = &template.auto_columns {
+                write!(out, "grid-auto-columns: {}{}", to_grid_len(l), end)?;
+            }
+            if let Some(f) = template.auto_flow {
+                write!(out, "grid-auto-flow: {}{}", f.as_str(), end)?;
+            }
+            out.write_str("}}")?;
+            out.end_rule()?;
+
+            if let Some(subgrid) = template.subgrid {
+                let (cols, rows) = match subgrid {
+                    Subgrid::Columns => (true, false),
+                    Subgrid::Rows => (false, true),
+                    Subgrid::Both => (true, true),
+                };
+                write!(
+                    out,
+                    "@supports (grid-template-columns: subgrid) {{.{}{{",
+                    class
+                )?;
+                if cols {
+                    write!(out, "grid-template-columns: subgrid{}", end)?;
+                }
+                if rows {
+                    write!(out, "grid-template-rows: subgrid{}", end)?;
+                }
+                out.write_str("}}")?;
+                out.end_rule()?;
+            }
+            Ok(())
         }
         Style::GridPosition(pos) => {
-            let class = format!(
-                ".grid-pos-{}-{}-{}-{}",
-                pos.row, pos.col, pos.width, pos.height,
-            );
-
-            let ms_pos = format!(
-                "-ms-grid-row: {}; -ms-grid-row-span: {}; -ms-grid-column: {}; -ms-grid-column-span: {};",
+            let end = if out.important() { " !important;" } else { ";" };
+            write!(
+                out,
+                ".grid-pos-{0}-{1}-{2}-{3}{{-ms-grid-row: {0}{end} -ms-grid-row-span: {3}{end} -ms-grid-column: {1}{end} -ms-grid-column-span: {2}{end}}}",
+                pos.row, pos.col, pos.width, pos.height, end = end,
+            )?;
+            out.end_rule()?;
+
+            write!(
+                out,
+                "@supports (display:grid) {{.grid-pos-{0}-{1}-{2}-{3}{{grid-row: {0} / {4}{end} grid-column: {1} / {5}{end}}}}}",
                 pos.row,
-                pos.height,
                 pos.col,
-                pos.width
-            );
-
-            let base = format!("{}{{{}}}", class, ms_pos);
-
-            let modern_pos = format!(
-                "grid-row: {} / {}; grid-column: {} / {};",
-                pos.row,
+                pos.width,
+                pos.height,
                 (pos.row + pos.height),
-                pos.col,
-                (pos.col + pos.width)
-            );
-
-            let modern_grid = format!("{}{{{}}}", class, modern_pos);
-
-            let supports =
-                format!("@supports (display:grid) {{{}}}", modern_grid);
-
-            vec![base, supports]
+                (pos.col + pos.width),
+                end = end,
+            )?;
+            out.end_rule()
+        }
+        Style::PseudoSelector(class, styles) => {
+            let pseudo = Some(class.clone());
+            for style in styles {
+                write_style_rule(opts, style, &pseudo, out)?;
+            }
+            Ok(())
         }
-        Style::PseudoSelector(class, styles) => styles
-            .into_iter()
-            .flat_map(|s| todo_render_style_rule(opts, s, Some(class.clone())))
-            .collect(),
+        Style::PseudoElement(element, props) => write_element_style(
+            opts,
+            pseudo,
+            &format_args!(".{}", rule.name()),
+            element.selector(),
+            declarations(props),
+            out,
+        ),
+        Style::Important(style) => {
+            let names = style.name();
+            let mut rules = Separate {
+                important: true,
+                ..Separate::default()
+            };
+            write_style_rule(opts, style, pseudo, &mut rules)?;
+            for rule in rules.rules {
+                let rule = names
+                    .split_whitespace()
+                    .fold(rule, |rule, name| important_class(&rule, name));
+                out.write_str(&rule)?;
+                out.end_rule()?;
+            }
+            Ok(())
+        }
+        Style::Static(style) => write_style_rule(opts, style, pseudo, out),
         Style::Transform(transform) => {
             let val = transform.value();
             let class = transform.class();
 
             if let (Some(class), Some(val)) = (class, val) {
-                render_style(
+                write_style(
                     opts,
-                    &pseudo,
-                    format!(".{}", class),
-                    vec![Property("transform".to_string(), val)],
+                    pseudo,
+                    &format_args!(".{}", class),
+                    [("transform", val)],
+                    out,
                 )
             } else {
-                vec![]
+                Ok(())
             }
         }
     }
 }
 
+// The lengths a `spacing` is rendered with, as CSS.
+struct SpacingValues {
+    x: String,
+    y: String,
+    half_x: String,
+    half_y: String,
+    neg_half_y: String,
+}
+
+// The rules spacing the children of elements with the class `cls`.
+fn write_spacing_rules(
+    opts: OptStruct,
+    pseudo: &Option<PseudoClass>,
+    cls: &str,
+    v: &SpacingValues,
+    out: &mut dyn RuleSink,
+) -> fmt::Result {
+    let row = Classes::Row.to_string();
+
+    let wrapped = Classes::Wrapped.to_string();
+
+    let col = Classes::Column.to_string();
+
+    let page = Classes::Page.to_string();
+
+    let paragraph = Classes::Paragraph.to_string();
+
+    let left = Classes::AlignLeft.to_string();
+
+    let right = Classes::AlignRight.to_string();
+
+    let any = Classes::Any.to_string();
+
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{} + .{}", cls, row, any, any),
+        [("margin-left", &v.x)],
+        out,
+    )?;
+    // margins don't apply to last element of normal, unwrapped rows
+    // render_style(
+    //     opts,
+    //     pseudo,
+    //     format!(
+    //         "{}{} > {}:first-child",
+    //         class,
+    //         row,
+    //         any
+    //     ),
+    //     vec![Property("margin".into(), "0".to_string())]
+    // )
+    // For wrapped rows, margins always apply because
+    // we handle "canceling out" the other margins
+    // manually in the element.
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{}.{} > .{}", cls, wrapped, row, any),
+        [("margin", format_args!("{} {}", v.half_y, v.half_x))],
+        out,
+    )?;
+    // render_style(
+    //     opts,
+    //     pseudo
+    //     format!(
+    //         "{}{} > {}:last-child",
+    //         class
+    //         wrapped_row,
+    //         any
+    //     ),
+    //     vec![
+    //         Property("margin-right".into(), "0".to_string())
+    //     ]
+    // ),
+    // columns
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{} + .{}", cls, col, any, any),
+        [("margin-top", &v.y)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{} + .{}", cls, page, any, any),
+        [("margin-top", &v.y)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{}", cls, page, left),
+        [("margin-right", &v.x)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{}", cls, page, right),
+        [("margin-left", &v.x)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{}", cls, paragraph),
+        [("line-height", format_args!("calc(1em + {})", v.y))],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!("textarea.{}.{}", any, cls),
+        [
+            ("line-height", format_args!("calc(1em + {})", v.y)),
+            ("height", format_args!("calc(100% + {})", v.y)),
+        ],
+        out,
+    )?;
+    // render_style(
+    //     opts,
+    //     pseudo,
+    //     format!("{}{} > {}", class, paragraph, any),
+    //     vec![
+    //         Property("margin-right".into(), px_x),
+    //         Property("margin-bottom".into(), px_y)
+    //     ]
+    // )
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{}.{} > .{}", cls, paragraph, left),
+        [("margin-right", &v.x)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{} .{} > .{}", cls, paragraph, right),
+        [("margin-left", &v.x)],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{} .{}::after", cls, paragraph),
+        [
+            ("content", "''"),
+            ("display", "block"),
+            ("height", "0"),
+            ("width", "0"),
+            ("margin-top", &v.neg_half_y),
+        ],
+        out,
+    )?;
+    write_style(
+        opts,
+        pseudo,
+        &format_args!(".{} .{}::before", cls, paragraph),
+        [
+            ("content", "''"),
+            ("display", "block"),
+            ("height", "0"),
+            ("width", "0"),
+            ("margin-bottom", &v.neg_half_y),
+        ],
+        out,
+    )
+}
+
 fn to_grid_len_helper(
     min: &Option<u64>,
     max: &Option<u64>,
@@ -3962,6 +5135,7 @@ fn to_grid_len_helper(
 ) -> String {
     match l {
         Length::Px(px) => format!("{}px", px),
+        Length::Calc(expr) => expr.clone(),
         Length::Content => match (min, max) {
             (None, None) => "max-content".to_string(),
             (Some(size), None) => {
@@ -3971,19 +5145,19 @@ fn to_grid_len_helper(
                 format!("minmax(max-content, {}px)", size)
             }
             (Some(min), Some(max)) => {
-                format!("minmax({}px, {}px", min, max)
+                format!("minmax({}px, {}px)", min, max)
             }
         },
         Length::Fill(i) => match (min, max) {
             (None, None) => format!("{}fr", i),
             (Some(size), None) => {
-                format!("minmax({}px, {}frfr)", size, i)
+                format!("minmax({}px, {}fr)", size, i)
             }
             (None, Some(size)) => {
                 format!("minmax(max-content, {}px)", size)
             }
             (Some(min), Some(max)) => {
-                format!("minmax({}px, {}px", min, max)
+                format!("minmax({}px, {}px)", min, max)
             }
         },
         Length::Min(m, len) => to_grid_len_helper(&Some(*m), max, &**len),
@@ -3997,7 +5171,7 @@ fn to_grid_len_helper(
 //         ( styles, transform ) ->
 //             Transform transform :: styles
 
-pub fn unwrap_decorations(attrs: Vec<Attribute>) -> Vec<Style> {
+pub fn unwrap_decorations<Msg>(attrs: Vec<Attribute<Msg>>) -> Vec<Style> {
     let (styles, transform) = attrs
         .into_iter()
         .fold((vec![], Transform::Untransformed), |(styles, t), attr| {
@@ -4010,8 +5184,49 @@ pub fn unwrap_decorations(attrs: Vec<Attribute>) -> Vec<Style> {
     styles
 }
 
-pub fn unwrap_decorations_helper(
-    attr: Attribute,
+/// A `will-change` hint for the properties an element's styles animate:
+/// the transforms and opacity of states like `mouse_over`, and those its
+/// `transition`s are on. It's for the element itself rather than the
+/// state, so the browser has it on its own layer before the animation
+/// starts instead of repainting it on every frame.
+pub fn layer_hint<'a>(
+    styles: impl Iterator<Item = &'a Style>,
+) -> Option<Style> {
+    fn animated<'a>(style: &'a Style, found: &mut Vec<&'a str>) {
+        match style {
+            Style::PseudoSelector(_, styles) => {
+                styles.iter().for_each(|style| animated(style, found))
+            }
+            Style::Transform(Transform::Untransformed) => {}
+            Style::Transform(_) => found.push("transform"),
+            Style::Transparency(_, _) => found.push("opacity"),
+            Style::Single(_, prop, value) if *prop == "transition" => {
+                found.extend(
+                    value
+                        .split_whitespace()
+                        .next()
+                        .filter(|p| *p == "transform" || *p == "opacity"),
+                )
+            }
+            _ => {}
+        }
+    }
+    let mut found = vec![];
+    styles.for_each(|style| animated(style, &mut found));
+    found.sort();
+    found.dedup();
+    if found.is_empty() {
+        return None;
+    }
+    Some(Style::Single(
+        format!("wc-{}", found.join("-")),
+        "will-change".into(),
+        found.join(", "),
+    ))
+}
+
+pub fn unwrap_decorations_helper<Msg>(
+    attr: Attribute<Msg>,
     styles: Vec<Style>,
     t: Transform,
 ) -> (Vec<Style>, Transform) {
@@ -4029,60 +5244,6 @@ pub fn unwrap_decorations_helper(
     }
 }
 
-// map : (msg -> msg1) -> Element msg -> Element msg1
-// map fn el =
-//     case el of
-//         Styled styled ->
-//             Styled
-//                 { styles = styled.styles
-//                 , html = \add context -> VirtualDom.map fn <| styled.html add context
-//                 }
-
-//         Unstyled html ->
-//             Unstyled (VirtualDom.map fn << html)
-
-//         Text str ->
-//             Text str
-
-//         Empty ->
-//             Empty
-
-// mapAttr : (msg -> msg1) -> Attribute aligned msg -> Attribute aligned msg1
-// mapAttr fn attr =
-//     case attr of
-//         NoAttribute ->
-//             NoAttribute
-
-//         Describe description ->
-//             Describe description
-
-//         AlignX x ->
-//             AlignX x
-
-//         AlignY y ->
-//             AlignY y
-
-//         Width x ->
-//             Width x
-
-//         Height x ->
-//             Height x
-
-//         Class x y ->
-//             Class x y
-
-//         StyleClass flag style ->
-//             StyleClass flag style
-
-//         Nearby location elem ->
-//             Nearby location (map fn elem)
-
-//         Attr htmlAttr ->
-//             Attr (VirtualDom.mapAttribute fn htmlAttr)
-
-//         TransformComponent fl trans ->
-//             TransformComponent fl trans
-
 // mapAttrFromStyle : (msg -> msg1) -> Attribute Never msg -> Attribute () msg1
 // mapAttrFromStyle fn attr =
 //     case attr of
@@ -0,0 +1,201 @@
+//! The lengths and colors the element model is built from.
+
+use ::core::fmt;
+use ::core::hash::{Hash, Hasher};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::compat::round;
+pub use crate::flag::{Field, Flag};
+
+// Floats are hashed by their bits, with `-0.0` made `0.0` as they're equal.
+pub(crate) fn hash_floats<H: Hasher>(floats: &[f32], state: &mut H) {
+    for f in floats {
+        (f + 0.0).to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub enum Length {
+    Px(u64),
+    Content,
+    Fill(u64),
+    Min(u64, Box<Length>),
+    Max(u64, Box<Length>),
+    /// A CSS `calc()` expression, best made with `Calc`.
+    Calc(String),
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Length::Px(px) => write!(f, "{}px", px),
+            Length::Content => write!(f, "auto"),
+            Length::Fill(i) => write!(f, "{}fr", i),
+            Length::Min(min, len) => {
+                write!(f, "min{}{}", min, len)
+            }
+            Length::Max(max, len) => {
+                write!(f, "max{}{}", max, len)
+            }
+            Length::Calc(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl Length {
+    pub fn class_name(&self) -> String {
+        match self {
+            Length::Px(px) => format!("{}px", px),
+            Length::Content => String::from("auto"),
+            Length::Fill(i) => format!("{}fr", i),
+            Length::Min(min, len) => {
+                format!("min{}{}", min, len)
+            }
+            Length::Max(max, len) => {
+                format!("max{}{}", max, len)
+            }
+            // Hashed, as the expression has characters classes can't.
+            Length::Calc(expr) => format!(
+                "calc{:x}",
+                expr.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+                })
+            ),
+        }
+    }
+}
+
+/// A length worked out by the browser from lengths in different units,
+/// e.g. `Calc::percent(50.0).minus(Calc::px(8.0))` for half the parent's
+/// size less 8 pixels. It's made a `Length` with `Length::from`.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub struct Calc(String);
+
+impl Calc {
+    pub fn px(n: f32) -> Self {
+        Calc(format!("{}px", n))
+    }
+
+    /// A percentage of the parent's size along the same axis.
+    pub fn percent(n: f32) -> Self {
+        Calc(format!("{}%", n))
+    }
+
+    /// A multiple of the font size.
+    pub fn em(n: f32) -> Self {
+        Calc(format!("{}em", n))
+    }
+
+    /// A percentage of the window's width.
+    pub fn vw(n: f32) -> Self {
+        Calc(format!("{}vw", n))
+    }
+
+    /// A percentage of the window's height.
+    pub fn vh(n: f32) -> Self {
+        Calc(format!("{}vh", n))
+    }
+
+    pub fn plus(self, other: Calc) -> Self {
+        Calc(format!("{} + {}", self.0, other.grouped()))
+    }
+
+    pub fn minus(self, other: Calc) -> Self {
+        Calc(format!("{} - {}", self.0, other.grouped()))
+    }
+
+    pub fn times(self, n: f32) -> Self {
+        Calc(format!("{} * {}", self.grouped(), n))
+    }
+
+    pub fn divided_by(self, n: f32) -> Self {
+        Calc(format!("{} / {}", self.grouped(), n))
+    }
+
+    // The expression, in parentheses if it's more than a single length.
+    fn grouped(&self) -> String {
+        if self.0.contains(' ') {
+            format!("({})", self.0)
+        } else {
+            self.0.clone()
+        }
+    }
+}
+
+impl From<Calc> for Length {
+    fn from(calc: Calc) -> Self {
+        Length::Calc(format!("calc({})", calc.0))
+    }
+}
+
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Hash for Color {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_floats(&[self.r, self.g, self.b, self.a], state);
+    }
+}
+
+impl Color {
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn format_color(&self) -> String {
+        let Self { r, g, b, a } = self;
+        format!(
+            "rgba({},{},{},{})",
+            r.float_class(),
+            g.float_class(),
+            b.float_class(),
+            *a as u32
+        )
+    }
+    pub fn format_color_class(&self) -> String {
+        let Self { r, g, b, a } = self;
+        format!(
+            "rgba({},{},{},{})",
+            r.float_class(),
+            g.float_class(),
+            b.float_class(),
+            a.float_class()
+        )
+    }
+}
+
+pub trait FloatClass {
+    fn float_class(&self) -> String;
+}
+
+impl FloatClass for f32 {
+    fn float_class(&self) -> String {
+        (round(self * 255.0) as u32).to_string()
+    }
+}
+
+#[test]
+fn test_primitive_classes() {
+    let orange = Color::new(1.0, 0.5, 0.0, 1.0);
+    assert_eq!(orange.format_color_class(), "rgba(255,128,0,255)");
+    let length = Length::Min(30, Box::new(Length::Fill(2)));
+    assert_eq!(length.class_name(), "min302fr");
+    let calc = Calc::percent(50.0).minus(Calc::px(8.0).plus(Calc::em(1.0)));
+    assert_eq!(
+        Length::from(calc.times(2.0)).to_string(),
+        "calc((50% - (8px + 1em)) * 2)"
+    );
+    assert_eq!(Flag::from(Flag::padding().value()), Flag::padding());
+    assert_eq!(Flag::from(Flag::order().value()), Flag::order());
+}
@@ -0,0 +1,74 @@
+use crate::model::{Attribute, Description};
+
+/// The main content of the page. There should only be one.
+pub fn main_content<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::Main)
+}
+
+/// A section of links for getting around the page or site.
+pub fn navigation<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::Navigation)
+}
+
+/// A heading, from `1` for the most important down to `6`. Levels below
+/// `1` are treated as `1`, and above `6` as `6`.
+///
+/// Headings should nest in order, without skipping levels, as screen
+/// reader users move through the page by them.
+pub fn heading<Msg>(level: u64) -> Attribute<Msg> {
+    Attribute::Describe(Description::Heading(level))
+}
+
+/// Content related to the main content, but that makes sense on its own,
+/// e.g. a sidebar.
+pub fn aside<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::Complementary)
+}
+
+/// Information about the page, like its copyright or contact details.
+pub fn footer<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::ContentInfo)
+}
+
+/// A description read out by screen readers in place of the element's
+/// content, e.g. for a button showing only an icon.
+pub fn description<Msg>(text: &str) -> Attribute<Msg> {
+    Attribute::Describe(Description::Label(text.to_string()))
+}
+
+/// Changes to the element's content are read out by screen readers once
+/// the user is idle. For one-off messages, an `Announcer` is simpler.
+pub fn announce<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::LivePolite)
+}
+
+/// Changes to the element's content are read out by screen readers
+/// straight away, interrupting whatever is being read.
+pub fn announce_urgently<Msg>() -> Attribute<Msg> {
+    Attribute::Describe(Description::LiveAssertive)
+}
+
+#[test]
+fn test_region() {
+    use crate::element::{column, el, layout};
+    use crate::model::Element;
+
+    let text = |s: &str| Element::Text(s.to_string());
+    let view = column::<()>(
+        vec![main_content()],
+        vec![
+            el(vec![heading(2)], text("Title")),
+            el(vec![heading(9)], text("Deep")),
+            el(vec![navigation(), description("Site")], Element::Empty),
+            el(vec![aside()], Element::Empty),
+            el(vec![footer()], Element::Empty),
+            el(vec![announce_urgently()], text("Saved")),
+        ],
+    );
+    let html = crate::render::to_html(&layout(vec![], view));
+    for tag in &["<main", "<h2", "<h6", "<nav", "<aside", "<footer"] {
+        assert!(html.contains(tag), "{} not in {}", tag, html);
+    }
+    assert!(html.contains("aria-label=\"Site\""), "{}", html);
+    assert!(html.contains("aria-live=\"assertive\""), "{}", html);
+}
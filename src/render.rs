@@ -0,0 +1,175 @@
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::vdom::{html_attributes, Node, NodeType};
+
+// Elements that can't have children, so have no closing tag.
+const VOID: [&str; 8] =
+    ["area", "br", "col", "hr", "img", "input", "link", "meta"];
+
+// Elements whose text is taken as is, so isn't escaped.
+const RAW_TEXT: [&str; 2] = ["style", "script"];
+
+// Raw text ends at the first `</style` or `</script`, so the text can't
+// close its element early, e.g. with a `content` of `'</style>'`. `<\/`
+// means the same in CSS and in a script's strings.
+fn write_raw<W: Write>(out: &mut W, text: &str) -> fmt::Result {
+    let mut rest = text;
+    while let Some(i) = rest.find("</") {
+        out.write_str(&rest[..i])?;
+        out.write_str("<\\/")?;
+        rest = &rest[i + 2..];
+    }
+    out.write_str(rest)
+}
+
+fn write_escaped<W: Write>(
+    out: &mut W,
+    text: &str,
+    attribute: bool,
+) -> fmt::Result {
+    let mut rest = text;
+    while let Some(i) =
+        rest.find(|c| matches!(c, '&' | '<' | '>') || (attribute && c == '"'))
+    {
+        out.write_str(&rest[..i])?;
+        out.write_str(match &rest[i..i + 1] {
+            "&" => "&amp;",
+            "<" => "&lt;",
+            ">" => "&gt;",
+            _ => "&quot;",
+        })?;
+        rest = &rest[i + 1..];
+    }
+    out.write_str(rest)
+}
+
+/// Write `node` out as HTML, a piece at a time, e.g. straight into a
+/// response for server side rendering. Event listeners are left out.
+///
+/// The classes of a node are written as a single `class` attribute, and
+/// its styles as a single `style` attribute.
+pub fn write_html<Msg, W: Write>(node: &Node<Msg>, out: &mut W) -> fmt::Result {
+    out.write_char('<')?;
+    out.write_str(&node.tag)?;
+
+    for (key, value) in html_attributes(&node.attrs) {
+        out.write_char(' ')?;
+        out.write_str(key)?;
+        out.write_str("=\"")?;
+        write_escaped(out, &value, true)?;
+        out.write_char('"')?;
+    }
+    out.write_char('>')?;
+
+    if VOID.contains(&node.tag.as_str()) {
+        return Ok(());
+    }
+    let raw = RAW_TEXT.contains(&node.tag.as_str());
+    for child in &node.children {
+        match child {
+            NodeType::Node(child) | NodeType::KeyedNode(_, child) => {
+                write_html(child, out)?
+            }
+            NodeType::Text(text) if raw => write_raw(out, text)?,
+            NodeType::Text(text) => write_escaped(out, text, false)?,
+        }
+    }
+    out.write_str("</")?;
+    out.write_str(&node.tag)?;
+    out.write_char('>')
+}
+
+/// `node` as a string of HTML.
+pub fn to_html<Msg>(node: &Node<Msg>) -> String {
+    let mut html = String::new();
+    // Writing to a `String` can't fail.
+    write_html(node, &mut html).unwrap();
+    html
+}
+
+/// Lets `write_html` write to a file or socket. Whatever went wrong with
+/// the last write is kept in `error`, as `fmt::Error` can't carry it.
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+    pub error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[test]
+fn test_write_html() {
+    use crate::element::{el, layout, link, padding};
+    use crate::model::Element;
+    use crate::vdom::{node, Attribute};
+
+    let view = el::<()>(
+        vec![padding(4)],
+        link(
+            vec![],
+            "/search?q=a&b".to_string(),
+            Element::Text("<Search>".to_string()),
+        ),
+    );
+    let html = to_html(&layout(vec![], view));
+    assert!(html.starts_with("<div><div class=\""));
+    assert!(html.contains(" href=\"/search?q=a&amp;b\""));
+    assert!(html.contains("&lt;Search&gt;"));
+    // Stylesheets are written as they are.
+    assert!(html.contains(".p-4 {"));
+
+    // But can't close their element.
+    let style = node::<()>(
+        "style".into(),
+        vec![],
+        vec![NodeType::Text(
+            "a::after { content: '</style><b>'; }".into(),
+        )],
+    );
+    assert_eq!(
+        to_html(&style),
+        "<style>a::after { content: '<\\/style><b>'; }</style>"
+    );
+
+    let mut out = IoWriter::new(vec![]);
+    write_html(&layout(vec![], Element::<()>::Empty), &mut out).unwrap();
+    assert!(String::from_utf8(out.into_inner())
+        .unwrap()
+        .ends_with("</div>"));
+
+    // Classes and styles are each gathered into one attribute.
+    let input = node::<()>(
+        "input".into(),
+        smallvec::smallvec![
+            Attribute::Class("a".to_string()),
+            Attribute::Style("margin".into(), "4px".to_string()),
+            Attribute::property("value", "x=1"),
+            Attribute::Class("b c".to_string()),
+            Attribute::Style("width".into(), "calc(100% + 8px)".to_string()),
+            Attribute::BoolAttr("disabled".into()),
+        ],
+        vec![],
+    );
+    assert_eq!(
+        to_html(&input),
+        "<input class=\"a b c\" style=\"margin: 4px; width: calc(100% + 8px)\" \
+         value=\"x=1\" disabled=\"\">"
+    );
+}
@@ -0,0 +1,328 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    element::link,
+    events::{on, with_default_action},
+    model::{Attribute, Element},
+    sub::Sub,
+    vdom::DefaultAction,
+};
+
+/// A page of the UI, e.g. `Home` or `User(42)`, that can be written to and
+/// read back from a path like `/users/42`.
+///
+/// `parse` is most easily written with the `Parser` combinators below.
+pub trait Route: Sized {
+    fn parse(path: &str) -> Option<Self>;
+    fn to_path(&self) -> String;
+}
+
+// The non-empty segments of a path, without any query or fragment.
+fn segments(path: &str) -> Vec<&str> {
+    path.split(['?', '#'])
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Parses a `T` from the start of the segments, with how many it used.
+type ParseFn<T> = dyn Fn(&[&str]) -> Option<(T, usize)> + Send + Sync;
+
+/// Parses the segments of a path into a `T`, for building `Route::parse`
+/// out of small pieces, e.g. `s("users").slash(int())` for `/users/42`.
+pub struct Parser<T>(Arc<ParseFn<T>>);
+
+impl<T> Clone for Parser<T> {
+    fn clone(&self) -> Self {
+        Parser(self.0.clone())
+    }
+}
+
+impl<T: 'static> Parser<T> {
+    /// Parse a whole path, failing if any segments are left over.
+    pub fn parse(&self, path: &str) -> Option<T> {
+        let segs = segments(path);
+        match (self.0)(&segs) {
+            Some((value, used)) if used == segs.len() => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn map<U, F>(self, f: F) -> Parser<U>
+    where
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        Parser(Arc::new(move |segs| {
+            (self.0)(segs).map(|(value, used)| (f(value), used))
+        }))
+    }
+
+    /// Parse `next` after this, e.g. `s("users").slash(int())`.
+    pub fn slash<U: 'static>(self, next: Parser<U>) -> Parser<(T, U)> {
+        Parser(Arc::new(move |segs| {
+            let (a, used_a) = (self.0)(segs)?;
+            let (b, used_b) = (next.0)(&segs[used_a..])?;
+            Some(((a, b), used_a + used_b))
+        }))
+    }
+}
+
+/// Match the root, `/`.
+pub fn top() -> Parser<()> {
+    Parser(Arc::new(|_| Some(((), 0))))
+}
+
+/// Match exactly the segment `segment`.
+pub fn s(segment: &str) -> Parser<()> {
+    let segment = segment.to_string();
+    Parser(Arc::new(move |segs| match segs.first() {
+        Some(s) if *s == segment => Some(((), 1)),
+        _ => None,
+    }))
+}
+
+pub fn int() -> Parser<i64> {
+    Parser(Arc::new(|segs| segs.first()?.parse().ok().map(|i| (i, 1))))
+}
+
+pub fn string() -> Parser<String> {
+    Parser(Arc::new(|segs| segs.first().map(|s| (s.to_string(), 1))))
+}
+
+/// Try each parser, taking the one that matches the most segments, or the
+/// first listed if several match as many.
+pub fn one_of<T: 'static>(parsers: Vec<Parser<T>>) -> Parser<T> {
+    Parser(Arc::new(move |segs| {
+        parsers
+            .iter()
+            .filter_map(|p| (p.0)(segs))
+            .fold(None, |best, m| match best {
+                Some((_, used)) if used >= m.1 => best,
+                _ => Some(m),
+            })
+    }))
+}
+
+/// Where the paths visited are kept.
+///
+/// Natively this is a `StateStack`; on the web it's a `BrowserHistory`,
+/// so the back button and bookmarks keep working.
+pub trait History: Send {
+    fn current(&self) -> String;
+    fn push(&mut self, path: String);
+    fn replace(&mut self, path: String);
+    fn back(&mut self);
+    fn forward(&mut self);
+}
+
+/// A history kept in memory, for native apps.
+pub struct StateStack {
+    entries: Vec<String>,
+    index: usize,
+}
+
+impl StateStack {
+    pub fn new(path: String) -> Self {
+        Self {
+            entries: vec![path],
+            index: 0,
+        }
+    }
+}
+
+impl History for StateStack {
+    fn current(&self) -> String {
+        self.entries[self.index].clone()
+    }
+
+    // Pushing drops anything that could have been gone forward to.
+    fn push(&mut self, path: String) {
+        self.entries.truncate(self.index + 1);
+        self.entries.push(path);
+        self.index += 1;
+    }
+
+    fn replace(&mut self, path: String) {
+        self.entries[self.index] = path;
+    }
+
+    fn back(&mut self) {
+        self.index = self.index.saturating_sub(1);
+    }
+
+    fn forward(&mut self) {
+        self.index = (self.index + 1).min(self.entries.len() - 1);
+    }
+}
+
+/// The browser's own history, navigated with `history.pushState` and
+/// `history.replaceState`.
+///
+/// The path is read back from `location` each time, so going back or
+/// forward with the browser's buttons (a `popstate`) is seen by the
+/// program at its next frame, like any other change of path.
+#[cfg(feature = "web")]
+pub struct BrowserHistory;
+
+#[cfg(feature = "web")]
+impl BrowserHistory {
+    fn history() -> web_sys::History {
+        web_sys::window().unwrap().history().unwrap()
+    }
+}
+
+#[cfg(feature = "web")]
+impl History for BrowserHistory {
+    fn current(&self) -> String {
+        let location = web_sys::window().unwrap().location();
+        let part = |p: Result<String, _>| p.unwrap_or_default();
+        part(location.pathname())
+            + &part(location.search())
+            + &part(location.hash())
+    }
+
+    fn push(&mut self, path: String) {
+        // Only fails for a path on another origin, which routes never are.
+        let _ = Self::history().push_state_with_url(
+            &wasm_bindgen::JsValue::NULL,
+            "",
+            Some(&path),
+        );
+    }
+
+    fn replace(&mut self, path: String) {
+        let _ = Self::history().replace_state_with_url(
+            &wasm_bindgen::JsValue::NULL,
+            "",
+            Some(&path),
+        );
+    }
+
+    fn back(&mut self) {
+        let _ = Self::history().back();
+    }
+
+    fn forward(&mut self) {
+        let _ = Self::history().forward();
+    }
+}
+
+/// A shared handle on a `History`, kept in the model so `update` can
+/// navigate, and given to `on_route_change` to hear about it.
+#[derive(Clone)]
+pub struct Navigator {
+    history: Arc<Mutex<Box<dyn History>>>,
+}
+
+impl Navigator {
+    pub fn new<H: History + 'static>(history: H) -> Self {
+        Self {
+            history: Arc::new(Mutex::new(Box::new(history))),
+        }
+    }
+
+    /// A navigator with an in-memory history starting at `route`.
+    pub fn native<R: Route>(route: &R) -> Self {
+        Self::new(StateStack::new(route.to_path()))
+    }
+
+    /// A navigator on the browser's history, starting wherever the page
+    /// was loaded.
+    #[cfg(feature = "web")]
+    pub fn browser() -> Self {
+        Self::new(BrowserHistory)
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.history) as *const () as usize
+    }
+
+    pub fn path(&self) -> String {
+        self.history.lock().unwrap().current()
+    }
+
+    /// The current route, if the current path is one.
+    pub fn route<R: Route>(&self) -> Option<R> {
+        R::parse(&self.path())
+    }
+
+    pub fn push<R: Route>(&self, route: &R) {
+        self.history.lock().unwrap().push(route.to_path());
+    }
+
+    pub fn replace<R: Route>(&self, route: &R) {
+        self.history.lock().unwrap().replace(route.to_path());
+    }
+
+    pub fn back(&self) {
+        self.history.lock().unwrap().back();
+    }
+
+    pub fn forward(&self) {
+        self.history.lock().unwrap().forward();
+    }
+}
+
+/// Get the new route whenever the path of `navigator` changes, whether by
+/// `push`, `replace`, `back` or `forward`. The route is `None` if the path
+/// doesn't parse.
+pub fn on_route_change<R, Msg, F>(navigator: &Navigator, f: F) -> Sub<Msg>
+where
+    R: Route,
+    F: Fn(Option<R>) -> Msg + Send + Sync + 'static,
+{
+    Sub::RouteChange(
+        navigator.clone(),
+        Arc::new(move |path: &str| f(R::parse(path))),
+    )
+}
+
+/// A link to `route`. Clicking it sends `on_navigate(route)`, which
+/// `update` will usually hand to `Navigator::push`, instead of the browser
+/// loading the page. Clicks with a modifier key held, as for opening it in
+/// a new tab, are still left to the browser.
+pub fn link_to<R, Msg, F>(
+    attrs: Vec<Attribute<Msg>>,
+    route: R,
+    label: Element<Msg>,
+    on_navigate: F,
+) -> Element<Msg>
+where
+    R: Route + Clone + Send + Sync + 'static,
+    F: Fn(R) -> Msg + Send + Sync + 'static,
+{
+    let url = route.to_path();
+    let mut attrs = attrs;
+    attrs.push(with_default_action(
+        DefaultAction::PreventPlainClick,
+        on("click".to_string(), move |_| on_navigate(route.clone())),
+    ));
+    link(attrs, url, label)
+}
+
+#[test]
+fn test_parse_routes() {
+    #[derive(Debug, PartialEq)]
+    enum Page {
+        Home,
+        User(i64),
+        Post(String),
+    }
+
+    let parser = one_of(vec![
+        top().map(|_| Page::Home),
+        s("users").slash(int()).map(|(_, id)| Page::User(id)),
+        s("posts").slash(string()).map(|(_, slug)| Page::Post(slug)),
+    ]);
+
+    assert_eq!(parser.parse("/"), Some(Page::Home));
+    assert_eq!(parser.parse("/users/42?tab=posts"), Some(Page::User(42)));
+    assert_eq!(
+        parser.parse("/posts/hello/"),
+        Some(Page::Post("hello".to_string()))
+    );
+    assert_eq!(parser.parse("/users/me"), None);
+    assert_eq!(parser.parse("/users/42/edit"), None);
+}
@@ -0,0 +1,123 @@
+use crate::model::Element;
+
+/// The named child regions of a reusable component, like the header and
+/// actions of a card, filled in by whoever uses it. The component decides
+/// where each goes, and what's shown in those left empty.
+pub struct Slots<Msg> {
+    filled: Vec<(&'static str, Element<Msg>)>,
+}
+
+impl<Msg> Default for Slots<Msg> {
+    fn default() -> Self {
+        Slots { filled: vec![] }
+    }
+}
+
+impl<Msg> Slots<Msg> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fill `name`, replacing whatever was in it.
+    pub fn insert(&mut self, name: &'static str, element: Element<Msg>) {
+        self.filled.retain(|(n, _)| *n != name);
+        self.filled.push((name, element));
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.filled.iter().any(|(n, _)| *n == name)
+    }
+
+    /// Take the element in `name` out, to put where it goes.
+    pub fn take(&mut self, name: &str) -> Option<Element<Msg>> {
+        let i = self.filled.iter().position(|(n, _)| *n == name)?;
+        Some(self.filled.remove(i).1)
+    }
+
+    /// Take the element in `name` out, or `default` if it wasn't filled.
+    pub fn take_or(
+        &mut self,
+        name: &str,
+        default: impl FnOnce() -> Element<Msg>,
+    ) -> Element<Msg> {
+        self.take(name).unwrap_or_else(default)
+    }
+
+    /// The slots that were filled but not taken, after a component has
+    /// taken those it knows, e.g. to warn about a misspelled name.
+    pub fn remaining(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.filled.iter().map(|(n, _)| *n)
+    }
+}
+
+/// A component with slots, which get filled with `slot`, as in
+/// `Card::new().slot("header", title).slot("actions", buttons)`.
+///
+/// Implementing it only takes giving access to the component's `Slots`.
+pub trait Slotted<Msg>: Sized {
+    fn slots_mut(&mut self) -> &mut Slots<Msg>;
+
+    /// Fill the slot `name` with `element`.
+    fn slot(mut self, name: &'static str, element: Element<Msg>) -> Self {
+        self.slots_mut().insert(name, element);
+        self
+    }
+}
+
+impl<Msg> Slotted<Msg> for Slots<Msg> {
+    fn slots_mut(&mut self) -> &mut Slots<Msg> {
+        self
+    }
+}
+
+#[test]
+fn test_slots() {
+    use crate::{
+        element::{column, el, layout, row},
+        render::to_html,
+    };
+
+    fn text(content: &str) -> Element<()> {
+        Element::Text(content.to_string())
+    }
+
+    struct Card {
+        slots: Slots<()>,
+    }
+
+    impl Card {
+        fn new() -> Self {
+            Card {
+                slots: Slots::new(),
+            }
+        }
+
+        fn view(mut self) -> Element<()> {
+            let header = self.slots.take_or("header", || text("Untitled"));
+            let body = self.slots.take_or("body", || Element::Empty);
+            let actions = self.slots.take_or("actions", || text("Close"));
+            assert_eq!(self.slots.remaining().count(), 0);
+            column(vec![], vec![header, body, actions])
+        }
+    }
+
+    impl Slotted<()> for Card {
+        fn slots_mut(&mut self) -> &mut Slots<()> {
+            &mut self.slots
+        }
+    }
+
+    let card = Card::new()
+        .slot("header", text("Old"))
+        .slot("header", el(vec![], text("Settings")))
+        .slot("body", text("Volume"));
+    assert!(card.slots.has("header") && !card.slots.has("actions"));
+    let html = to_html(&layout(vec![], card.view()));
+    let at = |s: &str| html.find(s).unwrap();
+    assert!(!html.contains("Old"));
+    assert!(at("Settings") < at("Volume") && at("Volume") < at("Close"));
+
+    let mut slots = Slots::<()>::new().slot("actions", row(vec![], vec![]));
+    assert!(slots.take("header").is_none());
+    assert_eq!(slots.remaining().collect::<Vec<_>>(), ["actions"]);
+}
@@ -0,0 +1,811 @@
+//! Working out where the nodes of a rendered tree go without a browser,
+//! from the classes and stylesheets a browser would lay them out by.
+//!
+//! The classes mean what they do when the tree is shown with Bevy: every
+//! node is a flex column, or a flex row with the row class, and filling,
+//! alignment and spacing are read from the same classes. Text is taken to
+//! wrap after as many characters as fit, each `char_width` wide.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::text::grapheme_count;
+use crate::vdom::{self, Node, NodeType, Rect, Rects};
+
+/// The properties of the rules in a tree's stylesheets that select a
+/// single class, by class. Rules for combinations of classes, like the
+/// static stylesheet's layout rules, are worked out from the classes
+/// themselves instead.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct StyleSheet {
+    hash: u64,
+    rules: HashMap<String, Vec<(String, String)>>,
+    // Where the last rule for each class is among all the rules, as of
+    // those that set the same property, the last one wins.
+    order: HashMap<String, usize>,
+}
+
+impl StyleSheet {
+    pub(crate) fn update<Msg>(&mut self, root: &Node<Msg>) {
+        let mut css = String::new();
+        collect_css(root, &mut css);
+        let mut hasher = DefaultHasher::new();
+        css.hash(&mut hasher);
+        let hash = hasher.finish();
+        if hash == self.hash {
+            return;
+        }
+        self.hash = hash;
+        self.rules.clear();
+        self.order.clear();
+        for (i, rule) in css.split('}').enumerate() {
+            let (selector, body) = match rule.split_once('{') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let class = match selector.trim().strip_prefix('.') {
+                Some(class) if !class.contains(|c| " .:>+~[".contains(c)) => {
+                    class
+                }
+                _ => continue,
+            };
+            let properties = body.split(';').filter_map(|property| {
+                let (name, value) = property.split_once(':')?;
+                Some((name.trim().to_string(), value.trim().to_string()))
+            });
+            self.rules
+                .entry(class.to_string())
+                .or_default()
+                .extend(properties);
+            self.order.insert(class.to_string(), i);
+        }
+    }
+
+    // The properties of the rule for `class`, in the order they were given.
+    pub(crate) fn rules(&self, class: &str) -> &[(String, String)] {
+        self.rules.get(class).map_or(&[], Vec::as_slice)
+    }
+
+    // The value of `name` for a node with `classes`, from the last rule
+    // setting it of those for its classes.
+    pub(crate) fn get<'a>(
+        &'a self,
+        classes: &[&str],
+        name: &str,
+    ) -> Option<&'a str> {
+        classes
+            .iter()
+            .filter_map(|class| {
+                let value = self
+                    .rules
+                    .get(*class)?
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| n == name)?;
+                Some((self.order[*class], value.1.as_str()))
+            })
+            .max_by_key(|(order, _)| *order)
+            .map(|(_, value)| value)
+    }
+}
+
+fn collect_css<Msg>(node: &Node<Msg>, css: &mut String) {
+    for child in &node.children {
+        match child {
+            NodeType::Text(text) if node.tag == "style" => css.push_str(text),
+            NodeType::Node(n) | NodeType::KeyedNode(_, n) => {
+                collect_css(n, css)
+            }
+            NodeType::Text(_) => {}
+        }
+    }
+}
+
+// The number in a class like `spacing-10-5` or `width-fill-2`.
+pub(crate) fn class_numbers<'a>(
+    classes: &'a [&str],
+    prefix: &'a str,
+) -> Option<Vec<f32>> {
+    classes.iter().find_map(|class| {
+        class
+            .strip_prefix(prefix)?
+            .split('-')
+            .map(|n| n.parse().ok())
+            .collect()
+    })
+}
+
+// A length from a stylesheet, in pixels or as a share of the parent's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Length {
+    Px(f32),
+    Percent(f32),
+}
+
+impl Length {
+    fn parse(value: &str) -> Option<Length> {
+        let value = value.trim_end_matches("!important").trim();
+        if let Some(px) = value.strip_suffix("px") {
+            px.trim().parse().ok().map(Length::Px)
+        } else if let Some(percent) = value.strip_suffix('%') {
+            percent.trim().parse().ok().map(Length::Percent)
+        } else if value == "0" {
+            Some(Length::Px(0.0))
+        } else {
+            None
+        }
+    }
+
+    // In pixels, if it's a share of the parent's size and that's known.
+    fn of(self, parent: Option<f32>) -> Option<f32> {
+        match self {
+            Length::Px(px) => Some(px),
+            Length::Percent(percent) => parent.map(|p| p * percent / 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Size {
+    /// As big as its content along its parent's direction, and stretched
+    /// across it.
+    Auto,
+    Exact(Length),
+    /// A portion of the free space along its parent's direction, and
+    /// stretched across it.
+    Fill(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Start,
+    Center,
+    End,
+}
+
+// Where a node out of its parent's flow is put, next to or over it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Nearby {
+    Above,
+    Below,
+    OnRight,
+    OnLeft,
+    Over,
+}
+
+// How a node is laid out, from its classes and those of its parent. Pairs
+// are along the x axis, then the y axis.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    row: bool,
+    size: [Size; 2],
+    min: [Option<Length>; 2],
+    max: [Option<Length>; 2],
+    /// The padding and border widths, top, right, bottom then left.
+    edges: [f32; 4],
+    gap: [f32; 2],
+    /// Where it's aligned across its parent's direction, if it isn't
+    /// stretched.
+    align: Option<Align>,
+    /// Whether the free space along its parent's direction goes before
+    /// and after it.
+    margins: (bool, bool),
+    nearby: Option<Nearby>,
+}
+
+impl Layout {
+    fn clamp(&self, axis: usize, size: f32, parent: Option<f32>) -> f32 {
+        let max = self.max[axis].and_then(|l| l.of(parent));
+        let min = self.min[axis].and_then(|l| l.of(parent));
+        let size = max.map_or(size, |max| size.min(max));
+        min.map_or(size, |min| size.max(min))
+    }
+
+    fn inner(&self, axis: usize, size: f32) -> f32 {
+        let edges = if axis == 0 {
+            self.edges[1] + self.edges[3]
+        } else {
+            self.edges[0] + self.edges[2]
+        };
+        (size - edges).max(0.0)
+    }
+}
+
+/// Lays out rendered trees, measuring text as if each character were
+/// `char_width` pixels wide, with lines `line_height` pixels apart. The
+/// defaults are about right for the default 20 pixel font.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Solver {
+    pub char_width: f32,
+    pub line_height: f32,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver {
+            char_width: 10.0,
+            line_height: 20.0,
+        }
+    }
+}
+
+impl Solver {
+    /// Where each node of `root` goes in a `width` by `height` window, by
+    /// its path from `root`, as `golden::browser_layout` gives them.
+    /// Stylesheets aren't laid out, and text has no rectangle of its own.
+    pub fn lay_out<Msg>(
+        &self,
+        root: &Node<Msg>,
+        width: f32,
+        height: f32,
+    ) -> Rects {
+        let mut sheet = StyleSheet::default();
+        sheet.update(root);
+        let mut pass = Pass {
+            solver: self,
+            sheet,
+            heights: HashMap::new(),
+        };
+        let layout = pass.layout(root, &[]);
+        let w = match layout.size[0] {
+            Size::Exact(length) => length.of(Some(width)).unwrap_or(width),
+            _ => width,
+        };
+        let w = layout.clamp(0, w, Some(width));
+        let h = match layout.size[1] {
+            Size::Exact(length) => length.of(Some(height)),
+            Size::Fill(_) => Some(height),
+            Size::Auto => None,
+        };
+        let h = h.unwrap_or_else(|| pass.height(root, &layout, w));
+        let h = layout.clamp(1, h, Some(height));
+        let mut out = Rects::new();
+        pass.place(root, &layout, &mut vec![], [0.0, 0.0], [w, h], &mut out);
+        out
+    }
+
+    // How many pixels high `text` is, wrapped at `width`, and how wide its
+    // longest line is.
+    fn text_size(&self, text: &str, width: f32) -> (f32, f32) {
+        let per_line = (width / self.char_width).floor().max(1.0);
+        let (mut lines, mut widest) = (0.0_f32, 0.0_f32);
+        for line in text.split('\n') {
+            let chars = grapheme_count(line) as f32;
+            lines += (chars / per_line).ceil().max(1.0);
+            widest = widest.max(chars.min(per_line) * self.char_width);
+        }
+        (widest, lines * self.line_height)
+    }
+}
+
+// A child in its parent's flow: its index among all the parent's children,
+// the child and its layout.
+type Child<'a, Msg> = (usize, &'a Node<Msg>, Layout);
+
+// Where children go, relative to their parent's content box, and how big
+// they are.
+type Boxes = Vec<([f32; 2], [f32; 2])>;
+
+// The state of a single `Solver::lay_out`.
+struct Pass<'a> {
+    solver: &'a Solver,
+    sheet: StyleSheet,
+    // The content heights of nodes already measured, by their address and
+    // width, as measuring a node measures its children again.
+    heights: HashMap<(usize, u32), f32>,
+}
+
+// The nodes of `node` that are laid out, with their index among all its
+// children.
+fn elements<Msg>(
+    node: &Node<Msg>,
+) -> impl Iterator<Item = (usize, &Node<Msg>)> {
+    node.children
+        .iter()
+        .enumerate()
+        .filter_map(|(i, child)| match child {
+            NodeType::Node(n) | NodeType::KeyedNode(_, n)
+                if n.tag != "style" =>
+            {
+                Some((i, n))
+            }
+            _ => None,
+        })
+}
+
+// The text directly in `node`, which is wrapped together.
+fn text_of<Msg>(node: &Node<Msg>) -> String {
+    node.children
+        .iter()
+        .filter_map(|child| match child {
+            NodeType::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Pass<'_> {
+    fn property<'n, Msg>(
+        &'n self,
+        node: &'n Node<Msg>,
+        classes: &[&str],
+        name: &str,
+    ) -> Option<&'n str> {
+        // Inline styles take precedence over any class.
+        node.attrs
+            .iter()
+            .rev()
+            .find_map(|attr| match attr {
+                vdom::Attribute::Style(n, value) if n.as_str() == name => {
+                    Some(value.as_str())
+                }
+                _ => None,
+            })
+            .or_else(|| self.sheet.get(classes, name))
+    }
+
+    fn layout<Msg>(&self, node: &Node<Msg>, parent: &[&str]) -> Layout {
+        let classes = Self::classes(node);
+        let has = |class: &str| classes.contains(&class);
+        let in_row = parent.contains(&"r");
+        let length =
+            |name| self.property(node, &classes, name).and_then(Length::parse);
+        let size = |fill: [&str; 2], prefix, name| {
+            if has(fill[0]) || has(fill[1]) {
+                let portion = class_numbers(&classes, prefix)
+                    .and_then(|n| n.first().copied())
+                    .unwrap_or(1.0);
+                Size::Fill(portion)
+            } else if has("ui") {
+                Size::Exact(Length::Percent(100.0))
+            } else {
+                length(name).map_or(Size::Auto, Size::Exact)
+            }
+        };
+        let edges = |name| {
+            let lengths: Option<Vec<f32>> = self
+                .property(node, &classes, name)?
+                .split_whitespace()
+                .map(|v| match Length::parse(v)? {
+                    Length::Px(px) => Some(px),
+                    Length::Percent(_) => None,
+                })
+                .collect();
+            Some(match lengths?[..] {
+                [all] => [all; 4],
+                [y, x] => [y, x, y, x],
+                [top, x, bottom] => [top, x, bottom, x],
+                [top, right, bottom, left] => [top, right, bottom, left],
+                _ => return None,
+            })
+        };
+        let padding = edges("padding").unwrap_or_default();
+        let border = edges("border-width").unwrap_or_default();
+        let gap = match class_numbers(&classes, "spacing-").as_deref() {
+            Some([x, y]) => [*x, *y],
+            _ => [0.0; 2],
+        };
+
+        let (along, across) = if in_row {
+            (["al", "cx", "ar"], ["at", "cy", "ab"])
+        } else {
+            (["at", "cy", "ab"], ["al", "cx", "ar"])
+        };
+        let margins = if has(along[1]) {
+            (true, true)
+        } else {
+            let end = if in_row { "acr" } else { "acb" };
+            (has(along[2]) || has(end), false)
+        };
+        let own = [
+            ("self-start", Align::Start),
+            ("self-center", Align::Center),
+            ("self-end", Align::End),
+        ];
+        let align = if has("self-stretch") {
+            None
+        } else if let Some((_, align)) = own.iter().find(|(c, _)| has(c)) {
+            Some(*align)
+        } else {
+            across
+                .iter()
+                .zip([Align::Start, Align::Center, Align::End])
+                .find(|(c, _)| has(c))
+                .map(|(_, align)| align)
+        };
+        let nearby = [
+            ("a", Nearby::Above),
+            ("b", Nearby::Below),
+            ("or", Nearby::OnRight),
+            ("ol", Nearby::OnLeft),
+            ("fr", Nearby::Over),
+            ("bh", Nearby::Over),
+        ];
+        Layout {
+            row: has("r"),
+            size: [
+                size(["wf", "wfp"], "width-fill-", "width"),
+                size(["hf", "hfp"], "height-fill-", "height"),
+            ],
+            min: [length("min-width"), length("min-height")],
+            max: [length("max-width"), length("max-height")],
+            edges: [0, 1, 2, 3].map(|i| padding[i] + border[i]),
+            gap,
+            align,
+            margins,
+            nearby: nearby.iter().find(|(c, _)| has(c)).map(|(_, n)| *n),
+        }
+    }
+
+    // The classes of `node`, which its children are laid out in.
+    fn classes<Msg>(node: &Node<Msg>) -> Vec<&str> {
+        node.attrs
+            .iter()
+            .flat_map(vdom::Attribute::classes)
+            .collect()
+    }
+
+    // How wide `node` is with nothing stretching it, at most `max`.
+    fn width<Msg>(&self, node: &Node<Msg>, layout: &Layout, max: f32) -> f32 {
+        if let Size::Exact(length) = layout.size[0] {
+            if let Some(width) = length.of(Some(max)) {
+                return layout.clamp(0, width, Some(max));
+            }
+        }
+        let inner = layout.inner(0, max);
+        let classes = Self::classes(node);
+        let (text, _) = self.solver.text_size(&text_of(node), inner);
+        let widths = elements(node).filter_map(|(_, child)| {
+            let child_layout = self.layout(child, &classes);
+            match child_layout.nearby {
+                Some(_) => None,
+                None => Some(self.width(child, &child_layout, inner)),
+            }
+        });
+        let content = if layout.row {
+            let (sum, count) =
+                widths.fold((0.0, 0), |(sum, n), w| (sum + w, n + 1));
+            sum + layout.gap[0] * (count.max(1) - 1) as f32
+        } else {
+            widths.fold(0.0, f32::max)
+        };
+        let width = (max - inner) + text.max(content);
+        layout.clamp(0, width.min(max), Some(max))
+    }
+
+    // How tall `node` is at `width`, with nothing stretching it.
+    fn height<Msg>(
+        &mut self,
+        node: &Node<Msg>,
+        layout: &Layout,
+        w: f32,
+    ) -> f32 {
+        let key = (node as *const Node<Msg> as usize, w.to_bits());
+        if let Some(height) = self.heights.get(&key) {
+            return *height;
+        }
+        let height = self.content(node, layout, [0.0; 2], w, None, None);
+        self.heights.insert(key, height);
+        height
+    }
+
+    // Lay out `node` at `at`, `size` big, recording where it and its
+    // descendants went in `out`.
+    fn place<Msg>(
+        &mut self,
+        node: &Node<Msg>,
+        layout: &Layout,
+        path: &mut Vec<usize>,
+        at: [f32; 2],
+        size: [f32; 2],
+        out: &mut Rects,
+    ) {
+        out.insert(
+            path.clone(),
+            Rect {
+                x: at[0],
+                y: at[1],
+                width: size[0],
+                height: size[1],
+            },
+        );
+        self.content(
+            node,
+            layout,
+            at,
+            size[0],
+            Some(size[1]),
+            Some((path, out)),
+        );
+    }
+
+    // Lay out the content of `node` in its box at `at`, `width` wide and
+    // `height` tall if that's known, returning how tall it is. Where
+    // children go is recorded in `out` if given, and only measured if not.
+    fn content<Msg>(
+        &mut self,
+        node: &Node<Msg>,
+        layout: &Layout,
+        at: [f32; 2],
+        width: f32,
+        height: Option<f32>,
+        out: Option<(&mut Vec<usize>, &mut Rects)>,
+    ) -> f32 {
+        let [top, _, bottom, left] = layout.edges;
+        let inner_w = layout.inner(0, width);
+        let (_, text) = match text_of(node) {
+            text if text.is_empty() => (0.0, 0.0),
+            text => self.solver.text_size(&text, inner_w),
+        };
+        let inner_h = height.map(|h| (layout.inner(1, h) - text).max(0.0));
+        let origin = [at[0] + left, at[1] + top + text];
+
+        let classes = Self::classes(node);
+        let children: Vec<Child<Msg>> = elements(node)
+            .map(|(i, child)| (i, child, self.layout(child, &classes)))
+            .collect();
+        let (flow, nearby): (Vec<_>, Vec<_>) = children
+            .into_iter()
+            .partition(|(_, _, l)| l.nearby.is_none());
+
+        let (boxes, extent) = if layout.row {
+            self.row(&flow, layout.gap[0], inner_w, inner_h)
+        } else {
+            self.column(&flow, layout.gap[1], inner_w, inner_h)
+        };
+        let own_height = height.unwrap_or(top + text + extent + bottom);
+        if let Some((path, out)) = out {
+            for ((i, child, child_layout), (offset, size)) in
+                flow.iter().zip(boxes)
+            {
+                let at = [origin[0] + offset[0], origin[1] + offset[1]];
+                path.push(*i);
+                self.place(child, child_layout, path, at, size, out);
+                path.pop();
+            }
+            for (i, child, child_layout) in &nearby {
+                let size = match child_layout.nearby {
+                    Some(Nearby::Over) => [width, own_height],
+                    _ => {
+                        let w = self.width(child, child_layout, width);
+                        [w, self.height(child, child_layout, w)]
+                    }
+                };
+                let at = match child_layout.nearby {
+                    Some(Nearby::Above) => [at[0], at[1] - size[1]],
+                    Some(Nearby::Below) => [at[0], at[1] + own_height],
+                    Some(Nearby::OnRight) => [at[0] + width, at[1]],
+                    Some(Nearby::OnLeft) => [at[0] - size[0], at[1]],
+                    _ => at,
+                };
+                path.push(*i);
+                self.place(child, child_layout, path, at, size, out);
+                path.pop();
+            }
+        }
+        own_height
+    }
+
+    // Where the children of a column go, relative to its content box, and
+    // how tall they are together.
+    fn column<Msg>(
+        &mut self,
+        children: &[Child<Msg>],
+        gap: f32,
+        width: f32,
+        height: Option<f32>,
+    ) -> (Boxes, f32) {
+        let widths: Vec<f32> = children
+            .iter()
+            .map(|(_, child, layout)| match (layout.size[0], layout.align) {
+                (Size::Exact(_), _) | (_, Some(_)) => {
+                    self.width(child, layout, width)
+                }
+                _ => layout.clamp(0, width, Some(width)),
+            })
+            .collect();
+        let heights = children
+            .iter()
+            .zip(&widths)
+            .map(|((_, child, layout), w)| match layout.size[1] {
+                // Filling a column of unknown height, it's as tall as its
+                // content.
+                Size::Fill(_) if height.is_some() => None,
+                Size::Exact(length) => Some(
+                    length
+                        .of(height)
+                        .unwrap_or_else(|| self.height(child, layout, *w)),
+                ),
+                _ => Some(self.height(child, layout, *w)),
+            })
+            .collect();
+        let gaps = gap * children.len().saturating_sub(1) as f32;
+        let heights = share(children, 1, heights, gaps, height);
+        let placed = along(children, 1, &heights, gap, height);
+        let boxes = children
+            .iter()
+            .zip(placed)
+            .zip(widths.iter().zip(&heights))
+            .map(|(((_, _, layout), y), (w, h))| {
+                let x = across(layout.align, width, *w);
+                ([x, y], [*w, *h])
+            })
+            .collect();
+        (boxes, heights.iter().sum::<f32>() + gaps)
+    }
+
+    // Where the children of a row go, relative to its content box, and how
+    // tall the row is.
+    fn row<Msg>(
+        &mut self,
+        children: &[Child<Msg>],
+        gap: f32,
+        width: f32,
+        height: Option<f32>,
+    ) -> (Boxes, f32) {
+        let widths = children
+            .iter()
+            .map(|(_, child, layout)| match layout.size[0] {
+                Size::Fill(_) => None,
+                _ => Some(self.width(child, layout, width)),
+            })
+            .collect();
+        let gaps = gap * children.len().saturating_sub(1) as f32;
+        let widths = share(children, 0, widths, gaps, Some(width));
+        // Stretched children are as tall as the tallest child, or as the
+        // row if it's been given a height.
+        let mut heights: Vec<f32> = children
+            .iter()
+            .zip(&widths)
+            .map(|((_, child, layout), w)| {
+                let h = match layout.size[1] {
+                    Size::Exact(length) => length.of(height),
+                    _ => None,
+                };
+                let h = h.unwrap_or_else(|| self.height(child, layout, *w));
+                layout.clamp(1, h, height)
+            })
+            .collect();
+        let tallest = heights.iter().copied().fold(0.0, f32::max);
+        let row_height = height.unwrap_or(tallest);
+        for ((_, _, layout), h) in children.iter().zip(&mut heights) {
+            if !matches!(layout.size[1], Size::Exact(_))
+                && layout.align.is_none()
+            {
+                *h = layout.clamp(1, row_height, height);
+            }
+        }
+        let placed = along(children, 0, &widths, gap, Some(width));
+        let boxes = children
+            .iter()
+            .zip(placed)
+            .zip(widths.iter().zip(&heights))
+            .map(|(((_, _, layout), x), (w, h))| {
+                let y = across(layout.align, row_height, *h);
+                ([x, y], [*w, *h])
+            })
+            .collect();
+        (boxes, row_height)
+    }
+}
+
+// The sizes along `axis` of `children`, with those filling the free space
+// sharing it out by their portions.
+fn share<Msg>(
+    children: &[Child<Msg>],
+    axis: usize,
+    sizes: Vec<Option<f32>>,
+    gaps: f32,
+    total: Option<f32>,
+) -> Vec<f32> {
+    let taken: f32 = sizes.iter().flatten().sum::<f32>() + gaps;
+    let portions: f32 = children
+        .iter()
+        .map(|(_, _, layout)| match layout.size[axis] {
+            Size::Fill(portion) => portion,
+            _ => 0.0,
+        })
+        .sum();
+    let free = total.map_or(0.0, |total| (total - taken).max(0.0));
+    children
+        .iter()
+        .zip(sizes)
+        .map(|((_, _, layout), size)| {
+            let size = size.unwrap_or_else(|| match layout.size[axis] {
+                Size::Fill(portion) => free * portion / portions,
+                _ => 0.0,
+            });
+            layout.clamp(axis, size, total)
+        })
+        .collect()
+}
+
+// Where `children`, `sizes` big along `axis`, start, with the free space
+// going to those aligned to take it.
+fn along<Msg>(
+    children: &[Child<Msg>],
+    axis: usize,
+    sizes: &[f32],
+    gap: f32,
+    total: Option<f32>,
+) -> Vec<f32> {
+    let gaps = gap * children.len().saturating_sub(1) as f32;
+    let used = sizes.iter().sum::<f32>() + gaps;
+    let free = total.map_or(0.0, |total| (total - used).max(0.0));
+    let margins: usize = children
+        .iter()
+        .map(|(_, _, l)| l.margins.0 as usize + l.margins.1 as usize)
+        .sum();
+    let margin = if margins == 0 {
+        0.0
+    } else {
+        free / margins as f32
+    };
+    let mut at = 0.0;
+    let mut out = vec![];
+    for ((_, _, layout), size) in children.iter().zip(sizes) {
+        if layout.margins.0 {
+            at += margin;
+        }
+        out.push(at);
+        at += size + gap;
+        if layout.margins.1 {
+            at += margin;
+        }
+    }
+    out
+}
+
+// Where something `size` big goes across `space`.
+fn across(align: Option<Align>, space: f32, size: f32) -> f32 {
+    match align {
+        Some(Align::Center) => (space - size) / 2.0,
+        Some(Align::End) => space - size,
+        _ => 0.0,
+    }
+}
+
+#[test]
+fn test_lay_out() {
+    use crate::element::{
+        column, el, height, layout, padding_xy, paragraph, px, row, spacing,
+        width,
+    };
+    use crate::model::Element;
+
+    let view: Element<()> = column(
+        vec![width(px(300)), padding_xy(10, 5), spacing(5)],
+        vec![
+            el(vec![width(px(100)), height(px(50))], Element::Empty),
+            row(
+                vec![spacing(20)],
+                vec![
+                    el(vec![width(px(60)), height(px(30))], Element::Empty),
+                    el(vec![width(px(40)), height(px(20))], Element::Empty),
+                ],
+            ),
+            paragraph(vec![], vec![Element::Text("word ".repeat(10))]),
+        ],
+    );
+    let node = layout(vec![], view);
+    let rects = Solver::default().lay_out(&node, 800.0, 600.0);
+    let rect = |x, y, width, height| Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+    let column = [0, 2, 0];
+    let at = |path: &[usize]| rects[&[&column[..], path].concat()];
+    assert_eq!(at(&[]), rect(0.0, 0.0, 300.0, 140.0));
+    // Inside the padding, with the first child as big as it's made.
+    assert_eq!(at(&[0, 0]), rect(10.0, 5.0, 100.0, 50.0));
+    // Spaced out, and with the row as tall as its tallest child.
+    assert_eq!(at(&[1, 0]), rect(10.0, 60.0, 280.0, 30.0));
+    assert_eq!(at(&[1, 0, 1, 0]), rect(90.0, 60.0, 40.0, 20.0));
+    // 50 characters, at 28 to a line.
+    assert_eq!(at(&[2, 0]), rect(10.0, 95.0, 280.0, 40.0));
+    // The stylesheets take up no room.
+    assert_eq!(rects[&vec![0, 1]].height, 0.0);
+}
@@ -0,0 +1,169 @@
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use crate::{
+    model::Attribute,
+    vdom::{self, Node},
+};
+
+/// Something done to an element that can play a sound.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub enum Interaction {
+    Hover,
+    Click,
+    Focus,
+}
+
+impl Interaction {
+    /// The pointer interaction an event reported by the backend is, if
+    /// any. Focus is handled by `Program::focus`.
+    pub fn from_event(event: &str) -> Option<Self> {
+        match event {
+            "mouseenter" => Some(Interaction::Hover),
+            "click" => Some(Interaction::Click),
+            _ => None,
+        }
+    }
+
+    fn attr(self) -> &'static str {
+        match self {
+            Interaction::Hover => "data-sound-hover",
+            Interaction::Click => "data-sound-click",
+            Interaction::Focus => "data-sound-focus",
+        }
+    }
+}
+
+/// The sounds played when elements that can be clicked are hovered,
+/// clicked or focused, unless they have their own from
+/// `on_interaction_sound`. Sounds are named however the app loads them,
+/// e.g. by asset path.
+#[derive(Debug, Default, PartialOrd, PartialEq, Clone, Copy, Hash)]
+pub struct SoundMap {
+    pub hover: Option<&'static str>,
+    pub click: Option<&'static str>,
+    pub focus: Option<&'static str>,
+}
+
+impl SoundMap {
+    pub fn get(&self, interaction: Interaction) -> Option<&'static str> {
+        match interaction {
+            Interaction::Hover => self.hover,
+            Interaction::Click => self.click,
+            Interaction::Focus => self.focus,
+        }
+    }
+}
+
+/// A sound for the app to play, from `Program::take_sounds`. With the
+/// `bevy` feature, they're sent as events.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Event))]
+pub struct UiSound {
+    pub sound: String,
+    pub interaction: Interaction,
+}
+
+/// Play `sound` when the element is hovered, clicked or focused, instead
+/// of the one from `interaction_sounds`.
+pub fn on_interaction_sound<Msg>(
+    interaction: Interaction,
+    sound: &str,
+) -> Attribute<Msg> {
+    Attribute::Attr(vdom::Attribute::property(interaction.attr(), sound))
+}
+
+// Whether the view uses any interaction sounds of its own.
+pub(crate) fn has_sounds<Msg>(node: &Node<Msg>) -> bool {
+    let interactions =
+        [Interaction::Hover, Interaction::Click, Interaction::Focus];
+    interactions.iter().any(|i| node.attr(i.attr()).is_some())
+        || node.children.iter().any(|child| match child {
+            vdom::NodeType::Node(n) | vdom::NodeType::KeyedNode(_, n) => {
+                has_sounds(n)
+            }
+            vdom::NodeType::Text(_) => false,
+        })
+}
+
+/// The sound for `interaction` with the node at `path`: its own, or the
+/// default from `sounds` if it can be clicked. A click plays the sound of
+/// the nearest element it lands in that has one or can be clicked, as the
+/// click goes to it.
+pub(crate) fn sound_for<Msg>(
+    root: &Node<Msg>,
+    path: &[usize],
+    interaction: Interaction,
+    sounds: Option<&SoundMap>,
+) -> Option<String> {
+    let default = || sounds.and_then(|s| s.get(interaction));
+    let lengths = match interaction {
+        Interaction::Click => 0..=path.len(),
+        _ => path.len()..=path.len(),
+    };
+    for len in lengths.rev() {
+        let node = root.at(&path[..len])?;
+        if let Some(sound) = node.attr(interaction.attr()) {
+            return Some(sound.to_string());
+        }
+        if interaction == Interaction::Focus
+            || node.events.iter().any(|l| l.event == "click")
+        {
+            return default().map(str::to_string);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_interaction_sounds() {
+    use crate::{
+        app::Program,
+        cmd::Cmd,
+        element::{el, interaction_sounds},
+        events::on_click,
+        focus::focusable,
+        model::Element,
+    };
+
+    let mut program = Program::new(
+        || (),
+        |_, _: ()| Cmd::none(),
+        |_| {
+            el(
+                vec![
+                    on_click(()),
+                    focusable(),
+                    on_interaction_sound(Interaction::Click, "confirm.ogg"),
+                ],
+                el(vec![], Element::Text("OK".to_string())),
+            )
+        },
+    )
+    .with_options(vec![interaction_sounds(SoundMap {
+        hover: Some("hover.ogg"),
+        focus: Some("focus.ogg"),
+        ..SoundMap::default()
+    })]);
+    program.render();
+    let sounds = |program: &mut Program<(), ()>| {
+        program
+            .take_sounds()
+            .into_iter()
+            .map(|s| s.sound)
+            .collect::<Vec<_>>()
+    };
+    // The click lands on the label, but plays the button's sound.
+    program.interacted(&[0, 2, 0, 0], Interaction::Click);
+    program.interacted(&[0, 2, 0, 0], Interaction::Hover);
+    program.interacted(&[0, 2, 0], Interaction::Hover);
+    program.focus(&[0, 2, 0]);
+    assert_eq!(
+        sounds(&mut program),
+        vec!["confirm.ogg", "hover.ogg", "focus.ogg"]
+    );
+    assert!(sounds(&mut program).is_empty());
+}
@@ -0,0 +1,31 @@
+//! The parts of the API kept stable between releases: elements and their
+//! attributes, inputs, and the architecture running them. Code using only
+//! these paths keeps compiling while the bigger subsystems change.
+//!
+//! The same modules are at the crate root too, alongside the internals
+//! they're built on, like `model`, `style` and `vdom`, which can change in
+//! any release. The few types of the `experimental` subsystems these take
+//! or return, like `sub::Degradation`, are re-exported where they're used.
+
+pub use crate::model::{Attribute, Color, Element, Length};
+
+pub use crate::{
+    announcer, app, background, border, cmd, element, events, focus, font,
+    format, i18n, input, keyboard, keyed, layouts, lazy, region, render,
+    router, slots, sub, test, text, theme,
+};
+
+#[test]
+fn test_stable_paths() {
+    use crate::stable::{
+        element::{el, layout, padding},
+        font, Color, Element,
+    };
+
+    let view: Element<()> = el(
+        vec![padding(4), font::color(Color::new(0.0, 0.0, 0.0, 1.0))],
+        Element::Text("Stable".to_string()),
+    );
+    let html = crate::stable::render::to_html(&layout(vec![], view));
+    assert!(html.contains("Stable"));
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::cmd::Cmd;
+
+/// Somewhere to keep UI preferences between runs, as strings by key.
+///
+/// Natively this is a `FileStorage`; on the web it's a `LocalStorage`.
+pub trait Storage: Send + Sync {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&self, key: &str, value: &str) -> io::Result<()>;
+}
+
+/// Keeps each key in its own file in `dir`.
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    // A key's a single file name, so it can't escape `dir`: keys like
+    // `panels/left` or `..` are refused rather than joined.
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        if key.is_empty()
+            || key == "."
+            || key == ".."
+            || key.contains(['/', '\\', '\0'])
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("storage key {:?} isn't a file name", key),
+            ));
+        }
+        Ok(self.dir.join(key))
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.path(key).ok()?).ok()
+    }
+
+    fn save(&self, key: &str, value: &str) -> io::Result<()> {
+        let path = self.path(key)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, value)
+    }
+}
+
+/// Keeps each key in the page's `window.localStorage`.
+#[cfg(feature = "web")]
+pub struct LocalStorage;
+
+#[cfg(feature = "web")]
+impl LocalStorage {
+    // Looked up each time, as the browser's handle can't be shared
+    // between threads.
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+}
+
+#[cfg(feature = "web")]
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        Self::storage()?.get_item(key).ok()?
+    }
+
+    // Fails when storage is disabled, e.g. in some private windows, or
+    // over its quota.
+    fn save(&self, key: &str, value: &str) -> io::Result<()> {
+        let storage = Self::storage().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Unsupported, "no localStorage")
+        })?;
+        storage
+            .set_item(key, value)
+            .map_err(|err| io::Error::other(format!("{:?}", err)))
+    }
+}
+
+/// Keeps everything in memory, e.g. for tests.
+#[derive(Default)]
+pub struct MemoryStorage(Mutex<HashMap<String, String>>);
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, value: &str) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+/// A value that can be written to and read back from storage.
+pub trait Persist: Sized {
+    fn to_stored(&self) -> String;
+    fn from_stored(stored: &str) -> Option<Self>;
+}
+
+macro_rules! persist_via_str {
+    ($($t:ty),*) => {
+        $(
+            impl Persist for $t {
+                fn to_stored(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_stored(stored: &str) -> Option<Self> {
+                    stored.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+persist_via_str!(
+    bool, String, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64
+);
+
+/// A piece of UI state, e.g. the theme or a panel's width, that's loaded
+/// from storage when the model is created and saved whenever it changes.
+pub struct Persisted<T> {
+    key: String,
+    value: T,
+    storage: Arc<dyn Storage>,
+}
+
+impl<T: Persist> Persisted<T> {
+    /// Load the value saved under `key`, or `default` if there isn't one
+    /// or it can no longer be read.
+    pub fn load(storage: Arc<dyn Storage>, key: String, default: T) -> Self {
+        let value = storage
+            .load(&key)
+            .and_then(|stored| T::from_stored(&stored))
+            .unwrap_or(default);
+        Self {
+            key,
+            value,
+            storage,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Change the value, returning the command that saves it. A failed save
+    /// is dropped, and the value is saved again on the next change.
+    pub fn set<Msg: 'static>(&mut self, value: T) -> Cmd<Msg> {
+        self.value = value;
+        self.save()
+    }
+
+    /// Change the value in place, returning the command that saves it.
+    pub fn update<Msg: 'static, F: FnOnce(&mut T)>(
+        &mut self,
+        f: F,
+    ) -> Cmd<Msg> {
+        f(&mut self.value);
+        self.save()
+    }
+
+    fn save<Msg: 'static>(&self) -> Cmd<Msg> {
+        let storage = self.storage.clone();
+        let key = self.key.clone();
+        let stored = self.value.to_stored();
+        Cmd::run(async move {
+            let _ = storage.save(&key, &stored);
+        })
+    }
+}
+
+impl<T> Deref for Persisted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+#[test]
+fn test_persisted_round_trip() {
+    use std::future::Future;
+    use std::task::{Context, Waker};
+
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+    let mut width = Persisted::load(storage.clone(), "width".to_string(), 240);
+    assert_eq!(*width, 240);
+
+    let cmd: Cmd<()> = width.set(320);
+    let mut cx = Context::from_waker(Waker::noop());
+    for mut future in cmd.into_futures() {
+        let _ = future.as_mut().poll(&mut cx);
+    }
+
+    let width = Persisted::load(storage, "width".to_string(), 240);
+    assert_eq!(*width, 320);
+}
+
+#[test]
+fn test_file_storage_keys() {
+    let dir = std::env::temp_dir()
+        .join(format!("storage-keys-{}", std::process::id()));
+    let storage = FileStorage::new(dir.clone());
+
+    storage.save("theme", "dark").unwrap();
+    assert_eq!(storage.load("theme").as_deref(), Some("dark"));
+
+    for key in ["", ".", "..", "../theme", "panels/left", "a\\b"] {
+        let err = storage.save(key, "x").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(storage.load(key), None);
+    }
+    assert!(!dir.parent().unwrap().join("theme").exists());
+    let _ = fs::remove_dir_all(dir);
+}
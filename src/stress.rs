@@ -0,0 +1,195 @@
+use crate::element::{
+    above, alpha, below, center_x, column, el, fill, fill_portion, focused,
+    grid_template, in_front, mouse_over, move_up, on_right, padding, paragraph,
+    px, rgb255, row, shrink, spacing, width, wrapped_row,
+};
+use crate::flag::Flag;
+use crate::model::{
+    Attribute, Element, GridTemplate, Length, Repeat, Style, Track,
+};
+
+/// A small xorshift generator, so the same seed always makes the same
+/// tree on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is the one state xorshift never leaves, so the seed that
+        // mixes to it starts from the mixing constant instead.
+        match seed ^ 0x9e37_79b9_7f4a_7c15 {
+            0 => Rng(0x9e37_79b9_7f4a_7c15),
+            state => Rng(state),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number from 0 up to, but not including, `n`.
+    pub fn below(&mut self, n: u32) -> u32 {
+        (self.next_u64() % n.max(1) as u64) as u32
+    }
+
+    /// True one time in `n`.
+    pub fn one_in(&mut self, n: u32) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// The shape of the trees `random_tree` makes.
+#[derive(Debug, Clone)]
+pub struct StressConfig {
+    /// How many layouts deep a tree can nest.
+    pub max_depth: u32,
+    /// The most children a row, column or grid can have.
+    pub max_children: u32,
+    /// One element in this many gets a nearby element, like `below`.
+    pub nearby: u32,
+    /// One element in this many gets `mouse_over` or `focused` styles.
+    pub pseudo: u32,
+    /// Whether grids are made, which need the `grid` feature to lay out.
+    pub grids: bool,
+}
+
+impl Default for StressConfig {
+    fn default() -> Self {
+        StressConfig {
+            max_depth: 6,
+            max_children: 6,
+            nearby: 8,
+            pseudo: 6,
+            grids: cfg!(feature = "grid"),
+        }
+    }
+}
+
+fn background<Msg>(rng: &mut Rng) -> Attribute<Msg> {
+    let color = rgb255(
+        rng.below(256) as u8,
+        rng.below(256) as u8,
+        rng.below(256) as u8,
+    );
+    Attribute::Style(
+        Flag::bg_color(),
+        Style::Colored(
+            format!("bg-{}", color.format_color_class()),
+            "background-color".into(),
+            color,
+        ),
+    )
+}
+
+fn length(rng: &mut Rng) -> Length {
+    match rng.below(4) {
+        0 => shrink(),
+        1 => fill(),
+        2 => fill_portion(1 + rng.below(3) as u64),
+        _ => px(10 + rng.below(20) as u64 * 10),
+    }
+}
+
+fn attrs<Msg>(rng: &mut Rng, config: &StressConfig) -> Vec<Attribute<Msg>> {
+    let mut attrs = vec![width(length(rng))];
+    if rng.one_in(2) {
+        attrs.push(padding(rng.below(5) * 4));
+    }
+    if rng.one_in(2) {
+        attrs.push(spacing(rng.below(5) * 4));
+    }
+    if rng.one_in(3) {
+        attrs.push(background(rng));
+    }
+    if rng.one_in(4) {
+        attrs.push(center_x());
+    }
+    if rng.one_in(config.pseudo) {
+        attrs.push(match rng.below(2) {
+            0 => mouse_over(vec![alpha(0.5), background(rng)]),
+            _ => focused(vec![move_up(rng.below(4) as f32)]),
+        });
+    }
+    attrs
+}
+
+fn leaf<Msg>(rng: &mut Rng) -> Element<Msg> {
+    Element::Text(format!("text {}", rng.below(1000)))
+}
+
+fn tree<Msg>(rng: &mut Rng, config: &StressConfig, depth: u32) -> Element<Msg> {
+    let mut attrs = attrs(rng, config);
+    if depth + 1 < config.max_depth && rng.one_in(config.nearby) {
+        let nearby = el(vec![padding(2)], leaf(rng));
+        attrs.push(match rng.below(4) {
+            0 => above(nearby),
+            1 => below(nearby),
+            2 => on_right(nearby),
+            _ => in_front(nearby),
+        });
+    }
+    if depth >= config.max_depth || rng.one_in(4) {
+        return el(attrs, leaf(rng));
+    }
+    let count = 1 + rng.below(config.max_children);
+    let kind = rng.below(if config.grids { 6 } else { 5 });
+    if kind == 3 {
+        return paragraph(attrs, (0..count).map(|_| leaf(rng)).collect());
+    }
+    if kind == 4 {
+        return el(attrs, tree(rng, config, depth + 1));
+    }
+    if kind == 5 {
+        let columns = 1 + rng.below(4);
+        attrs.push(grid_template(GridTemplate {
+            columns: vec![Track::Repeat(Repeat::Count(columns), fill())],
+            ..GridTemplate::default()
+        }));
+    }
+    let children = (0..count).map(|_| tree(rng, config, depth + 1)).collect();
+    match kind {
+        1 => column(attrs, children),
+        2 => wrapped_row(attrs, children),
+        // Grids are rows whose children are placed on the grid instead.
+        _ => row(attrs, children),
+    }
+}
+
+/// A random tree of rows, columns, grids and text, with nearby elements
+/// and pseudo-class styles sprinkled in, for fuzzing, benchmarks and
+/// comparing layouts. The same `seed` and `config` always make the same
+/// tree.
+pub fn random_tree<Msg>(seed: u64, config: &StressConfig) -> Element<Msg> {
+    tree(&mut Rng::new(seed), config, 0)
+}
+
+#[test]
+fn test_rng_never_stuck() {
+    let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+    assert_ne!(rng.next_u64(), 0);
+    assert_ne!(rng.next_u64(), rng.next_u64());
+}
+
+#[test]
+fn test_random_tree() {
+    use crate::element::layout;
+    use crate::render::to_html;
+
+    let config = StressConfig::default();
+    let html =
+        |seed| to_html(&layout(vec![], random_tree::<()>(seed, &config)));
+    assert_eq!(html(7), html(7));
+    assert_ne!(html(7), html(8));
+
+    let flat = StressConfig {
+        max_depth: 0,
+        ..StressConfig::default()
+    };
+    for seed in 0..20 {
+        let node = layout(vec![], random_tree::<()>(seed, &flat));
+        assert_eq!(to_html(&node).matches("text ").count(), 1);
+    }
+}
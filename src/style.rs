@@ -1,3 +1,9 @@
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
 type Class = (&'static str, Vec<Rule>);
 
 #[derive(Debug, Clone)]
@@ -169,6 +175,7 @@ pub enum Classes {
     TextRight,
     TextLeft,
     Transition,
+    Layer,
 
     // input text
     InputText,
@@ -180,6 +187,13 @@ pub enum Classes {
 
     // link
     Link,
+
+    // accessibility
+    VisuallyHidden,
+    VisuallyHiddenFocusable,
+
+    // frame budget
+    Reduced,
 }
 
 impl Classes {
@@ -300,6 +314,7 @@ impl Classes {
             Self::TextRight => "tr",
             Self::TextLeft => "tl",
             Self::Transition => "ts",
+            Self::Layer => "lyr",
 
             // input text
             Self::InputText => "it",
@@ -311,6 +326,13 @@ impl Classes {
 
             // link
             Self::Link => "lnk",
+
+            // accessibility
+            Self::VisuallyHidden => "vh",
+            Self::VisuallyHiddenFocusable => "vhf",
+
+            // frame budget
+            Self::Reduced => "reduced",
         }
     }
 }
@@ -346,7 +368,7 @@ fn describe_alignment(
 ) -> Rule {
     let create_description = |alignment: &Alignment| {
         let (content, indiv) = values(alignment);
-        let rules = vec![
+        vec![
             Rule::Descriptor(
                 ContentDescriptor(*alignment).to_string(),
                 content,
@@ -358,9 +380,7 @@ fn describe_alignment(
                     indiv,
                 )],
             ),
-        ];
-        println!("{:?}", &rules);
-        rules
+        ]
     };
     Rule::Batch(
         vec![
@@ -379,15 +399,13 @@ fn describe_alignment(
 
 fn grid_alignments(values: Box<dyn Fn(&Alignment) -> Vec<Rule>>) -> Rule {
     let create_description = |alignment: &Alignment| {
-        let rules = vec![Rule::Child(
+        vec![Rule::Child(
             Classes::Any.to_string(),
             vec![Rule::Descriptor(
                 SelfDescriptor(*alignment).to_string(),
                 values(alignment),
             )],
-        )];
-        println!("{:?}", &rules);
-        rules
+        )]
     };
     Rule::Batch(
         vec![
@@ -640,79 +658,16 @@ const OVERRIDES: &'static str = "
     s.r > .s { flex-basis: auto !important; }
     .s.r > .s.ctr { flex-basis: auto !important; }
 }
-input[type=\"search\"],
-input[type=\"search\"]::-webkit-search-decoration,
-input[type=\"search\"]::-webkit-search-cancel-button,
-input[type=\"search\"]::-webkit-search-results-button,
-input[type=\"search\"]::-webkit-search-results-decoration {
-  -webkit-appearance:none;
-}
-input[type=range] {
-  -webkit-appearance: none; 
-  background: transparent;
-  position:absolute;
-  left:0;
-  top:0;
-  z-index:10;
-  width: 100%;
-  outline: dashed 1px;
-  height: 100%;
-  opacity: 0;
-}
-input[type=range]::-moz-range-track {
-    background: transparent;
-    cursor: pointer;
-}
-input[type=range]::-ms-track {
-    background: transparent;
-    cursor: pointer;
-}
-input[type=range]::-webkit-slider-runnable-track {
-    background: transparent;
-    cursor: pointer;
-}
-input[type=range]::-webkit-slider-thumb {
-    -webkit-appearance: none;
-    opacity: 0.5;
-    width: 80px;
-    height: 80px;
-    background-color: black;
-    border:none;
-    border-radius: 5px;
-}
-input[type=range]::-moz-range-thumb {
-    opacity: 0.5;
-    width: 80px;
-    height: 80px;
-    background-color: black;
-    border:none;
-    border-radius: 5px;
-}
-input[type=range]::-ms-thumb {
-    opacity: 0.5;
-    width: 80px;
-    height: 80px;
-    background-color: black;
-    border:none;
-    border-radius: 5px;
-}
-input[type=range][orient=vertical]{
-    writing-mode: bt-lr; /* IE */
-    -webkit-appearance: slider-vertical;  /* WebKit */
-}
-.explain {
-    border: 6px solid rgb(174, 121, 15) !important;
-}
-.explain > .s {
-    border: 4px dashed rgb(0, 151, 167) !important;
-}
-.ctr {
-    border: none !important;
-}
-.explain > .ctr > .s {
-    border: 4px dashed rgb(0, 151, 167) !important;
-}
+";
 
+// Set on the root while the frame budget is exceeded, see `budget`. The
+// root is a bare `div`, and the nodes under it aren't all `.s`, like the
+// text of a paragraph, or are pseudo-elements, like a spinner's.
+const REDUCED_EFFECTS: &str = "
+.reduced, .reduced *, .reduced *::before, .reduced *::after {
+    transition: none !important;
+    animation: none !important;
+}
 ";
 
 const INPUT_TEXT_RESET: &'static str = "
@@ -805,7 +760,7 @@ const EXPLAINER: &'static str = "
 ";
 
 fn common_values() -> Vec<Class> {
-    vec![
+    let mut values = vec![
         (".border-0", vec![Rule::Prop("border-width", "0px")]),
         (".border-1", vec![Rule::Prop("border-width", "1px")]),
         (".border-2", vec![Rule::Prop("border-width", "2px")]),
@@ -863,73 +818,166 @@ fn common_values() -> Vec<Class> {
         (".p-22", vec![Rule::Prop("padding", "22px")]),
         (".p-23", vec![Rule::Prop("padding", "23px")]),
         (".p-24", vec![Rule::Prop("padding", "24px")]),
-        (".v-smcp", vec![Rule::Prop("font-variant", "small-caps")]),
-        (".v-smcp-off", vec![Rule::Prop("font-variant", "normal")]),
+    ];
+    if cfg!(feature = "typography-variants") {
+        values.extend(vec![
+            (".v-smcp", vec![Rule::Prop("font-variant", "small-caps")]),
+            (".v-smcp-off", vec![Rule::Prop("font-variant", "normal")]),
+            (
+                ".v-zero",
+                vec![Rule::Prop("font-feature-settings", "\"zero\"")],
+            ),
+            (
+                ".v-zero-off",
+                vec![Rule::Prop("font-feature-settings", "\"zero\" 0")],
+            ),
+            (
+                ".v-onum",
+                vec![Rule::Prop("font-feature-settings", "\"onum\"")],
+            ),
+            (
+                ".v-onum-off",
+                vec![Rule::Prop("font-feature-settings", "\"onum\" 0")],
+            ),
+            (
+                ".v-liga",
+                vec![Rule::Prop("font-feature-settings", "\"liga\"")],
+            ),
+            (
+                ".v-liga-off",
+                vec![Rule::Prop("font-feature-settings", "\"liga\" 0")],
+            ),
+            (
+                ".v-dlig",
+                vec![Rule::Prop("font-feature-settings", "\"dlig\"")],
+            ),
+            (
+                ".v-dlig-off",
+                vec![Rule::Prop("font-feature-settings", "\"dlig\" 0")],
+            ),
+            (
+                ".v-ordn",
+                vec![Rule::Prop("font-feature-settings", "\"ordn\"")],
+            ),
+            (
+                ".v-ordn-off",
+                vec![Rule::Prop("font-feature-settings", "\"ordn\" 0")],
+            ),
+            (
+                ".v-tnum",
+                vec![Rule::Prop("font-feature-settings", "\"tnum\"")],
+            ),
+            (
+                ".v-tnum-off",
+                vec![Rule::Prop("font-feature-settings", "\"tnum\" 0")],
+            ),
+            (
+                ".v-afrc",
+                vec![Rule::Prop("font-feature-settings", "\"afrc\"")],
+            ),
+            (
+                ".v-afrc-off",
+                vec![Rule::Prop("font-feature-settings", "\"afrc\" 0")],
+            ),
+            (
+                ".v-frac",
+                vec![Rule::Prop("font-feature-settings", "\"frac\"")],
+            ),
+            (
+                ".v-frac-off",
+                vec![Rule::Prop("font-feature-settings", "\"frac\" 0")],
+            ),
+        ]);
+    }
+    values.extend(vec![
         (
-            ".v-zero",
-            vec![Rule::Prop("font-feature-settings", "\"zero\"")],
+            ".snap-x",
+            vec![Rule::Prop("scroll-snap-type", "x mandatory")],
         ),
         (
-            ".v-zero-off",
-            vec![Rule::Prop("font-feature-settings", "\"zero\" 0")],
+            ".snap-y",
+            vec![Rule::Prop("scroll-snap-type", "y mandatory")],
         ),
         (
-            ".v-onum",
-            vec![Rule::Prop("font-feature-settings", "\"onum\"")],
+            ".snap-start",
+            vec![Rule::Child(
+                ".s",
+                vec![Rule::Prop("scroll-snap-align", "start")],
+            )],
         ),
         (
-            ".v-onum-off",
-            vec![Rule::Prop("font-feature-settings", "\"onum\" 0")],
+            ".snap-center",
+            vec![Rule::Child(
+                ".s",
+                vec![Rule::Prop("scroll-snap-align", "center")],
+            )],
         ),
         (
-            ".v-liga",
-            vec![Rule::Prop("font-feature-settings", "\"liga\"")],
+            ".snap-end",
+            vec![Rule::Child(
+                ".s",
+                vec![Rule::Prop("scroll-snap-align", "end")],
+            )],
         ),
         (
-            ".v-liga-off",
-            vec![Rule::Prop("font-feature-settings", "\"liga\" 0")],
+            ".snap-none",
+            vec![Rule::Child(
+                ".s",
+                vec![Rule::Prop("scroll-snap-align", "none")],
+            )],
         ),
         (
-            ".v-dlig",
-            vec![Rule::Prop("font-feature-settings", "\"dlig\"")],
+            ".snap-self-start",
+            vec![Rule::Prop("scroll-snap-align", "start !important")],
         ),
         (
-            ".v-dlig-off",
-            vec![Rule::Prop("font-feature-settings", "\"dlig\" 0")],
+            ".snap-self-center",
+            vec![Rule::Prop("scroll-snap-align", "center !important")],
         ),
         (
-            ".v-ordn",
-            vec![Rule::Prop("font-feature-settings", "\"ordn\"")],
+            ".snap-self-end",
+            vec![Rule::Prop("scroll-snap-align", "end !important")],
         ),
         (
-            ".v-ordn-off",
-            vec![Rule::Prop("font-feature-settings", "\"ordn\" 0")],
+            ".snap-self-none",
+            vec![Rule::Prop("scroll-snap-align", "none !important")],
         ),
         (
-            ".v-tnum",
-            vec![Rule::Prop("font-feature-settings", "\"tnum\"")],
+            ".self-start",
+            vec![Rule::Prop("align-self", "flex-start !important")],
         ),
         (
-            ".v-tnum-off",
-            vec![Rule::Prop("font-feature-settings", "\"tnum\" 0")],
+            ".self-center",
+            vec![Rule::Prop("align-self", "center !important")],
         ),
         (
-            ".v-afrc",
-            vec![Rule::Prop("font-feature-settings", "\"afrc\"")],
+            ".self-end",
+            vec![Rule::Prop("align-self", "flex-end !important")],
         ),
         (
-            ".v-afrc-off",
-            vec![Rule::Prop("font-feature-settings", "\"afrc\" 0")],
+            ".self-stretch",
+            vec![Rule::Prop("align-self", "stretch !important")],
         ),
         (
-            ".v-frac",
-            vec![Rule::Prop("font-feature-settings", "\"frac\"")],
+            ".self-baseline",
+            vec![Rule::Prop("align-self", "baseline !important")],
         ),
         (
-            ".v-frac-off",
-            vec![Rule::Prop("font-feature-settings", "\"frac\" 0")],
+            ".col-break-avoid",
+            vec![Rule::Prop("break-inside", "avoid")],
         ),
-    ]
+        (
+            ".col-break-before",
+            vec![Rule::Prop("break-before", "column")],
+        ),
+        (
+            ".col-break-after",
+            vec![Rule::Prop("break-after", "column")],
+        ),
+        (".col-span", vec![Rule::Prop("column-span", "all")]),
+        (".page-break", vec![Rule::Prop("break-after", "page")]),
+    ]);
+    values
 }
 
 #[test]
@@ -970,10 +1018,37 @@ fn test() {
 pub fn rules() -> String {
     let mut sheet = basesheet();
     &mut sheet.extend(common_values());
-    format!("{}{}",
-        OVERRIDES,
-        render_compact(sheet),
-    )
+    let mut rules = OVERRIDES.to_string();
+    rules.push_str(REDUCED_EFFECTS);
+    if cfg!(feature = "inputs") {
+        rules.push_str(INPUT_TEXT_RESET);
+        rules.push_str(SLIDER_RESET);
+        rules.push_str(TRACK_RESET);
+        rules.push_str(THUMB_RESET);
+    }
+    rules.push_str(EXPLAINER);
+    rules.push_str(&render_compact(sheet));
+    rules
+}
+
+// The rules for a subsystem that can be left out of the stylesheet by
+// turning off its feature.
+fn when(enabled: bool, rules: Vec<Rule>) -> Rule {
+    Rule::Batch(if enabled { rules } else { vec![] })
+}
+
+fn visually_hidden() -> Vec<Rule> {
+    vec![
+        Rule::Prop("position", "absolute !important"),
+        Rule::Prop("width", "1px !important"),
+        Rule::Prop("height", "1px !important"),
+        Rule::Prop("padding", "0 !important"),
+        Rule::Prop("margin", "-1px !important"),
+        Rule::Prop("overflow", "hidden !important"),
+        Rule::Prop("clip", "rect(0, 0, 0, 0) !important"),
+        Rule::Prop("white-space", "nowrap !important"),
+        Rule::Prop("border-width", "0 !important"),
+    ]
 }
 
 fn basesheet() -> Vec<Class> {
@@ -1246,6 +1321,9 @@ fn basesheet() -> Vec<Class> {
                         )
                     ]
                 ),
+                Rule::Descriptor(".lyr",
+                    vec![Rule::Prop("transform", "translateZ(0)")]
+                ),
                 Rule::Descriptor(".sb",
                     vec![
                         Rule::Prop("overflow", "auto"),
@@ -1301,15 +1379,18 @@ fn basesheet() -> Vec<Class> {
                         Rule::Prop("display", "inline-block"),
                     ]
                 ),
-                Rule::Descriptor("it",
-                    // chrome and safari have a minimum recognized line height for text input of 1.05
-                    // If it's 1, it bumps up to something like 1.2
-                    vec![
-                        Rule::Prop("line-height", "1.05"),
-                        Rule::Prop("background", "transparent"),
-                        Rule::Prop("text-align", "inherit"),
-                    ]
-                ),
+                when(cfg!(feature = "inputs"), vec![
+                    Rule::Descriptor("it",
+                        // chrome and safari have a minimum recognized line height for text input of 1.05
+                        // If it's 1, it bumps up to something like 1.2
+                        vec![
+                            Rule::Prop("line-height", "1.05"),
+                            Rule::Prop("background", "transparent"),
+                            Rule::Prop("text-align", "inherit"),
+                        ]
+
+                    ),
+                ]),
                 // Rule::Descriptor(".e", elDescription),
                 Rule::Descriptor(".r",
                     vec![
@@ -1527,35 +1608,38 @@ fn basesheet() -> Vec<Class> {
                         ),
                     ]
                 ),
-                Rule::Descriptor(".g",
-                    vec![
-                        Rule::Prop("display", "-ms-grid"),
-                        Rule::Child(".gp",
-                            vec![
-                                Rule::Child(".s",
-                                    vec![Rule::Prop("width", "100%")]
-                                ),
-                            ]
-                        ),
-                        Rule::Supports(("display", "grid"),
-                            vec![("display", "grid")]
-                        ),
-                        grid_alignments(Box::new(|alignment: &Alignment| match alignment {
-                            Alignment::Top =>
-                                vec![Rule::Prop("justify-content", "flex-start")],
-                            Alignment::Bottom =>
-                                vec![Rule::Prop("justify-content", "flex-end")],
-                            Alignment::Right =>
-                                vec![Rule::Prop("align-items", "flex-end")],
-                            Alignment::Left =>
-                                vec![Rule::Prop("align-items", "flex-start")],
-                            Alignment::CenterX =>
-                                vec![Rule::Prop("align-items", "center")],
-                            Alignment::CenterY =>
-                                vec![Rule::Prop("justify-content", "center")],
-                        }))
-                    ]
-                ),
+                when(cfg!(feature = "grid"), vec![
+                    Rule::Descriptor(".g",
+                        vec![
+                            Rule::Prop("display", "-ms-grid"),
+                            Rule::Child(".gp",
+                                vec![
+                                    Rule::Child(".s",
+                                        vec![Rule::Prop("width", "100%")]
+                                    ),
+                                ]
+                            ),
+                            Rule::Supports(("display", "grid"),
+                                vec![("display", "grid")]
+                            ),
+                            grid_alignments(Box::new(|alignment: &Alignment| match alignment {
+                                Alignment::Top =>
+                                    vec![Rule::Prop("justify-content", "flex-start")],
+                                Alignment::Bottom =>
+                                    vec![Rule::Prop("justify-content", "flex-end")],
+                                Alignment::Right =>
+                                    vec![Rule::Prop("align-items", "flex-end")],
+                                Alignment::Left =>
+                                    vec![Rule::Prop("align-items", "flex-start")],
+                                Alignment::CenterX =>
+                                    vec![Rule::Prop("align-items", "center")],
+                                Alignment::CenterY =>
+                                    vec![Rule::Prop("justify-content", "center")],
+                            }))
+                        ]
+
+                    ),
+                ]),
                 Rule::Descriptor(".pg",
                     vec![
                         Rule::Prop("display", "block"),
@@ -1616,44 +1700,47 @@ fn basesheet() -> Vec<Class> {
                         }))
                     ]
                 ),
-                Rule::Descriptor(".iml",
-                    vec![
-                        Rule::Prop("white-space", "pre-wrap !important"),
-                        Rule::Prop("height", "100%"),
-                        Rule::Prop("width", "100%"),
-                        Rule::Prop("background-color", "transparent"),
-                    ]
-                ),
-                Rule::Descriptor(".implw",
-                    // Get this.
-                    // This allows multiline input to anchor scrolling to the bottom of the node
-                    // when in a scrolling viewport, and the user is adding content.
-                    // however, it only works in chrome.  In firefox, it prevents scrolling.
-                    //
-                    // But how crazy is this solution?
-                    // vec![
-                    //     Rule::Prop("display", "flex"),
-                    //     Rule::Prop("flex-direction", "column-reverse"),
-                    // ]
-                    vec![
-                        // to increase specificity to beat another rule
-                        Rule::Descriptor(".e",
-                            vec![Rule::Prop("flex-basis", "auto")]
-                        ),
-                    ]
-                ),
-                Rule::Descriptor("imlp",
-                    vec![
-                        Rule::Prop("white-space", "pre-wrap !important"),
-                        Rule::Prop("cursor", "text"),
-                        Rule::Child(".imlf",
-                            vec![
-                                Rule::Prop("white-space", "pre-wrap !important"),
-                                Rule::Prop("color", "transparent"),
-                            ]
-                        ),
-                    ]
-                ),
+                when(cfg!(feature = "inputs"), vec![
+                    Rule::Descriptor(".iml",
+                        vec![
+                            Rule::Prop("white-space", "pre-wrap !important"),
+                            Rule::Prop("height", "100%"),
+                            Rule::Prop("width", "100%"),
+                            Rule::Prop("background-color", "transparent"),
+                        ]
+                    ),
+                    Rule::Descriptor(".implw",
+                        // Get this.
+                        // This allows multiline input to anchor scrolling to the bottom of the node
+                        // when in a scrolling viewport, and the user is adding content.
+                        // however, it only works in chrome.  In firefox, it prevents scrolling.
+                        //
+                        // But how crazy is this solution?
+                        // vec![
+                        //     Rule::Prop("display", "flex"),
+                        //     Rule::Prop("flex-direction", "column-reverse"),
+                        // ]
+                        vec![
+                            // to increase specificity to beat another rule
+                            Rule::Descriptor(".e",
+                                vec![Rule::Prop("flex-basis", "auto")]
+                            ),
+                        ]
+                    ),
+                    Rule::Descriptor("imlp",
+                        vec![
+                            Rule::Prop("white-space", "pre-wrap !important"),
+                            Rule::Prop("cursor", "text"),
+                            Rule::Child(".imlf",
+                                vec![
+                                    Rule::Prop("white-space", "pre-wrap !important"),
+                                    Rule::Prop("color", "transparent"),
+                                ]
+                            ),
+                        ]
+
+                    ),
+                ]),
                 Rule::Descriptor(".p",
                     vec![
                         Rule::Prop("display", "block"),
@@ -1765,6 +1852,10 @@ fn basesheet() -> Vec<Class> {
                 Rule::Descriptor(".hidden",
                     vec![Rule::Prop("display", "none")]
                 ),
+                // Hidden from sight but still read by screen readers.
+                Rule::Descriptor(".vh", visually_hidden()),
+                // Same as above, but revealed when it receives focus.
+                Rule::Descriptor(".vhf:not(:focus)", visually_hidden()),
                 Rule::Descriptor(".w1",
                     vec![Rule::Prop("font-weight", "100")]
                 ),
@@ -1844,3 +1935,14 @@ fn basesheet() -> Vec<Class> {
         ),
     ]
 }
+
+#[test]
+fn test_feature_rules() {
+    let rules = rules();
+    assert_eq!(rules.contains(".s.g {"), cfg!(feature = "grid"));
+    assert_eq!(rules.contains("input[type=range]"), cfg!(feature = "inputs"));
+    assert_eq!(
+        rules.contains(".v-smcp {"),
+        cfg!(feature = "typography-variants")
+    );
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::keyboard::KeyboardKind;
+use crate::router::Navigator;
+
+pub use crate::budget::Degradation;
+
+/// Whether the window the UI is shown in can currently be seen.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+}
+
+/// A source of messages that don't come from the view, like the passing of
+/// time or the window being resized.
+///
+/// Subscriptions are recomputed from the model each time they are checked,
+/// so listening to something is as simple as returning a `Sub` for it, and
+/// stopping is as simple as no longer returning it.
+pub enum Sub<Msg> {
+    Every(Duration, Arc<dyn Fn() -> Msg + Send + Sync>),
+    Resize(Arc<dyn Fn(f32, f32) -> Msg + Send + Sync>),
+    VisibilityChange(Arc<dyn Fn(Visibility) -> Msg + Send + Sync>),
+    DegradationChange(Arc<dyn Fn(Degradation) -> Msg + Send + Sync>),
+    VirtualKeyboard(Arc<dyn Fn(Option<KeyboardKind>) -> Msg + Send + Sync>),
+    RouteChange(Navigator, Arc<dyn Fn(&str) -> Msg + Send + Sync>),
+    #[cfg(feature = "bevy")]
+    Event(Arc<dyn bridge::EventBridge<Msg>>),
+}
+
+impl<Msg> Clone for Sub<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Every(interval, f) => Self::Every(*interval, f.clone()),
+            Self::Resize(f) => Self::Resize(f.clone()),
+            Self::VisibilityChange(f) => Self::VisibilityChange(f.clone()),
+            Self::DegradationChange(f) => Self::DegradationChange(f.clone()),
+            Self::VirtualKeyboard(f) => Self::VirtualKeyboard(f.clone()),
+            Self::RouteChange(navigator, f) => {
+                Self::RouteChange(navigator.clone(), f.clone())
+            }
+            #[cfg(feature = "bevy")]
+            Self::Event(bridge) => Self::Event(bridge.clone()),
+        }
+    }
+}
+
+/// Send `msg` every `interval`, e.g. to tick a clock or autosave.
+pub fn every<Msg>(interval: Duration, msg: Msg) -> Sub<Msg>
+where
+    Msg: Clone + Send + Sync + 'static,
+{
+    Sub::Every(interval, Arc::new(move || msg.clone()))
+}
+
+/// Get the new logical width and height of the window when it's resized.
+pub fn on_resize<Msg, F>(f: F) -> Sub<Msg>
+where
+    F: Fn(f32, f32) -> Msg + Send + Sync + 'static,
+{
+    Sub::Resize(Arc::new(f))
+}
+
+/// Find out when the window is hidden or shown again, e.g. to pause
+/// animations or polling while nobody is looking.
+pub fn on_visibility_change<Msg, F>(f: F) -> Sub<Msg>
+where
+    F: Fn(Visibility) -> Msg + Send + Sync + 'static,
+{
+    Sub::VisibilityChange(Arc::new(f))
+}
+
+/// Find out when the UI starts or stops cutting back to stay within the
+/// frame budget set with `Program::with_frame_budget`, e.g. to drop
+/// decorative effects of the app's own.
+pub fn on_degradation_change<Msg, F>(f: F) -> Sub<Msg>
+where
+    F: Fn(Degradation) -> Msg + Send + Sync + 'static,
+{
+    Sub::DegradationChange(Arc::new(f))
+}
+
+/// Find out when a text input gains focus and wants an on-screen keyboard,
+/// with the kind it wants, and when it's done with it, for showing the
+/// built-in `keyboard::keyboard`.
+pub fn on_virtual_keyboard<Msg, F>(f: F) -> Sub<Msg>
+where
+    F: Fn(Option<KeyboardKind>) -> Msg + Send + Sync + 'static,
+{
+    Sub::VirtualKeyboard(Arc::new(f))
+}
+
+#[cfg(feature = "bevy")]
+pub use self::bridge::{on_event, EventBridge};
+
+#[cfg(feature = "bevy")]
+mod bridge {
+    use std::any::{Any, TypeId};
+    use std::marker::PhantomData;
+    use std::sync::Arc;
+
+    use bevy::ecs::event::{Event, EventCursor, Events};
+    use bevy::ecs::world::World;
+
+    use super::Sub;
+
+    /// Turns the Bevy events of one type into messages.
+    pub trait EventBridge<Msg>: Send + Sync {
+        fn event_type(&self) -> TypeId;
+
+        /// Read the events sent since `cursor`, returning the messages and
+        /// the cursor to continue from next time.
+        fn read(
+            &self,
+            world: &World,
+            cursor: Option<&(dyn Any + Send + Sync)>,
+        ) -> (Vec<Msg>, Box<dyn Any + Send + Sync>);
+    }
+
+    struct Bridge<E, Msg> {
+        f: Arc<dyn Fn(&E) -> Msg + Send + Sync>,
+        _event: PhantomData<fn(&E)>,
+    }
+
+    impl<E: Event, Msg> EventBridge<Msg> for Bridge<E, Msg> {
+        fn event_type(&self) -> TypeId {
+            TypeId::of::<E>()
+        }
+
+        fn read(
+            &self,
+            world: &World,
+            cursor: Option<&(dyn Any + Send + Sync)>,
+        ) -> (Vec<Msg>, Box<dyn Any + Send + Sync>) {
+            let events = match world.get_resource::<Events<E>>() {
+                Some(events) => events,
+                None => return (vec![], Box::new(EventCursor::<E>::default())),
+            };
+            // A new subscription only hears about events sent from now on.
+            let mut cursor = cursor
+                .and_then(|c| c.downcast_ref::<EventCursor<E>>())
+                .cloned()
+                .unwrap_or_else(|| events.get_cursor_current());
+            let msgs = cursor.read(events).map(|e| (self.f)(e)).collect();
+            (msgs, Box::new(cursor))
+        }
+    }
+
+    /// Bridge any Bevy event into the UI, e.g. a game's `ScoreChanged`.
+    pub fn on_event<E, Msg, F>(f: F) -> Sub<Msg>
+    where
+        E: Event,
+        Msg: 'static,
+        F: Fn(&E) -> Msg + Send + Sync + 'static,
+    {
+        Sub::Event(Arc::new(Bridge {
+            f: Arc::new(f),
+            _event: PhantomData,
+        }))
+    }
+}
@@ -0,0 +1,131 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::ptr;
+
+use crate::compat::{HashSet, RwLock};
+
+/// An interned string, for the names that come from a small, fixed set:
+/// tags, attribute and event names, and CSS property names. Comparing and
+/// hashing one is comparing and hashing a pointer.
+///
+/// Interned strings are never freed, so class names, `data-` attribute
+/// names and other strings that are built from user data stay `String`s.
+#[derive(Clone, Copy)]
+pub struct Symbol(&'static str);
+
+static INTERNER: RwLock<Option<HashSet<&'static str>>> = RwLock::new(None);
+
+impl Symbol {
+    pub fn new(name: &str) -> Self {
+        if let Some(names) = INTERNER.read().as_ref() {
+            if let Some(name) = names.get(name) {
+                return Symbol(name);
+            }
+        }
+        let mut names = INTERNER.write();
+        let names = names.get_or_insert_with(HashSet::default);
+        // It may have been added since the read lock was let go.
+        if let Some(name) = names.get(name) {
+            return Symbol(name);
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        names.insert(name);
+        Symbol(name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+// Each name is interned once, so two symbols are the same name only if
+// they point at the same string.
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_ptr().hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::new(&name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+// Ordered by name rather than by when they were interned, so sorting by
+// them gives the same order every run.
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[test]
+fn test_intern() {
+    let a = Symbol::new("background-color");
+    assert_eq!(a, Symbol::from("background-color".to_string()));
+    assert_ne!(a, Symbol::new("color"));
+    assert_eq!(a, "background-color");
+    // Ordered by name.
+    assert!(a < Symbol::new("color"));
+    assert_eq!(
+        format!("{}: {:?}", a, a),
+        "background-color: \"background-color\""
+    );
+}
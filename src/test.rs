@@ -0,0 +1,451 @@
+use std::collections::BTreeMap;
+use std::task::{Context, Poll, Waker};
+
+use crate::app::Program;
+use crate::cmd::{BoxFuture, Cmd};
+use crate::element::layout;
+use crate::model::Element;
+use crate::vdom::{html_attributes, Attribute, EventData, Node, NodeType};
+
+// The classes and the other attributes of a node, by name, with its
+// styles as a `style` attribute.
+fn split<Msg>(node: &Node<Msg>) -> (Vec<&str>, BTreeMap<&str, String>) {
+    let classes = node.attrs.iter().flat_map(Attribute::classes).collect();
+    let attrs = html_attributes(&node.attrs)
+        .into_iter()
+        .filter(|(key, _)| *key != "class")
+        .collect();
+    (classes, attrs)
+}
+
+fn describe<Msg>(child: &NodeType<Msg>) -> String {
+    match child {
+        NodeType::Node(node) | NodeType::KeyedNode(_, node) => {
+            format!("<{}>", node.tag)
+        }
+        NodeType::Text(text) => format!("text {:?}", text),
+    }
+}
+
+fn diff_nodes<Msg>(
+    expected: &Node<Msg>,
+    actual: &Node<Msg>,
+    path: &mut Vec<usize>,
+    out: &mut Vec<String>,
+) {
+    let at = format!("at {:?} <{}>", path, expected.tag);
+    if expected.tag != actual.tag {
+        out.push(format!("{}: found <{}>", at, actual.tag));
+        return;
+    }
+    let (expected_classes, expected_attrs) = split(expected);
+    let (actual_classes, actual_attrs) = split(actual);
+    for class in expected_classes
+        .iter()
+        .filter(|c| !actual_classes.contains(c))
+    {
+        out.push(format!("{}: missing class `{}`", at, class));
+    }
+    for class in actual_classes
+        .iter()
+        .filter(|c| !expected_classes.contains(c))
+    {
+        out.push(format!("{}: unexpected class `{}`", at, class));
+    }
+    for (key, value) in &expected_attrs {
+        match actual_attrs.get(key) {
+            None => out.push(format!("{}: missing attribute `{}`", at, key)),
+            Some(actual) if actual != value => out.push(format!(
+                "{}: attribute `{}` is {:?}, expected {:?}",
+                at, key, actual, value
+            )),
+            _ => {}
+        }
+    }
+    for key in actual_attrs
+        .keys()
+        .filter(|k| !expected_attrs.contains_key(*k))
+    {
+        out.push(format!("{}: unexpected attribute `{}`", at, key));
+    }
+    for listener in &expected.events {
+        if !actual.events.iter().any(|l| l.event == listener.event) {
+            out.push(format!("{}: missing `{}` listener", at, listener.event));
+        }
+    }
+    for listener in &actual.events {
+        if !expected.events.iter().any(|l| l.event == listener.event) {
+            out.push(format!(
+                "{}: unexpected `{}` listener",
+                at, listener.event
+            ));
+        }
+    }
+    // Stylesheets follow from the classes, so they aren't compared.
+    if expected.tag == "style" {
+        return;
+    }
+
+    for (i, pair) in expected.children.iter().zip(&actual.children).enumerate()
+    {
+        path.push(i);
+        match pair {
+            (NodeType::Text(expected), NodeType::Text(actual)) => {
+                if expected != actual {
+                    out.push(format!(
+                        "at {:?}: text is {:?}, expected {:?}",
+                        path, actual, expected
+                    ));
+                }
+            }
+            (
+                NodeType::Node(expected) | NodeType::KeyedNode(_, expected),
+                NodeType::Node(actual) | NodeType::KeyedNode(_, actual),
+            ) => diff_nodes(expected, actual, path, out),
+            (expected, actual) => out.push(format!(
+                "at {:?}: found {}, expected {}",
+                path,
+                describe(actual),
+                describe(expected)
+            )),
+        }
+        path.pop();
+    }
+    let (expected_len, actual_len) =
+        (expected.children.len(), actual.children.len());
+    for (i, child) in expected.children.iter().enumerate().skip(actual_len) {
+        out.push(format!(
+            "{}: missing child {} at {}",
+            at,
+            describe(child),
+            i
+        ));
+    }
+    for (i, child) in actual.children.iter().enumerate().skip(expected_len) {
+        out.push(format!("{}: extra child {} at {}", at, describe(child), i));
+    }
+}
+
+/// The differences between two rendered trees, one per line, each saying
+/// where it is as a path of child indices from the root.
+pub fn ui_diff<Msg>(expected: &Node<Msg>, actual: &Node<Msg>) -> Vec<String> {
+    let mut out = vec![];
+    diff_nodes(expected, actual, &mut vec![], &mut out);
+    out
+}
+
+/// Assert two elements render the same, listing the differences if they
+/// don't.
+#[track_caller]
+pub fn assert_ui_eq<Msg>(expected: Element<Msg>, actual: Element<Msg>) {
+    let diff = ui_diff(&layout(vec![], expected), &layout(vec![], actual));
+    if !diff.is_empty() {
+        panic!("elements render differently:\n  {}", diff.join("\n  "));
+    }
+}
+
+// The first node, depth first, that `matches`.
+fn find<'a, Msg>(
+    node: &'a Node<Msg>,
+    path: &mut Vec<usize>,
+    matches: &dyn Fn(&Node<Msg>) -> bool,
+) -> Option<&'a Node<Msg>> {
+    if matches(node) {
+        return Some(node);
+    }
+    for (i, child) in node.children.iter().enumerate() {
+        if let NodeType::Node(child) | NodeType::KeyedNode(_, child) = child {
+            path.push(i);
+            if let Some(found) = find(child, path, matches) {
+                return Some(found);
+            }
+            path.pop();
+        }
+    }
+    None
+}
+
+/// The path to the node with the `id`, and the node.
+pub fn find_by_id<'a, Msg>(
+    root: &'a Node<Msg>,
+    id: &str,
+) -> Option<(Vec<usize>, &'a Node<Msg>)> {
+    let mut path = vec![];
+    let node = find(root, &mut path, &|node| node.attr("id") == Some(id))?;
+    Some((path, node))
+}
+
+/// The path to the node holding the text `text`, and the node.
+pub fn find_by_text<'a, Msg>(
+    root: &'a Node<Msg>,
+    text: &str,
+) -> Option<(Vec<usize>, &'a Node<Msg>)> {
+    let mut path = vec![];
+    let node = find(root, &mut path, &|node| {
+        node.children
+            .iter()
+            .any(|c| matches!(c, NodeType::Text(t) if t == text))
+    })?;
+    Some((path, node))
+}
+
+/// Drives a program the way a user would, without a backend: clicking
+/// and typing into nodes found by their id, and applying the messages
+/// they produce. Each step works on the view as rendered after the last.
+///
+/// ```ignore
+/// let form = TestHarness::new(init, update, view)
+///     .click("save-button")
+///     .type_text("name-input", "hi")
+///     .render();
+/// assert_eq!(form.model().name, "hi");
+/// ```
+///
+/// Commands are run in the same step if they finish without waiting, and
+/// polled again after each step otherwise. Nothing wakes them up, so
+/// anything waiting on a timer or the network never finishes.
+pub struct TestHarness<Model, Msg> {
+    program: Program<Model, Msg>,
+    pending: Vec<BoxFuture<Option<Msg>>>,
+}
+
+impl<Model, Msg> TestHarness<Model, Msg> {
+    pub fn new(
+        init: fn() -> Model,
+        update: fn(&mut Model, Msg) -> Cmd<Msg>,
+        view: fn(&Model) -> Element<Msg>,
+    ) -> Self {
+        Self::from_program(Program::new(init, update, view))
+    }
+
+    pub fn from_program(program: Program<Model, Msg>) -> Self {
+        Self {
+            program,
+            pending: vec![],
+        }
+    }
+
+    pub fn model(&self) -> &Model {
+        self.program.model()
+    }
+
+    pub fn program(&mut self) -> &mut Program<Model, Msg> {
+        &mut self.program
+    }
+
+    /// The most recently rendered view.
+    pub fn node(&self) -> &Node<Msg> {
+        self.program.node()
+    }
+
+    /// Render the view again if the model has changed.
+    pub fn render(mut self) -> Self {
+        self.program.render();
+        self
+    }
+
+    /// Send `event` to the node with the `id`, then to each of its
+    /// ancestors in turn, as it would bubble up in a browser.
+    #[track_caller]
+    pub fn fire(mut self, id: &str, event: &str, data: EventData) -> Self {
+        let path = self.path(id);
+        let msgs = (0..=path.len())
+            .rev()
+            .flat_map(|len| self.program.dispatch(&path[..len], event, &data))
+            .collect();
+        self.send(msgs);
+        self
+    }
+
+    /// Click the node with the `id`.
+    #[track_caller]
+    pub fn click(self, id: &str) -> Self {
+        self.fire(id, "click", EventData::Pointer { x: 0.0, y: 0.0 })
+    }
+
+    /// Focus the node with the `id`.
+    #[track_caller]
+    pub fn focus(mut self, id: &str) -> Self {
+        let path = self.path(id);
+        let msgs = self.program.focus(&path);
+        self.send(msgs);
+        self
+    }
+
+    /// Press `key` on the focused node, with the names browsers give keys,
+    /// like `"Enter"` or `"a"`.
+    pub fn key(mut self, key: &str) -> Self {
+        let shift = key.chars().count() == 1 && key != key.to_lowercase();
+        let msgs = self.program.key_down(key, shift);
+        self.send(msgs);
+        self
+    }
+
+    /// Focus the node with the `id` and type `text` into it, a key at a
+    /// time. Each key is followed by an `input` event with all the node's
+    /// text so far, its `value` and what's been typed, as from a text
+    /// input.
+    #[track_caller]
+    pub fn type_text(mut self, id: &str, text: &str) -> Self {
+        let (_, node) = self.find(id);
+        let mut value = node.attr("value").unwrap_or_default().to_string();
+        let mut harness = self.focus(id);
+        for c in text.chars() {
+            value.push(c);
+            let input = EventData::Text(value.clone());
+            harness = harness.key(&c.to_string()).fire(id, "input", input);
+        }
+        harness
+    }
+
+    #[track_caller]
+    fn path(&mut self, id: &str) -> Vec<usize> {
+        self.find(id).0
+    }
+
+    #[track_caller]
+    fn find(&mut self, id: &str) -> (Vec<usize>, &Node<Msg>) {
+        match find_by_id(self.program.render(), id) {
+            Some(found) => found,
+            None => panic!("no node with the id `{}`", id),
+        }
+    }
+
+    fn send(&mut self, msgs: Vec<Msg>) {
+        let mut msgs = msgs;
+        loop {
+            for msg in msgs.drain(..) {
+                let cmd = self.program.update(msg);
+                self.pending.extend(cmd.into_futures());
+            }
+            let mut cx = Context::from_waker(Waker::noop());
+            let mut pending = vec![];
+            for mut future in self.pending.drain(..) {
+                match future.as_mut().poll(&mut cx) {
+                    Poll::Ready(Some(msg)) => msgs.push(msg),
+                    Poll::Ready(None) => {}
+                    Poll::Pending => pending.push(future),
+                }
+            }
+            self.pending = pending;
+            if msgs.is_empty() {
+                break;
+            }
+        }
+        self.program.render();
+    }
+}
+
+#[test]
+fn test_ui_diff() {
+    use crate::element::{column, el, id, padding};
+
+    let view = |pad: u32, items: &[&str]| {
+        column::<()>(
+            vec![],
+            items
+                .iter()
+                .map(|item| {
+                    el(vec![padding(pad)], Element::Text(item.to_string()))
+                })
+                .collect(),
+        )
+    };
+    assert_ui_eq(view(4, &["a", "b"]), view(4, &["a", "b"]));
+
+    let expected = layout(vec![], view(4, &["a", "b"]));
+    let actual = layout(vec![], view(8, &["a", "c", "d"]));
+    let diff = ui_diff(&expected, &actual);
+    assert!(diff.iter().any(|d| d.ends_with("missing class `p-4`")));
+    assert!(diff
+        .iter()
+        .any(|d| d.ends_with("text is \"c\", expected \"b\"")));
+    assert!(diff.iter().any(|d| d.contains("extra child <div> at 2")));
+
+    let page = layout(
+        vec![],
+        el::<()>(
+            vec![id("save".to_string())],
+            Element::Text("Save".to_string()),
+        ),
+    );
+    let (path, node) = find_by_id(&page, "save").unwrap();
+    assert_eq!(
+        find_by_text(&page, "Save").unwrap().0[..path.len()],
+        path[..]
+    );
+    assert_eq!(node.tag, "div");
+    assert!(find_by_text(&page, "Cancel").is_none());
+}
+
+#[test]
+fn test_harness() {
+    use crate::element::{column, el, id};
+    use crate::events::{on, on_click};
+    use crate::focus::focusable;
+
+    struct Form {
+        name: String,
+        saved: Option<String>,
+    }
+    #[derive(Clone)]
+    enum Msg {
+        Key(String),
+        Save,
+        Saved(String),
+    }
+    let harness = TestHarness::new(
+        || Form {
+            name: String::new(),
+            saved: None,
+        },
+        |form, msg| match msg {
+            Msg::Key(key) => {
+                form.name.push_str(&key);
+                Cmd::none()
+            }
+            Msg::Save => {
+                let name = form.name.clone();
+                Cmd::perform(async move { name }, Msg::Saved)
+            }
+            Msg::Saved(name) => {
+                form.saved = Some(name);
+                Cmd::none()
+            }
+        },
+        |form| {
+            column(
+                vec![],
+                vec![
+                    el(
+                        vec![
+                            id("name-input".to_string()),
+                            focusable(),
+                            on("keydown".to_string(), |data| match data {
+                                EventData::Key { key, .. } => {
+                                    Msg::Key(key.clone())
+                                }
+                                _ => Msg::Key(String::new()),
+                            }),
+                        ],
+                        Element::Text(form.name.clone()),
+                    ),
+                    // The listener is on the button, the id on its label.
+                    el(
+                        vec![on_click(Msg::Save)],
+                        el(
+                            vec![id("save-button".to_string())],
+                            Element::Text("Save".to_string()),
+                        ),
+                    ),
+                ],
+            )
+        },
+    )
+    .type_text("name-input", "Hi")
+    .click("save-button")
+    .render();
+    assert_eq!(harness.model().name, "Hi");
+    assert_eq!(harness.model().saved.as_deref(), Some("Hi"));
+    assert!(find_by_text(harness.node(), "Hi").is_some());
+}
@@ -0,0 +1,150 @@
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::model::Element;
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const POP_DIRECTIONAL_FORMATTING: char = '\u{202C}';
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+
+/// Whether `c` is one of the invisible characters steering the direction
+/// of bidirectional text: the marks, and those opening or closing an
+/// embedding, override or isolate.
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200E}' | '\u{200F}' | '\u{061C}' | '\u{202A}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+    )
+}
+
+// The clusters of `text` that show up as something, with where each
+// starts.
+fn visible_graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.grapheme_indices(true)
+        .filter(|(_, g)| !g.chars().all(is_bidi_control))
+}
+
+/// How many characters `text` looks like it has: an emoji joined from
+/// several with zero-width joiners, or a letter with combining accents,
+/// counts as one, and bidi controls don't count.
+pub fn grapheme_count(text: &str) -> usize {
+    visible_graphemes(text).count()
+}
+
+/// The first `max` characters of `text`, counted as by `grapheme_count`,
+/// so the cut never lands inside an emoji sequence or between a letter
+/// and its accents.
+pub fn truncate_graphemes(text: &str, max: usize) -> &str {
+    match visible_graphemes(text).nth(max) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
+
+/// `text` cut down to at most `max` characters, counted as by
+/// `grapheme_count`, with an ellipsis in place of what was cut.
+///
+/// Embeddings and isolates left open by the cut are closed before the
+/// ellipsis, so it stays at the end rather than being reordered into
+/// right-to-left text, and doesn't change the direction of what follows.
+/// A zero-width joiner left dangling by the cut is dropped.
+pub fn ellipsize(text: &str, max: usize) -> String {
+    if visible_graphemes(text).nth(max).is_none() {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let kept = truncate_graphemes(text, max - 1)
+        .trim_end_matches(|c| c == ZERO_WIDTH_JOINER || is_bidi_control(c));
+    let mut result = kept.to_string();
+    result.extend(close_directional(kept));
+    result.push('…');
+    result
+}
+
+/// `text` set apart from whatever it's put in, for its direction to be
+/// worked out on its own, e.g. for a name in a sentence in another
+/// script. Without this, a name in Arabic at the end of an English
+/// sentence would carry the punctuation after it along to the left.
+pub fn isolate(text: &str) -> String {
+    format!(
+        "{}{}{}",
+        FIRST_STRONG_ISOLATE, text, POP_DIRECTIONAL_ISOLATE
+    )
+}
+
+// The characters closing the embeddings and isolates left open at the end
+// of `text`, innermost first.
+fn close_directional(text: &str) -> Vec<char> {
+    let mut open = vec![];
+    for c in text.chars() {
+        match c {
+            '\u{202A}' | '\u{202B}' | '\u{202D}' | '\u{202E}' => {
+                open.push(POP_DIRECTIONAL_FORMATTING)
+            }
+            '\u{2066}' | '\u{2067}' | '\u{2068}' => {
+                open.push(POP_DIRECTIONAL_ISOLATE)
+            }
+            // Only closes an embedding opened inside the innermost
+            // isolate.
+            POP_DIRECTIONAL_FORMATTING
+                if open.last() == Some(&POP_DIRECTIONAL_FORMATTING) =>
+            {
+                open.pop();
+            }
+            // Closes the innermost isolate, and any embeddings in it.
+            POP_DIRECTIONAL_ISOLATE => {
+                if let Some(i) =
+                    open.iter().rposition(|&c| c == POP_DIRECTIONAL_ISOLATE)
+                {
+                    open.truncate(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    open.reverse();
+    open
+}
+
+/// Text cut down to `max` characters with `ellipsize`.
+pub fn text_ellipsized<Msg>(text: &str, max: usize) -> Element<Msg> {
+    Element::Text(ellipsize(text, max))
+}
+
+#[test]
+fn test_unicode_text() {
+    let family = "👩\u{200D}👩\u{200D}👧";
+    assert_eq!(grapheme_count(family), 1);
+    assert_eq!(
+        truncate_graphemes(&format!("{}{}", family, family), 1),
+        family
+    );
+    assert_eq!(truncate_graphemes("e\u{301}te\u{301}", 2), "e\u{301}t");
+
+    assert_eq!(ellipsize("hello", 5), "hello");
+    assert_eq!(ellipsize("hello world", 5), "hell…");
+    assert_eq!(
+        ellipsize(&format!("ab{}cd", family), 4),
+        format!("ab{}…", family)
+    );
+    // The joiner of the cut-off joined pair is dropped.
+    assert_eq!(ellipsize("ab\u{200D}cd", 3), "ab…");
+
+    let rtl = "\u{202B}שלום עולם\u{202C} ok";
+    let cut = ellipsize(rtl, 4);
+    assert_eq!(cut, "\u{202B}שלו\u{202C}…");
+    assert_eq!(grapheme_count(&cut), 4);
+    let nested = "\u{2067}a\u{202E}bcdef\u{2069}";
+    assert_eq!(ellipsize(nested, 3), "\u{2067}a\u{202E}b\u{202C}\u{2069}…");
+
+    assert_eq!(isolate("אבי"), "\u{2068}אבי\u{2069}");
+}
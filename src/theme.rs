@@ -0,0 +1,219 @@
+use crate::{
+    background, border,
+    element::{mouse_down, mouse_over, padding, padding_xy, pointer},
+    font,
+    model::{static_bundle, Attribute, Color, Shadow},
+};
+
+const fn hex(rgb: u32) -> Color {
+    Color {
+        r: ((rgb >> 16) & 0xff) as f32 / 255.0,
+        g: ((rgb >> 8) & 0xff) as f32 / 255.0,
+        b: (rgb & 0xff) as f32 / 255.0,
+        a: 1.0,
+    }
+}
+
+/// The colors of a theme. Each `on_` color is for text drawn on top of the
+/// color it's named after.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Palette {
+    pub primary: Color,
+    pub primary_hover: Color,
+    pub primary_active: Color,
+    pub on_primary: Color,
+    pub background: Color,
+    pub on_background: Color,
+    pub surface: Color,
+    pub on_surface: Color,
+    pub muted: Color,
+    pub border: Color,
+    pub danger: Color,
+}
+
+/// A modular scale, where each step is `ratio` times the one before it.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Scale {
+    pub base: f32,
+    pub ratio: f32,
+}
+
+impl Scale {
+    pub fn step(&self, n: i32) -> f32 {
+        self.base * self.ratio.powi(n)
+    }
+
+    pub fn px(&self, n: i32) -> u32 {
+        self.step(n).round() as u32
+    }
+}
+
+/// A small design system: a palette, spacing and type scales, and the
+/// styles the built in widgets are drawn with.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Theme {
+    pub palette: Palette,
+    pub spacing: Scale,
+    pub typography: Scale,
+    pub radius: u32,
+    pub shadow: Shadow,
+}
+
+/// The default light theme, as a constant so themes built from it can be
+/// statics.
+pub const LIGHT: Theme = Theme {
+    palette: Palette {
+        primary: hex(0x2563eb),
+        primary_hover: hex(0x1d4ed8),
+        primary_active: hex(0x1e40af),
+        on_primary: hex(0xffffff),
+        background: hex(0xf8fafc),
+        on_background: hex(0x0f172a),
+        surface: hex(0xffffff),
+        on_surface: hex(0x1e293b),
+        muted: hex(0x64748b),
+        border: hex(0xcbd5e1),
+        danger: hex(0xdc2626),
+    },
+    spacing: Scale {
+        base: 8.0,
+        ratio: 1.5,
+    },
+    typography: Scale {
+        base: 16.0,
+        ratio: 1.25,
+    },
+    radius: 6,
+    shadow: Shadow {
+        color: Color {
+            a: 0.12,
+            ..hex(0x0f172a)
+        },
+        offset: (0, 2),
+        blur: 8,
+        size: 0,
+    },
+};
+
+pub const DARK: Theme = Theme {
+    palette: Palette {
+        primary: hex(0x3b82f6),
+        primary_hover: hex(0x60a5fa),
+        primary_active: hex(0x93c5fd),
+        on_primary: hex(0x0b1120),
+        background: hex(0x0b1120),
+        on_background: hex(0xe2e8f0),
+        surface: hex(0x1e293b),
+        on_surface: hex(0xf1f5f9),
+        muted: hex(0x94a3b8),
+        border: hex(0x334155),
+        danger: hex(0xf87171),
+    },
+    shadow: Shadow {
+        color: Color {
+            a: 0.4,
+            ..hex(0x000000)
+        },
+        ..LIGHT.shadow
+    },
+    ..LIGHT
+};
+
+pub fn default_light() -> Theme {
+    LIGHT
+}
+
+pub fn default_dark() -> Theme {
+    DARK
+}
+
+impl Theme {
+    /// The size of text `n` steps up (or down) the type scale.
+    pub fn font_size(&self, n: i32) -> u32 {
+        self.typography.px(n)
+    }
+
+    /// The spacing `n` steps up (or down) the spacing scale.
+    pub fn space(&self, n: i32) -> u32 {
+        self.spacing.px(n)
+    }
+
+    /// For the root of the layout: the background, text color and base
+    /// font size.
+    pub fn page<Msg>(&self) -> Vec<Attribute<Msg>> {
+        vec![
+            background::color(self.palette.background),
+            font::color(self.palette.on_background),
+            font::size(self.font_size(0)),
+        ]
+    }
+
+    // The widgets' styles go in the static stylesheet, as every instance
+    // of a widget shares them.
+    pub fn button<Msg>(&self) -> Vec<Attribute<Msg>> {
+        static_bundle(vec![
+            background::color(self.palette.primary),
+            font::color(self.palette.on_primary),
+            padding_xy(self.space(1), self.space(0)),
+            border::rounded(self.radius),
+            pointer(),
+            mouse_over(vec![background::color(self.palette.primary_hover)]),
+            mouse_down(vec![background::color(self.palette.primary_active)]),
+        ])
+    }
+
+    pub fn input<Msg>(&self) -> Vec<Attribute<Msg>> {
+        static_bundle(vec![
+            background::color(self.palette.surface),
+            font::color(self.palette.on_surface),
+            padding(self.space(0)),
+            border::rounded(self.radius),
+            border::width(1),
+            border::color(self.palette.border),
+        ])
+    }
+
+    pub fn card<Msg>(&self) -> Vec<Attribute<Msg>> {
+        static_bundle(vec![
+            background::color(self.palette.surface),
+            font::color(self.palette.on_surface),
+            padding(self.space(2)),
+            border::rounded(self.radius * 2),
+            border::shadow(self.shadow),
+            border::width(1),
+            border::color(self.palette.border),
+        ])
+    }
+}
+
+#[test]
+fn test_theme_widgets() {
+    use crate::element::{el, layout};
+    use crate::model::Element;
+    use crate::render::to_html;
+
+    assert_eq!(LIGHT.space(1), 12);
+    assert_eq!(LIGHT.font_size(2), 25);
+
+    let button = el::<()>(DARK.button(), Element::Text("Save".to_string()));
+    let html = to_html(&layout(vec![], button));
+    let primary = DARK.palette.primary.format_color_class();
+    let hover = DARK.palette.primary_hover.format_color_class();
+    assert!(html.contains(&format!("bg-{}", primary)));
+    assert!(html.contains(&format!("bg-{}-hv", hover)));
+    assert!(html.contains("p-12-8"));
+
+    // The widgets' styles are in the static stylesheet, not the view's.
+    let sheets: Vec<&str> = html.split("<style>").skip(1).collect();
+    assert!(sheets[0].contains(&format!(".bg-{}", primary)));
+    assert!(!sheets[1].contains(&format!(".bg-{}", primary)));
+
+    let card = el::<()>(LIGHT.card(), Element::Empty);
+    let html = to_html(&layout(vec![], card));
+    assert!(html.contains("br-12"));
+    assert!(html.contains("b-1"));
+    assert!(html.contains(&format!(
+        "bc-{}",
+        LIGHT.palette.border.format_color_class()
+    )));
+}
@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    model::{Color, Shadow},
+    theme::{default_light, Theme},
+};
+
+/// Why a tokens file couldn't be turned into a theme.
+#[derive(Debug)]
+pub enum TokenError {
+    Json(serde_json::Error),
+    /// The token at this path doesn't have a value of the right type.
+    Invalid(String),
+    /// The token at this path refers to one that doesn't exist, or refers
+    /// back to itself.
+    Reference(String),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid tokens file: {}", e),
+            Self::Invalid(path) => write!(f, "invalid value for `{}`", path),
+            Self::Reference(path) => {
+                write!(f, "unresolved reference in `{}`", path)
+            }
+        }
+    }
+}
+
+impl Error for TokenError {}
+
+// The colors of a palette, by their names in a tokens file.
+const COLORS: [&str; 11] = [
+    "primary",
+    "primary-hover",
+    "primary-active",
+    "on-primary",
+    "background",
+    "on-background",
+    "surface",
+    "on-surface",
+    "muted",
+    "border",
+    "danger",
+];
+
+fn palette_color<'a>(theme: &'a mut Theme, name: &str) -> &'a mut Color {
+    let p = &mut theme.palette;
+    match name {
+        "primary" => &mut p.primary,
+        "primary-hover" => &mut p.primary_hover,
+        "primary-active" => &mut p.primary_active,
+        "on-primary" => &mut p.on_primary,
+        "background" => &mut p.background,
+        "on-background" => &mut p.on_background,
+        "surface" => &mut p.surface,
+        "on-surface" => &mut p.on_surface,
+        "muted" => &mut p.muted,
+        "border" => &mut p.border,
+        _ => &mut p.danger,
+    }
+}
+
+fn hex_color(color: &Color) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let rgb = format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    );
+    if color.a < 1.0 {
+        format!("{}{:02x}", rgb, channel(color.a))
+    } else {
+        rgb
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| (d * 17) as u8))
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let c = |i: usize| digits.get(i).map_or(1.0, |d| *d as f32 / 255.0);
+    Some(Color {
+        r: c(0),
+        g: c(1),
+        b: c(2),
+        a: c(3),
+    })
+}
+
+fn parse_color(value: &Value) -> Option<Color> {
+    match value {
+        Value::String(hex) => parse_hex(hex),
+        // The newer form, `{ "colorSpace": "srgb", "components": [..] }`.
+        Value::Object(color) => {
+            let components = color.get("components")?.as_array()?;
+            let c = |i: usize| components.get(i)?.as_f64().map(|c| c as f32);
+            Some(Color {
+                r: c(0)?,
+                g: c(1)?,
+                b: c(2)?,
+                a: color.get("alpha").and_then(Value::as_f64).unwrap_or(1.0)
+                    as f32,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A dimension in pixels, from `"8px"`, `"0.5rem"` or
+/// `{ "value": 8, "unit": "px" }`.
+fn parse_dimension(value: &Value) -> Option<f32> {
+    let (n, unit) = match value {
+        Value::String(s) => {
+            let unit_at = s.find(|c: char| c.is_ascii_alphabetic())?;
+            (s[..unit_at].trim().parse().ok()?, &s[unit_at..])
+        }
+        Value::Object(d) => {
+            (d.get("value")?.as_f64()? as f32, d.get("unit")?.as_str()?)
+        }
+        Value::Number(n) => (n.as_f64()? as f32, "px"),
+        _ => return None,
+    };
+    match unit {
+        "px" => Some(n),
+        "rem" | "em" => Some(n * 16.0),
+        _ => None,
+    }
+}
+
+fn parse_shadow(value: &Value) -> Option<Shadow> {
+    // A list of shadows uses the first.
+    let shadow = match value {
+        Value::Array(shadows) => shadows.first()?,
+        shadow => shadow,
+    };
+    let px = |key: &str| {
+        shadow
+            .get(key)
+            .map_or(Some(0.0), parse_dimension)
+            .map(|d| d.round().clamp(0.0, 255.0) as u8)
+    };
+    Some(Shadow {
+        color: parse_color(shadow.get("color")?)?,
+        offset: (px("offsetX")?, px("offsetY")?),
+        blur: px("blur")?,
+        size: px("spread")?,
+    })
+}
+
+// Follow `{group.token}` references until a value that isn't one.
+fn resolve<'a>(
+    root: &'a Value,
+    path: &str,
+    depth: usize,
+) -> Result<Option<&'a Value>, TokenError> {
+    let token = match path.split('.').try_fold(root, |v, key| v.get(key)) {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+    // Tokens Studio leaves off the `$`.
+    let value = match token.get("$value").or_else(|| token.get("value")) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    match value.as_str() {
+        Some(s) if s.starts_with('{') && s.ends_with('}') => {
+            if depth > 16 {
+                return Err(TokenError::Reference(path.to_string()));
+            }
+            match resolve(root, &s[1..s.len() - 1], depth + 1)? {
+                Some(value) => Ok(Some(value)),
+                None => Err(TokenError::Reference(path.to_string())),
+            }
+        }
+        _ => Ok(Some(value)),
+    }
+}
+
+fn read<T>(
+    root: &Value,
+    path: &str,
+    parse: fn(&Value) -> Option<T>,
+) -> Result<Option<T>, TokenError> {
+    match resolve(root, path, 0)? {
+        Some(value) => match parse(value) {
+            Some(t) => Ok(Some(t)),
+            None => Err(TokenError::Invalid(path.to_string())),
+        },
+        None => Ok(None),
+    }
+}
+
+fn token(kind: &str, value: Value) -> Value {
+    json!({ "$type": kind, "$value": value })
+}
+
+fn px(n: f32) -> Value {
+    Value::String(format!("{}px", n))
+}
+
+impl Theme {
+    /// A theme from a W3C design tokens file, e.g. one exported from Figma
+    /// or Tokens Studio. Anything the file doesn't set is taken from
+    /// `default_light`.
+    pub fn from_tokens(json: &str) -> Result<Theme, TokenError> {
+        default_light().merge_tokens(json)
+    }
+
+    /// This theme, with whatever the tokens file sets replaced.
+    ///
+    /// Colors are read from `color.primary`, `color.on-primary` and so on,
+    /// the scales from `spacing.base`, `spacing.ratio`, `font.size.base`
+    /// and `font.size.ratio`, and the rest from `radius` and `shadow`.
+    pub fn merge_tokens(&self, json: &str) -> Result<Theme, TokenError> {
+        let root: Value =
+            serde_json::from_str(json).map_err(TokenError::Json)?;
+        let mut theme = *self;
+        for name in COLORS.iter() {
+            if let Some(color) =
+                read(&root, &format!("color.{}", name), parse_color)?
+            {
+                *palette_color(&mut theme, name) = color;
+            }
+        }
+        let number = |v: &Value| v.as_f64().map(|n| n as f32);
+        if let Some(base) = read(&root, "spacing.base", parse_dimension)? {
+            theme.spacing.base = base;
+        }
+        if let Some(ratio) = read(&root, "spacing.ratio", number)? {
+            theme.spacing.ratio = ratio;
+        }
+        if let Some(base) = read(&root, "font.size.base", parse_dimension)? {
+            theme.typography.base = base;
+        }
+        if let Some(ratio) = read(&root, "font.size.ratio", number)? {
+            theme.typography.ratio = ratio;
+        }
+        if let Some(radius) = read(&root, "radius", parse_dimension)? {
+            theme.radius = radius.round() as u32;
+        }
+        if let Some(shadow) = read(&root, "shadow", parse_shadow)? {
+            theme.shadow = shadow;
+        }
+        Ok(theme)
+    }
+
+    /// This theme as a W3C design tokens file.
+    pub fn to_tokens(&self) -> String {
+        let mut theme = *self;
+        let colors: Map<String, Value> = COLORS
+            .iter()
+            .map(|name| {
+                let color = hex_color(palette_color(&mut theme, name));
+                (name.to_string(), token("color", Value::String(color)))
+            })
+            .collect();
+        let shadow = &self.shadow;
+        let tokens = json!({
+            "color": colors,
+            "spacing": {
+                "base": token("dimension", px(self.spacing.base)),
+                "ratio": token("number", json!(self.spacing.ratio)),
+            },
+            "font": {
+                "size": {
+                    "base": token("dimension", px(self.typography.base)),
+                    "ratio": token("number", json!(self.typography.ratio)),
+                },
+            },
+            "radius": token("dimension", px(self.radius as f32)),
+            "shadow": token("shadow", json!({
+                "color": hex_color(&shadow.color),
+                "offsetX": px(shadow.offset.0 as f32),
+                "offsetY": px(shadow.offset.1 as f32),
+                "blur": px(shadow.blur as f32),
+                "spread": px(shadow.size as f32),
+            })),
+        });
+        serde_json::to_string_pretty(&tokens).unwrap()
+    }
+}
+
+#[test]
+fn test_tokens_round_trip() {
+    let dark = crate::theme::default_dark();
+    assert_eq!(Theme::from_tokens(&dark.to_tokens()).unwrap(), dark);
+
+    let theme = Theme::from_tokens(
+        r##"{
+            "brand": { "blue": { "$type": "color", "$value": "#0af" } },
+            "color": { "primary": { "$value": "{brand.blue}" } },
+            "radius": { "type": "dimension", "value": "0.5rem" }
+        }"##,
+    )
+    .unwrap();
+    assert_eq!(hex_color(&theme.palette.primary), "#00aaff");
+    assert_eq!(theme.radius, 8);
+    assert_eq!(theme.spacing, default_light().spacing);
+}
@@ -1,6 +1,20 @@
-use std::collections::HashMap;
-
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::time::Duration;
+
+use smallvec::SmallVec;
+
+use crate::compat::HashMap;
 use crate::model::Property;
+use crate::symbol::Symbol;
 
 // pub trait Node {
 //     fn create_element(html_tag: String);
@@ -146,147 +160,676 @@ pub fn add_class(
 // ) {
 // }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct Node {
-    pub tag: String,
-    pub attrs: Vec<Attribute>,
-    pub children: Vec<NodeType>,
+/// Extra information a backend reports along with an event.
+#[derive(Debug, PartialOrd, PartialEq, Clone)]
+pub enum EventData {
+    None,
+    /// Where the pointer is, in logical pixels from the top left of the
+    /// window.
+    Pointer {
+        x: f32,
+        y: f32,
+    },
+    /// Where the pointer came up, and where it went down before that if
+    /// the program saw it, so listeners can tell how far it was dragged.
+    Release {
+        x: f32,
+        y: f32,
+        start: Option<(f32, f32)>,
+    },
+    /// Where a click was, with the time between each of the clicks in a
+    /// row in about the same place, oldest first, as the program saw it.
+    Click {
+        x: f32,
+        y: f32,
+        gaps: Vec<Duration>,
+    },
+    /// The key pressed, named as in the web's `KeyboardEvent.key`, e.g.
+    /// `ArrowLeft` or `a`.
+    Key {
+        key: String,
+        shift: bool,
+    },
+    /// How much of the element is showing in its scroll container, from
+    /// 0 to 1.
+    Intersection {
+        ratio: f32,
+    },
+    Wheel(Wheel),
+    Caret(Caret),
+    /// The text of an input, after it changes.
+    Text(String),
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub enum NodeType {
-    Node(Node),
-    KeyedNode(String, Node),
+/// Where the caret is in a text input, after it's typed in or moved.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Caret {
+    /// How many characters of the input's text are before it.
+    pub offset: usize,
+    /// Its top left corner, in pixels from the input's, taking the
+    /// input's scrolling into account.
+    pub x: f32,
+    pub y: f32,
+    /// The height of the line it's on.
+    pub line_height: f32,
+}
+
+/// What the deltas of a wheel event are measured in.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub enum DeltaUnit {
+    /// Pixels, from touchpads and precise wheels.
+    Pixel,
+    /// Lines of text, from most mouse wheels.
+    Line,
+    Page,
+}
+
+/// A wheel or touchpad scroll, with the modifier keys held during it.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Copy)]
+pub struct Wheel {
+    pub dx: f32,
+    pub dy: f32,
+    pub unit: DeltaUnit,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Wheel {
+    /// The deltas in pixels, with lines and pages the given heights.
+    pub fn pixels(&self, line_height: f32, page_height: f32) -> (f32, f32) {
+        let scale = match self.unit {
+            DeltaUnit::Pixel => 1.0,
+            DeltaUnit::Line => line_height,
+            DeltaUnit::Page => page_height,
+        };
+        (self.dx * scale, self.dy * scale)
+    }
+
+    /// Whether this is asking to zoom rather than scroll: a wheel turned
+    /// with `Ctrl` held, or a pinch on a touchpad, which browsers report
+    /// the same way.
+    pub fn is_zoom(&self) -> bool {
+        self.ctrl
+    }
+}
+
+pub type HandlerFn<Msg> = dyn Fn(&EventData) -> Option<Msg> + Send + Sync;
+
+/// Turns the messages of a view into those of the view embedding it.
+pub type MapFn<A, B> = dyn Fn(A) -> B + Send + Sync;
+
+/// Turns an event reported by the backend into a message, or ignores it.
+pub struct Handler<Msg>(pub Arc<HandlerFn<Msg>>);
+
+impl<Msg> Handler<Msg> {
+    pub fn handle(&self, data: &EventData) -> Option<Msg> {
+        (self.0)(data)
+    }
+}
+
+impl<Msg: 'static> Handler<Msg> {
+    /// Turn the messages this handler sends into another type.
+    pub fn map<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> Handler<B> {
+        let f = f.clone();
+        Handler(Arc::new(move |data| self.handle(data).map(|msg| f(msg))))
+    }
+}
+
+impl<Msg> Clone for Handler<Msg> {
+    fn clone(&self) -> Self {
+        Handler(self.0.clone())
+    }
+}
+
+/// How often a listener's messages are sent on to `update`.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Rate {
+    /// Every time.
+    #[default]
+    Every,
+    /// Only the last, once none have come for this long, as for a search
+    /// box searching once typing stops.
+    Debounce(Duration),
+    /// At most once this often, keeping the last held back until it's
+    /// time, as for a scroll position.
+    Throttle(Duration),
+}
+
+/// Whether the backend takes its own action for an event as well as
+/// running the listeners, e.g. the browser following a clicked link.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DefaultAction {
+    /// Taken as usual.
+    #[default]
+    Allow,
+    /// Never taken.
+    Prevent,
+    /// Not taken for a click of the main button with no modifier keys
+    /// held, leaving those opening a link in a new tab or window.
+    PreventPlainClick,
+}
+
+impl DefaultAction {
+    /// Whether it's not taken for an event, `plain` being whether it's a
+    /// click of the main button with no modifier keys held.
+    pub fn prevents(self, plain: bool) -> bool {
+        match self {
+            DefaultAction::Allow => false,
+            DefaultAction::Prevent => true,
+            DefaultAction::PreventPlainClick => plain,
+        }
+    }
+}
+
+/// An event listener on a node, e.g. `click`.
+pub struct Listener<Msg> {
+    pub event: Symbol,
+    pub handler: Handler<Msg>,
+    pub rate: Rate,
+    pub default: DefaultAction,
+}
+
+impl<Msg: 'static> Listener<Msg> {
+    pub fn map<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> Listener<B> {
+        Listener {
+            event: self.event,
+            handler: self.handler.map(f),
+            rate: self.rate,
+            default: self.default,
+        }
+    }
+}
+
+impl<Msg> Clone for Listener<Msg> {
+    fn clone(&self) -> Self {
+        Listener {
+            event: self.event,
+            handler: self.handler.clone(),
+            rate: self.rate,
+            default: self.default,
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for Listener<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "on {}", self.event)
+    }
+}
+
+// Handlers are closures, usually made anew for each frame, so listeners are
+// only compared and hashed by event, rate and default action. A cached node
+// is given the handlers of the view it's reused for.
+impl<Msg> PartialEq for Listener<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        self.event == other.event
+            && self.rate == other.rate
+            && self.default == other.default
+    }
+}
+
+impl<Msg> Hash for Listener<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.event.hash(state);
+        self.rate.hash(state);
+        self.default.hash(state);
+    }
+}
+
+pub fn on<Msg>(event: String, handler: Handler<Msg>) -> Listener<Msg> {
+    Listener {
+        event: event.into(),
+        handler,
+        rate: Rate::Every,
+        default: DefaultAction::Allow,
+    }
+}
+
+/// Where an element was laid out, in pixels from the top left corner of
+/// the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+/// Where each node of a rendered tree was laid out, by its path of child
+/// indices from the root node. Text isn't included.
+pub type Rects = BTreeMap<Vec<usize>, Rect>;
+
+/// The attributes of a node, stored inline as most nodes have only a few.
+/// `Node::attrs` used to be a `Vec`; convert one with `.into()`.
+pub type Attrs = SmallVec<[Attribute; 2]>;
+
+/// The event listeners of a node, stored inline as most have one at most.
+pub type Listeners<Msg> = SmallVec<[Listener<Msg>; 1]>;
+
+pub struct Node<Msg> {
+    pub tag: Symbol,
+    pub attrs: Attrs,
+    pub events: Listeners<Msg>,
+    // A `NodeType` holds a `Node`, so children can't be stored inline.
+    // They're gathered into a `Vec` sized for all of them up front instead.
+    pub children: Vec<NodeType<Msg>>,
+}
+
+impl<Msg> Node<Msg> {
+    /// The node found by following `path`, a list of child indices
+    /// starting from this node.
+    pub fn at(&self, path: &[usize]) -> Option<&Node<Msg>> {
+        match path {
+            [] => Some(self),
+            [i, rest @ ..] => match self.children.get(*i) {
+                Some(NodeType::Node(n)) | Some(NodeType::KeyedNode(_, n)) => {
+                    n.at(rest)
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// The value of the attribute `name`, e.g. `href`.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .filter_map(Attribute::name_value)
+            .find_map(|(k, v)| if k == name { Some(v) } else { None })
+    }
+
+    /// Run the `event` listeners on the node found by following `path`,
+    /// a list of child indices starting from this node.
+    pub fn dispatch(
+        &self,
+        path: &[usize],
+        event: &str,
+        data: &EventData,
+    ) -> Vec<Msg> {
+        self.dispatch_rated(path, event, data)
+            .into_iter()
+            .map(|(_, _, msg)| msg)
+            .collect()
+    }
+
+    /// Like `dispatch`, with the index among the node's listeners and the
+    /// rate of the listener each message came from, for the runner to
+    /// hold back as it asks.
+    pub fn dispatch_rated(
+        &self,
+        path: &[usize],
+        event: &str,
+        data: &EventData,
+    ) -> Vec<(usize, Rate, Msg)> {
+        match path {
+            [] => self
+                .events
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| l.event == event)
+                .filter_map(|(i, l)| {
+                    Some((i, l.rate, l.handler.handle(data)?))
+                })
+                .collect(),
+            [i, rest @ ..] => match self.children.get(*i) {
+                Some(NodeType::Node(n)) | Some(NodeType::KeyedNode(_, n)) => {
+                    n.dispatch_rated(rest, event, data)
+                }
+                _ => vec![],
+            },
+        }
+    }
+}
+
+impl<Msg: 'static> Node<Msg> {
+    /// Turn the messages this node and its children send into another
+    /// type.
+    pub fn map<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> Node<B> {
+        Node {
+            tag: self.tag,
+            attrs: self.attrs,
+            events: self.events.into_iter().map(|l| l.map(f)).collect(),
+            children: self.children.into_iter().map(|c| c.map(f)).collect(),
+        }
+    }
+}
+
+// Implemented by hand so `Msg` itself doesn't need to be `Clone` or `Debug`.
+impl<Msg> Clone for Node<Msg> {
+    fn clone(&self) -> Self {
+        Node {
+            tag: self.tag,
+            attrs: self.attrs.clone(),
+            events: self.events.clone(),
+            children: self.children.clone(),
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for Node<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("tag", &self.tag)
+            .field("attrs", &self.attrs)
+            .field("events", &self.events)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl<Msg> PartialEq for Node<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+            && self.attrs == other.attrs
+            && self.events == other.events
+            && self.children == other.children
+    }
+}
+
+impl<Msg> Hash for Node<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.tag.hash(state);
+        self.attrs.hash(state);
+        self.events.hash(state);
+        self.children.hash(state);
+    }
+}
+
+pub enum NodeType<Msg> {
+    Node(Node<Msg>),
+    KeyedNode(String, Node<Msg>),
     Text(String),
 }
 
-impl Default for NodeType {
+impl<Msg: 'static> NodeType<Msg> {
+    pub fn map<B: 'static>(self, f: &Arc<MapFn<Msg, B>>) -> NodeType<B> {
+        match self {
+            NodeType::Node(n) => NodeType::Node(n.map(f)),
+            NodeType::KeyedNode(k, n) => NodeType::KeyedNode(k, n.map(f)),
+            NodeType::Text(t) => NodeType::Text(t),
+        }
+    }
+}
+
+impl<Msg> Clone for NodeType<Msg> {
+    fn clone(&self) -> Self {
+        match self {
+            NodeType::Node(n) => NodeType::Node(n.clone()),
+            NodeType::KeyedNode(k, n) => {
+                NodeType::KeyedNode(k.clone(), n.clone())
+            }
+            NodeType::Text(t) => NodeType::Text(t.clone()),
+        }
+    }
+}
+
+impl<Msg> fmt::Debug for NodeType<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeType::Node(n) => f.debug_tuple("Node").field(n).finish(),
+            NodeType::KeyedNode(k, n) => {
+                f.debug_tuple("KeyedNode").field(k).field(n).finish()
+            }
+            NodeType::Text(t) => f.debug_tuple("Text").field(t).finish(),
+        }
+    }
+}
+
+impl<Msg> PartialEq for NodeType<Msg> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NodeType::Node(a), NodeType::Node(b)) => a == b,
+            (NodeType::KeyedNode(ka, a), NodeType::KeyedNode(kb, b)) => {
+                ka == kb && a == b
+            }
+            (NodeType::Text(a), NodeType::Text(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<Msg> Hash for NodeType<Msg> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            NodeType::Node(n) => n.hash(state),
+            NodeType::KeyedNode(k, n) => {
+                k.hash(state);
+                n.hash(state);
+            }
+            NodeType::Text(t) => t.hash(state),
+        }
+    }
+}
+
+impl<Msg> Default for NodeType<Msg> {
     fn default() -> Self {
         NodeType::Node(Node {
-            tag: "div".to_string(),
-            attrs: vec![],
+            tag: "div".into(),
+            attrs: Attrs::new(),
+            events: Listeners::new(),
             children: vec![],
         })
     }
 }
 
-pub fn text(txt: String) -> NodeType {
+pub fn text<Msg>(txt: String) -> NodeType<Msg> {
     NodeType::Text(txt)
 }
 
-pub fn node(
-    tag: String,
-    attrs: Vec<Attribute>,
-    children: Vec<NodeType>,
-) -> Node {
+/// A node with `attrs` and `children`, and no listeners yet.
+///
+/// The attributes are kept in an `Attrs`, which stores the first few
+/// inline. A `Vec` of them, as this took before, still works, though
+/// building an `Attrs` with `smallvec!` saves allocating.
+pub fn node<Msg>(
+    tag: Symbol,
+    attrs: impl Into<Attrs>,
+    children: Vec<NodeType<Msg>>,
+) -> Node<Msg> {
     Node {
         tag,
-        attrs,
+        attrs: attrs.into(),
+        events: Listeners::new(),
         children,
     }
 }
 
-pub fn keyed_node(
+pub fn keyed_node<Msg>(
     key: String,
-    tag: String,
-    attrs: Vec<Attribute>,
-    children: Vec<NodeType>,
-) -> NodeType {
+    tag: Symbol,
+    attrs: Attrs,
+    children: Vec<NodeType<Msg>>,
+) -> NodeType<Msg> {
     NodeType::KeyedNode(
         key,
         Node {
             tag,
             attrs,
+            events: Listeners::new(),
             children,
         },
     )
 }
 
 pub fn property(property: Property) -> Attribute {
-    Attribute(format!("{}={}", property.0, property.1))
+    Attribute::Property(property.0, property.1)
 }
 
-#[derive(Debug, Default, PartialOrd, PartialEq, Clone)]
-pub struct Attribute(pub String);
+/// An attribute of a node. Classes and inline styles are kept apart from
+/// the other attributes, as a renderer gathers all of them into the node's
+/// single `class` and `style`.
+#[derive(Debug, PartialOrd, PartialEq, Clone, Hash)]
+pub enum Attribute {
+    /// Classes, separated by spaces.
+    Class(String),
+    /// A property of the inline style, e.g. `margin` and `4px`.
+    Style(Symbol, String),
+    /// An attribute with a value, e.g. `href` and a URL.
+    Property(Symbol, String),
+    /// An attribute that's on by being there, e.g. `disabled`.
+    BoolAttr(Symbol),
+    /// A `data-` attribute, e.g. `data-test-id` and `save`. Its name is the
+    /// app's own, so it's kept as a `String` rather than interned.
+    Data(String, String),
+}
+
+impl Attribute {
+    pub fn property(name: &str, value: impl ToString) -> Self {
+        Attribute::Property(name.into(), value.to_string())
+    }
+
+    /// The classes, if this is `Class`.
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        match self {
+            Attribute::Class(classes) => classes.as_str(),
+            _ => "",
+        }
+        .split_whitespace()
+    }
+
+    /// The name and value of a `Property` or `Data`, or of a `BoolAttr`
+    /// with an empty value.
+    pub fn name_value(&self) -> Option<(&str, &str)> {
+        match self {
+            Attribute::Property(name, value) => Some((name.as_str(), value)),
+            Attribute::Data(name, value) => Some((name, value)),
+            Attribute::BoolAttr(name) => Some((name.as_str(), "")),
+            _ => None,
+        }
+    }
+}
+
+/// `attrs` as they're written in HTML: the classes joined into a `class`
+/// attribute and the styles into a `style` attribute, each left out if
+/// there are none, followed by the rest in order.
+pub fn html_attributes(attrs: &[Attribute]) -> Vec<(&str, String)> {
+    let classes = attrs
+        .iter()
+        .flat_map(Attribute::classes)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let styles = attrs
+        .iter()
+        .filter_map(|a| match a {
+            Attribute::Style(name, value) => {
+                Some(format!("{}: {}", name, value))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    let mut html = vec![];
+    if !classes.is_empty() {
+        html.push(("class", classes));
+    }
+    if !styles.is_empty() {
+        html.push(("style", styles));
+    }
+    html.extend(
+        attrs
+            .iter()
+            .filter_map(Attribute::name_value)
+            .map(|(name, value)| (name, value.to_string())),
+    );
+    html
+}
 
 pub mod html {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
     use crate::vdom;
     use vdom::{node, Node, NodeType};
 
     // pub type Node = Node;
 
-    pub fn text(txt: String) -> NodeType {
+    pub fn text<Msg>(txt: String) -> NodeType<Msg> {
         vdom::text(txt)
     }
 
-    pub fn div(attrs: Vec<vdom::Attribute>, children: Vec<NodeType>) -> Node {
-        Node {
-            tag: "div".to_string(),
-            attrs,
-            children,
-        }
+    pub fn div<Msg>(
+        attrs: impl Into<vdom::Attrs>,
+        children: Vec<NodeType<Msg>>,
+    ) -> Node<Msg> {
+        node("div".into(), attrs, children)
     }
 
     // paragraph html tag
-    pub fn p(attrs: Vec<vdom::Attribute>, children: Vec<NodeType>) -> Node {
-        Node {
-            tag: "p".to_string(),
-            attrs,
-            children,
-        }
+    pub fn p<Msg>(
+        attrs: impl Into<vdom::Attrs>,
+        children: Vec<NodeType<Msg>>,
+    ) -> Node<Msg> {
+        node("p".into(), attrs, children)
     }
 
     // strikethrough html tag
-    pub fn s(attrs: Vec<vdom::Attribute>, children: Vec<NodeType>) -> Node {
-        Node {
-            tag: "s".to_string(),
-            attrs,
-            children,
-        }
+    pub fn s<Msg>(
+        attrs: impl Into<vdom::Attrs>,
+        children: Vec<NodeType<Msg>>,
+    ) -> Node<Msg> {
+        node("s".into(), attrs, children)
     }
 
     // underline html tag
-    pub fn u(attrs: Vec<vdom::Attribute>, children: Vec<NodeType>) -> Node {
-        Node {
-            tag: "u".to_string(),
-            attrs,
-            children,
-        }
+    pub fn u<Msg>(
+        attrs: impl Into<vdom::Attrs>,
+        children: Vec<NodeType<Msg>>,
+    ) -> Node<Msg> {
+        node("u".into(), attrs, children)
     }
 
     pub mod attributes {
+        use alloc::string::{String, ToString};
+
         use crate::vdom;
 
         pub fn class(cls: String) -> vdom::Attribute {
-            vdom::Attribute(cls)
+            vdom::Attribute::Class(cls)
         }
 
+        /// A property of the inline style.
         pub fn style(k: String, v: String) -> vdom::Attribute {
-            vdom::Attribute(format!("{}={}", k, v))
+            vdom::Attribute::Style(k.into(), v)
+        }
+
+        pub fn attribute(k: String, v: String) -> vdom::Attribute {
+            vdom::Attribute::Property(k.into(), v)
+        }
+
+        pub fn id(i: String) -> vdom::Attribute {
+            attribute("id".to_string(), i)
         }
 
         pub fn src(s: String) -> vdom::Attribute {
-            style("src".to_string(), s)
+            attribute("src".to_string(), s)
         }
 
         pub fn alt(description: String) -> vdom::Attribute {
-            style("alt".to_string(), description)
+            attribute("alt".to_string(), description)
         }
 
         pub fn href(url: String) -> vdom::Attribute {
-            style("href".to_string(), url)
+            attribute("href".to_string(), url)
         }
 
         pub fn rel(r: String) -> vdom::Attribute {
-            style("rel".to_string(), r)
+            attribute("rel".to_string(), r)
         }
 
         pub fn target(t: String) -> vdom::Attribute {
-            style("target".to_string(), t)
+            attribute("target".to_string(), t)
         }
 
         pub fn download(file_name: String) -> vdom::Attribute {
-            style("download".to_string(), file_name)
+            attribute("download".to_string(), file_name)
         }
     }
 }